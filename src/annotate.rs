@@ -0,0 +1,203 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String,ToString};
+use crate::ToHexString;
+use crate::util::{from_be_bytes,keccak256,u256};
+
+// ============================================================================
+// Annotators
+// ============================================================================
+
+/// Describes constants when printing a disassembly, such as those
+/// which look like addresses or function selectors.
+pub trait Annotator {
+    /// Describe the constant pushed by a `PUSH` instruction, or
+    /// return `None` if it should be printed as usual.
+    fn annotate(&self, bytes: &[u8]) -> Option<String>;
+
+    /// Describe a (known) storage slot accessed by an `SLOAD` or
+    /// `SSTORE`, or return `None` if it has no description.
+    fn annotate_slot(&self, _slot: u256) -> Option<String> { None }
+}
+
+/// Annotates nothing.
+impl Annotator for () {
+    fn annotate(&self, _bytes: &[u8]) -> Option<String> { None }
+}
+
+/// Annotates only when present.
+impl<A:Annotator> Annotator for Option<A> {
+    fn annotate(&self, bytes: &[u8]) -> Option<String> {
+        self.as_ref()?.annotate(bytes)
+    }
+
+    fn annotate_slot(&self, slot: u256) -> Option<String> {
+        self.as_ref()?.annotate_slot(slot)
+    }
+}
+
+/// Tries the first annotator and then, failing that, the second.
+impl<S:Annotator,T:Annotator> Annotator for (S,T) {
+    fn annotate(&self, bytes: &[u8]) -> Option<String> {
+        self.0.annotate(bytes).or_else(|| self.1.annotate(bytes))
+    }
+
+    fn annotate_slot(&self, slot: u256) -> Option<String> {
+        self.0.annotate_slot(slot).or_else(|| self.1.annotate_slot(slot))
+    }
+}
+
+impl<A:Annotator+?Sized> Annotator for &A {
+    fn annotate(&self, bytes: &[u8]) -> Option<String> {
+        (**self).annotate(bytes)
+    }
+
+    fn annotate_slot(&self, slot: u256) -> Option<String> {
+        (**self).annotate_slot(slot)
+    }
+}
+
+// ============================================================================
+// Addresses
+// ============================================================================
+
+/// Annotates `PUSH20` constants which look like addresses (e.g.
+/// `addr:0xdead...`).  Masks where every byte is either `0x00` or
+/// `0xff` are not considered addresses.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+pub struct Addresses;
+
+impl Annotator for Addresses {
+    fn annotate(&self, bytes: &[u8]) -> Option<String> {
+        if bytes.len() != 20 || bytes.iter().all(|b| *b == 0 || *b == 0xff) {
+            None
+        } else {
+            Some(format!("addr:{}",bytes.to_hex_string()))
+        }
+    }
+}
+
+// ============================================================================
+// Selectors
+// ============================================================================
+
+/// A dictionary of known function signatures, which annotates `PUSH4`
+/// constants matching their selectors (e.g.
+/// `sel:transfer(address,uint256)`).
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct Selectors {
+    signatures: BTreeMap<[u8;4],String>
+}
+
+impl Selectors {
+    pub fn new() -> Self {
+        Selectors{signatures: BTreeMap::new()}
+    }
+
+    /// Add a function signature (e.g. `"transfer(address,uint256)"`),
+    /// whose selector is the first four bytes of its hash.
+    pub fn insert(&mut self, signature: &str) {
+        let hash = keccak256(signature.as_bytes());
+        let selector = [hash[0],hash[1],hash[2],hash[3]];
+        self.signatures.insert(selector,signature.to_string());
+    }
+
+    /// Get the signature with a given selector (if known).
+    pub fn get(&self, selector: &[u8]) -> Option<&str> {
+        let selector : [u8;4] = selector.try_into().ok()?;
+        self.signatures.get(&selector).map(|s| s.as_str())
+    }
+
+    /// Get the number of known signatures.
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Check whether no signatures are known.
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+}
+
+impl From<&[&str]> for Selectors {
+    fn from(signatures: &[&str]) -> Self {
+        let mut r = Selectors::new();
+        for s in signatures { r.insert(s); }
+        r
+    }
+}
+
+impl Annotator for Selectors {
+    fn annotate(&self, bytes: &[u8]) -> Option<String> {
+        self.get(bytes).map(|s| format!("sel:{}",s))
+    }
+}
+
+// ============================================================================
+// Storage Names
+// ============================================================================
+
+/// Names for storage slots (e.g. `0 => "owner"`).  These describe
+/// accesses to a named slot (where the slot is known), and pushes of
+/// a named slot given in full (e.g. `PUSH32 slot:owner`), as is
+/// typical for slots determined by a hash.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct StorageNames {
+    names: BTreeMap<u256,String>
+}
+
+impl StorageNames {
+    pub fn new() -> Self {
+        StorageNames{names: BTreeMap::new()}
+    }
+
+    /// Name a given storage slot.
+    pub fn insert(&mut self, slot: u256, name: &str) {
+        self.names.insert(slot,name.to_string());
+    }
+
+    /// Name the storage slot given by the hash of a string (e.g.
+    /// `"my.app.config"`).
+    pub fn insert_hashed(&mut self, preimage: &str, name: &str) {
+        self.insert_hashed_less(preimage,0,name);
+    }
+
+    /// Name the storage slot given by the hash of a string less a
+    /// given amount (modulo `2^256`).  For example, the slots of
+    /// EIP-1967 are the hash less one, so the implementation slot is
+    /// named by `insert_hashed_less("eip1967.proxy.implementation",1,
+    /// "implementation")`.
+    pub fn insert_hashed_less(&mut self, preimage: &str, n: u128, name: &str) {
+        let hash = keccak256(preimage.as_bytes());
+        let slot = u256::from_halves(from_be_bytes(&hash[..16]),from_be_bytes(&hash[16..]));
+        self.insert(slot - u256::from(n),name);
+    }
+
+    /// Get the name of a given storage slot (if any).
+    pub fn get(&self, slot: u256) -> Option<&str> {
+        self.names.get(&slot).map(|s| s.as_str())
+    }
+}
+
+impl Annotator for StorageNames {
+    fn annotate(&self, bytes: &[u8]) -> Option<String> {
+        if bytes.len() != 32 { return None; }
+        let slot = u256::from_halves(from_be_bytes(&bytes[..16]),from_be_bytes(&bytes[16..]));
+        self.get(slot).map(|n| format!("slot:{}",n))
+    }
+
+    fn annotate_slot(&self, slot: u256) -> Option<String> {
+        self.get(slot).map(|n| n.to_string())
+    }
+}
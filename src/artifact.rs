@@ -0,0 +1,170 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::BTreeMap;
+use std::fmt;
+use serde_json::Value;
+
+/// Every library placeholder stands for a 20 byte address, and hence
+/// occupies 40 characters of a hex string.
+const PLACEHOLDER_LEN : usize = 40;
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ArtifactError {
+    /// The artifact is not valid JSON.
+    Json(serde_json::Error),
+    /// A required field is missing (or is not a string).
+    MissingField(&'static str),
+    /// A bytecode string contains something which is neither a hex
+    /// digit nor part of a library placeholder.
+    InvalidHex(usize)
+}
+
+impl fmt::Display for ArtifactError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArtifactError::Json(e) => write!(f,"invalid artifact ({})",e),
+            ArtifactError::MissingField(n) => write!(f,"missing field \"{}\"",n),
+            ArtifactError::InvalidHex(i) => write!(f,"invalid hex character at {}",i)
+        }
+    }
+}
+
+impl std::error::Error for ArtifactError { }
+
+impl From<serde_json::Error> for ArtifactError {
+    fn from(e: serde_json::Error) -> Self { ArtifactError::Json(e) }
+}
+
+// ============================================================================
+// Link References
+// ============================================================================
+
+/// Identifies a library address embedded in a bytecode sequence.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct LinkReference {
+    /// Name of the library (where known) or, otherwise, the text of
+    /// its placeholder (e.g. a hash for `__$...$__` placeholders).
+    pub library: String,
+    /// Byte offset of the address within the bytecode.
+    pub offset: usize
+}
+
+impl LinkReference {
+    /// Length (in bytes) of every linked address.
+    pub const LENGTH : usize = 20;
+}
+
+// ============================================================================
+// Artifacts
+// ============================================================================
+
+/// The bytecode from a Hardhat or Truffle artifact file, with library
+/// placeholders linked.  Placeholders for which no address is given
+/// are filled with zeros, and recorded as _unresolved_ so they can be
+/// treated symbolically.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Artifact {
+    /// The creation bytecode.
+    pub bytecode: Vec<u8>,
+    /// The runtime bytecode (empty if not given).
+    pub deployed_bytecode: Vec<u8>,
+    /// Unresolved link references in the creation bytecode.
+    pub unresolved: Vec<LinkReference>,
+    /// Unresolved link references in the runtime bytecode.
+    pub deployed_unresolved: Vec<LinkReference>
+}
+
+impl Artifact {
+    /// Load an artifact from its JSON text, linking library
+    /// placeholders using a given mapping from library names to
+    /// addresses.
+    pub fn from_json(text: &str, libraries: &BTreeMap<String,[u8;20]>) -> Result<Self,ArtifactError> {
+        let json : Value = serde_json::from_str(text)?;
+        // Extract bytecode (Hardhat and Truffle agree here)
+        let hex = json["bytecode"].as_str().ok_or(ArtifactError::MissingField("bytecode"))?;
+        let deployed_hex = json["deployedBytecode"].as_str().unwrap_or("");
+        // Extract names from link references (Hardhat only)
+        let names = link_names(&json["linkReferences"]);
+        let deployed_names = link_names(&json["deployedLinkReferences"]);
+        // Link
+        let (bytecode,unresolved) = link(hex,&names,libraries)?;
+        let (deployed_bytecode,deployed_unresolved) = link(deployed_hex,&deployed_names,libraries)?;
+        //
+        Ok(Artifact{bytecode,deployed_bytecode,unresolved,deployed_unresolved})
+    }
+}
+
+/// Link a hex string containing library placeholders, such as
+/// `__Lib_____` (Truffle) or `__$<hash>$__` (solc / Hardhat).  The
+/// name of the library at each placeholder is taken from `names`
+/// (keyed by byte offset) if present, otherwise from the placeholder
+/// itself.  This returns the linked bytes, along with any link
+/// references which could not be resolved.
+pub fn link(hex: &str, names: &BTreeMap<usize,String>, libraries: &BTreeMap<String,[u8;20]>) -> Result<(Vec<u8>,Vec<LinkReference>),ArtifactError> {
+    let chars = hex.strip_prefix("0x").unwrap_or(hex).as_bytes();
+    let mut bytes = Vec::new();
+    let mut unresolved = Vec::new();
+    let mut i = 0;
+    //
+    while i < chars.len() {
+        if chars[i..].starts_with(b"__") && i + PLACEHOLDER_LEN <= chars.len() {
+            let offset = bytes.len();
+            let text = std::str::from_utf8(&chars[i..i+PLACEHOLDER_LEN]).map_err(|_| ArtifactError::InvalidHex(i))?;
+            let library = match names.get(&offset) {
+                Some(n) => n.clone(),
+                None => text.trim_matches(|c| c == '_' || c == '$').to_string()
+            };
+            match libraries.get(&library) {
+                Some(addr) => bytes.extend_from_slice(addr),
+                None => {
+                    bytes.extend_from_slice(&[0;LinkReference::LENGTH]);
+                    unresolved.push(LinkReference{library,offset});
+                }
+            }
+            i = i + PLACEHOLDER_LEN;
+        } else {
+            let digits = chars.get(i..i+2).ok_or(ArtifactError::InvalidHex(i))?;
+            if !digits.iter().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ArtifactError::InvalidHex(i));
+            }
+            let s = std::str::from_utf8(digits).unwrap();
+            bytes.push(u8::from_str_radix(s,16).unwrap());
+            i = i + 2;
+        }
+    }
+    //
+    Ok((bytes,unresolved))
+}
+
+/// Extract the library name for each link reference offset from a
+/// Hardhat `linkReferences` object.  This has the form `{ file: {
+/// library: [ { start, length } ] } }`.
+fn link_names(refs: &Value) -> BTreeMap<usize,String> {
+    let mut names = BTreeMap::new();
+    if let Some(files) = refs.as_object() {
+        for libs in files.values().filter_map(|v| v.as_object()) {
+            for (lib,sites) in libs {
+                for site in sites.as_array().into_iter().flatten() {
+                    if let Some(start) = site["start"].as_u64() {
+                        names.insert(start as usize,lib.clone());
+                    }
+                }
+            }
+        }
+    }
+    names
+}
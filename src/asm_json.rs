@@ -0,0 +1,130 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::BTreeMap;
+use serde_json::{json,Value};
+use crate::{Bytecode,Instruction,Instruction::*};
+
+// ============================================================================
+// Assembly JSON
+// ============================================================================
+
+impl Bytecode {
+    /// Convert this bytecode sequence into the assembly JSON format
+    /// used by solc (i.e. `--asm-json`).  Each `JUMPDEST` is given as
+    /// a `tag` followed by a `JUMPDEST`, and each label pushed as a
+    /// `PUSH [tag]`, where tags are numbered by label.  Push values
+    /// are given in full (i.e. including leading zeros), so the width
+    /// of every push is preserved.  Since solc has no equivalent for a
+    /// push truncated by the end of the code, or for a
+    /// pseudo-instruction, these are given as `PUSHPAD` (with the
+    /// number of bytes `present`) and `PSEUDO` (with its `pseudo`
+    /// name) respectively.  Source locations are not given.
+    pub fn to_asm_json(&self) -> Value {
+        let mut items = Vec::new();
+        for insn in self.instructions() {
+            match insn {
+                JUMPDEST(l) => {
+                    items.push(json!({"name": "tag", "value": l.to_string()}));
+                    items.push(json!({"name": "JUMPDEST"}));
+                }
+                PUSHL(l) => items.push(json!({"name": "PUSH [tag]", "value": l.to_string()})),
+                PUSH(bs) if bs.is_empty() => items.push(json!({"name": "PUSH0"})),
+                PUSH(bs) => items.push(json!({"name": "PUSH", "value": to_hex(bs)})),
+                PUSHPAD(bs,n) => items.push(json!({"name": "PUSHPAD", "value": to_hex(bs), "present": n.to_string()})),
+                LINKREF(i) => items.push(json!({"name": "PUSHLIB", "value": i.to_string()})),
+                DATA(bs) => items.push(json!({"name": "DATA", "value": to_hex(bs)})),
+                PSEUDO(n,bs) => items.push(json!({"name": "PSEUDO", "value": to_hex(bs), "pseudo": n})),
+                _ => items.push(json!({"name": mnemonic(insn)}))
+            }
+        }
+        json!({".code": items})
+    }
+
+    /// Convert solc's assembly JSON format back into a bytecode
+    /// sequence, returning `None` if it is malformed or uses items
+    /// which have no equivalent (e.g. `PUSH #[$]` for subassemblies).
+    /// This accepts either the object produced by solc, or the array
+    /// of items (i.e. its `.code`).
+    pub fn from_asm_json(json: &Value) -> Option<Bytecode> {
+        let items = match json {
+            Value::Array(items) => items,
+            _ => json[".code"].as_array()?
+        };
+        let mut code = Bytecode::new();
+        let mut tags : BTreeMap<String,usize> = BTreeMap::new();
+        let mut i = 0;
+        //
+        while i < items.len() {
+            let name = items[i]["name"].as_str()?;
+            let value = items[i].get("value").map(|v| v.as_str()).unwrap_or(Some(""))?;
+            let insn = match name {
+                "tag" => {
+                    let l = label(&mut code,&mut tags,value);
+                    // Skip the accompanying JUMPDEST (if present)
+                    if items.get(i+1).map(|n| n["name"] == "JUMPDEST") == Some(true) { i = i + 1; }
+                    JUMPDEST(l)
+                }
+                "JUMPDEST" => JUMPDEST(code.fresh_label()),
+                "PUSH [tag]" => PUSHL(label(&mut code,&mut tags,value)),
+                "PUSH" => PUSH(from_hex(value)?),
+                "PUSHLIB" => LINKREF(value.parse().ok()?),
+                "DATA" => DATA(from_hex(value)?),
+                "PUSHPAD" => {
+                    let bs = from_hex(value)?;
+                    let n : usize = items[i]["present"].as_str()?.parse().ok()?;
+                    if n >= bs.len() { return None; }
+                    PUSHPAD(bs,n)
+                }
+                "PSEUDO" => PSEUDO(items[i]["pseudo"].as_str()?.to_string(),from_hex(value)?),
+                "PREVRANDAO" => DIFFICULTY,
+                _ => Instruction::from_mnemonic(name,None)?
+            };
+            code.push(insn);
+            i = i + 1;
+        }
+        //
+        Some(code)
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Determine the mnemonic of an instruction without operands (e.g.
+/// `DUP1`).
+fn mnemonic(insn: &Instruction) -> String {
+    match insn {
+        DUP(n) => format!("DUP{}",n),
+        SWAP(n) => format!("SWAP{}",n),
+        LOG(n) => format!("LOG{}",n),
+        _ => format!("{:?}",insn)
+    }
+}
+
+/// Allocate the label for a given tag (if not already done).
+fn label(code: &mut Bytecode, tags: &mut BTreeMap<String,usize>, tag: &str) -> usize {
+    *tags.entry(tag.to_string()).or_insert_with(|| code.fresh_label())
+}
+
+/// Convert bytes into hex (without a prefix), as solc does.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}",b)).collect()
+}
+
+/// Convert hex (without a prefix) into bytes, where an odd number of
+/// digits is padded with a leading zero.
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.is_empty() || !hex.is_ascii() { return None; }
+    let hex = if hex.len() % 2 == 1 { format!("0{}",hex) } else { hex.to_string() };
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i+2],16).ok()).collect()
+}
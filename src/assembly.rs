@@ -0,0 +1,357 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String,ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::ops::Range;
+use crate::{AbstractState,Backend,Bytecode,Diagnostic,Disassembly,Instruction,Location,Term};
+use crate::compiler::Compiler;
+use crate::opt::Provenance;
+use crate::util::from_be_bytes;
+
+// ============================================================================
+// Items
+// ============================================================================
+
+/// An instruction within an assembly, along with any information
+/// associated with it.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Item {
+    pub insn: Instruction,
+    /// Free-form comment attached to this instruction.
+    pub comment: Option<String>,
+    /// Where this instruction came from.  For compiled code this is a
+    /// span of source text, whilst for disassembled code it is a byte
+    /// offset.
+    pub location: Location,
+    /// Which optimisation pass produced this instruction, and from
+    /// what (if any).
+    pub provenance: Option<Provenance>
+}
+
+impl Item {
+    pub fn new(insn: Instruction) -> Self {
+        Item{insn, comment: None, location: Location::Unknown, provenance: None}
+    }
+
+    /// Attach a comment to this item.
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_string());
+        self
+    }
+
+    /// Attach a span of source characters to this item.
+    pub fn with_span(mut self, span: Range<usize>) -> Self {
+        self.location = Location::Span(span);
+        self
+    }
+
+    /// Attach a bytecode offset to this item.
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.location = Location::Offset(offset);
+        self
+    }
+
+    /// Attach the provenance of this item.
+    pub fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = Some(provenance);
+        self
+    }
+}
+
+// ============================================================================
+// Assembly
+// ============================================================================
+
+/// A sequence of instructions sitting between the IL and raw bytes.
+/// Unlike `Bytecode`, each instruction can carry a comment and a
+/// location, and labels can be given names.  An assembly can be
+/// produced either by compiling IL statements, or by lifting a
+/// disassembly.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct Assembly {
+    items: Vec<Item>,
+    /// Name of each label (if it has one), indexed by label.
+    labels: Vec<Option<String>>
+}
+
+impl Assembly {
+    pub fn new() -> Self {
+        Assembly{items: Vec::new(), labels: Vec::new()}
+    }
+
+    pub fn push(&mut self, insn: Instruction) {
+        self.items.push(Item::new(insn));
+    }
+
+    pub fn push_item(&mut self, item: Item) {
+        self.items.push(item);
+    }
+
+    /// Get access to the items in this assembly.
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// Get mutable access to the items in this assembly (e.g. to
+    /// attach comments).
+    pub fn items_mut(&mut self) -> &mut [Item] {
+        &mut self.items
+    }
+
+    /// Allocate a fresh (unnamed) label.
+    pub fn fresh_label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    /// Give a name to a given label.
+    pub fn name_label(&mut self, label: usize, name: &str) {
+        self.labels[label] = Some(name.to_string());
+    }
+
+    /// Get the name of a given label (if it has one).
+    pub fn label_name(&self, label: usize) -> Option<&str> {
+        self.labels.get(label)?.as_deref()
+    }
+
+    /// Compile a sequence of IL statements into an assembly.  Labels
+    /// in the resulting assembly are named after the corresponding
+    /// labels in the source.
+    pub fn compile(terms: &[Term]) -> Result<Self,Diagnostic> {
+        Self::compile_with_spans(terms,&[])
+    }
+
+    /// Compile a sequence of IL statements into an assembly, where
+    /// each statement came from a given span of source text (see
+    /// `Parser::spans()`).  Every instruction is located at the span
+    /// of the statement it was compiled from, as is any error which
+    /// is not otherwise located.
+    pub fn compile_with_spans(terms: &[Term], spans: &[Range<usize>]) -> Result<Self,Diagnostic> {
+        let current = Cell::new(None);
+        let mut backend = Locating{asm: Assembly::new(), current: &current};
+        let mut compiler = Compiler::new(&mut backend);
+        for (i,t) in terms.iter().enumerate() {
+            current.set(spans.get(i).cloned());
+            compiler.translate(t).map_err(|d| match (&d.location,spans.get(i)) {
+                (Location::Unknown|Location::Term(_),Some(span)) => d.with_span(span.clone()),
+                _ => d
+            })?;
+        }
+        let names = compiler.labels().clone();
+        let mut asm = backend.asm;
+        for (name,lab) in names {
+            asm.labels[lab] = Some(name);
+        }
+        Ok(asm)
+    }
+
+    /// Lift a disassembly into an assembly.  Each instruction is
+    /// located at its byte offset, and every `JUMPDEST` becomes a
+    /// label named after its offset.
+    pub fn lift<T:AbstractState>(disasm: &Disassembly<T>) -> Self {
+        let mut asm = Assembly::new();
+        let mut pc = 0;
+        //
+        for insn in disasm.to_vec() {
+            let len = insn.length(&[]);
+            let insn = match insn {
+                Instruction::JUMPDEST(_) => {
+                    let lab = asm.fresh_label();
+                    asm.name_label(lab,&format!("_{:#x}",pc));
+                    Instruction::JUMPDEST(lab)
+                }
+                _ => insn
+            };
+            asm.push_item(Item::new(insn).with_offset(pc));
+            pc = pc + len;
+        }
+        //
+        asm
+    }
+
+    /// Lift a disassembly into an assembly which can be patched (see
+    /// `replace()`, `insert()` and `delete()`).  This is the same as
+    /// `lift()`, except that any push of the location of a `JUMPDEST`
+    /// is assumed to give a jump target, and becomes a push of its
+    /// label.  Such jumps remain correct when instructions move,
+    /// though other uses of code locations (e.g. by `CODECOPY`) are
+    /// not adjusted.
+    pub fn lift_relocatable<T:AbstractState>(disasm: &Disassembly<T>) -> Self {
+        let mut asm = Self::lift(disasm);
+        let mut labels = BTreeMap::new();
+        for item in &asm.items {
+            if let (Instruction::JUMPDEST(l),Location::Offset(pc)) = (&item.insn,&item.location) {
+                labels.insert(*pc,*l);
+            }
+        }
+        for item in &mut asm.items {
+            match &item.insn {
+                Instruction::PUSH(bs) if !bs.is_empty() && bs.len() <= 8 => {
+                    if let Some(l) = labels.get(&(from_be_bytes(bs) as usize)) {
+                        item.insn = Instruction::PUSHL(*l);
+                    }
+                }
+                _ => {}
+            }
+        }
+        asm
+    }
+
+    /// Convert this assembly into a bytecode sequence, dropping all
+    /// comments, locations and label names.
+    pub fn to_bytecode(&self) -> Bytecode {
+        let mut bytecode = Bytecode::new();
+        for _ in &self.labels {
+            bytecode.fresh_label();
+        }
+        for item in &self.items {
+            bytecode.push(item.insn.clone());
+        }
+        bytecode
+    }
+}
+
+// ============================================================================
+// Patching
+// ============================================================================
+
+/// Identifies a problem patching an assembly.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+#[non_exhaustive]
+pub enum PatchError {
+    /// No instruction was originally located at this offset.
+    InvalidOffset(usize)
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::InvalidOffset(pc) => write!(f,"no instruction at {:#x}",pc)
+        }
+    }
+}
+
+impl Assembly {
+    /// Replace the instruction originally located at a given offset.
+    pub fn replace(&mut self, offset: usize, insn: Instruction) -> Result<(),PatchError> {
+        let i = self.index_of(offset)?;
+        self.items[i].insn = insn;
+        Ok(())
+    }
+
+    /// Insert instructions immediately before the instruction
+    /// originally located at a given offset.
+    pub fn insert(&mut self, offset: usize, insns: &[Instruction]) -> Result<(),PatchError> {
+        let i = self.index_of(offset)?;
+        let items = insns.iter().map(|insn| Item::new(insn.clone()));
+        self.items.splice(i..i,items);
+        Ok(())
+    }
+
+    /// Delete the instruction originally located at a given offset.
+    pub fn delete(&mut self, offset: usize) -> Result<(),PatchError> {
+        let i = self.index_of(offset)?;
+        self.items.remove(i);
+        Ok(())
+    }
+
+    /// Lay out this assembly as raw bytes, additionally returning the
+    /// new offset of every remaining instruction which has an original
+    /// offset (i.e. mapping old offsets to new).  This can be used to
+    /// adjust external references into the code.
+    pub fn relayout(&self) -> Result<(Vec<u8>,BTreeMap<usize,usize>),Diagnostic> {
+        let (bytes,map) = self.to_bytecode().to_bytes_with_map()?;
+        let mut offsets = BTreeMap::new();
+        for (i,item) in self.items.iter().enumerate() {
+            if let Location::Offset(pc) = item.location {
+                offsets.insert(pc,map.insn_offset(i).unwrap());
+            }
+        }
+        Ok((bytes,offsets))
+    }
+
+    fn index_of(&self, offset: usize) -> Result<usize,PatchError> {
+        self.items.iter().position(|i| i.location == Location::Offset(offset))
+            .ok_or(PatchError::InvalidOffset(offset))
+    }
+}
+
+/// A backend which builds an assembly, locating each instruction at
+/// the span of the statement being compiled (if known).
+struct Locating<'a> {
+    asm: Assembly,
+    /// Span of the statement currently being compiled.
+    current: &'a Cell<Option<Range<usize>>>
+}
+
+impl<'a> Backend for Locating<'a> {
+    fn fresh_label(&mut self) -> usize {
+        self.asm.fresh_label()
+    }
+
+    fn emit(&mut self, insn: Instruction) {
+        let item = Item::new(insn);
+        self.asm.push_item(match self.current.take() {
+            Some(span) => {
+                self.current.set(Some(span.clone()));
+                item.with_span(span)
+            }
+            None => item
+        });
+    }
+}
+
+impl From<&Bytecode> for Assembly {
+    fn from(bytecode: &Bytecode) -> Self {
+        let items = bytecode.instructions().iter().map(|i| Item::new(i.clone())).collect();
+        Assembly{items, labels: vec![None; bytecode.num_labels()]}
+    }
+}
+
+// ============================================================================
+// Display
+// ============================================================================
+
+/// Print one item per line.  Labels are shown by name where they have
+/// one, and comments, locations and provenance are appended (e.g.
+/// `JUMPDEST exit ; loop exit (at 0x1f) [merge-blocks from #9]`).
+impl fmt::Display for Assembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for item in &self.items {
+            match &item.insn {
+                Instruction::JUMPDEST(l) if self.label_name(*l).is_some() => {
+                    write!(f,"JUMPDEST {}",self.label_name(*l).unwrap())?;
+                }
+                Instruction::PUSHL(l) if self.label_name(*l).is_some() => {
+                    write!(f,"PUSHL {}",self.label_name(*l).unwrap())?;
+                }
+                insn => write!(f,"{}",insn)?
+            }
+            if let Some(c) = &item.comment {
+                write!(f," ; {}",c)?;
+            }
+            if item.location != Location::Unknown {
+                write!(f," (at {})",item.location)?;
+            }
+            if let Some(p) = &item.provenance {
+                write!(f," [{}]",p)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{Bytecode,Instruction,Term};
+
+// ============================================================================
+// Backends
+// ============================================================================
+
+/// A target for which IL can be compiled (see `Compiler`).  The
+/// compiler lowers each statement into a target-independent sequence
+/// of stack instructions, where branches are given using labels
+/// (i.e. `PUSHL` and `JUMPDEST`).  A backend then determines what is
+/// emitted for each (e.g. an EOF backend would use relative jumps).
+pub trait Backend {
+    /// Allocate a fresh label.
+    fn fresh_label(&mut self) -> usize;
+
+    /// Emit an instruction.
+    fn emit(&mut self, insn: Instruction);
+
+    /// Record a condition assumed to hold immediately before the next
+    /// instruction (see `Term::Assume`).  By default, this is ignored.
+    fn assume(&mut self, _cond: Term) {}
+}
+
+/// The EVM backend, which records every instruction as is.
+impl Backend for Bytecode {
+    fn fresh_label(&mut self) -> usize {
+        Bytecode::fresh_label(self)
+    }
+
+    fn emit(&mut self, insn: Instruction) {
+        self.push(insn)
+    }
+
+    fn assume(&mut self, cond: Term) {
+        Bytecode::assume(self,cond)
+    }
+}
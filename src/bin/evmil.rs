@@ -1,6 +1,6 @@
-use std::convert::TryFrom;
 use std::error::Error;
 use std::fs;
+use std::path::Path;
 
 use clap::{arg, Arg, ArgMatches, Command};
 use log::LevelFilter;
@@ -8,8 +8,9 @@ use log4rs::append::console::ConsoleAppender;
 use log4rs::config::{Appender, Config, Root};
 use log4rs::encode::pattern::{PatternEncoder};
 //
-use evmil::{Bytecode,Parser,ToHexString};
-use evmil::{FromHexString,Disassembly,CfaState,Instruction,AbstractState};
+use evmil::{Addresses,EvmVersion,FileLoader,FromHexString,Options,Pipeline,Selectors,StorageNames,ToHexString};
+use evmil::util::u256;
+use evmil::{compile_il_with,hexdump_hex};
 
 fn main() -> Result<(),Box<dyn Error>> {
     // Parse command-line arguments
@@ -22,11 +23,30 @@ fn main() -> Result<(),Box<dyn Error>> {
 	    Command::new("compile")
                 .about("Compile EvmIL code to EVM bytecode")
                 .arg(Arg::new("file").required(true))
+                .arg(arg!(--metadata "Append a metadata trailer identifying the compiler and source"))
+                .arg(Arg::new("evm-version").long("evm-version").takes_value(true)
+                     .help("Target a given version of the EVM (e.g. shanghai, which pushes zero using PUSH0)"))
                 .visible_alias("c"))
         .subcommand(
 	    Command::new("disassemble")
                 .about("Disassemble a raw hex string into EVM bytecode")
                 .arg(Arg::new("code").required(true))
+                .arg(Arg::new("data-from").long("data-from").takes_value(true)
+                     .help("Treat all bytes from this offset as data"))
+                .arg(arg!(--args "Treat bytes after the metadata as constructor arguments"))
+                .arg(arg!(--lint "Warn about unused values and potential security problems"))
+                .arg(Arg::new("sarif").long("sarif").takes_value(true)
+                     .help("Print problems as a SARIF log, identifying the bytecode using this URI"))
+                .arg(arg!(--explain "Annotate each block with why it is considered reachable"))
+                .arg(Arg::new("context-depth").long("context-depth").takes_value(true)
+                     .help("Distinguish states by the last few branch sites through which they are reached"))
+                .arg(arg!(--returns "Resolve function returns by tracking return addresses"))
+                .arg(arg!(--hexdump "Print a hex dump marking each block as code or data"))
+                .arg(Arg::new("signatures").long("signatures").takes_value(true)
+                     .help("Annotate selectors using a file of function signatures (one per line)"))
+                .arg(arg!(--addresses "Annotate constants which look like addresses"))
+                .arg(Arg::new("storage").long("storage").takes_value(true)
+                     .help("Name storage slots using a file of lines such as `0 = owner`, `\"my.app.config\" = config` (a hashed slot) or `\"eip1967.proxy.admin\" - 1 = admin` (a hashed slot less one)"))
                 .visible_alias("d"))
 	.get_matches();
     // Extract top-level flags
@@ -53,54 +73,88 @@ fn compile(args: &ArgMatches) -> Result<bool,Box<dyn Error>> {
     let filename = args.get_one::<String>("file").unwrap();
     // Read the test file
     let input = fs::read_to_string(filename)?;
-    // Parse test file
-    let terms = Parser::new(&input).parse()?;
-    // Translate statements into bytecode instructions
-    let code = Bytecode::try_from(terms.as_slice()).unwrap();
-    // Translate instructions into bytes
-    let bytes : Vec<u8> = code.try_into().unwrap();
-    // Print the final hex string
-    println!("{}",bytes.to_hex_string());
-    //
-    Ok(true)
+    // Determine the targeted version (if any)
+    let evm_version = match args.get_one::<String>("evm-version") {
+        Some(name) => Some(EvmVersion::from_name(name).ok_or_else(|| format!("unknown EVM version `{}`",name))?),
+        None => None
+    };
+    // Parse, compile and assemble the file
+    let options = Options {
+        metadata: args.is_present("metadata"),
+        evm_version,
+        ..Default::default()
+    };
+    // Imports are relative to the file's directory
+    let root = Path::new(filename).parent().unwrap_or(Path::new("."));
+    match compile_il_with(&input,&options,FileLoader::new(root)) {
+        Ok(bytes) => {
+            // Print the final hex string
+            println!("{}",bytes.to_hex_string());
+            Ok(true)
+        }
+        Err(d) => {
+            eprintln!("{}: {}",filename,d);
+            Ok(false)
+        }
+    }
 }
 
 /// Disassemble a given bytecode sequence.
 fn disassemble(args: &ArgMatches) -> Result<bool,Box<dyn Error>> {
     // Extract hex string to be disassembled.
     let hex = args.get_one::<String>("code").unwrap();
-    // Parse hex string into bytes
-    let bytes = hex.from_hex_string().unwrap();
-    // Construct disassembly
-    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
-    // Disassemble bytes into instructions
-    let instructions = disasm.to_vec();
-    // Print them all out.
-    let mut pc = 0;
-    for insn in instructions {
-        match insn {
-            Instruction::JUMPDEST(_) => {
-                let st = disasm.get_state(pc);
-                let len = st.stack().len();
-                println!("");
-                if len.is_constant() {
-                    println!("// Stack +{}",len.unwrap());
-                } else {
-                    println!("// Stack +{}",len);
-                }
-	        println!("{:#08x}: {}",pc,insn);
-            }
-            Instruction::JUMP|Instruction::JUMPI => {
-                let st = disasm.get_state(pc);
-                println!("{:#08x}: {} // {}",pc,insn,st.peek(0));
-            }
-            _ => {
-	        println!("{:#08x}: {}",pc,insn);
+    // Determine options
+    let options = Options {
+        data_from: args.get_one::<String>("data-from").map(|s| s.parse()).transpose()?,
+        constructor_args: args.is_present("args"),
+        lint: args.is_present("lint"),
+        explain: args.is_present("explain"),
+        context_depth: args.get_one::<String>("context-depth").map(|s| s.parse()).transpose()?.unwrap_or(0),
+        track_returns: args.is_present("returns"),
+        ..Default::default()
+    };
+    // Analyse once, then report any problems
+    let bytes = hex.from_hex_string()?;
+    let pipeline = Pipeline::new(&bytes,&options);
+    let report = pipeline.report();
+    if let Some(uri) = args.get_one::<String>("sarif") {
+        println!("{:#}",report.to_sarif(uri));
+        return Ok(true);
+    }
+    for d in report.diagnostics {
+        eprintln!("{}",d);
+    }
+    // Print hex dump (if requested)
+    if args.is_present("hexdump") {
+        print!("{}",hexdump_hex(hex,&options)?);
+        return Ok(true);
+    }
+    // Determine annotations
+    let mut selectors = Selectors::new();
+    if let Some(file) = args.get_one::<String>("signatures") {
+        for line in fs::read_to_string(file)?.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            selectors.insert(line);
+        }
+    }
+    let addresses = args.is_present("addresses").then(|| Addresses);
+    let mut slots = StorageNames::new();
+    if let Some(file) = args.get_one::<String>("storage") {
+        for line in fs::read_to_string(file)?.lines().filter(|l| !l.trim().is_empty()) {
+            let (slot,name) = line.split_once('=').ok_or("expected `slot = name`")?;
+            let (slot,name) = (slot.trim(),name.trim());
+            // A hashed slot may be offset (e.g. `"eip1967.proxy.admin" - 1`)
+            let (slot,less) = match slot.rsplit_once('-') {
+                Some((s,n)) if s.trim_end().ends_with('"') => (s.trim_end(),n.trim().parse::<u128>()?),
+                _ => (slot,0)
+            };
+            match slot.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+                Some(preimage) => slots.insert_hashed_less(preimage,less,name),
+                None => slots.insert(slot.parse::<u256>()?,name)
             }
         }
-        pc = pc + insn.length(&[]); // broken
     }
-    // TODO
+    // Print disassembly
+    print!("{}",pipeline.listing(&(selectors,(addresses,slots))));
     Ok(true)
 }
 
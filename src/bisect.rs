@@ -0,0 +1,75 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec::Vec;
+use crate::Instruction;
+
+// ============================================================================
+// Bisection
+// ============================================================================
+
+/// The outcome of bisecting a bytecode sequence.  This identifies the
+/// shortest failing prefix, along with the last instruction decoded
+/// in that prefix (i.e. the one which triggers the failure).
+#[derive(Debug,PartialEq)]
+pub struct Bisection {
+    /// Length (in bytes) of the shortest failing prefix.
+    pub prefix: usize,
+    /// Offset of the last instruction in the prefix.
+    pub pc: usize,
+    /// The last instruction in the prefix.
+    pub insn: Instruction
+}
+
+/// Find the shortest prefix of a bytecode sequence for which a given
+/// check fails.  Prefixes always end on an instruction boundary (as
+/// determined by a linear sweep).  This assumes that, once a prefix
+/// fails, all longer prefixes also fail, and uses a binary search.
+/// If the sequence as a whole does not fail, `None` is returned.  If
+/// the empty prefix fails (e.g. for an empty sequence), this is
+/// reported as the implicit `STOP` at offset zero.
+pub fn bisect<F>(bytes: &[u8], fails: F) -> Option<Bisection>
+where F: Fn(&[u8]) -> bool {
+    if !fails(bytes) { return None; }
+    if fails(&[]) { return Some(Bisection{prefix: 0, pc: 0, insn: Instruction::STOP}); }
+    // Determine instruction boundaries
+    let mut ends = Vec::new();
+    let mut pc = 0;
+    while pc < bytes.len() {
+        pc = pc + Instruction::decode(pc,bytes).length(&[]);
+        ends.push(pc);
+    }
+    // The last boundary may extend beyond a truncated push.
+    let n = ends.len();
+    ends[n-1] = bytes.len();
+    // Binary search for the shortest failing prefix.  The invariant
+    // is that prefix `ends[hi]` fails, whilst those before `lo` do not.
+    let mut lo = 0;
+    let mut hi = n - 1;
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if fails(&bytes[..ends[mid]]) { hi = mid; } else { lo = mid + 1; }
+    }
+    let prefix = ends[hi];
+    let pc = if hi == 0 { 0 } else { ends[hi-1] };
+    let insn = Instruction::decode(pc,&bytes[..prefix]);
+    Some(Bisection{prefix,pc,insn})
+}
+
+/// Determine whether building the control-flow analysis for a given
+/// bytecode sequence panics.  This is intended for use with
+/// `bisect()`.
+#[cfg(feature = "std")]
+pub fn cfa_panics(bytes: &[u8]) -> bool {
+    std::panic::catch_unwind(|| {
+        let _ = crate::Disassembly::<crate::CfaState>::new(bytes).build();
+    }).is_err()
+}
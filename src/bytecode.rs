@@ -9,10 +9,10 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use crate::{Term};
-use crate::instruction;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::{Diagnostic,Hint,Term};
 use crate::instruction::{Instruction,Offset};
-use crate::compiler;
 use crate::compiler::Compiler;
 
 // ============================================================================
@@ -26,12 +26,15 @@ pub struct Bytecode {
     /// The underlying bytecode sequence.
     bytecodes: Vec<Instruction>,
     /// Counts the number of labels
-    labels: usize
+    labels: usize,
+    /// Conditions assumed to hold immediately before the instruction
+    /// at a given index (see `Term::Assume`).
+    assumptions: Vec<(usize,Term)>
 }
 
 impl Bytecode {
     pub fn new() -> Self {
-        Bytecode{bytecodes:Vec::new(), labels:0}
+        Bytecode{bytecodes:Vec::new(), labels:0, assumptions:Vec::new()}
     }
 
     pub fn push(&mut self, insn: Instruction) {
@@ -43,6 +46,43 @@ impl Bytecode {
 	&self.bytecodes
     }
 
+    /// Get mutable access to the raw sequence of instructions.  This
+    /// is used by the optimisation passes to rewrite instructions in
+    /// place.  Since instructions may then move, any assumptions are
+    /// discarded.
+    pub(crate) fn instructions_mut(&mut self) -> &mut Vec<Instruction> {
+        self.assumptions.clear();
+        &mut self.bytecodes
+    }
+
+    /// Record a condition which is assumed to hold immediately before
+    /// the next instruction pushed.
+    pub fn assume(&mut self, cond: Term) {
+        self.assumptions.push((self.bytecodes.len(),cond));
+    }
+
+    /// Get the conditions assumed to hold, each with the index of the
+    /// instruction before which it holds.
+    pub fn assumptions(&self) -> &[(usize,Term)] {
+        &self.assumptions
+    }
+
+    /// Determine the hints which pass the assumptions made by this
+    /// bytecode sequence on to the analysis of its encoding (see
+    /// `Disassembly::with_hints()`).  An assumption which is not
+    /// followed by any instruction is dropped.
+    pub fn hints(&self) -> Result<Vec<Hint>,Diagnostic> {
+        let (_,map) = self.to_bytes_with_map()?;
+        Ok(self.assumptions.iter().filter_map(|(i,t)| {
+            map.insn_offset(*i).map(|pc| Hint::Assume(pc,t.clone()))
+        }).collect())
+    }
+
+    /// Return the number of labels allocated thus far.
+    pub fn num_labels(&self) -> usize {
+        self.labels
+    }
+
     /// Return the number of labels in the instruction sequence thus
     /// far.
     pub fn fresh_label(&mut self) -> usize {
@@ -56,16 +96,31 @@ impl Bytecode {
     /// ways.  For example, the target for a `PUSHL` does not match
     /// any known `JUMPEST` label; Or, the stack size is exceeded,
     /// etc.
-    pub fn to_bytes(&self) -> Result<Vec<u8>,instruction::Error> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>,Diagnostic> {
+        let mut bytes = Vec::new();
+        self.encode_into(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Translate this sequence of bytecode instructions into a
+    /// sequence of raw bytes, whilst also returning an `OffsetMap`
+    /// which identifies the byte offset of every instruction and
+    /// label in the resulting sequence.
+    pub fn to_bytes_with_map(&self) -> Result<(Vec<u8>,OffsetMap),Diagnostic> {
         let offsets = self.determine_offsets();
         let mut bytes = Vec::new();
+        let mut insns = Vec::with_capacity(self.bytecodes.len());
         //
         for b in &self.bytecodes {
+            // Record instruction offset
+            insns.push(bytes.len());
             // Encode instruction
-            b.encode(&offsets,&mut bytes)?;
+            b.encode(&offsets,&mut bytes).map_err(|e| Diagnostic::from(e).with_offset(insns[insns.len()-1]))?;
         }
+        // Extract label offsets
+        let labels = offsets.iter().map(|o| o.0 as usize).collect();
         // Done
-        Ok(bytes)
+        Ok((bytes,OffsetMap{insns,labels}))
     }
 
     /// Determine the offsets of all labels within the instruction
@@ -73,7 +128,7 @@ impl Bytecode {
     /// further away affect the overall size of the bytecode sequence
     /// (hence, a label can affect the offset of itself or other
     /// labels).
-    fn determine_offsets(&self) -> Vec<Offset> {
+    pub(crate) fn determine_offsets(&self) -> Vec<Offset> {
         // Construct initial set of empty offsets
         let mut offsets = vec![Offset(0); self.labels];
         // Iterate to a fixpoint.
@@ -109,6 +164,9 @@ impl Bytecode {
                     }
                 }
                 Instruction::PUSH(bs) => offset = offset + (bs.len() as u16),
+                Instruction::PUSHPAD(_,n) => offset = offset + (*n as u16),
+                Instruction::LINKREF(_) => offset = offset + 20,
+                Instruction::DATA(bs)|Instruction::PSEUDO(_,bs) => offset = offset + (bs.len() as u16) - 1,
                 Instruction::PUSHL(lab) => {
                     // This time calculate a more accurate figure.
                     offset = offset + offsets[*lab].width()
@@ -122,11 +180,64 @@ impl Bytecode {
     }
 }
 
+// ============================================================================
+// Offset Map
+// ============================================================================
+
+/// Records the byte offset of every instruction and label within an
+/// encoded `Bytecode` sequence.  This allows instructions produced by
+/// the compiler to be correlated with those recovered by the
+/// disassembler (or with program counters from an execution trace).
+#[derive(Clone,Debug,PartialEq)]
+pub struct OffsetMap {
+    /// Byte offset of each instruction (indexed by instruction).
+    insns: Vec<usize>,
+    /// Byte offset of each label (indexed by label).
+    labels: Vec<usize>
+}
+
+impl OffsetMap {
+    /// Determine the byte offset of the instruction at a given index
+    /// within the original `Bytecode` sequence.
+    pub fn insn_offset(&self, index: usize) -> Option<usize> {
+        self.insns.get(index).copied()
+    }
+
+    /// Determine the byte offset of a given label.
+    pub fn label_offset(&self, label: usize) -> Option<usize> {
+        self.labels.get(label).copied()
+    }
+
+    /// Determine the index of the instruction starting at a given
+    /// byte offset (if any).  Observe that offsets which fall within
+    /// the operands of an instruction (e.g. a `PUSH`) do not identify
+    /// an instruction.
+    pub fn insn_at(&self, offset: usize) -> Option<usize> {
+        // Instruction offsets are strictly increasing
+        self.insns.binary_search(&offset).ok()
+    }
+
+    /// Determine the label (if any) located at a given byte offset.
+    pub fn label_at(&self, offset: usize) -> Option<usize> {
+        self.labels.iter().position(|o| *o == offset)
+    }
+
+    /// Get the byte offsets of all instructions (in order).
+    pub fn insn_offsets(&self) -> &[usize] {
+        &self.insns
+    }
+
+    /// Get the byte offsets of all labels (indexed by label).
+    pub fn label_offsets(&self) -> &[usize] {
+        &self.labels
+    }
+}
+
 // ============================================================================
 // Helpers
 // ============================================================================
 
-fn try_from(terms: &[Term]) -> Result<Bytecode,compiler::Error> {
+fn try_from(terms: &[Term]) -> Result<Bytecode,Diagnostic> {
     let mut bytecode = Bytecode::new();
     let mut compiler = Compiler::new(&mut bytecode);
     // Translate statements one-by-one
@@ -144,7 +255,7 @@ fn try_from(terms: &[Term]) -> Result<Bytecode,compiler::Error> {
 /// Translate a sequence of IL statements into EVM bytecode, or fail
 /// with an error.
 impl TryFrom<&[Term]> for Bytecode {
-    type Error = compiler::Error;
+    type Error = Diagnostic;
 
     fn try_from(terms: &[Term]) -> Result<Bytecode,Self::Error> {
         try_from(terms)
@@ -154,7 +265,7 @@ impl TryFrom<&[Term]> for Bytecode {
 /// Translate a sequence of IL statements into EVM bytecode, or fail
 /// with an error.
 impl<const N: usize> TryFrom<&[Term;N]> for Bytecode {
-    type Error = compiler::Error;
+    type Error = Diagnostic;
 
     fn try_from(terms: &[Term;N]) -> Result<Bytecode,Self::Error> {
         try_from(terms)
@@ -165,7 +276,7 @@ impl<const N: usize> TryFrom<&[Term;N]> for Bytecode {
 /// can fail for a number of reasons (e.g. dangling branches, stack
 /// depth exceeded, etc).
 impl TryInto<Vec<u8>> for Bytecode {
-    type Error = instruction::Error;
+    type Error = Diagnostic;
 
     fn try_into(self) -> Result<Vec<u8>,Self::Error> {
         self.to_bytes()
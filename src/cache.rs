@@ -0,0 +1,168 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use serde_json::{json,Value};
+use crate::{AbstractState,Block,CfaState,Disassembly,ToHexString};
+use crate::util::keccak256;
+
+// ============================================================================
+// Analysis
+// ============================================================================
+
+/// Identifies the format of a stored analysis.  Since the results of
+/// an analysis can change between releases (as well as the format
+/// itself), this includes the crate version.  Stored analyses
+/// recorded with a different version are discarded.
+pub const ANALYSIS_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"),"/1");
+
+/// The results of a completed analysis of a bytecode sequence.  Unlike
+/// a `Disassembly`, these do not depend on the bytecode itself (or the
+/// abstract domain used), and can be stored and reloaded.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Analysis {
+    /// The blocks identified (in order).
+    pub blocks: Vec<Block>,
+    /// Whether each block is reachable.
+    pub reachable: Vec<bool>,
+    /// The successors of each block (i.e. the control-flow graph).
+    pub successors: Vec<Vec<usize>>
+}
+
+impl Analysis {
+    /// Convert this analysis into JSON.
+    pub fn to_json(&self) -> Value {
+        let blocks : Vec<Value> = self.blocks.iter().map(|b| json!([b.start,b.end])).collect();
+        json!({
+            "version": ANALYSIS_VERSION,
+            "blocks": blocks,
+            "reachable": self.reachable,
+            "successors": self.successors
+        })
+    }
+
+    /// Convert JSON back into an analysis, returning `None` if it is
+    /// malformed or was recorded by a different version.
+    pub fn from_json(json: &Value) -> Option<Self> {
+        if json["version"].as_str()? != ANALYSIS_VERSION { return None; }
+        let mut blocks = Vec::new();
+        for b in json["blocks"].as_array()? {
+            let start = b[0].as_u64()? as usize;
+            let end = b[1].as_u64()? as usize;
+            if start >= end { return None; }
+            blocks.push(Block::new(start,end));
+        }
+        let reachable = json["reachable"].as_array()?.iter().map(|r| r.as_bool()).collect::<Option<Vec<bool>>>()?;
+        let mut successors = Vec::new();
+        for s in json["successors"].as_array()? {
+            let ids = s.as_array()?.iter().map(|i| i.as_u64().map(|i| i as usize)).collect::<Option<Vec<usize>>>()?;
+            successors.push(ids);
+        }
+        if reachable.len() != blocks.len() || successors.len() != blocks.len() { return None; }
+        if successors.iter().flatten().any(|i| *i >= blocks.len()) { return None; }
+        //
+        Some(Analysis{blocks,reachable,successors})
+    }
+}
+
+impl<'a,T:AbstractState+fmt::Display> Disassembly<'a,T> {
+    /// Extract the results of this (built) disassembly.
+    pub fn analysis(&self) -> Analysis {
+        let n = self.blocks().len();
+        Analysis {
+            blocks: self.blocks().to_vec(),
+            reachable: (0..n).map(|i| self.is_block_reachable(i)).collect(),
+            successors: (0..n).map(|i| self.successors(i)).collect()
+        }
+    }
+}
+
+// ============================================================================
+// Cache
+// ============================================================================
+
+/// A cache of completed analyses keyed by the Keccak-256 hash of the
+/// bytecode.  If a directory is given, every analysis is also stored
+/// there (as JSON) so that it persists across runs.
+#[derive(Clone,Debug,Default)]
+pub struct AnalysisCache {
+    dir: Option<PathBuf>,
+    entries: BTreeMap<[u8;32],Analysis>
+}
+
+impl AnalysisCache {
+    /// Construct an empty cache held only in memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a cache which persists analyses in a given
+    /// directory (created if necessary).
+    pub fn with_dir<P:Into<PathBuf>>(dir: P) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(AnalysisCache{dir: Some(dir), entries: BTreeMap::new()})
+    }
+
+    /// Look up the analysis for a given bytecode sequence, loading it
+    /// from disk if necessary.  An unreadable (e.g. corrupt or out of
+    /// date) file is treated as missing.
+    pub fn get(&mut self, bytes: &[u8]) -> Option<&Analysis> {
+        let key = keccak256(bytes);
+        if !self.entries.contains_key(&key) {
+            let text = fs::read_to_string(self.path(&key)?).ok()?;
+            let analysis = Analysis::from_json(&serde_json::from_str(&text).ok()?)?;
+            self.entries.insert(key,analysis);
+        }
+        self.entries.get(&key)
+    }
+
+    /// Store the analysis for a given bytecode sequence.
+    pub fn insert(&mut self, bytes: &[u8], analysis: Analysis) -> io::Result<()> {
+        let key = keccak256(bytes);
+        if let Some(path) = self.path(&key) {
+            fs::write(path,analysis.to_json().to_string())?;
+        }
+        self.entries.insert(key,analysis);
+        Ok(())
+    }
+
+    /// Get the analysis for a given bytecode sequence, performing
+    /// (and storing) it only if it is not already cached.
+    pub fn analyse(&mut self, bytes: &[u8]) -> io::Result<Analysis> {
+        if let Some(analysis) = self.get(bytes) {
+            return Ok(analysis.clone());
+        }
+        let disasm : Disassembly<CfaState> = Disassembly::new(bytes).build();
+        let analysis = disasm.analysis();
+        self.insert(bytes,analysis.clone())?;
+        Ok(analysis)
+    }
+
+    /// Number of analyses currently held in memory.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether no analyses are currently held in memory.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn path(&self, key: &[u8;32]) -> Option<PathBuf> {
+        let name = format!("{}.json",key.to_hex_string());
+        self.dir.as_ref().map(|d| d.join(name))
+    }
+}
@@ -0,0 +1,84 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool,Ordering};
+
+// ============================================================================
+// Cancellation
+// ============================================================================
+
+/// Signals that an analysis should stop early.  This is checked
+/// regularly whilst iterating to a fixpoint, so that long running
+/// analyses can be bounded (e.g. by a deadline).
+pub trait Cancellation {
+    /// Check whether the analysis should stop.
+    fn is_cancelled(&self) -> bool;
+}
+
+/// Never cancels.
+impl Cancellation for () {
+    fn is_cancelled(&self) -> bool { false }
+}
+
+/// Cancels once the flag is set (e.g. by another thread).
+impl Cancellation for AtomicBool {
+    fn is_cancelled(&self) -> bool {
+        self.load(Ordering::Relaxed)
+    }
+}
+
+/// Cancels when either does.
+impl<S:Cancellation,T:Cancellation> Cancellation for (S,T) {
+    fn is_cancelled(&self) -> bool {
+        self.0.is_cancelled() || self.1.is_cancelled()
+    }
+}
+
+impl<C:Cancellation+?Sized> Cancellation for &C {
+    fn is_cancelled(&self) -> bool {
+        (**self).is_cancelled()
+    }
+}
+
+/// Cancels once a given point in time has passed.
+#[cfg(feature = "std")]
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Deadline(pub std::time::Instant);
+
+#[cfg(feature = "std")]
+impl Deadline {
+    /// Construct a deadline a given duration from now.
+    pub fn after(timeout: std::time::Duration) -> Self {
+        Deadline(std::time::Instant::now() + timeout)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Cancellation for Deadline {
+    fn is_cancelled(&self) -> bool {
+        std::time::Instant::now() >= self.0
+    }
+}
+
+/// The partial results of an analysis which was cancelled before
+/// reaching a fixpoint.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Cancelled<T>(pub T);
+
+/// The partial results of an analysis which did not reach a fixpoint
+/// within a given number of iterations.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Unconverged<T> {
+    pub partial: T,
+    /// Blocks whose contexts were still changing.
+    pub changing: Vec<usize>
+}
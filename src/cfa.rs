@@ -9,15 +9,20 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::{fmt};
-use crate::{Instruction,Instruction::*};
-use crate::{AbstractState};
+use alloc::vec::Vec;
+use core::{cmp,fmt};
+use core::ops::Range;
+use crate::{BinOp,Instruction,Instruction::*,Intrinsic,Term};
+use crate::{AbstractState,Disassembly,InstructionVisitor};
 use crate::dfa::{AbstractValue,AbstractStack,BOTTOM_STACK,EMPTY_STACK};
+use crate::dfa::{AbstractMemory,EMPTY_MEMORY,Symbol};
 use crate::util;
 use crate::util::Interval;
 
 const MAX_CODE_SIZE : u128 = 24576;
 const UNKNOWN : AbstractValue = AbstractValue::Unknown;
+/// Upper bound on the constants compared in a guard (see `guard()`).
+const GUARD_LIMIT : usize = 1 << 16;
 
 // ============================================================================
 // Disassembly Context
@@ -25,13 +30,17 @@ const UNKNOWN : AbstractValue = AbstractValue::Unknown;
 
 #[derive(Debug,PartialEq)]
 pub struct CfaState {
-    stack: AbstractStack
+    stack: AbstractStack,
+    memory: AbstractMemory,
+    /// Bounds on opaque values which are assumed to hold (see
+    /// `Hint::Assume`).
+    bounds: Vec<(Symbol,Interval)>
 }
 
 impl CfaState {
     pub fn new(stack: AbstractStack) -> Self {
         // Done
-        Self{stack}
+        Self{stack, memory: EMPTY_MEMORY, bounds: Vec::new()}
     }
     pub fn is_bottom(&self) -> bool {
         self.stack.is_bottom()
@@ -40,40 +49,274 @@ impl CfaState {
     pub fn stack(&self) -> &AbstractStack{
         &self.stack
     }
-    pub fn push(self, val: AbstractValue) -> Self {
-        CfaState::new(self.stack.push(val))
+    /// Access the memory component of this abstract EVM.
+    pub fn memory(&self) -> &AbstractMemory{
+        &self.memory
+    }
+    /// Access the bounds on opaque values which hold in this state
+    /// (e.g. because of an assumption, or a branch taken).
+    pub fn bounds(&self) -> &[(Symbol,Interval)] {
+        &self.bounds
+    }
+    pub fn push(mut self, val: AbstractValue) -> Self {
+        self.stack = self.stack.push(val);
+        self
     }
     pub fn pop(mut self, n: usize) -> Self {
         assert!(n > 0);
-        let mut stack = self.stack;
-        for i in 0..n {
-            stack = stack.pop();
+        for _i in 0..n {
+            self.stack = self.stack.pop();
         }
-        CfaState::new(stack)
+        self
+    }
+    pub fn set(mut self, n:usize, val: AbstractValue) -> Self {
+        self.stack = self.stack.set(n,val);
+        self
+    }
+    /// Load a word from memory at the offset given on top of the
+    /// stack.
+    fn mload(self) -> Self {
+        let val = self.memory.read(self.peek(0));
+        self.pop(1).push(val)
+    }
+    /// Store the second word on the stack into memory at the offset
+    /// given on top of the stack.
+    fn mstore(mut self) -> Self {
+        self.memory.write(self.peek(0),self.peek(1));
+        self.pop(2)
+    }
+    /// Push an opaque value read from the execution environment by a
+    /// given instruction which has no operands (e.g. `CALLVALUE`).
+    fn read_env(self, insn: &Instruction) -> Self {
+        let opcode = insn.opcode(&[]).unwrap();
+        self.push(AbstractValue::Opaque(Symbol::new(opcode,None)))
+    }
+    /// Push an opaque value read from the execution environment by a
+    /// given instruction with one operand (e.g. `BALANCE`).  Unless
+    /// the operand is known, the result is simply unknown.
+    fn read_env_at(self, insn: &Instruction) -> Self {
+        let opcode = insn.opcode(&[]).unwrap();
+        let val = match self.peek(0) {
+            AbstractValue::Known(n) => AbstractValue::Opaque(Symbol::new(opcode,Some(n))),
+            _ => UNKNOWN
+        };
+        self.pop(1).push(val)
     }
-    pub fn set(self, n:usize, val: AbstractValue) -> Self {
-        CfaState::new(self.stack.set(n,val))
+    /// Forget all volatile values (e.g. account balances), which
+    /// might be changed by an instruction such as `CALL`.
+    fn forget_volatile(mut self) -> Self {
+        let forget = |v: AbstractValue| if v.is_volatile() { UNKNOWN } else { v };
+        self.stack = self.stack.map(forget);
+        self.memory.retain(|v| !v.is_volatile());
+        self.bounds.retain(|(s,_)| !s.is_volatile());
+        self
+    }
+    /// Compare the top two stack items for equality.  Since opaque
+    /// values are fixed, identical values are known to be equal.
+    /// Likewise, values whose bounds are disjoint are known to differ.
+    /// Observe that known values are never truncated (see
+    /// `constant()`), so can be compared directly.
+    fn equals(self) -> Self {
+        let val = match (self.peek(0),self.peek(1)) {
+            (AbstractValue::Unknown,_)|(_,AbstractValue::Unknown) => UNKNOWN,
+            (x,y) if x == y => AbstractValue::Known(1),
+            (AbstractValue::Known(_),AbstractValue::Known(_)) => AbstractValue::Known(0),
+            (x,y) => match (self.bound(x),self.bound(y)) {
+                (Some(i),Some(j)) if less(i,j) == Some(true) || less(j,i) == Some(true) => {
+                    return self.pop(2).decide(&EQ,false);
+                }
+                (_,_) => UNKNOWN
+            }
+        };
+        self.pop(2).push(val)
+    }
+    /// Compare the top two stack items (at least one of which is
+    /// opaque) using a given comparison of their bounds.
+    fn compare<F:Fn(Interval,Interval)->Option<bool>>(self, insn: &Instruction, cmp: F) -> Self {
+        let outcome = match (self.peek(0),self.peek(1)) {
+            (AbstractValue::Known(_),AbstractValue::Known(_)) => None,
+            (x,y) => match (self.bound(x),self.bound(y)) {
+                (Some(i),Some(j)) => cmp(i,j),
+                (_,_) => None
+            }
+        };
+        match outcome {
+            Some(b) => self.pop(2).decide(insn,b),
+            None => {
+                // Otherwise, the outcome may refine the operand later on
+                let val = guard(insn,self.peek(0),self.peek(1)).map_or(UNKNOWN,AbstractValue::Opaque);
+                self.pop(2).push(val)
+            }
+        }
+    }
+    /// Determine whether the opaque value on top of the stack is zero
+    /// (which is decided when its bounds exclude zero).
+    fn iszero(self) -> Self {
+        let outcome = match self.peek(0) {
+            AbstractValue::Opaque(s) => match self.bound(AbstractValue::Opaque(s)) {
+                Some(i) if i.start > 0 => Some(false),
+                Some(i) if i.end == 0 => Some(true),
+                _ => None
+            },
+            _ => None
+        };
+        // Negating a guard gives another
+        let negated = match self.peek(0) {
+            AbstractValue::Opaque(c) => condition(c).and_then(|(s,i)| complement(i).and_then(|j| guard_symbol(s,j))),
+            _ => None
+        };
+        match (outcome,negated) {
+            (Some(b),_) => self.pop(1).decide(&ISZERO,b),
+            (None,Some(c)) => self.pop(1).push(AbstractValue::Opaque(c)),
+            (None,None) => self.pop(1).push(UNKNOWN)
+        }
+    }
+    /// Pop the operands of a `JUMPI` whose branch is (or is not)
+    /// taken.  When its condition is a guard on some opaque value
+    /// (e.g. `calldatasize() < 4`), that value is constrained
+    /// accordingly.
+    fn jumpi(self, taken: bool) -> Self {
+        let refinement = match self.peek(1) {
+            AbstractValue::Opaque(c) => condition(c).and_then(|(s,i)| {
+                let i = if taken { Some(i) } else { complement(i) };
+                i.map(|i| (s,i))
+            }),
+            _ => None
+        };
+        match refinement {
+            Some((s,i)) => self.pop(2).constrain(s,i),
+            None => self.pop(2)
+        }
+    }
+    /// Push the outcome of a comparison decided by the bounds of its
+    /// operands.  This is an opaque value bounded by the outcome
+    /// (rather than a known value) so that, unlike a constant, it
+    /// decides the branch of a `JUMPI`.
+    fn decide(self, insn: &Instruction, outcome: bool) -> Self {
+        let s = Symbol::new(insn.opcode(&[]).unwrap(),Some(outcome as usize));
+        let b = outcome as usize;
+        self.push(AbstractValue::Opaque(s)).constrain(s,Interval::new(b,b))
+    }
+    /// Determine whether the condition of a `JUMPI` (i.e. the second
+    /// item on the stack) is decided by its bounds.
+    fn decided(&self) -> Option<bool> {
+        match self.peek(1) {
+            AbstractValue::Opaque(s) => match self.bound(AbstractValue::Opaque(s)) {
+                Some(i) if i.start > 0 => Some(true),
+                Some(i) if i.end == 0 => Some(false),
+                _ => None
+            },
+            _ => None
+        }
+    }
+    /// Determine bounds on a given value (if there are any).
+    fn bound(&self, val: AbstractValue) -> Option<Interval> {
+        match val {
+            AbstractValue::Known(n) => Some(Interval::new(n,n)),
+            AbstractValue::Opaque(s) => self.bounds.iter().find(|(t,_)| *t == s).map(|(_,i)| *i),
+            AbstractValue::Unknown => None
+        }
+    }
+    /// Restrict the bounds of an opaque value.  If these become
+    /// empty, then this state is unreachable.
+    fn constrain(mut self, sym: Symbol, range: Interval) -> Self {
+        if self.is_bottom() { return self; }
+        let range = match self.bounds.iter().position(|(t,_)| *t == sym) {
+            Some(k) => {
+                let (_,i) = self.bounds.remove(k);
+                (cmp::max(i.start,range.start),cmp::min(i.end,range.end))
+            }
+            None => (range.start,range.end)
+        };
+        if range.0 > range.1 { return CfaState::bottom(); }
+        self.bounds.push((sym,Interval::new(range.0,range.1)));
+        self
+    }
+    /// Merge the bounds of another state into this, such that only
+    /// bounds on values which both have are kept.  This returns
+    /// whether anything changed.
+    fn merge_bounds(&mut self, other: &[(Symbol,Interval)]) -> bool {
+        let mut changed = false;
+        self.bounds.retain_mut(|(s,i)| match other.iter().find(|(t,_)| t == s) {
+            Some((_,j)) => {
+                let k = i.union(j);
+                changed |= k != *i;
+                *i = k;
+                true
+            }
+            None => {
+                changed = true;
+                false
+            }
+        });
+        changed
+    }
+    /// Store a single byte into memory at the offset given on top of
+    /// the stack.
+    fn mstore8(mut self) -> Self {
+        self.memory.invalidate(self.peek(0),AbstractValue::Known(1));
+        self.pop(2)
+    }
+    /// Copy a region of the executing bytecode into memory (i.e.
+    /// `CODECOPY`).  When the region is known, each whole word copied
+    /// is known as well (provided it is small enough to be tracked).
+    fn codecopy(mut self, code: &[u8]) -> Self {
+        self.memory.invalidate(self.peek(0),self.peek(2));
+        if let (AbstractValue::Known(dest),AbstractValue::Known(offset),AbstractValue::Known(len)) = (self.peek(0),self.peek(1),self.peek(2)) {
+            let mut i = 0;
+            while i + 32 <= len {
+                // Bytes beyond the end of the code are read as zero
+                let word : Vec<u8> = (offset+i..offset+i+32).map(|j| code.get(j).copied().unwrap_or(0)).collect();
+                self.memory.write(AbstractValue::Known(dest+i),constant(&word));
+                i = i + 32;
+            }
+        }
+        self.pop(3)
+    }
+    /// Account for an instruction which writes a region of memory,
+    /// where `offset` and `len` identify stack items holding the
+    /// region's offset and length.  Since such instructions pop
+    /// several operands, these are popped as well.
+    fn mwrite(mut self, offset: usize, len: usize, n: usize) -> Self {
+        self.memory.invalidate(self.peek(offset),self.peek(len));
+        self.pop(n)
     }
 }
 
 impl Clone for CfaState {
     fn clone(&self) -> Self {
-        CfaState::new(self.stack.clone())
+        CfaState{stack: self.stack.clone(), memory: self.memory.clone(), bounds: self.bounds.clone()}
     }
 }
 
 impl fmt::Display for CfaState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f,"{}",self.stack)
+        write!(f,"{}",self.stack)?;
+        if !self.memory.is_empty() {
+            write!(f,"{}",self.memory)?;
+        }
+        for (s,i) in &self.bounds {
+            if i.end == usize::MAX {
+                write!(f,"{{{}:{:#x}..}}",s,i.start)?;
+            } else {
+                write!(f,"{{{}:{:#x}..{:#x}}}",s,i.start,i.end)?;
+            }
+        }
+        Ok(())
     }
 }
 
 impl AbstractState for CfaState {
+    type Value = AbstractValue;
+
     fn is_reachable(&self) -> bool { !self.stack.is_bottom() }
 
     fn branch(&self, _pc: usize, insn: &Instruction) -> Self {
         match insn {
-            JUMPI => self.clone().pop(2),
+            _ if self.is_bottom() => CfaState::bottom(),
+            // A condition decided to be false is never taken
+            JUMPI if self.decided() == Some(false) => CfaState::bottom(),
+            JUMPI => self.clone().jumpi(true),
             JUMP => self.clone().pop(1),
             _ => {
                 unreachable!()
@@ -81,7 +324,13 @@ impl AbstractState for CfaState {
         }
     }
 
+    fn height(&self) -> Option<Interval> {
+        if self.is_bottom() { None } else { Some(self.stack.len()) }
+    }
+
     fn peek(&self, n: usize) -> AbstractValue {
+        // Nothing is known about an unreachable state
+        if self.is_bottom() { return UNKNOWN; }
         self.stack.peek(n)
     }
 
@@ -92,13 +341,44 @@ impl AbstractState for CfaState {
                     *self = other;
                     return true;
                 } else {
-                    return self.stack.merge_into(&other.stack);
+                    let changed = self.stack.merge_into(&other.stack);
+                    let changed = self.merge_bounds(&other.bounds) || changed;
+                    return self.memory.merge_into(&other.memory) || changed;
                 }
             }
         }
         false
     }
 
+    fn transfer_in(self, insn: &Instruction, code: &[u8]) -> Self {
+        match insn {
+            _ if self.is_bottom() => self,
+            // The executing code is known, hence so is its size.
+            CODESIZE => self.push(AbstractValue::Known(code.len())),
+            CODECOPY => self.codecopy(code),
+            _ => self.transfer(insn)
+        }
+    }
+
+    fn assume(self, cond: &Term) -> Self {
+        match cond {
+            Term::Binary(BinOp::LogicalAnd,l,r) => self.assume(l).assume(r),
+            Term::Binary(bop,l,r) => {
+                // Normalise the symbol to the left
+                let (bop,l,r) = match (symbol(l),bop.commute()) {
+                    (None,Some(op)) => (op,r,l),
+                    (_,_) => (*bop,l,r)
+                };
+                match (symbol(l),literal(r).and_then(|n| range(bop,n))) {
+                    (Some(s),Some(i)) => self.constrain(s,i),
+                    (Some(_),None) if bop == BinOp::LessThan && literal(r) == Some(0) => CfaState::bottom(),
+                    (_,_) => self
+                }
+            }
+            _ => self
+        }
+    }
+
     fn bottom() -> Self { CfaState::new(BOTTOM_STACK) }
 
     fn origin() -> Self {
@@ -112,6 +392,9 @@ impl AbstractState for CfaState {
     /// Update an abstract state with the effects of a given instruction.
     fn transfer(self, insn: &Instruction) -> CfaState {
         match insn {
+            // An unreachable state remains so (e.g. after contradictory
+            // assumptions)
+            _ if self.is_bottom() => self,
             STOP => CfaState::bottom(),
             // 0s: Stop and Arithmetic Operations
             ADD|MUL|SUB|DIV|SDIV|MOD|SMOD|EXP|SIGNEXTEND => {
@@ -121,13 +404,17 @@ impl AbstractState for CfaState {
                 self.pop(3).push(UNKNOWN)
             }
             // 0s: Stop and Arithmetic Operations
-            ISZERO|NOT => {
+            ISZERO => self.iszero(),
+            NOT => {
                 self.pop(1).push(UNKNOWN)
             }
             // Binary Comparators
-            LT|GT|SLT|SGT|EQ => {
+            LT => self.compare(insn,less),
+            GT => self.compare(insn,|i,j| less(j,i)),
+            SLT|SGT => {
                 self.pop(2).push(UNKNOWN)
             }
+            EQ => self.equals(),
             // Binary bitwise operators
             AND|OR|XOR|BYTE|SHL|SHR|SAR => {
                 self.pop(2).push(UNKNOWN)
@@ -140,49 +427,52 @@ impl AbstractState for CfaState {
                 self.pop(2).push(UNKNOWN)
             }
             // 30s: Environmental Information
-            ADDRESS => self.push(UNKNOWN),
-            BALANCE => self.pop(1).push(UNKNOWN),
-            ORIGIN => self.push(UNKNOWN),
-            CALLER => self.push(UNKNOWN),
-            CALLVALUE => self.push(UNKNOWN),
+            ADDRESS => self.read_env(insn),
+            BALANCE => self.read_env_at(insn),
+            ORIGIN => self.read_env(insn),
+            CALLER => self.read_env(insn),
+            CALLVALUE => self.read_env(insn),
             CALLDATALOAD => self.pop(1).push(UNKNOWN),
-            CALLDATASIZE => self.push(UNKNOWN),
-            CALLDATACOPY => self.pop(3),
-            CODESIZE => self.push(UNKNOWN),
-            CODECOPY => self.pop(3),
-            GASPRICE => self.push(UNKNOWN),
-            EXTCODESIZE => self.pop(1).push(UNKNOWN),
-            EXTCODECOPY => self.pop(4),
+            CALLDATASIZE => self.read_env(insn),
+            CALLDATACOPY => self.mwrite(0,2,3),
+            CODESIZE => self.read_env(insn),
+            CODECOPY => self.mwrite(0,2,3),
+            GASPRICE => self.read_env(insn),
+            EXTCODESIZE => self.read_env_at(insn),
+            EXTCODECOPY => self.mwrite(1,3,4),
             RETURNDATASIZE => self.push(UNKNOWN),
-            RETURNDATACOPY => self.pop(3),
-            EXTCODEHASH => self.pop(1).push(UNKNOWN),
+            RETURNDATACOPY => self.mwrite(0,2,3),
+            EXTCODEHASH => self.read_env_at(insn),
             // 40s: Block Information
-            BLOCKHASH => self.pop(1).push(UNKNOWN),
-            COINBASE => self.push(UNKNOWN),
-            TIMESTAMP => self.push(UNKNOWN),
-            NUMBER   => self.push(UNKNOWN),
-            DIFFICULTY => self.push(UNKNOWN),
-            GASLIMIT => self.push(UNKNOWN),
-            CHAINID => self.push(UNKNOWN),
-            SELFBALANCE => self.push(UNKNOWN),
+            BLOCKHASH => self.read_env_at(insn),
+            COINBASE => self.read_env(insn),
+            TIMESTAMP => self.read_env(insn),
+            NUMBER   => self.read_env(insn),
+            DIFFICULTY => self.read_env(insn),
+            GASLIMIT => self.read_env(insn),
+            CHAINID => self.read_env(insn),
+            SELFBALANCE => self.read_env(insn),
+            BASEFEE => self.read_env(insn),
+            BLOBHASH => self.read_env_at(insn),
+            BLOBBASEFEE => self.read_env(insn),
             // 50s: Stack, Memory, Storage and Flow Operations
             POP => self.pop(1),
-            MLOAD => self.pop(1).push(UNKNOWN),
-            MSTORE|MSTORE8 => self.pop(2),
+            MLOAD => self.mload(),
+            MSTORE => self.mstore(),
+            MSTORE8 => self.mstore8(),
             SLOAD => self.pop(1).push(UNKNOWN),
             SSTORE => self.pop(2),
-            JUMPI => self.pop(2),
+            // A condition decided to be true is always taken
+            JUMPI if self.decided() == Some(true) => CfaState::bottom(),
+            JUMPI => self.jumpi(false),
             PC|MSIZE|GAS => self.push(UNKNOWN),
             JUMPDEST(_) => self, // nop
+            TLOAD => self.pop(1).push(UNKNOWN),
+            TSTORE => self.pop(2),
+            MCOPY => self.mwrite(0,2,3),
             // 60 & 70s: Push Operations
-            PUSH(bytes) => {
-                let n = util::from_be_bytes(&bytes);
-                if n <= MAX_CODE_SIZE {
-                    self.push(AbstractValue::Known(n as usize))
-                } else {
-                    self.push(UNKNOWN)
-                }
-            }
+            PUSH(bytes)|PUSHPAD(bytes,_) => self.push(constant(bytes)),
+            LINKREF(_) => self.push(UNKNOWN),
             // 80s: Duplicate Operations
             DUP(n) => {
                 let m = (*n - 1) as usize;
@@ -201,14 +491,16 @@ impl AbstractState for CfaState {
                 self.pop((n+2) as usize)
             }
             // f0s: System Operations
-            CREATE => self.pop(3).push(UNKNOWN),
-            CALL|CALLCODE => self.pop(7).push(UNKNOWN),
-            DELEGATECALL|STATICCALL => self.pop(6).push(UNKNOWN),
-            CREATE2 => self.pop(4).push(UNKNOWN),
-            INVALID|JUMP|RETURN|REVERT => {
+            CREATE => self.forget_volatile().pop(3).push(UNKNOWN),
+            CALL|CALLCODE => self.forget_volatile().mwrite(5,6,7).push(UNKNOWN),
+            DELEGATECALL|STATICCALL => self.forget_volatile().mwrite(4,5,6).push(UNKNOWN),
+            CREATE2 => self.forget_volatile().pop(4).push(UNKNOWN),
+            DATA(_)|INVALID|JUMP|RETURN|REVERT => {
                 CfaState::bottom()
             }
             SELFDESTRUCT => self.pop(1),
+            // Pseudo-instructions are opaque
+            PSEUDO(..) => self,
             _ => {
                 // This is a catch all to ensure no instructions are
                 // missed above.
@@ -217,3 +509,178 @@ impl AbstractState for CfaState {
         }
     }
 }
+
+// ============================================================================
+// Assumptions
+// ============================================================================
+
+/// Determine whether every value within one interval is less than
+/// every value in another (or vice versa), or `None` if neither holds.
+/// An interval which ends at `usize::MAX` has no upper bound.
+fn less(i: Interval, j: Interval) -> Option<bool> {
+    if i.end < j.start {
+        Some(true)
+    } else if j.end <= i.start && j.end != usize::MAX {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Determine the symbol denoting the (undecided) outcome of a given
+/// comparison between two values, where one is opaque and the other
+/// known (e.g. `calldatasize() < 4`).  This is a _guard_, which
+/// constrains the opaque value once a branch is taken on it.
+fn guard(insn: &Instruction, x: AbstractValue, y: AbstractValue) -> Option<Symbol> {
+    use AbstractValue::{Known,Opaque};
+    // Comparisons are of the top item (x) with the next (y)
+    let (s,i) = match (insn,x,y) {
+        (LT,Opaque(s),Known(n)) if n > 0 => (s,Interval::new(0,n-1)),
+        (LT,Known(n),Opaque(s)) => (s,Interval::new(n+1,usize::MAX)),
+        (GT,Opaque(s),Known(n)) => (s,Interval::new(n+1,usize::MAX)),
+        (GT,Known(n),Opaque(s)) if n > 0 => (s,Interval::new(0,n-1)),
+        (_,_,_) => { return None; }
+    };
+    guard_symbol(s,i)
+}
+
+/// Determine the symbol denoting a guard which holds when a given
+/// opaque value lies within a given interval, which must either start
+/// at zero or have no upper bound.  This is identified by an `LT`
+/// whose operand encodes both (so identical guards are equal), and
+/// which can be decoded using `condition()`.  Only values which are
+/// fixed and have no operand (e.g. `calldatasize()`) can be guarded.
+fn guard_symbol(s: Symbol, i: Interval) -> Option<Symbol> {
+    let (side,k) = if i.start == 0 { (0,i.end) } else if i.end == usize::MAX { (1,i.start) } else { return None; };
+    if s.operand.is_some() || s.is_volatile() || k >= GUARD_LIMIT { return None; }
+    let key = (((s.opcode as usize) << 1 | side) << 16) | k;
+    Some(Symbol::new(LT.opcode(&[]).unwrap(),Some(key)))
+}
+
+/// Determine the opaque value constrained by a guard, along with the
+/// values for which it holds (see `guard_symbol()`).  Since the
+/// opcode of a guarded value is never zero, this is distinct from the
+/// outcome of an `LT` decided by bounds (see `decide()`).
+fn condition(c: Symbol) -> Option<(Symbol,Interval)> {
+    let key = c.operand.filter(|k| *k >= 2 * GUARD_LIMIT)?;
+    if c.opcode != LT.opcode(&[]).unwrap() { return None; }
+    let s = Symbol::new((key >> 17) as u8,None);
+    let k = key % GUARD_LIMIT;
+    let i = if (key >> 16) & 1 == 0 { Interval::new(0,k) } else { Interval::new(k,usize::MAX) };
+    Some((s,i))
+}
+
+/// Determine the values outside an interval which starts at zero or
+/// has no upper bound (if there are any).
+fn complement(i: Interval) -> Option<Interval> {
+    if i.start == 0 && i.end < usize::MAX {
+        Some(Interval::new(i.end+1,usize::MAX))
+    } else if i.start > 0 && i.end == usize::MAX {
+        Some(Interval::new(0,i.start-1))
+    } else {
+        None
+    }
+}
+
+/// Determine the values of `x` for which `x bop n` holds (if this can
+/// be expressed as an interval).
+fn range(bop: BinOp, n: usize) -> Option<Interval> {
+    match bop {
+        BinOp::Equals => Some(Interval::new(n,n)),
+        BinOp::NotEquals if n == 0 => Some(Interval::new(1,usize::MAX)),
+        BinOp::LessThan if n > 0 => Some(Interval::new(0,n-1)),
+        BinOp::LessThanOrEquals => Some(Interval::new(0,n)),
+        BinOp::GreaterThan if n < usize::MAX => Some(Interval::new(n+1,usize::MAX)),
+        BinOp::GreaterThanOrEquals => Some(Interval::new(n,usize::MAX)),
+        _ => None
+    }
+}
+
+/// Determine the symbol denoting the value of an IL expression which
+/// reads the execution environment (e.g. `calldatasize()`).
+fn symbol(term: &Term) -> Option<Symbol> {
+    let (insn,operand) = match term {
+        Term::Intrinsic(Intrinsic::CallDataSize,_) => (CALLDATASIZE,None),
+        Term::Intrinsic(Intrinsic::MsgSender,_) => (CALLER,None),
+        Term::Intrinsic(Intrinsic::MsgValue,_) => (CALLVALUE,None),
+        Term::Intrinsic(Intrinsic::Balance,args) if args.len() == 1 => (BALANCE,Some(literal(&args[0])?)),
+        _ => { return None; }
+    };
+    Some(Symbol::new(insn.opcode(&[]).unwrap(),operand))
+}
+
+/// Determine the value of an IL literal, provided it fits into a
+/// `usize`.
+fn literal(term: &Term) -> Option<usize> {
+    let (digits,radix) = match term {
+        Term::Int(digits) => (digits,10),
+        Term::Hex(digits) => (digits,16),
+        _ => { return None; }
+    };
+    let n = digits.iter().position(|d| *d != 0).unwrap_or(digits.len());
+    if digits.len() - n > 19 { return None; }
+    usize::try_from(util::from_be_digits(&digits[n..],radix)).ok()
+}
+
+// ============================================================================
+// Stack Bounds
+// ============================================================================
+
+impl<'a> Disassembly<'a,CfaState> {
+    /// Determine bounds on the height of the stack at a given program
+    /// location, which must be the start of an instruction.  This
+    /// returns the lower bound and, if one is known, the upper bound.
+    /// No upper bound is known for an unreachable location.
+    pub fn stack_bounds(&self, pc: usize) -> (usize,Option<usize>) {
+        let st = self.get_state(pc);
+        if st.is_bottom() {
+            (0,None)
+        } else {
+            (st.stack.min_len(),Some(st.stack.max_len()))
+        }
+    }
+}
+
+// ============================================================================
+// Code Copies
+// ============================================================================
+
+impl<'a> Disassembly<'a,CfaState> {
+    /// Identify the regions of bytecode copied into memory by
+    /// reachable `CODECOPY` instructions with known arguments (e.g.
+    /// the runtime code returned by a constructor).  Each region is
+    /// given with the location of the `CODECOPY` which copies it.
+    pub fn code_copies(&self) -> Vec<(usize,Range<usize>)> {
+        let mut copies = CodeCopies(Vec::new());
+        self.visit_instructions(&mut copies);
+        copies.0
+    }
+}
+
+/// Collects the regions copied by `CODECOPY` instructions.
+struct CodeCopies(Vec<(usize,Range<usize>)>);
+
+impl InstructionVisitor<CfaState> for CodeCopies {
+    fn visit_instruction(&mut self, pc: usize, insn: &Instruction, state: &CfaState) {
+        if *insn == CODECOPY {
+            if let (AbstractValue::Known(offset),AbstractValue::Known(len)) = (state.peek(1),state.peek(2)) {
+                self.0.push((pc,offset..offset+len));
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Determine the abstract value of a big-endian constant (e.g. the
+/// operand of a `PUSH`).  This is known only when small enough to be
+/// a code offset, noting that any bytes beyond the low sixteen must
+/// be zero (as these would otherwise be lost).
+fn constant(bytes: &[u8]) -> AbstractValue {
+    let k = bytes.len().saturating_sub(16);
+    if bytes[..k].iter().any(|b| *b != 0) { return UNKNOWN; }
+    let n = util::from_be_bytes(&bytes[k..]);
+    if n <= MAX_CODE_SIZE { AbstractValue::Known(n as usize) } else { UNKNOWN }
+}
@@ -9,11 +9,22 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::collections::HashMap;
-use crate::{BinOp,Bytecode,Instruction,Region,Term};
+use alloc::collections::BTreeMap;
+use alloc::string::{String,ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp;
+use crate::{Backend,BinOp,Bytecode,Diagnostic,Instruction,Intrinsic,Region,Term,evaluate};
 use crate::util::*;
 
-type Result = std::result::Result<(),Error>;
+type Result = core::result::Result<(),Error>;
+
+/// Switches with at most this many cases (or subsets of cases during
+/// a binary search) are dispatched by comparing each case in turn.
+const LINEAR_CASES : usize = 4;
+
+/// The maximum number of items on the EVM stack.
+const STACK_LIMIT : usize = 1024;
 
 // ============================================================================
 // Errors
@@ -26,35 +37,106 @@ pub enum Error {
     /// Attempt to read from an invalid memory region.
     InvalidMemoryAccess,
     /// Attempt to write something which doesn't exist, or is not an lval.
-    InvalidLVal
+    InvalidLVal,
+    /// A constant shift is not less than the word width (i.e. `256`).
+    InvalidShift,
+    /// A tuple is used where a single value is expected, or the
+    /// widths of a tuple assignment do not match.
+    InvalidTuple,
+    /// Translating the given term (identified by its position) would
+    /// exceed the maximum height of the stack (i.e. `1024`).
+    StackTooDeep(TermId,Term),
+    /// A switch case is not a literal, or duplicates an earlier case.
+    InvalidCase,
+    /// A bit field is empty, or extends beyond the word width (i.e.
+    /// `256`).
+    InvalidBitField,
+    /// An array is declared more than once, has no elements, is too
+    /// large, or is not in memory or storage.
+    InvalidArray,
+    /// A region holding declared arrays is also accessed directly
+    /// (e.g. `storage[1]`), which could overlap with them.
+    MixedAccess,
+    /// A constant index is not less than the length of its array.
+    IndexOutOfBounds
+}
+
+// ============================================================================
+// Term Identifiers
+// ============================================================================
+
+/// Identifies a term translated by a compiler, given by the index of
+/// its enclosing statement (i.e. in the order statements were given
+/// to `translate()`) and its position in a pre-order traversal of
+/// that statement (see `Term::subterm()`).
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct TermId {
+    pub statement: usize,
+    pub index: usize
 }
 
 // ============================================================================
 // Compiler
 // ============================================================================
 
-pub struct Compiler<'a> {
-    /// Access to the bytecode stream being constructed.
-    bytecode: &'a mut Bytecode,
+/// Translates IL into instructions for a given backend (which, by
+/// default, is the EVM).
+pub struct Compiler<'a,B:Backend+?Sized = Bytecode> {
+    /// Access to the backend receiving the instructions produced.
+    backend: &'a mut B,
     /// Mapping from label names to their allocated labels in the
-    /// underlying bytecode.
-    labels: HashMap<String, usize>
+    /// backend.
+    labels: BTreeMap<String, usize>,
+    /// Number of statements translated so far.
+    statements: usize,
+    /// Position of each term within the statement being translated,
+    /// keyed by its address.
+    positions: BTreeMap<usize,usize>,
+    /// Number of nested calls to `translate()` in progress.
+    depth: usize,
+    /// Estimated size and static gas of everything emitted so far.
+    emitted: (usize,u64),
+    /// Estimated size and static gas of the code emitted for each
+    /// term (see `term_costs()`).
+    costs: BTreeMap<TermId,(usize,u64)>,
+    /// The region, first offset and length of each declared array.
+    arrays: BTreeMap<String,(Region,usize,usize)>,
+    /// Regions accessed directly (e.g. `storage[1]`), which cannot
+    /// also hold arrays.
+    direct: Vec<Region>,
+    /// Whether or not to check array indices at runtime (see
+    /// `with_bounds_checks()`).
+    bounds_checks: bool,
+    /// Height of the stack after everything emitted so far within
+    /// the statement being translated.
+    height: usize,
+    /// Whether the stack limit was exceeded by the last instruction
+    /// emitted, and is yet to be reported.
+    overflow: bool
 }
 
-impl<'a> Compiler<'a> {
-    pub fn new(bytecode: &'a mut Bytecode) -> Self {
-        Self{bytecode, labels: HashMap::new()}
+impl<'a,B:Backend+?Sized> Compiler<'a,B> {
+    pub fn new(backend: &'a mut B) -> Self {
+        Self{backend, labels: BTreeMap::new(), statements: 0, positions: BTreeMap::new(), depth: 0, emitted: (0,0), costs: BTreeMap::new(), arrays: BTreeMap::new(), direct: Vec::new(), bounds_checks: true, height: 0, overflow: false}
     }
 
-    /// Get the underlying bytecode label for a given label
-    /// identifier.  If necessary, this allocates that label in the
-    /// `Bytecode` object.
+    /// Check that the index of every access to a declared array whose
+    /// index is not constant lies within bounds when executed, failing
+    /// (as for `assert`) otherwise.  This is enabled by default, and
+    /// constant indices are always checked when compiling.
+    pub fn with_bounds_checks(mut self, enable: bool) -> Self {
+        self.bounds_checks = enable;
+        self
+    }
+
+    /// Get the underlying backend label for a given label identifier.
+    /// If necessary, this allocates that label in the backend.
     pub fn label(&mut self, l: &str) -> usize {
         match self.labels.get(l) {
             Some(idx) => *idx,
             None => {
                 // Allocate underlying index
-                let idx = self.bytecode.fresh_label();
+                let idx = self.backend.fresh_label();
                 // Cache it for later
                 self.labels.insert(l.to_string(),idx);
                 // Done
@@ -63,22 +145,101 @@ impl<'a> Compiler<'a> {
         }
     }
 
-    pub fn translate(&mut self, term: &Term) -> Result {
+    /// Get the mapping from label names to their allocated labels in
+    /// the backend.
+    pub fn labels(&self) -> &BTreeMap<String, usize> {
+        &self.labels
+    }
+
+    /// Get the estimated size (in bytes) and static gas of the code
+    /// emitted for each term translated so far, ordered by term.  The
+    /// code for a term includes that of its subterms.  Sizes are only
+    /// estimates, since labels are assumed to require two bytes.
+    pub fn term_costs(&self) -> Vec<(TermId,usize,u64)> {
+        self.costs.iter().map(|(id,(size,gas))| (*id,*size,*gas)).collect()
+    }
+
+    /// Translate a statement into instructions for the backend,
+    /// reporting any error as a diagnostic.
+    pub fn translate(&mut self, term: &Term) -> core::result::Result<(),Diagnostic> {
+        self.translate_tracked(term).map_err(Diagnostic::from)
+    }
+
+    /// Translate a term, recording the cost of the code emitted for it
+    /// (see `term_costs()`).
+    fn translate_tracked(&mut self, term: &Term) -> Result {
+        self.track(term,|c| c.translate_term(term))
+    }
+
+    /// Translate a term using a given translation, recording the cost
+    /// of the code emitted for it as for `translate_tracked()`.  This
+    /// allows a term to be translated in a different way depending
+    /// on its context (e.g. a comparison being branched on).
+    fn track<F:FnOnce(&mut Self)->Result>(&mut self, term: &Term, translate: F) -> Result {
+        if self.depth == 0 {
+            // A new statement
+            self.positions = positions(term);
+            self.statements += 1;
+            // Every statement begins with an empty stack
+            self.height = 0;
+        }
+        let id = self.positions.get(&address(term)).map(|i| TermId{statement: self.statements-1, index: *i});
+        let before = self.emitted;
+        self.depth += 1;
+        let r = translate(self);
+        self.depth -= 1;
+        if let Some(id) = id {
+            let cost = self.costs.entry(id).or_insert((0,0));
+            cost.0 += self.emitted.0 - before.0;
+            cost.1 += self.emitted.1 - before.1;
+        }
+        // Report the innermost term which exceeded the stack limit
+        if r.is_ok() && self.overflow {
+            self.overflow = false;
+            let id = id.unwrap_or(TermId{statement: self.statements-1, index: 0});
+            return Err(Error::StackTooDeep(id,term.clone()));
+        }
+        r
+    }
+
+    /// Emit an instruction, accounting for its cost and its effect on
+    /// the height of the stack.
+    fn emit(&mut self, insn: Instruction) {
+        let (n,m) = insn.stack_effect();
+        self.height = self.height.saturating_sub(n) + m;
+        self.overflow |= self.height > STACK_LIMIT;
+        let size = match insn {
+            Instruction::PUSHL(_) => 3,
+            _ => insn.length(&[])
+        };
+        self.emitted.0 += size;
+        self.emitted.1 += insn.static_gas();
+        self.backend.emit(insn);
+    }
+
+    fn translate_term(&mut self, term: &Term) -> Result {
         match term {
             // Statements
             Term::Assert(e) => self.translate_assert(e),
+            Term::Assume(e) => self.translate_assume(e),
             Term::Assignment(e1,e2) => self.translate_assignment(e1,e2),
             Term::Fail => self.translate_fail(),
             Term::Goto(l) => self.translate_goto(l),
             Term::IfGoto(e,l) => self.translate_ifgoto(e,l),
             Term::Label(l) => self.translate_label(l),
+            Term::Require(e) => self.translate_require(e),
             Term::Revert(es) => self.translate_revert(es),
             Term::Succeed(es) => self.translate_succeed(es),
+            Term::Switch(e,cs) => self.translate_switch(e,cs),
             Term::Stop => self.translate_stop(),
+            Term::Array(r,name,len) => self.translate_array(*r,name,*len),
             // Expressions
             Term::Binary(bop,e1,e2) => self.translate_binary(*bop,e1,e2),
             Term::ArrayAccess(src,index) => self.translate_array_access(src,index),
-            Term::MemoryAccess(_) => Err(Error::InvalidMemoryAccess),
+            Term::BitField(e,offset,width) => self.translate_bitfield(e,*offset,*width),
+            Term::Intrinsic(i,args) => self.translate_intrinsic(*i,args),
+            Term::MemoryAccess(_)|Term::Variable(_) => Err(Error::InvalidMemoryAccess),
+            Term::Tuple(_) => Err(Error::InvalidTuple),
             // Values
             Term::Int(bytes) => self.translate_literal(bytes,10),
             Term::Hex(bytes) => self.translate_literal(bytes,16),
@@ -92,25 +253,103 @@ impl<'a> Compiler<'a> {
 
     fn translate_assert(&mut self, expr: &Term) -> Result {
         // Allocate labels for true/false outcomes
-        let lab = self.bytecode.fresh_label();
+        let lab = self.backend.fresh_label();
         // Translate conditional branch
         self.translate_conditional(expr,Some(lab),None)?;
         // False branch
-        self.bytecode.push(Instruction::INVALID);
+        self.emit(Instruction::INVALID);
         // True branch
-        self.bytecode.push(Instruction::JUMPDEST(lab));
+        self.emit(Instruction::JUMPDEST(lab));
         //
         Ok(())
     }
 
+    /// Translate an assumption, which produces no code.  Instead, it
+    /// is recorded against the next instruction (so it can be passed
+    /// on to the analysis).
+    fn translate_assume(&mut self, expr: &Term) -> Result {
+        if expr.width() != 1 {
+            return Err(Error::InvalidTuple);
+        }
+        self.backend.assume(expr.clone());
+        Ok(())
+    }
+
+    /// Translate the declaration of an array, which produces no code.
+    /// Instead, it is allocated after the arrays declared before it in
+    /// the same region.
+    fn translate_array(&mut self, region: Region, name: &str, len: usize) -> Result {
+        if len == 0 || self.arrays.contains_key(name) || region == Region::CallData {
+            return Err(Error::InvalidArray);
+        }
+        if self.direct.contains(&region) {
+            return Err(Error::MixedAccess);
+        }
+        let stride = stride(region);
+        // Arrays are allocated in order, so this one follows the last
+        let start = self.arrays.values()
+            .filter(|(r,_,_)| *r == region)
+            .map(|(_,s,n)| s + n * stride)
+            .max().unwrap_or(0);
+        // The array must lie within the region
+        len.checked_mul(stride).and_then(|n| n.checked_add(start)).ok_or(Error::InvalidArray)?;
+        self.arrays.insert(name.to_string(),(region,start,len));
+        Ok(())
+    }
+
+    /// Record that a region is accessed directly, which fails if it
+    /// holds any arrays.
+    fn access(&mut self, region: Region) -> Result {
+        if self.arrays.values().any(|(r,_,_)| *r == region) {
+            return Err(Error::MixedAccess);
+        }
+        if !self.direct.contains(&region) { self.direct.push(region); }
+        Ok(())
+    }
+
     fn translate_assignment(&mut self, lhs: &Term, rhs: &Term) -> Result {
+        if let Term::Tuple(_) = lhs {
+            return self.translate_assignment_tuple(lhs,rhs);
+        }
         // Translate value being assigned
-        self.translate(rhs)?;
+        self.translate_tracked(rhs)?;
         // Translate assignent itself
+        self.translate_assignment_lval(lhs)
+    }
+
+    /// Translate an assignment to a tuple of lvals, such as `(x,y) =
+    /// (1,2)`.  All values are loaded onto the stack first (with the
+    /// first on top), and then assigned in order.  Hence, the
+    /// right-hand side is evaluated before any assignment is made.
+    fn translate_assignment_tuple(&mut self, lhs: &Term, rhs: &Term) -> Result {
+        let mut lvals = Vec::new();
+        flatten(lhs,&mut lvals);
+        if lvals.len() != rhs.width() {
+            return Err(Error::InvalidTuple);
+        }
+        // Translate values being assigned
+        self.translate_words(rhs)?;
+        // Disjoint fields of the same word are assigned together
+        if let Some((word,fields)) = disjoint_fields(&lvals) {
+            return self.translate_assignment_fields(word,&fields);
+        }
+        // Translate assignments themselves
+        for lval in lvals {
+            self.translate_assignment_lval(lval)?;
+        }
+        Ok(())
+    }
+
+    /// Translate the assignment of the value on top of the stack to a
+    /// given lval.
+    fn translate_assignment_lval(&mut self, lhs: &Term) -> Result {
         match lhs {
             Term::ArrayAccess(src,idx) => {
                 self.translate_assignment_array(&src,&idx)?;
             }
+            Term::BitField(word,offset,width) => {
+                self.translate_assignment_fields(word,&[(*offset,*width)])?;
+            }
             _ => {
                 return Err(Error::InvalidLVal);
             }
@@ -124,6 +363,13 @@ impl<'a> Compiler<'a> {
             Term::MemoryAccess(r) => {
                 self.translate_assignment_memory(*r,index)
             }
+            Term::Variable(name) => {
+                match self.translate_element(name,index)? {
+                    Region::Memory => self.emit(Instruction::MSTORE),
+                    _ => self.emit(Instruction::SSTORE)
+                }
+                Ok(())
+            }
             _ => {
                 Err(Error::InvalidMemoryAccess)
             }
@@ -131,12 +377,13 @@ impl<'a> Compiler<'a> {
     }
 
     fn translate_assignment_memory(&mut self, region: Region, address: &Term) -> Result {
+        self.access(region)?;
         // Translate index expression
-        self.translate(address)?;
+        self.translate_tracked(address)?;
         // Dispatch based on region
         match region {
-            Region::Memory => self.bytecode.push(Instruction::MSTORE),
-            Region::Storage => self.bytecode.push(Instruction::SSTORE),
+            Region::Memory => self.emit(Instruction::MSTORE),
+            Region::Storage => self.emit(Instruction::SSTORE),
             _ => {
                 return Err(Error::InvalidMemoryAccess);
             }
@@ -145,8 +392,38 @@ impl<'a> Compiler<'a> {
         Ok(())
     }
 
+    /// Translate the assignment of values on the stack (with the
+    /// first on top) to disjoint fields of a given word.  Each value
+    /// is masked and shifted into position, and then the remaining
+    /// bits of the word are read back in.  Hence, the word is read
+    /// and written once, however many fields are assigned.
+    fn translate_assignment_fields(&mut self, word: &Term, fields: &[(usize,usize)]) -> Result {
+        for (i,(offset,width)) in fields.iter().enumerate() {
+            check_field(*offset,*width)?;
+            if i > 0 { self.emit(Instruction::SWAP(1)); }
+            // Mask value and shift into position
+            if *width < 256 {
+                self.emit(make_mask(&[(0,*width)],false));
+                self.emit(Instruction::AND);
+            }
+            if *offset > 0 {
+                self.emit(make_push(*offset as u128)?);
+                self.emit(Instruction::SHL);
+            }
+            if i > 0 { self.emit(Instruction::OR); }
+        }
+        // Retain the other bits (unless there are none)
+        if fields.iter().map(|(_,w)| w).sum::<usize>() < 256 {
+            self.translate_tracked(word)?;
+            self.emit(make_mask(fields,true));
+            self.emit(Instruction::AND);
+            self.emit(Instruction::OR);
+        }
+        self.translate_assignment_lval(word)
+    }
+
     fn translate_fail(&mut self) -> Result {
-        self.bytecode.push(Instruction::INVALID);
+        self.emit(Instruction::INVALID);
         Ok(())
     }
 
@@ -154,8 +431,8 @@ impl<'a> Compiler<'a> {
         // Allocate labels branch target
         let lab = self.label(label);
         // Translate unconditional branch
-        self.bytecode.push(Instruction::PUSHL(lab));
-        self.bytecode.push(Instruction::JUMP);
+        self.emit(Instruction::PUSHL(lab));
+        self.emit(Instruction::JUMP);
         //
         Ok(())
     }
@@ -171,18 +448,31 @@ impl<'a> Compiler<'a> {
         // Determine underlying index of label
         let lab = self.label(label);
         // Construct corresponding JumpDest
-        self.bytecode.push(Instruction::JUMPDEST(lab));
+        self.emit(Instruction::JUMPDEST(lab));
         // Done
         Ok(())
     }
 
+    fn translate_require(&mut self, expr: &Term) -> Result {
+        // Allocate label for true outcome
+        let lab = self.backend.fresh_label();
+        // Translate conditional branch
+        self.translate_conditional(expr,Some(lab),None)?;
+        // False branch
+        self.translate_succeed_revert(Instruction::REVERT,&[])?;
+        // True branch
+        self.emit(Instruction::JUMPDEST(lab));
+        //
+        Ok(())
+    }
+
     fn translate_revert(&mut self, exprs: &[Term]) -> Result {
         self.translate_succeed_revert(Instruction::REVERT,exprs)
     }
 
     fn translate_succeed(&mut self, exprs: &[Term]) -> Result {
         if exprs.len() == 0 {
-            self.bytecode.push(Instruction::STOP);
+            self.emit(Instruction::STOP);
             Ok(())
         } else {
             self.translate_succeed_revert(Instruction::RETURN,exprs)
@@ -191,28 +481,135 @@ impl<'a> Compiler<'a> {
 
     fn translate_succeed_revert(&mut self, insn: Instruction, exprs: &[Term]) -> Result {
         if exprs.len() == 0 {
-            self.bytecode.push(Instruction::PUSH(vec![0]));
-            self.bytecode.push(Instruction::PUSH(vec![0]));
+            self.emit(Instruction::PUSH(vec![0]));
+            self.emit(Instruction::PUSH(vec![0]));
         } else {
-            for i in 0 .. exprs.len() {
+            // Tuples are flattened into consecutive words
+            let mut words = Vec::new();
+            for e in exprs { flatten(e,&mut words); }
+            //
+            // Evaluate every word before storing any of them, since
+            // the words are stored over memory which the operands
+            // themselves may read (e.g. arrays).
+            for w in &words { self.translate_tracked(w)?; }
+            for i in (0 .. words.len()).rev() {
                 let addr = (i * 0x20) as u128;
-                self.translate(&exprs[i])?;
-                self.bytecode.push(make_push(addr)?);
-                self.bytecode.push(Instruction::MSTORE);
+                self.emit(make_push(addr)?);
+                self.emit(Instruction::MSTORE);
             }
-            let len = (exprs.len() * 0x20) as u128;
-            self.bytecode.push(Instruction::PUSH(vec![0]));
-            self.bytecode.push(make_push(len)?);
+            // Both take the offset (i.e. zero) on top of the size
+            let len = (words.len() * 0x20) as u128;
+            self.emit(make_push(len)?);
+            self.emit(Instruction::PUSH(vec![0]));
         }
-        self.bytecode.push(insn);
+        self.emit(insn);
         Ok(())
     }
 
     fn translate_stop(&mut self) -> Result {
-        self.bytecode.push(Instruction::STOP);
+        self.emit(Instruction::STOP);
         Ok(())
     }
 
+    /// Translate a switch statement.  The value being switched on is
+    /// kept on the stack whilst it is compared against the cases,
+    /// which are sorted so that a binary search can be used when
+    /// there are many of them.  Each target is reached through a
+    /// _trampoline_ which first pops the value.  If no case matches,
+    /// execution continues after the switch.
+    fn translate_switch(&mut self, expr: &Term, cases: &[(Term,String)]) -> Result {
+        let mut targets : Vec<(String,usize)> = Vec::new();
+        let mut values : Vec<(u256,usize)> = Vec::new();
+        // Allocate one trampoline per target
+        for (value,label) in cases {
+            let val = match value {
+                Term::Int(digits) => u256::from_be_digits(digits,10),
+                Term::Hex(digits) => u256::from_be_digits(digits,16),
+                _ => { return Err(Error::InvalidCase); }
+            }.ok_or(Error::LiteralOverflow)?;
+            let tramp = match targets.iter().find(|(l,_)| l == label) {
+                Some((_,t)) => *t,
+                None => {
+                    let t = self.backend.fresh_label();
+                    targets.push((label.clone(),t));
+                    t
+                }
+            };
+            values.push((val,tramp));
+        }
+        values.sort_by_key(|(v,_)| *v);
+        if values.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(Error::InvalidCase);
+        }
+        // Dispatch
+        self.translate_tracked(expr)?;
+        let nomatch = self.backend.fresh_label();
+        self.translate_switch_search(&values,nomatch)?;
+        if values.len() > LINEAR_CASES {
+            self.emit(Instruction::JUMPDEST(nomatch));
+        }
+        self.emit(Instruction::POP);
+        if targets.is_empty() { return Ok(()); }
+        // Trampolines
+        let end = self.backend.fresh_label();
+        self.emit(Instruction::PUSHL(end));
+        self.emit(Instruction::JUMP);
+        for (label,tramp) in targets {
+            let lab = self.label(&label);
+            self.emit(Instruction::JUMPDEST(tramp));
+            self.emit(Instruction::POP);
+            self.emit(Instruction::PUSHL(lab));
+            self.emit(Instruction::JUMP);
+        }
+        self.emit(Instruction::JUMPDEST(end));
+        Ok(())
+    }
+
+    /// Compare the value on top of the stack against a sorted set of
+    /// cases, branching to the trampoline of the matching case.
+    /// Otherwise, this either falls through or branches to `nomatch`.
+    fn translate_switch_search(&mut self, cases: &[(u256,usize)], nomatch: usize) -> Result {
+        if cases.len() <= LINEAR_CASES {
+            for (val,tramp) in cases {
+                self.emit(Instruction::DUP(1));
+                self.emit(make_push_word(*val));
+                self.emit(Instruction::EQ);
+                self.emit(Instruction::PUSHL(*tramp));
+                self.emit(Instruction::JUMPI);
+            }
+        } else {
+            let (lower,upper) = cases.split_at(cases.len() / 2);
+            let lab = self.backend.fresh_label();
+            // Branch if below the upper half
+            self.emit(make_push_word(upper[0].0));
+            self.emit(Instruction::DUP(2));
+            self.emit(Instruction::LT);
+            self.emit(Instruction::PUSHL(lab));
+            self.emit(Instruction::JUMPI);
+            self.translate_switch_search(upper,nomatch)?;
+            self.emit(Instruction::PUSHL(nomatch));
+            self.emit(Instruction::JUMP);
+            self.emit(Instruction::JUMPDEST(lab));
+            self.translate_switch_search(lower,nomatch)?;
+        }
+        Ok(())
+    }
+
+    /// Translate an expression which may produce multiple words.  In
+    /// such case, the components of a tuple are loaded in reverse
+    /// order so that the first ends up on top of the stack.
+    fn translate_words(&mut self, expr: &Term) -> Result {
+        match expr {
+            Term::Tuple(es) => {
+                for e in es.iter().rev() {
+                    self.translate_words(e)?;
+                }
+                Ok(())
+            }
+            _ => self.translate_tracked(expr)
+        }
+    }
+
     // ============================================================================
     // Conditional Expressions
     // ============================================================================
@@ -243,10 +640,10 @@ impl<'a> Compiler<'a> {
 	match (true_lab,false_lab) {
 	    (Some(_),None) => {
 		// Harder case
-		let lab = self.bytecode.fresh_label();
+		let lab = self.backend.fresh_label();
 		self.translate_conditional(lhs, None, Some(lab))?;
 		self.translate_conditional(rhs, true_lab, None)?;
-		self.bytecode.push(Instruction::JUMPDEST(lab));
+		self.emit(Instruction::JUMPDEST(lab));
 	    }
 	    (None,Some(_)) => {
 		// Easy case
@@ -266,10 +663,10 @@ impl<'a> Compiler<'a> {
 	match (true_lab,false_lab) {
 	    (None,Some(_)) => {
 		// Harder case
-		let lab = self.bytecode.fresh_label();
+		let lab = self.backend.fresh_label();
 		self.translate_conditional(lhs, Some(lab), None)?;
 		self.translate_conditional(rhs, None, false_lab)?;
-		self.bytecode.push(Instruction::JUMPDEST(lab));
+		self.emit(Instruction::JUMPDEST(lab));
 	    }
 	    (Some(_),None) => {
 		// Easy case
@@ -284,20 +681,36 @@ impl<'a> Compiler<'a> {
 
     /// Translate a conditional expression which cannot be translated
     /// by exploiting branches.  In such case, we have to generate the
-    /// boolean value and dispatch based on that.
+    /// boolean value and dispatch based on that.  However, comparisons
+    /// which the EVM implements by negating another (e.g. `<=` using
+    /// `GT; ISZERO`) are translated as the other with the branch
+    /// targets swapped, which avoids negating twice.
     fn translate_conditional_other(&mut self, expr: &Term, true_lab: Option<usize>, false_lab: Option<usize>) -> Result {
         // Translate conditional expression
-        self.translate(expr)?;
+        match expr {
+            Term::Binary(bop@(BinOp::NotEquals|BinOp::LessThanOrEquals|BinOp::GreaterThanOrEquals),l,r) => {
+                let bop = bop.negate().unwrap();
+                self.track(expr,|c| c.translate_binary_arithmetic(bop,l,r))?;
+                return self.translate_branch(false_lab,true_lab);
+            }
+            _ => self.translate_tracked(expr)?
+        }
+        self.translate_branch(true_lab,false_lab)
+    }
+
+    /// Branch on the value on top of the stack, either to a `true`
+    /// target or to a `false` target (but not both).
+    fn translate_branch(&mut self, true_lab: Option<usize>, false_lab: Option<usize>) -> Result {
         //
         match (true_lab,false_lab) {
             (Some(lab),None) => {
-                self.bytecode.push(Instruction::PUSHL(lab));
-                self.bytecode.push(Instruction::JUMPI);
+                self.emit(Instruction::PUSHL(lab));
+                self.emit(Instruction::JUMPI);
             }
             (None,Some(lab)) => {
-                self.bytecode.push(Instruction::ISZERO);
-                self.bytecode.push(Instruction::PUSHL(lab));
-                self.bytecode.push(Instruction::JUMPI);
+                self.emit(Instruction::ISZERO);
+                self.emit(Instruction::PUSHL(lab));
+                self.emit(Instruction::JUMPI);
             }
             (_,_) => {
                 unreachable!("")
@@ -328,52 +741,70 @@ impl<'a> Compiler<'a> {
     /// These are more challenging than standard binary operators because
     /// they exhibit _short circuiting behaviour_.
     fn translate_logical_connective(&mut self, bop: BinOp, lhs: &Term, rhs: &Term) -> Result {
-        self.translate(lhs)?;
-        self.bytecode.push(Instruction::DUP(1));
+        self.translate_tracked(lhs)?;
+        self.emit(Instruction::DUP(1));
         if bop == BinOp::LogicalAnd {
-            self.bytecode.push(Instruction::ISZERO);
+            self.emit(Instruction::ISZERO);
         }
         // Allocate fresh label
-        let lab = self.bytecode.fresh_label();
-        self.bytecode.push(Instruction::PUSHL(lab));
-        self.bytecode.push(Instruction::JUMPI);
-        self.bytecode.push(Instruction::POP);
-        self.translate(rhs)?;
-        self.bytecode.push(Instruction::JUMPDEST(lab));
+        let lab = self.backend.fresh_label();
+        self.emit(Instruction::PUSHL(lab));
+        self.emit(Instruction::JUMPI);
+        self.emit(Instruction::POP);
+        self.translate_tracked(rhs)?;
+        self.emit(Instruction::JUMPDEST(lab));
         // Done
         Ok(())
     }
 
     /// Translate a binary arithmetic operation or comparison.  This is
     /// pretty straightforward, as we just load items on the stack and
-    /// perform the op.  Observe that the right-hand side is loaded onto
-    /// the stack first.
+    /// perform the op.  Observe that the right-hand side is normally
+    /// loaded onto the stack first.  However, where the operator can
+    /// be commuted and the left-hand side requires more stack space,
+    /// the left-hand side is loaded first instead.  This reduces the
+    /// maximum stack height needed to evaluate the expression (in the
+    /// manner of Sethi-Ullman).
     fn translate_binary_arithmetic(&mut self, bop: BinOp, lhs: &Term, rhs: &Term) -> Result {
-        self.translate(rhs)?;
-        self.translate(lhs)?;
+        if let BinOp::ShiftLeft|BinOp::ShiftRight|BinOp::SignedShiftRight = bop {
+            return self.translate_shift(bop,lhs,rhs);
+        }
+        let bop = match bop.commute() {
+            Some(cop) if stack_need(lhs) > stack_need(rhs) => {
+                self.translate_tracked(lhs)?;
+                self.translate_tracked(rhs)?;
+                cop
+            }
+            _ => {
+                self.translate_tracked(rhs)?;
+                self.translate_tracked(lhs)?;
+                bop
+            }
+        };
         //
         match bop {
             // standard
-            BinOp::Add => self.bytecode.push(Instruction::ADD),
-            BinOp::Subtract => self.bytecode.push(Instruction::SUB),
-            BinOp::Divide => self.bytecode.push(Instruction::DIV),
-            BinOp::Multiply => self.bytecode.push(Instruction::MUL),
-            BinOp::Remainder => self.bytecode.push(Instruction::MOD),
-            BinOp::Equals => self.bytecode.push(Instruction::EQ),
-            BinOp::LessThan => self.bytecode.push(Instruction::LT),
-            BinOp::GreaterThan => self.bytecode.push(Instruction::GT),
+            BinOp::Add => self.emit(Instruction::ADD),
+            BinOp::Subtract => self.emit(Instruction::SUB),
+            BinOp::Divide => self.emit(Instruction::DIV),
+            BinOp::Multiply => self.emit(Instruction::MUL),
+            BinOp::Remainder => self.emit(Instruction::MOD),
+            BinOp::Exponent => self.emit(Instruction::EXP),
+            BinOp::Equals => self.emit(Instruction::EQ),
+            BinOp::LessThan => self.emit(Instruction::LT),
+            BinOp::GreaterThan => self.emit(Instruction::GT),
             // non-standard
             BinOp::NotEquals => {
-                self.bytecode.push(Instruction::EQ);
-                self.bytecode.push(Instruction::ISZERO);
+                self.emit(Instruction::EQ);
+                self.emit(Instruction::ISZERO);
             }
             BinOp::LessThanOrEquals => {
-                self.bytecode.push(Instruction::GT);
-                self.bytecode.push(Instruction::ISZERO);
+                self.emit(Instruction::GT);
+                self.emit(Instruction::ISZERO);
             }
             BinOp::GreaterThanOrEquals => {
-                self.bytecode.push(Instruction::LT);
-                self.bytecode.push(Instruction::ISZERO);
+                self.emit(Instruction::LT);
+                self.emit(Instruction::ISZERO);
             }
             _ => {
                 unreachable!();
@@ -383,6 +814,35 @@ impl<'a> Compiler<'a> {
         Ok(())
     }
 
+    /// Translate a shift operation.  Unlike other arithmetic
+    /// operations, `SHL`, `SHR` and `SAR` expect the shift amount on
+    /// top of the stack and, hence, the left-hand side is loaded
+    /// first.  A constant shift amount must be less than the word
+    /// width.
+    fn translate_shift(&mut self, bop: BinOp, lhs: &Term, rhs: &Term) -> Result {
+        let literal = match rhs {
+            Term::Int(digits) => Some((digits,10)),
+            Term::Hex(digits) => Some((digits,16)),
+            _ => None
+        };
+        if let Some((digits,radix)) = literal {
+            // Ignoring leading zeros, a valid shift has at most three digits.
+            let zeros = digits.iter().take_while(|d| **d == 0).count();
+            let digits = &digits[zeros..];
+            if digits.len() > 3 || from_be_digits(digits,radix) >= 256 {
+                return Err(Error::InvalidShift);
+            }
+        }
+        self.translate_tracked(lhs)?;
+        self.translate_tracked(rhs)?;
+        match bop {
+            BinOp::ShiftLeft => self.emit(Instruction::SHL),
+            BinOp::ShiftRight => self.emit(Instruction::SHR),
+            _ => self.emit(Instruction::SAR)
+        }
+        Ok(())
+    }
+
     // ============================================================================
     // Array Access Expressions
     // ============================================================================
@@ -396,6 +856,13 @@ impl<'a> Compiler<'a> {
             Term::MemoryAccess(r) => {
                 self.translate_memory_access(*r,index)
             }
+            Term::Variable(name) => {
+                match self.translate_element(name,index)? {
+                    Region::Memory => self.emit(Instruction::MLOAD),
+                    _ => self.emit(Instruction::SLOAD)
+                }
+                Ok(())
+            }
             _ => {
                 Err(Error::InvalidMemoryAccess)
             }
@@ -403,37 +870,213 @@ impl<'a> Compiler<'a> {
     }
 
     fn translate_memory_access(&mut self, region: Region, index: &Term) -> Result {
+        self.access(region)?;
         // Translate index expression
-        self.translate(index)?;
+        self.translate_tracked(index)?;
         // Dispatch based on region
         match region {
             Region::Memory => {
-                self.bytecode.push(Instruction::MLOAD);
+                self.emit(Instruction::MLOAD);
             }
             Region::Storage => {
-                self.bytecode.push(Instruction::SLOAD);
+                self.emit(Instruction::SLOAD);
             }
             Region::CallData => {
-                self.bytecode.push(Instruction::CALLDATALOAD);
+                self.emit(Instruction::CALLDATALOAD);
             }
         }
         //
         Ok(())
     }
 
+    /// Translate the location of an element of a declared array,
+    /// returning the region it is in.  A constant index is checked,
+    /// and the location determined, when compiling.  Otherwise, the
+    /// location is calculated from the index (after checking it, if
+    /// enabled).
+    fn translate_element(&mut self, name: &str, index: &Term) -> core::result::Result<Region,Error> {
+        let Some((region,start,len)) = self.arrays.get(name).copied() else {
+            return Err(Error::InvalidMemoryAccess);
+        };
+        let stride = stride(region);
+        let value = evaluate(index);
+        if let Some(i) = value {
+            if i >= u256::from(len) { return Err(Error::IndexOutOfBounds); }
+            let offset = (i.low() as usize).checked_mul(stride).and_then(|o| o.checked_add(start));
+            self.emit(make_push(offset.ok_or(Error::IndexOutOfBounds)? as u128)?);
+            return Ok(region);
+        }
+        self.translate_tracked(index)?;
+        if self.bounds_checks {
+            let lab = self.backend.fresh_label();
+            self.emit(Instruction::DUP(1));
+            self.emit(make_push(len as u128)?);
+            self.emit(Instruction::GT);
+            self.emit(Instruction::PUSHL(lab));
+            self.emit(Instruction::JUMPI);
+            self.emit(Instruction::INVALID);
+            self.emit(Instruction::JUMPDEST(lab));
+        }
+        if stride != 1 {
+            self.emit(make_push(stride as u128)?);
+            self.emit(Instruction::MUL);
+        }
+        if start != 0 {
+            self.emit(make_push(start as u128)?);
+            self.emit(Instruction::ADD);
+        }
+        Ok(region)
+    }
+
+    /// Translate a bit field of the form `word[offset:width]`, which
+    /// is shifted down and masked.
+    fn translate_bitfield(&mut self, word: &Term, offset: usize, width: usize) -> Result {
+        check_field(offset,width)?;
+        self.translate_tracked(word)?;
+        if offset > 0 {
+            self.emit(make_push(offset as u128)?);
+            self.emit(Instruction::SHR);
+        }
+        if offset + width < 256 {
+            self.emit(make_mask(&[(0,width)],false));
+            self.emit(Instruction::AND);
+        }
+        Ok(())
+    }
+
+    // ============================================================================
+    // Intrinsics
+    // ============================================================================
+
+    /// Translate an intrinsic invocation (e.g. `balance(addr)`).  The
+    /// arguments are loaded in reverse order so that the first
+    /// argument ends up on top of the stack, as expected by the
+    /// corresponding instruction.
+    fn translate_intrinsic(&mut self, intrinsic: Intrinsic, args: &[Term]) -> Result {
+        for arg in args.iter().rev() {
+            self.translate_tracked(arg)?;
+        }
+        //
+        match intrinsic {
+            Intrinsic::Balance => self.emit(Instruction::BALANCE),
+            Intrinsic::CallDataSize => self.emit(Instruction::CALLDATASIZE),
+            Intrinsic::MsgSender => self.emit(Instruction::CALLER),
+            Intrinsic::MsgValue => self.emit(Instruction::CALLVALUE)
+        }
+        //
+        Ok(())
+    }
+
     // ============================================================================
     // Values
     // ============================================================================
 
     fn translate_literal(&mut self, digits: &[u8], radix: u32) -> Result {
-        let val = from_be_digits(digits,radix);
-        self.bytecode.push(make_push(val)?);
+        let val = u256::from_be_digits(digits,radix).ok_or(Error::LiteralOverflow)?;
+        self.emit(make_push_word(val));
         Ok(())
     }
 }
 
+/// Determine the maximum number of stack items required to evaluate
+/// a given expression, assuming the evaluation order chosen by the
+/// compiler.  This is essentially the _Sethi-Ullman number_ for the
+/// expression, and is used to schedule the evaluation of operands.
+fn stack_need(term: &Term) -> usize {
+    match term {
+        Term::Binary(BinOp::LogicalAnd|BinOp::LogicalOr,lhs,rhs) => {
+            // Left-hand side is duplicated and then a label pushed.
+            cmp::max(cmp::max(stack_need(lhs),3),stack_need(rhs))
+        }
+        Term::Binary(BinOp::ShiftLeft|BinOp::ShiftRight|BinOp::SignedShiftRight,lhs,rhs) => {
+            // Left-hand side always evaluated first
+            cmp::max(stack_need(lhs),stack_need(rhs)+1)
+        }
+        Term::Binary(bop,lhs,rhs) => {
+            let l = stack_need(lhs);
+            let r = stack_need(rhs);
+            if bop.commute().is_some() && l == r {
+                l + 1
+            } else if bop.commute().is_some() {
+                cmp::max(l,r)
+            } else {
+                // Right-hand side always evaluated first
+                cmp::max(r,l+1)
+            }
+        }
+        // Checking an index requires two more items
+        Term::ArrayAccess(src,index) if matches!(**src,Term::Variable(_)) => cmp::max(stack_need(index),3),
+        Term::ArrayAccess(_,index) => stack_need(index),
+        Term::BitField(word,_,_) => cmp::max(stack_need(word),2),
+        Term::Intrinsic(_,args) => {
+            // Arguments evaluated in reverse order
+            let mut need = 1;
+            for (i,arg) in args.iter().rev().enumerate() {
+                need = cmp::max(need,i + stack_need(arg));
+            }
+            need
+        }
+        Term::Int(_)|Term::Hex(_) => 1,
+        _ => 0
+    }
+}
+
+/// Flatten a (possibly nested) tuple into its components.  Any other
+/// term is left as is.
+fn flatten<'a>(term: &'a Term, items: &mut Vec<&'a Term>) {
+    match term {
+        Term::Tuple(es) => {
+            for e in es { flatten(e,items); }
+        }
+        _ => items.push(term)
+    }
+}
+
+/// Check whether a given bit field is non-empty, and fits within a
+/// word.
+fn check_field(offset: usize, width: usize) -> Result {
+    if width == 0 || offset + width > 256 {
+        Err(Error::InvalidBitField)
+    } else {
+        Ok(())
+    }
+}
+
+/// Determine whether a sequence of lvals are disjoint fields of the
+/// same word (in which case, these are returned).
+fn disjoint_fields<'a>(lvals: &[&'a Term]) -> Option<(&'a Term,Vec<(usize,usize)>)> {
+    let mut word = None;
+    let mut fields = Vec::new();
+    for lval in lvals {
+        let Term::BitField(w,offset,width) = lval else { return None; };
+        if *word.get_or_insert(&**w) != &**w { return None; }
+        fields.push((*offset,*width));
+    }
+    let mut sorted = fields.clone();
+    sorted.sort();
+    if sorted.windows(2).any(|w| w[0].0 + w[0].1 > w[1].0) { return None; }
+    Some((word?,fields))
+}
+
+/// Construct a push of a mask whose bits are set within the given
+/// fields, or cleared if inverted.
+fn make_mask(fields: &[(usize,usize)], invert: bool) -> Instruction {
+    let mut bytes = vec![0u8; 32];
+    for (offset,width) in fields {
+        for i in *offset..*offset+*width {
+            bytes[31 - (i / 8)] |= 1 << (i % 8);
+        }
+    }
+    if invert {
+        for b in bytes.iter_mut() { *b = !*b; }
+    }
+    // Drop leading zeros
+    let zeros = cmp::min(bytes.iter().take_while(|b| **b == 0).count(),31);
+    Instruction::PUSH(bytes.split_off(zeros))
+}
+
 /// Construct a push instruction from a value.
-fn make_push(val: u128) -> std::result::Result<Instruction,Error> {
+fn make_push(val: u128) -> core::result::Result<Instruction,Error> {
     let bytes = to_be_bytes(val);
     // Sanity check size of literal
     if bytes.len() > 32 {
@@ -443,3 +1086,36 @@ fn make_push(val: u128) -> std::result::Result<Instruction,Error> {
         Ok(Instruction::PUSH(bytes))
     }
 }
+
+/// Construct a push instruction from a word, using as few bytes as
+/// possible.
+fn make_push_word(val: u256) -> Instruction {
+    let bytes = val.to_be_bytes();
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+    // Zero is pushed as a single byte
+    Instruction::PUSH(bytes[cmp::min(zeros,31)..].to_vec())
+}
+
+/// Determine the distance between consecutive elements of an array in
+/// a given region, noting that memory is byte addressed.
+fn stride(region: Region) -> usize {
+    if region == Region::Memory { 32 } else { 1 }
+}
+
+/// Identify a term by its address, which distinguishes it from every
+/// other term within the same statement.
+fn address(term: &Term) -> usize {
+    term as *const Term as usize
+}
+
+/// Determine the position of every term within a statement, as given
+/// by a pre-order traversal, keyed by address.
+fn positions(stmt: &Term) -> BTreeMap<usize,usize> {
+    let mut positions = BTreeMap::new();
+    let mut worklist = vec![stmt];
+    while let Some(t) = worklist.pop() {
+        positions.insert(address(t),positions.len());
+        worklist.extend(t.children().into_iter().rev());
+    }
+    positions
+}
@@ -0,0 +1,162 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::{Coverage,FailureKind,Options,Report,Statistics,analyze_with};
+
+// ============================================================================
+// Corpus
+// ============================================================================
+
+/// A collection of labelled bytecode sequences (e.g. deployed
+/// contracts), which are analysed together.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct Corpus {
+    units: Vec<(String,Vec<u8>)>
+}
+
+impl Corpus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a bytecode sequence with a given label (e.g. its address).
+    /// Labels need not be unique.
+    pub fn insert<S:Into<String>>(&mut self, label: S, bytes: Vec<u8>) {
+        self.units.push((label.into(),bytes));
+    }
+
+    /// Get the number of bytecode sequences in this corpus.
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    /// Check whether this corpus is empty.
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// Iterate the labelled bytecode sequences in order of insertion.
+    pub fn iter(&self) -> impl Iterator<Item=(&str,&[u8])> {
+        self.units.iter().map(|(l,b)| (l.as_str(),b.as_slice()))
+    }
+
+    /// Analyse every bytecode sequence in turn using the given options
+    /// (e.g. `lint` to include lints), and combine the results.  With
+    /// the standard library, a sequence whose analysis panics is
+    /// reported as a failure rather than ending the whole run.
+    pub fn analyze(&self, options: &Options) -> CorpusReport {
+        let results = self.units.iter().map(|(_,b)| analyze_unit(b,options)).collect();
+        self.combine(results)
+    }
+
+    /// Analyse every bytecode sequence as for `analyze()`, using (at
+    /// most) a given number of threads.  The results are the same as
+    /// for `analyze()`, and in the same order.
+    #[cfg(feature = "std")]
+    pub fn analyze_parallel(&self, options: &Options, threads: usize) -> CorpusReport {
+        let size = self.units.len().div_ceil(threads.max(1)).max(1);
+        let results = std::thread::scope(|s| {
+            let handles : Vec<_> = self.units.chunks(size).map(|units| {
+                s.spawn(move || units.iter().map(|(_,b)| analyze_unit(b,options)).collect::<Vec<_>>())
+            }).collect();
+            // Failures are caught within each thread
+            handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+        });
+        self.combine(results)
+    }
+
+    /// Combine the results of analysing each bytecode sequence (in
+    /// order) into a single report.
+    fn combine(&self, results: Vec<Result<Report,String>>) -> CorpusReport {
+        let mut report = CorpusReport::default();
+        for ((label,_),result) in self.units.iter().zip(results) {
+            match result {
+                Ok(r) => {
+                    report.statistics.merge(&r.statistics);
+                    report.coverage.merge(&r.coverage);
+                    for d in &r.diagnostics {
+                        *report.counts.entry(d.code).or_insert(0) += 1;
+                    }
+                    report.reports.push((label.clone(),r));
+                }
+                Err(reason) => report.failures.push((label.clone(),reason))
+            }
+        }
+        report
+    }
+}
+
+// ============================================================================
+// Corpus Report
+// ============================================================================
+
+/// The combined results of analysing every bytecode sequence in a
+/// corpus.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct CorpusReport {
+    /// The report for each sequence analysed (in order), with its
+    /// label.
+    pub reports: Vec<(String,Report)>,
+    /// Each sequence whose analysis failed (in order), with its label
+    /// and the reason.
+    pub failures: Vec<(String,String)>,
+    /// Statistics for the reachable code of every sequence analysed.
+    pub statistics: Statistics,
+    /// Classification of the bytes of every sequence analysed.
+    pub coverage: Coverage,
+    /// Number of diagnostics (including lints) with each code.
+    pub counts: BTreeMap<&'static str,usize>
+}
+
+impl CorpusReport {
+    /// Get the labels of sequences with (at least) one diagnostic
+    /// with a given code (e.g. `"S001"`).
+    pub fn labels_with(&self, code: &str) -> Vec<&str> {
+        self.reports.iter()
+            .filter(|(_,r)| r.diagnostics.iter().any(|d| d.code == code))
+            .map(|(l,_)| l.as_str())
+            .collect()
+    }
+
+    /// Get the labels of sequences whose most serious analysis failure
+    /// is of a given kind (see `Report::failure()`).  For example,
+    /// sequences with only precision failures may be worth analysing
+    /// again with hints.
+    pub fn labels_failing(&self, kind: FailureKind) -> Vec<&str> {
+        self.reports.iter()
+            .filter(|(_,r)| r.failure() == Some(kind))
+            .map(|(l,_)| l.as_str())
+            .collect()
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+#[cfg(feature = "std")]
+fn analyze_unit(bytes: &[u8], options: &Options) -> Result<Report,String> {
+    std::panic::catch_unwind(|| analyze_with(bytes,options)).map_err(|e| {
+        match (e.downcast_ref::<&str>(),e.downcast_ref::<String>()) {
+            (Some(msg),_) => msg.to_string(),
+            (_,Some(msg)) => msg.clone(),
+            _ => "analysis panicked".to_string()
+        }
+    })
+}
+
+#[cfg(not(feature = "std"))]
+fn analyze_unit(bytes: &[u8], options: &Options) -> Result<Report,String> {
+    Ok(analyze_with(bytes,options))
+}
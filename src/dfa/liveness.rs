@@ -0,0 +1,300 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use crate::{AbstractState,Cancellation,Cancelled,Diagnostic,Disassembly,Instruction};
+use crate::Instruction::*;
+
+// ============================================================================
+// Live Sets
+// ============================================================================
+
+/// Identifies which stack positions are live at a given point.  That
+/// is, positions whose values may subsequently be used.  Position `0`
+/// is the top of the stack, and all positions beyond those explicitly
+/// recorded are either live or dead together.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct LiveSet {
+    bits: Vec<bool>,
+    rest: bool
+}
+
+impl LiveSet {
+    /// Construct a set where no positions are live.
+    pub fn empty() -> Self {
+        LiveSet{bits: Vec::new(), rest: false}
+    }
+
+    /// Construct a set where every position is live.
+    pub fn all() -> Self {
+        LiveSet{bits: Vec::new(), rest: true}
+    }
+
+    /// Construct a set where exactly the given positions are live.
+    pub fn new(positions: &[usize]) -> Self {
+        let mut r = Self::empty();
+        for p in positions { r.insert(*p); }
+        r
+    }
+
+    /// Check whether a given stack position is live.
+    pub fn is_live(&self, i: usize) -> bool {
+        self.bits.get(i).copied().unwrap_or(self.rest)
+    }
+
+    /// Check whether no positions are live.
+    pub fn is_empty(&self) -> bool {
+        !self.rest && self.bits.iter().all(|b| !b)
+    }
+
+    /// Mark a given stack position as live.
+    pub fn insert(&mut self, i: usize) {
+        if !self.rest {
+            if i >= self.bits.len() { self.bits.resize(i+1,false); }
+            self.bits[i] = true;
+        } else if i < self.bits.len() {
+            self.bits[i] = true;
+            self.normalise();
+        }
+    }
+
+    /// Merge another set into this one, returning `true` if this
+    /// changed anything.
+    pub fn join(&mut self, other: &LiveSet) -> bool {
+        let n = core::cmp::max(self.bits.len(),other.bits.len());
+        let bits : Vec<bool> = (0..n).map(|i| self.is_live(i) || other.is_live(i)).collect();
+        let r = LiveSet{bits, rest: self.rest || other.rest}.normalised();
+        let changed = *self != r;
+        *self = r;
+        changed
+    }
+
+    /// Determine which positions are live before a given instruction,
+    /// given those live after it.  An instruction's operands are live
+    /// if it has a side effect, or if any of its results are live.
+    pub fn transfer(&self, insn: &Instruction) -> LiveSet {
+        let (n,m) = insn.stack_effect();
+        // Instructions after which execution cannot continue
+        let halts = matches!(insn, STOP|INVALID|RETURN|REVERT|SELFDESTRUCT);
+        let after = if halts { LiveSet::empty() } else { self.clone() };
+        let used = match insn {
+            POP => false,
+            CREATE|CREATE2|CALL|CALLCODE|DELEGATECALL|STATICCALL => true,
+            _ if m == 0 => true,
+            _ => (0..m).any(|i| after.is_live(i))
+        };
+        let bits = (0..after.bits.len() + n).map(|i| {
+            match insn {
+                DUP(k) if i + 1 == *k as usize => after.is_live(0) || after.is_live(i+1),
+                DUP(_) => after.is_live(i+1),
+                SWAP(k) if i == 0 => after.is_live(*k as usize),
+                SWAP(k) if i == *k as usize => after.is_live(0),
+                SWAP(_) => after.is_live(i),
+                _ if i < n => used,
+                _ => after.is_live(i + m - n)
+            }
+        }).collect();
+        LiveSet{bits, rest: after.rest}.normalised()
+    }
+
+    /// Drop trailing positions which are implied by `rest`.
+    fn normalise(&mut self) {
+        while self.bits.last() == Some(&self.rest) {
+            self.bits.pop();
+        }
+    }
+
+    fn normalised(mut self) -> Self {
+        self.normalise();
+        self
+    }
+}
+
+impl fmt::Display for LiveSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let live : Vec<usize> = (0..self.bits.len()).filter(|i| self.bits[*i]).collect();
+        write!(f,"{{")?;
+        for (i,p) in live.iter().enumerate() {
+            if i != 0 { write!(f,",")?; }
+            write!(f,"{}",p)?;
+        }
+        if self.rest {
+            if !live.is_empty() { write!(f,",")?; }
+            write!(f,"{}..",self.bits.len())?;
+        }
+        write!(f,"}}")
+    }
+}
+
+// ============================================================================
+// Liveness
+// ============================================================================
+
+/// The result of a liveness analysis, giving the stack positions live
+/// on entry to (and exit from) each block.  Unreachable blocks have
+/// no live positions.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Liveness {
+    pub(crate) live_in: Vec<LiveSet>,
+    live_out: Vec<LiveSet>
+}
+
+impl Liveness {
+    /// Get the stack positions live on entry to a given block.
+    pub fn live_in(&self, block: usize) -> &LiveSet {
+        &self.live_in[block]
+    }
+
+    /// Get the stack positions live on exit from a given block.
+    pub fn live_out(&self, block: usize) -> &LiveSet {
+        &self.live_out[block]
+    }
+}
+
+impl<'a,T> Disassembly<'a,T>
+where T:AbstractState+fmt::Display {
+    /// Determine which stack positions are live at the start and end
+    /// of each block.  This is a backwards analysis over the control
+    /// flow determined by this disassembly, so should be applied
+    /// after `build()`.  Every position is assumed live after a
+    /// branch whose target is unknown.
+    pub fn liveness(&self) -> Liveness {
+        // Cannot be cancelled
+        self.liveness_with(&()).unwrap_or_else(|c| c.0)
+    }
+
+    /// Determine which stack positions are live at the start and end
+    /// of each block, stopping early if cancelled.  In which case,
+    /// the partial results may omit some live positions.
+    pub fn liveness_with<C:Cancellation+?Sized>(&self, cancel: &C) -> Result<Liveness,Cancelled<Liveness>> {
+        let n = self.blocks().len();
+        let mut live_in = vec![LiveSet::empty(); n];
+        let mut live_out = vec![LiveSet::empty(); n];
+        let mut changed = true;
+        //
+        while changed {
+            changed = false;
+            // Iterate blocks in reverse order
+            for i in (0..n).rev() {
+                if cancel.is_cancelled() { return Err(Cancelled(Liveness{live_in,live_out})); }
+                if !self.is_block_reachable(i) { continue; }
+                let insns = self.block_liveness(i,&live_in);
+                let (_,insn,after) = &insns[0];
+                let entry = after.transfer(insn);
+                changed |= live_in[i] != entry;
+                live_in[i] = entry;
+                live_out[i] = insns.last().unwrap().2.clone();
+            }
+        }
+        //
+        Ok(Liveness{live_in,live_out})
+    }
+
+    /// Report values which are produced but never used, such as a
+    /// value which is pushed and then immediately popped, or which is
+    /// left on the stack when execution halts.  Where the only use of
+    /// a value is by an instruction whose own result is unused, just
+    /// the latter is reported.
+    pub fn unused_values(&self) -> Vec<Diagnostic> {
+        let live = self.liveness();
+        let mut diags = Vec::new();
+        //
+        for i in 0..self.blocks().len() {
+            if !self.is_block_reachable(i) { continue; }
+            let insns = self.block_liveness(i,&live.live_in);
+            // Determine which instruction consumes each value
+            let mut stack : Vec<Option<usize>> = Vec::new();
+            let mut consumer = vec![None; insns.len()];
+            for (j,(_,insn,_)) in insns.iter().enumerate() {
+                let (n,m) = insn.stack_effect();
+                match insn {
+                    DUP(_) => stack.push(Some(j)),
+                    SWAP(k) => {
+                        let k = *k as usize;
+                        if k < stack.len() {
+                            let len = stack.len();
+                            stack.swap(len-1,len-1-k);
+                        } else {
+                            stack.clear();
+                        }
+                    }
+                    _ => {
+                        for _ in 0..n {
+                            if let Some(Some(p)) = stack.pop() {
+                                consumer[p] = Some(j);
+                            }
+                        }
+                        for _ in 0..m { stack.push(Some(j)); }
+                    }
+                }
+            }
+            // Report unused values
+            for (j,(pc,insn,after)) in insns.iter().enumerate() {
+                let (_,m) = insn.stack_effect();
+                let unused = match insn {
+                    DUP(_) => !after.is_live(0),
+                    SWAP(_)|CREATE|CREATE2|CALL|CALLCODE|DELEGATECALL|STATICCALL => false,
+                    _ => m > 0 && (0..m).all(|k| !after.is_live(k))
+                };
+                if !unused { continue; }
+                let msg = format!("value produced by {} is never used",insn);
+                match consumer[j] {
+                    Some(c) if insns[c].1 == POP => {
+                        let note = format!("discarded at {:#x}",insns[c].0);
+                        diags.push(Diagnostic::warning("D005",&msg).with_offset(*pc).with_note(&note));
+                    }
+                    Some(_) => {}
+                    None => diags.push(Diagnostic::warning("D005",&msg).with_offset(*pc))
+                }
+            }
+        }
+        //
+        diags
+    }
+
+    /// Apply the liveness transfer function backwards through a given
+    /// (reachable) block.  This returns each instruction in the block,
+    /// along with its location and the positions live after it.
+    pub(crate) fn block_liveness(&self, id: usize, live_in: &[LiveSet]) -> Vec<(usize,Instruction,LiveSet)> {
+        let blk = &self.blocks()[id];
+        let branches = self.branches(id);
+        // Decode instructions
+        let mut insns = Vec::new();
+        let mut pc = blk.start;
+        while pc < blk.end {
+            let insn = self.decode(pc);
+            let len = insn.length(&[]);
+            insns.push((pc,insn,LiveSet::empty()));
+            pc = pc + len;
+        }
+        // Positions live after the block
+        let mut live = if self.falls_through(id) { live_in[id+1].clone() } else { LiveSet::empty() };
+        //
+        for (pc,insn,after) in insns.iter_mut().rev() {
+            if insn.can_branch() {
+                // Account for the branch target (if valid)
+                let target = match branches.iter().find(|(b,_)| b == pc) {
+                    Some((_,Some(t))) => live_in[*t].clone(),
+                    Some((_,None)) => LiveSet::all(),
+                    None => LiveSet::empty()
+                };
+                if *insn == JUMP { live = target; } else { live.join(&target); }
+            }
+            *after = live.clone();
+            live = live.transfer(insn);
+        }
+        //
+        insns
+    }
+}
@@ -0,0 +1,114 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::collections::BTreeMap;
+use core::fmt;
+use crate::dfa::AbstractValue;
+
+/// The number of bytes in a single word of memory.
+const WORD_SIZE : usize = 32;
+
+/// Represents the empty abstract memory, where nothing is known about
+/// the contents of memory.
+pub const EMPTY_MEMORY : AbstractMemory = AbstractMemory{words: BTreeMap::new()};
+
+// ============================================================================
+// Abstract Memory
+// ============================================================================
+
+/// An abstract memory tracks the (known) values of words stored at
+/// known offsets in memory.  Any location not explicitly tracked is
+/// considered to hold an unknown value.  This is sufficient to follow
+/// values (e.g. jump targets) which round-trip through memory via
+/// `MSTORE` and `MLOAD` at constant offsets.
+#[derive(Clone,Debug,PartialEq)]
+pub struct AbstractMemory {
+    /// Maps the starting offset of a word to its value.  No value
+    /// stored here is `Unknown`, and no two words overlap.
+    words: BTreeMap<usize,AbstractValue>
+}
+
+impl AbstractMemory {
+    /// Determine whether anything is known about this memory.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Read the word starting at a given offset.  This returns
+    /// `Unknown` unless the offset is known, and a known value was
+    /// written at exactly that offset.
+    pub fn read(&self, offset: AbstractValue) -> AbstractValue {
+        match offset {
+            AbstractValue::Known(n) => {
+                self.words.get(&n).copied().unwrap_or(AbstractValue::Unknown)
+            }
+            _ => AbstractValue::Unknown
+        }
+    }
+
+    /// Write a word to a given offset.  If the offset is unknown then
+    /// any word could be overwritten and, hence, everything is
+    /// forgotten.
+    pub fn write(&mut self, offset: AbstractValue, val: AbstractValue) {
+        match offset {
+            AbstractValue::Known(n) => {
+                self.invalidate(AbstractValue::Known(n),AbstractValue::Known(WORD_SIZE));
+                if val != AbstractValue::Unknown { self.words.insert(n,val); }
+            }
+            _ => self.words.clear()
+        }
+    }
+
+    /// Write an arbitrary region of memory (e.g. as done by
+    /// `CALLDATACOPY`).  All words overlapping the region are
+    /// forgotten.  If the region is not known, then everything is
+    /// forgotten.
+    pub fn invalidate(&mut self, offset: AbstractValue, len: AbstractValue) {
+        match (offset,len) {
+            (_,AbstractValue::Known(0)) => {}
+            (AbstractValue::Known(start),AbstractValue::Known(n)) => {
+                // Any word starting within WORD_SIZE bytes before the
+                // region could overlap it.
+                let lower = start.saturating_sub(WORD_SIZE - 1);
+                let upper = start + n;
+                self.words.retain(|k,_| *k < lower || *k >= upper);
+            }
+            (_,_) => self.words.clear()
+        }
+    }
+
+    /// Forget every word whose value does not satisfy a given
+    /// predicate.
+    pub fn retain<F>(&mut self, f: F)
+    where F: Fn(&AbstractValue) -> bool {
+        self.words.retain(|_,v| f(v));
+    }
+
+    /// Merge another abstract memory into this one, whilst reporting
+    /// whether this memory changed or not.  Only words with the same
+    /// value in both are retained.
+    pub fn merge_into(&mut self, other: &AbstractMemory) -> bool {
+        let n = self.words.len();
+        self.words.retain(|k,v| other.words.get(k) == Some(v));
+        self.words.len() != n
+    }
+}
+
+impl fmt::Display for AbstractMemory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{{")?;
+        for (i,(k,v)) in self.words.iter().enumerate() {
+            if i != 0 { write!(f,",")?; }
+            write!(f,"{:#x}:{}",k,v)?;
+        }
+        write!(f,"}}")
+    }
+}
@@ -9,6 +9,10 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+mod liveness;
+mod memory;
 mod stack;
 
+pub use liveness::*;
+pub use memory::*;
 pub use stack::*;
@@ -9,7 +9,9 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::{cmp,fmt,mem};
+use alloc::vec::Vec;
+use core::{cmp,fmt,mem};
+use crate::Instruction;
 use crate::util::{Interval,MAX_INTERVAL};
 
 /// Represents the singleton set of empty abstract stacks (which is
@@ -22,11 +24,16 @@ pub const BOTTOM_STACK : AbstractStack = AbstractStack{lower: MAX_INTERVAL, uppe
 // Abstract Value
 // ============================================================================
 
-/// An abstract value is either a known constant, or an unknown
-/// (i.e. arbitrary value).
+/// An abstract value is either a known constant, an opaque value
+/// read from the execution environment, or an unknown (i.e.
+/// arbitrary value).
 #[derive(Clone,Copy,Debug,PartialEq)]
 pub enum AbstractValue {
     Known(usize),
+    /// Although the actual value of an opaque value is unknown, the
+    /// same symbol always denotes the same value.  Thus, for example,
+    /// two values produced by `CALLVALUE` are always equal.
+    Opaque(Symbol),
     Unknown
 }
 
@@ -41,15 +48,24 @@ impl AbstractValue {
 
     pub fn is_known(&self) -> bool {
         match self {
-            AbstractValue::Known(n) => true,
-            AbstractValue::Unknown => false
+            AbstractValue::Known(_) => true,
+            _ => false
+        }
+    }
+
+    /// Determine whether this is an opaque value whose underlying
+    /// value may change during execution (see `Symbol::is_volatile()`).
+    pub fn is_volatile(&self) -> bool {
+        match self {
+            AbstractValue::Opaque(s) => s.is_volatile(),
+            _ => false
         }
     }
 
     pub fn unwrap(&self) -> usize {
         match self {
             AbstractValue::Known(n) => *n,
-            AbstractValue::Unknown => {
+            _ => {
                 panic!("unwrapping unknown value");
             }
         }
@@ -60,11 +76,51 @@ impl fmt::Display for AbstractValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             AbstractValue::Unknown => write!(f,"(??)"),
+            AbstractValue::Opaque(s) => write!(f,"({})",s),
             AbstractValue::Known(n) => write!(f,"({:#08x})",n)
         }
     }
 }
 
+// ============================================================================
+// Symbols
+// ============================================================================
+
+/// A symbol identifies a value read from the execution environment
+/// by a given instruction (e.g. `CALLER`), possibly for a given
+/// (known) operand (e.g. `BALANCE` of a given address).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Symbol {
+    /// Opcode of the instruction which read this value.
+    pub opcode: u8,
+    /// Operand of the instruction which read this value (if any).
+    pub operand: Option<usize>
+}
+
+impl Symbol {
+    pub fn new(opcode: u8, operand: Option<usize>) -> Self {
+        Self{opcode,operand}
+    }
+
+    /// Determine whether the value denoted by this symbol can change
+    /// during a transaction.  For example, the balance of an account
+    /// can change as a result of a call, whilst the caller cannot.
+    pub fn is_volatile(&self) -> bool {
+        // BALANCE, EXTCODESIZE, EXTCODEHASH or SELFBALANCE
+        matches!(self.opcode, 0x31|0x3b|0x3f|0x47)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let insn = Instruction::decode(0,&[self.opcode]);
+        match self.operand {
+            Some(n) => write!(f,"{:?}[{:#x}]",insn,n),
+            None => write!(f,"{:?}",insn)
+        }
+    }
+}
+
 // ============================================================================
 // Disassembly Context
 // ============================================================================
@@ -154,6 +210,21 @@ impl AbstractStack {
         }
     }
 
+    /// Apply a given function to every value on this stack.  Since
+    /// values in the lower portion are unknown, the function should
+    /// map `Unknown` to `Unknown`.
+    pub fn map<F>(mut self, f: F) -> Self
+    where F: Fn(AbstractValue) -> AbstractValue {
+        // Should never be called on bottom
+        assert!(!self.is_bottom());
+        //
+        for v in self.upper.iter_mut() {
+            *v = f(*v);
+        }
+        // Rebalance (which can be necessary if values now unknown)
+        self.rebalance()
+    }
+
     /// Set `ith` item from the top on this stack.  Thus, `0` is the
     /// top of the stack, etc.
     pub fn set(mut self, n: usize, val: AbstractValue) -> Self {
@@ -211,7 +282,7 @@ impl AbstractStack {
         let mut i = 0;
         // Determine whether any rebalancing necessary.
         while i < self.upper.len() {
-            if let AbstractValue::Known(_) = self.upper[i] {
+            if self.upper[i] != AbstractValue::Unknown {
                 break;
             }
             i = i + 1;
@@ -0,0 +1,287 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::string::{String,ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use crate::{compiler,instruction};
+use crate::compiler::TermId;
+
+// ============================================================================
+// Severity
+// ============================================================================
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Note => write!(f,"note"),
+            Severity::Warning => write!(f,"warning"),
+            Severity::Error => write!(f,"error")
+        }
+    }
+}
+
+// ============================================================================
+// Failure Kind
+// ============================================================================
+
+/// Classifies why (part of) a bytecode sequence could not be
+/// analysed, so that sequences worth examining by hand can be picked
+/// out automatically.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub enum FailureKind {
+    /// The analysis was not precise enough (e.g. it could not
+    /// determine the target of a jump).  A more precise analysis, or
+    /// hints, may do better.
+    Precision,
+    /// The bytes themselves are invalid (e.g. an undefined opcode is
+    /// reachable), so no analysis can do better.
+    Structural
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FailureKind::Precision => write!(f,"precision"),
+            FailureKind::Structural => write!(f,"structural")
+        }
+    }
+}
+
+// ============================================================================
+// Location
+// ============================================================================
+
+/// Identifies where a diagnostic arises.  Source-level tools (e.g. the
+/// parser) report character spans, whilst bytecode-level tools
+/// (e.g. the disassembler) report byte offsets.  The compiler, which
+/// has no access to the source text, reports terms.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Location {
+    /// No location is known.
+    Unknown,
+    /// A span of characters in the source text.
+    Span(Range<usize>),
+    /// A byte offset within a bytecode sequence.
+    Offset(usize),
+    /// A term within a sequence of IL statements given to the
+    /// compiler.
+    Term(TermId)
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Location::Unknown => write!(f,"?"),
+            Location::Span(r) => write!(f,"{}..{}",r.start,r.end),
+            Location::Offset(pc) => write!(f,"{:#x}",pc),
+            Location::Term(id) => write!(f,"statement {}, term {}",id.statement,id.index)
+        }
+    }
+}
+
+// ============================================================================
+// Diagnostic
+// ============================================================================
+
+/// A user-facing message produced by one of the tools in this crate
+/// (parser, compiler, assembler or disassembler).  Each diagnostic
+/// has a stable `code` which identifies the kind of problem, and
+/// optional notes giving further detail.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub location: Location,
+    pub notes: Vec<String>,
+    /// Whether this reports an analysis failure and, if so, of what
+    /// kind.
+    pub failure: Option<FailureKind>
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: &str) -> Self {
+        Diagnostic{severity, code, message: message.to_string(), location: Location::Unknown, notes: Vec::new(), failure: None}
+    }
+
+    pub fn error(code: &'static str, message: &str) -> Self {
+        Self::new(Severity::Error,code,message)
+    }
+
+    pub fn warning(code: &'static str, message: &str) -> Self {
+        Self::new(Severity::Warning,code,message)
+    }
+
+    /// Attach a span of source characters to this diagnostic.
+    pub fn with_span(mut self, span: Range<usize>) -> Self {
+        self.location = Location::Span(span);
+        self
+    }
+
+    /// Attach a bytecode offset to this diagnostic.
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.location = Location::Offset(offset);
+        self
+    }
+
+    /// Attach the position of an IL term to this diagnostic.
+    pub fn with_term(mut self, id: TermId) -> Self {
+        self.location = Location::Term(id);
+        self
+    }
+
+    /// Append a note to this diagnostic.
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    /// Mark this diagnostic as reporting an analysis failure of a
+    /// given kind.
+    pub fn with_failure(mut self, kind: FailureKind) -> Self {
+        self.failure = Some(kind);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{}[{}]: {}",self.severity,self.code,self.message)?;
+        if self.location != Location::Unknown {
+            write!(f," (at {})",self.location)?;
+        }
+        for n in &self.notes {
+            write!(f,"\n  note: {}",n)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Diagnostic { }
+
+// ============================================================================
+// Conversions
+// ============================================================================
+
+#[cfg(feature = "std")]
+impl From<crate::parser::Error> for Diagnostic {
+    fn from(e: crate::parser::Error) -> Diagnostic {
+        use crate::parser::ErrorCode;
+        let d = match &e.code {
+            ErrorCode::UnexpectedToken => {
+                Diagnostic::error("P001","unexpected token")
+            }
+            ErrorCode::UnexpectedEof => {
+                Diagnostic::error("P002","unexpected end of file")
+            }
+            ErrorCode::ExpectedToken(t) => {
+                Diagnostic::error("P003","missing token")
+                    .with_note(&alloc::format!("expected {:?}",t))
+            }
+            ErrorCode::ExpectedTokenIn(ts) => {
+                Diagnostic::error("P004","missing token")
+                    .with_note(&alloc::format!("expected one of {:?}",ts))
+            }
+            ErrorCode::UnknownImport(path) => {
+                Diagnostic::error("P005","unknown import")
+                    .with_note(&alloc::format!("cannot load \"{}\"",path))
+            }
+        };
+        let d = match &e.file {
+            Some(file) => d.with_note(&alloc::format!("in imported file \"{}\"",file)),
+            None => d
+        };
+        d.with_span(e.span.range())
+    }
+}
+
+impl From<compiler::Error> for Diagnostic {
+    fn from(e: compiler::Error) -> Diagnostic {
+        match e {
+            compiler::Error::LiteralOverflow => {
+                Diagnostic::error("C001","literal too large")
+                    .with_note("literals cannot exceed 2^256")
+            }
+            compiler::Error::InvalidMemoryAccess => {
+                Diagnostic::error("C002","invalid memory access")
+            }
+            compiler::Error::InvalidLVal => {
+                Diagnostic::error("C003","invalid assignment target")
+            }
+            compiler::Error::InvalidShift => {
+                Diagnostic::error("C004","invalid shift")
+                    .with_note("a constant shift must be less than 256")
+            }
+            compiler::Error::InvalidTuple => {
+                Diagnostic::error("C005","invalid tuple")
+            }
+            compiler::Error::StackTooDeep(id,term) => {
+                Diagnostic::error("C006","stack too deep")
+                    .with_term(id)
+                    .with_note("the stack cannot exceed 1024 items")
+                    .with_note(&alloc::format!("in expression {}",term))
+            }
+            compiler::Error::InvalidCase => {
+                Diagnostic::error("C007","invalid switch case")
+                    .with_note("every case must be a distinct literal")
+            }
+            compiler::Error::InvalidBitField => {
+                Diagnostic::error("C008","invalid bit field")
+                    .with_note("a bit field must be non-empty and lie within 256 bits")
+            }
+            compiler::Error::InvalidArray => {
+                Diagnostic::error("C009","invalid array")
+                    .with_note("an array must be declared once, be non-empty and lie in memory or storage")
+            }
+            compiler::Error::MixedAccess => {
+                Diagnostic::error("C011","region accessed both directly and through arrays")
+                    .with_note("arrays are allocated from the start of their region, so would overlap")
+            }
+            compiler::Error::IndexOutOfBounds => {
+                Diagnostic::error("C010","index out of bounds")
+                    .with_note("a constant index must be less than the length of its array")
+            }
+        }
+    }
+}
+
+impl From<instruction::Error> for Diagnostic {
+    fn from(e: instruction::Error) -> Diagnostic {
+        match e {
+            instruction::Error::InvalidPush => {
+                Diagnostic::error("A001","invalid push")
+                    .with_note("a push must have at most 32 bytes")
+            }
+            instruction::Error::InvalidDup => {
+                Diagnostic::error("A002","invalid dup")
+                    .with_note("a dup or swap must be between 1 and 16")
+            }
+            instruction::Error::InvalidLabelOffset => {
+                Diagnostic::error("A003","label offset out of range")
+                    .with_note("code size cannot exceed the 24Kb limit")
+            }
+        }
+    }
+}
@@ -9,20 +9,33 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::fmt;
-use crate::{Instruction,Instruction::*};
+use alloc::collections::{BTreeMap,BTreeSet,VecDeque};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::format;
+use alloc::string::{String,ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use crate::{Cancellation,Cancelled,Instruction,Instruction::*,Term,Unconverged};
+use crate::{AnalysisLogger,Diagnostic,FailureKind,InstructionVisitor,Pseudos};
 use crate::dfa::AbstractValue;
+use crate::util::Interval;
 
 // ============================================================================
 // Disassembly
 // ============================================================================
 
+/// The context (and pending returns) with which a block was last
+/// executed, keyed by the branch sites through which it was reached.
+type Memo<T> = BTreeMap<Vec<usize>,(T,BTreeSet<usize>)>;
+
 /// Identifies a sequential block of instructions within the original
 /// bytecode sequence.  That is, a sequence does not contain a jump
 /// destination (other than at the very start), and ends either with a
 /// terminating instruction (e.g. `RETURN`, `REVERT`, etc) or an
 /// unconditional branch (to another block).
-#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Hash,PartialOrd,Ord)]
 pub struct Block {
     /// Starting offset (in bytes) of this block.
     pub start: usize,
@@ -45,27 +58,302 @@ impl Block {
     }
 }
 
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{:#x}..{:#x}",self.start,self.end)
+    }
+}
+
+/// Determine the end of the Solidity metadata (i.e. the CBOR-encoded
+/// map followed by its two byte length) in a given bytecode sequence.
+/// Anything after this in deployment bytecode is typically the
+/// ABI-encoded constructor arguments, and this offset can be passed
+/// to `Disassembly::with_data_from()`.  This also recognises the
+/// trailer produced by the IL compiler (see `Metadata`).
+pub fn metadata_end(bytes: &[u8]) -> Option<usize> {
+    const MARKERS : [&[u8];4] = [b"\xa2\x64ipfs", b"\xa1\x65bzzr", b"\xa2\x65bzzr", b"\xa2\x65evmil"];
+    // Search backwards for the last metadata map
+    for p in (0..bytes.len()).rev() {
+        if !MARKERS.iter().any(|m| bytes[p..].starts_with(m)) { continue; }
+        // Find the length field which identifies this map
+        for e in (p+2)..=bytes.len() {
+            let len = ((bytes[e-2] as usize) << 8) | (bytes[e-1] as usize);
+            if e - 2 - p == len { return Some(e); }
+        }
+    }
+    None
+}
+
+/// Compare two byte sequences, returning every maximal run of
+/// differing bytes.  Where one is longer, its excess forms (or
+/// extends) the final run.
+fn diff(expected: &[u8], actual: &[u8]) -> Vec<Mismatch> {
+    let n = expected.len().max(actual.len());
+    let differs = |i: usize| expected.get(i) != actual.get(i);
+    let mut mismatches = Vec::new();
+    let mut i = 0;
+    while i < n {
+        if !differs(i) { i += 1; continue; }
+        let start = i;
+        while i < n && differs(i) { i += 1; }
+        let slice = |bs: &[u8]| bs[start.min(bs.len())..i.min(bs.len())].to_vec();
+        mismatches.push(Mismatch{offset: start, expected: slice(expected), actual: slice(actual)});
+    }
+    mismatches
+}
+
+/// Format a list of offsets.
+fn offsets(pcs: &[usize]) -> String {
+    let pcs : Vec<String> = pcs.iter().map(|pc| format!("{:#x}",pc)).collect();
+    pcs.join(", ")
+}
+
+/// Format the height of a stack, which is a single number when known
+/// exactly.
+fn height(h: &Interval) -> String {
+    if h.is_constant() { h.start.to_string() } else { h.to_string() }
+}
+
+/// Decode the next instruction in a given sequence of bytes, giving
+/// precedence to any pseudo-instructions.
+fn decode(pc: usize, bytes: &[u8], pseudos: &Pseudos) -> Instruction {
+    pseudos.decode(pc,bytes).unwrap_or_else(|| Instruction::decode(pc,bytes))
+}
+
+/// A hint provided by the user to guide disassembly.
+#[derive(Clone,Debug,PartialEq,Eq)]
+#[non_exhaustive]
+pub enum Hint {
+    /// Execution can begin at this offset (e.g. a target of a computed
+    /// jump which the analysis cannot resolve).
+    Entry(usize),
+    /// This range of bytes is data, not code.
+    Data(Range<usize>),
+    /// This condition holds immediately before the instruction at
+    /// this offset (e.g. from an IL `assume` statement).
+    Assume(usize,Term)
+}
+
+/// Identifies a jump which cannot be verified as valid.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum InvalidJump {
+    /// A jump (at `pc`) whose target is known, but is not a
+    /// `JUMPDEST`.
+    Target{pc: usize, target: usize},
+    /// A jump (at `pc`) whose target is unknown.
+    Unknown{pc: usize}
+}
+
+impl InvalidJump {
+    /// Determine the kind of failure this represents.  A known target
+    /// which is not a `JUMPDEST` is invalid whatever the analysis,
+    /// whereas an unknown target may be resolved by a more precise
+    /// one.
+    pub fn failure(&self) -> FailureKind {
+        match self {
+            InvalidJump::Target{..} => FailureKind::Structural,
+            InvalidJump::Unknown{..} => FailureKind::Precision
+        }
+    }
+}
+
+/// Identifies a run of bytes which differ when a disassembly is
+/// re-encoded (see `Disassembly::reassemble()`).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Mismatch {
+    /// Offset of the first differing byte.
+    pub offset: usize,
+    /// The original bytes.
+    pub expected: Vec<u8>,
+    /// The bytes produced instead.
+    pub actual: Vec<u8>
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"at {:#x}: expected 0x",self.offset)?;
+        for b in &self.expected { write!(f,"{:02x}",b)?; }
+        write!(f,", found 0x")?;
+        for b in &self.actual { write!(f,"{:02x}",b)?; }
+        Ok(())
+    }
+}
+
+/// Identifies a block whose incoming edges disagree on the height
+/// of the stack, such that merging their states loses precision (see
+/// `Disassembly::height_mismatches()`).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct HeightMismatch {
+    /// Offset of the block.
+    pub offset: usize,
+    /// The offset of each instruction from which control flows into
+    /// the block (i.e. a branch, or the last instruction of the block
+    /// before), with the height of the stack it passes on.
+    pub incoming: Vec<(usize,Interval)>
+}
+
+impl fmt::Display for HeightMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"stack heights differ at {:#x}:",self.offset)?;
+        for (i,(from,h)) in self.incoming.iter().enumerate() {
+            if i != 0 { write!(f,",")?; }
+            write!(f," {} from {:#x}",height(h),from)?;
+        }
+        Ok(())
+    }
+}
+
+/// Identifies when an edge between blocks is taken.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Condition {
+    /// The edge is always taken (e.g. by a `JUMP`).
+    Always,
+    /// The edge is taken when the condition of a `JUMPI` holds.
+    Taken,
+    /// The edge is taken when the condition of a `JUMPI` fails.
+    NotTaken
+}
+
+/// Explains why a block is (or is not) considered reachable.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Reachability {
+    /// Execution can begin at this block.
+    Entry,
+    /// The block is reached from a given block, by either a jump or
+    /// falling through from the instruction at `pc`.
+    Edge{from: usize, pc: usize, condition: Condition},
+    /// No incoming edge was found, so the block is considered data.
+    NoIncomingEdge
+}
+
+impl fmt::Display for Reachability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reachability::Entry => write!(f,"entry point"),
+            Reachability::Edge{pc,condition,..} => {
+                write!(f,"reached from {:#08x}",pc)?;
+                match condition {
+                    Condition::Always => Ok(()),
+                    Condition::Taken => write!(f," (if taken)"),
+                    Condition::NotTaken => write!(f," (if not taken)")
+                }
+            }
+            Reachability::NoIncomingEdge => write!(f,"no incoming edge")
+        }
+    }
+}
+
+/// Explains why the byte at a given offset is considered code or data
+/// (see `Disassembly::explain()`).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Explanation {
+    /// The offset being explained.
+    pub offset: usize,
+    /// Whether the byte is considered code (rather than data).
+    pub is_code: bool,
+    /// The block containing the offset.
+    pub block: Block,
+    /// Why the block is (or is not) considered reachable.
+    pub reachability: Reachability,
+    /// Whether the offset lies within a data region given as a hint.
+    pub hinted: bool,
+    /// Offsets of the reachable branches which can target the block.
+    pub branches: Vec<usize>,
+    /// Offsets of the reachable jumps whose target lies within the
+    /// block, but is not a `JUMPDEST`.
+    pub invalid: Vec<usize>,
+    /// The nearest terminating instruction (e.g. `STOP`) before the
+    /// block, along with its offset.
+    pub terminator: Option<(usize,Instruction)>,
+    /// The Solidity metadata trailer, when the offset lies within or
+    /// after it.
+    pub metadata: Option<Range<usize>>
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.is_code { "code" } else { "data" };
+        writeln!(f,"{:#x} is {}",self.offset,kind)?;
+        write!(f,"  in block {} ({})",self.block,self.reachability)?;
+        if self.hinted {
+            write!(f,"\n  within a data region given as a hint")?;
+        }
+        if self.branches.is_empty() {
+            write!(f,"\n  not targeted by any branch")?;
+        } else {
+            write!(f,"\n  targeted by branches at {}",offsets(&self.branches))?;
+        }
+        if !self.invalid.is_empty() {
+            write!(f,"\n  targeted by jumps at {} (not at a JUMPDEST)",offsets(&self.invalid))?;
+        }
+        if let Some((pc,insn)) = &self.terminator {
+            write!(f,"\n  follows {} at {:#x}",insn,pc)?;
+        }
+        match &self.metadata {
+            Some(r) if r.contains(&self.offset) => write!(f,"\n  within the metadata trailer ({:#x}..{:#x})",r.start,r.end),
+            Some(r) => write!(f,"\n  after the metadata trailer ({:#x}..{:#x})",r.start,r.end),
+            None => Ok(())
+        }
+    }
+}
+
 // ============================================================================
 // Abstract State
 // ============================================================================
 
+/// An abstract word represents the possible values of a single stack
+/// item.  The disassembler only needs to know whether a value is
+/// known (e.g. to determine a branch target), leaving the domain free
+/// to track other information (e.g. intervals, value sets, taint,
+/// etc).
+pub trait AbstractWord : Clone {
+    /// Determine whether this represents a single known value.
+    fn is_known(&self) -> bool;
+    /// Extract the known value represented (or panic otherwise).
+    fn unwrap(&self) -> usize;
+}
+
+impl AbstractWord for AbstractValue {
+    fn is_known(&self) -> bool { AbstractValue::is_known(self) }
+    fn unwrap(&self) -> usize { AbstractValue::unwrap(self) }
+}
+
 /// An abstract state provides information about the possible states
 /// of the EVM at a given point.
 pub trait AbstractState : Clone {
+    /// The domain of values held on the stack.
+    type Value : AbstractWord;
     /// Determines whether a given block is considered reachable or
     /// not.
     fn is_reachable(&self) -> bool;
     /// Apply a given instruction to this state, yielding an updated
     /// state.
     fn transfer(self, insn: &Instruction) -> Self;
+    /// Apply a given instruction to this state, where the bytecode
+    /// being executed is known (e.g. for `CODESIZE`).  By default,
+    /// the bytecode is ignored.
+    fn transfer_in(self, insn: &Instruction, _code: &[u8]) -> Self {
+        self.transfer(insn)
+    }
+    /// Refine this state with a condition known to hold at this point
+    /// (see `Hint::Assume`).  By default, conditions are ignored.
+    fn assume(self, _cond: &Term) -> Self {
+        self
+    }
     /// Apply a given branch to this stage, yielding an updated state
     /// at the point of the branch.
     fn branch(&self, target: usize, insn: &Instruction) -> Self;
     /// Merge this state with another, whilst returning a flag
     /// indicating whether anything changed.
     fn merge(&mut self, other: Self) -> bool;
+    /// Determine the possible heights of the stack, or `None` if this
+    /// is unknown (e.g. the state is unreachable).  By default,
+    /// heights are not tracked.
+    fn height(&self) -> Option<Interval> {
+        None
+    }
     /// Determine value on top of stack
-    fn peek(&self, n: usize) -> AbstractValue;
+    fn peek(&self, n: usize) -> Self::Value;
     /// Identify bottom value
     fn bottom() -> Self;
     /// Identify origin value
@@ -73,6 +361,7 @@ pub trait AbstractState : Clone {
 }
 
 impl AbstractState for () {
+    type Value = AbstractValue;
     /// Default implementation indicates everything is reachable.
     fn is_reachable(&self) -> bool { true }
     /// Default implementation does nothing
@@ -105,20 +394,192 @@ pub struct Disassembly<'a,T = ()> {
     /// The set of known blocks (in order).
     blocks: Vec<Block>,
     /// The (incoming) contexts for each block.
-    contexts: Vec<T>
+    contexts: Vec<T>,
+    /// Offsets of library addresses which are not yet linked.
+    links: Vec<usize>,
+    /// Regions of bytes which are considered data (e.g. appended
+    /// constructor arguments).  These are sorted and disjoint.
+    data: Vec<Range<usize>>,
+    /// Additional entry points (other than the start).
+    entries: Vec<usize>,
+    /// Conditions assumed to hold immediately before the instruction
+    /// at a given offset.
+    assumptions: Vec<(usize,Term)>,
+    /// Number of branch sites distinguishing the contexts of a block
+    /// (see `with_context_depth()`).
+    depth: usize,
+    /// The (incoming) contexts for each block, keyed by the branch
+    /// sites through which they were reached.  This is only used when
+    /// the depth is non-zero.
+    calls: Vec<BTreeMap<Vec<usize>,T>>,
+    /// Whether or not to track return addresses (see
+    /// `with_return_tracking()`).
+    track_returns: bool,
+    /// The locations to which each block could eventually return
+    /// (i.e. the top of a shadow return address stack).  This is only
+    /// used when tracking return addresses.
+    returns: Vec<BTreeSet<usize>>,
+    /// Whether or not to memoise the execution of blocks (see
+    /// `with_memoisation()`).
+    memoise: bool,
+    /// The context (and pending returns) with which each block was
+    /// last executed, keyed by branch sites as for `calls`.  This is
+    /// only used when memoising.
+    memo: Vec<Memo<T>>,
+    /// Byte patterns decoded as pseudo-instructions (see
+    /// `with_pseudos()`).
+    pseudos: Pseudos,
+    /// The height of the stack passed on to each block, keyed by the
+    /// instruction from which it flows (see `height_mismatches()`).
+    heights: Vec<BTreeMap<usize,Interval>>
 }
 
 impl<'a,T> Disassembly<'a,T>
 where T:AbstractState {
     pub fn new(bytes: &'a [u8]) -> Self {
         // Perform linear scan of blocks
-        let blocks = Self::scan_blocks(bytes);
+        let blocks = Self::scan_blocks(bytes,&Pseudos::new());
         // Construct default contexts
         let mut contexts = vec![T::bottom(); blocks.len()];
-        // Update origin context
-        contexts[0] = T::origin();
+        // Update origin context (unless there is no code)
+        if let Some(c) = contexts.first_mut() { *c = T::origin(); }
         // Done
-        Disassembly{bytes, blocks, contexts}
+        Disassembly{bytes, blocks, contexts, links: Vec::new(), data: Vec::new(), entries: Vec::new(), assumptions: Vec::new(), depth: 0, calls: Vec::new(), track_returns: false, returns: Vec::new(), memoise: false, memo: Vec::new(), pseudos: Pseudos::new(), heights: Vec::new()}
+    }
+
+    /// Identify the offsets of library addresses which are not yet
+    /// linked (e.g. from an artifact's link references).  A `PUSH20`
+    /// whose operand is at such an offset is disassembled as a
+    /// `LINKREF` (whose index is that of its offset), and its value
+    /// is unknown.  Since a `LINKREF` is encoded using zeros, an
+    /// address which is already present (i.e. is not zero) is left
+    /// as a `PUSH20`.  This should be applied before `build()`.
+    pub fn with_links(mut self, links: &[usize]) -> Self {
+        self.links = links.to_vec();
+        self
+    }
+
+    /// Treat every byte from a given (non-zero) offset as data, such
+    /// as the ABI-encoded constructor arguments appended to deployment
+    /// bytecode.  These bytes form a single unreachable block, and
+    /// are never decoded as instructions.  An offset of zero is
+    /// ignored.  This should be applied before `build()`.
+    pub fn with_data_from(self, start: usize) -> Self {
+        let n = self.bytes.len();
+        self.with_hints(&[Hint::Data(start..n)])
+    }
+
+    /// Apply hints where the analysis would otherwise misclassify code
+    /// as data (or vice versa).  An entry point is analysed as though
+    /// execution could begin there, whilst a data region is never
+    /// decoded as instructions.  Since data cannot begin at offset
+    /// zero, such a region is ignored (as are hints which lie outside
+    /// the bytecode).  An assumption refines the state at its offset.
+    /// This should be applied before `build()`.
+    pub fn with_hints(mut self, hints: &[Hint]) -> Self {
+        let n = self.bytes.len();
+        for h in hints {
+            match h {
+                Hint::Entry(pc) if *pc < n => self.entries.push(*pc),
+                Hint::Assume(pc,cond) if *pc < n => self.assumptions.push((*pc,cond.clone())),
+                Hint::Data(r) if 0 < r.start && r.start < r.end && r.start < n => {
+                    self.data.push(r.start..core::cmp::min(r.end,n));
+                }
+                _ => {}
+            }
+        }
+        // Normalise data regions
+        self.data.sort_by_key(|r| r.start);
+        let mut data : Vec<Range<usize>> = Vec::new();
+        for r in self.data.drain(..) {
+            match data.last_mut() {
+                Some(l) if r.start <= l.end => l.end = core::cmp::max(l.end,r.end),
+                _ => data.push(r)
+            }
+        }
+        self.data = data;
+        // Rescan blocks
+        let mut blocks = Vec::new();
+        let mut start = 0;
+        for r in &self.data {
+            blocks.extend(Self::scan_region(self.bytes,&self.pseudos,start,r.start));
+            blocks.push(Block::new(r.start,r.end));
+            start = r.end;
+        }
+        blocks.extend(Self::scan_region(self.bytes,&self.pseudos,start,n));
+        // Split blocks at entry points
+        for e in &self.entries {
+            if let Some(i) = blocks.iter().position(|b| b.encloses(*e) && b.start != *e) {
+                let b = blocks[i];
+                blocks[i] = Block::new(b.start,*e);
+                blocks.insert(i+1,Block::new(*e,b.end));
+            }
+        }
+        self.contexts = vec![T::bottom(); blocks.len()];
+        if let Some(c) = self.contexts.first_mut() { *c = T::origin(); }
+        for (i,b) in blocks.iter().enumerate() {
+            if self.entries.contains(&b.start) && !self.is_data(b.start) {
+                self.contexts[i] = T::origin();
+            }
+        }
+        self.blocks = blocks;
+        self.heights = Vec::new();
+        self
+    }
+
+    /// Decode any occurrences of the given byte patterns as opaque
+    /// pseudo-instructions, rather than as ordinary instructions.
+    /// This is useful for code containing vendor-specific opcodes,
+    /// which would otherwise be decoded as (possibly several) other
+    /// instructions.  Pseudo-instructions never terminate a block,
+    /// and have no effect on the state.  Patterns do not apply within
+    /// data regions.  This should be applied before `build()`.
+    pub fn with_pseudos(mut self, pseudos: &Pseudos) -> Self {
+        self.pseudos = pseudos.clone();
+        // Rescan blocks
+        self.with_hints(&[])
+    }
+
+    /// Analyse each block separately for every sequence of (up to)
+    /// `k` branch sites through which it is reached, rather than
+    /// merging the states from all of them.  For example, the states
+    /// from different callers of an internal function are then kept
+    /// apart, allowing its return jump to be resolved.  However, the
+    /// analysis can be much slower for larger `k`.  This should be
+    /// applied before `build()`.
+    pub fn with_context_depth(mut self, k: usize) -> Self {
+        self.depth = k;
+        self.calls = Vec::new();
+        self
+    }
+
+    /// Track return addresses, as a cheaper alternative to
+    /// distinguishing contexts (see `with_context_depth()`).  A block
+    /// which pushes the location of a `JUMPDEST` and then jumps to a
+    /// known target is treated as a call, whose continuation is that
+    /// location.  A `JUMP` whose target is unknown is then assumed to
+    /// return to the continuation of any call leading to it.  This
+    /// should be applied before `build()`.
+    pub fn with_return_tracking(mut self, enable: bool) -> Self {
+        self.track_returns = enable;
+        self.returns = Vec::new();
+        self
+    }
+
+    /// Skip executing a block during the analysis when its context is
+    /// unchanged since it was last executed, as everything flowing
+    /// from it must then be unchanged as well.  This is worthwhile
+    /// for domains whose transfer function is expensive (e.g. those
+    /// tracking memory), at the cost of retaining a copy of each
+    /// context.  Skipped blocks are not logged.  Observe that each
+    /// pass of the analysis still visits every reachable block (i.e.
+    /// there is no worklist), and memoisation only avoids executing
+    /// those whose context has not changed.  This should be applied
+    /// before `build()`.
+    pub fn with_memoisation(mut self, enable: bool) -> Self {
+        self.memoise = enable;
+        self.memo = Vec::new();
+        self
     }
 
     /// Get the state at a given program location.
@@ -131,16 +592,30 @@ where T:AbstractState {
         // Reconstruct state
         while pc < loc {
             // Decode instruction at the current position
-            let insn = Instruction::decode(pc,&self.bytes);
+            let insn = self.decode(pc);
             // Apply the transfer function!
-            ctx = ctx.transfer(&insn);
+            ctx = self.assume_at(pc,ctx).transfer_in(&insn,self.bytes);
             // Next instruction
             pc = pc + insn.length(&[]);
         }
         // Done
+        self.assume_at(loc,ctx)
+    }
+
+    /// Refine a state with any conditions assumed to hold immediately
+    /// before the instruction at a given location.
+    pub(crate) fn assume_at(&self, pc: usize, mut ctx: T) -> T {
+        for (_,cond) in self.assumptions.iter().filter(|(l,_)| *l == pc) {
+            ctx = ctx.assume(cond);
+        }
         ctx
     }
 
+    /// Get the blocks of this disassembly (in order).
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
     /// Get the enclosing block for a given bytecode location.
     pub fn get_enclosing_block(&self, pc: usize) -> &Block {
         for i in 0..self.blocks.len() {
@@ -189,10 +664,14 @@ where T:AbstractState {
             contexts.push(S::from(ctx));
         }
         // Done
-        Disassembly{bytes: self.bytes, blocks: self.blocks, contexts}
+        Disassembly{bytes: self.bytes, blocks: self.blocks, contexts, links: self.links, data: self.data, entries: self.entries, assumptions: self.assumptions, depth: self.depth, calls: Vec::new(), track_returns: self.track_returns, returns: Vec::new(), memoise: self.memoise, memo: Vec::new(), pseudos: self.pseudos, heights: Vec::new()}
     }
 
     /// Flattern the disassembly into a sequence of instructions.
+    /// Unreachable blocks are given as `DATA`, and pushes truncated
+    /// by the end of the code as `PUSHPAD`, such that encoding these
+    /// instructions reproduces the original bytes exactly (see
+    /// `reassemble()`).
     pub fn to_vec(&self) -> Vec<Instruction> {
         let mut insns = Vec::new();
         // Iterate blocks in order
@@ -214,6 +693,123 @@ where T:AbstractState {
         insns
     }
 
+    /// Encode the instructions of this disassembly (i.e. from
+    /// `to_vec()`), checking they reproduce the original bytes.  If
+    /// not, the diagnostic returned is located at the first differing
+    /// byte, with a note for every run of differing bytes (see
+    /// `mismatches()`).
+    pub fn reassemble(&self) -> Result<Vec<u8>,Diagnostic> {
+        let (bytes,mismatches) = self.encode();
+        match mismatches.first() {
+            None => Ok(bytes),
+            Some(m) => {
+                let mut diag = Diagnostic::error("D007","reassembled bytes differ").with_offset(m.offset);
+                for m in &mismatches { diag = diag.with_note(&m.to_string()); }
+                Err(diag)
+            }
+        }
+    }
+
+    /// Identify every run of bytes which differ when the instructions
+    /// of this disassembly are re-encoded (see `reassemble()`).
+    pub fn mismatches(&self) -> Vec<Mismatch> {
+        self.encode().1
+    }
+
+    /// Encode the instructions of this disassembly, additionally
+    /// returning every run of bytes which differs from the original.
+    fn encode(&self) -> (Vec<u8>,Vec<Mismatch>) {
+        let mut bytes = Vec::new();
+        for insn in self.to_vec() {
+            // Disassembled instructions never refer to labels
+            insn.encode(&[],&mut bytes).unwrap();
+        }
+        let mismatches = diff(&self.bytes,&bytes);
+        (bytes,mismatches)
+    }
+
+    /// Report problems found in the reachable parts of this
+    /// disassembly.  Currently, this flags any undefined opcodes
+    /// which could be executed, pushes truncated by the end of the
+    /// code, jumps which cannot be verified, and blocks reached with
+    /// differing stack heights.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut verifier = Verifier{diags: Vec::new()};
+        self.visit_instructions(&mut verifier);
+        let mut diags = verifier.diags;
+        for j in self.invalid_jumps() {
+            match j {
+                InvalidJump::Target{pc,target} => {
+                    let msg = format!("invalid jump target {:#x}",target);
+                    diags.push(Diagnostic::error("D002",&msg).with_offset(pc).with_failure(j.failure()));
+                }
+                InvalidJump::Unknown{pc} => {
+                    diags.push(Diagnostic::warning("D003","unknown jump target").with_offset(pc).with_failure(j.failure()));
+                }
+            }
+        }
+        for m in self.height_mismatches() {
+            let mut diag = Diagnostic::warning("D006","stack heights differ at join").with_offset(m.offset)
+                .with_failure(FailureKind::Precision);
+            for (from,h) in &m.incoming {
+                diag = diag.with_note(&format!("height {} from {:#x}",height(h),from));
+            }
+            diags.push(diag);
+        }
+        diags
+    }
+
+    /// Identify every block into which control flows with stacks of
+    /// differing heights, which the analysis merges at the cost of
+    /// precision.  This requires a domain which tracks the height of
+    /// the stack (see `AbstractState::height()`), and is only
+    /// meaningful after `build()`.
+    pub fn height_mismatches(&self) -> Vec<HeightMismatch> {
+        let mut mismatches = Vec::new();
+        for (i,heights) in self.heights.iter().enumerate() {
+            let mut hs = heights.values();
+            let Some(first) = hs.next() else { continue; };
+            if hs.any(|h| h != first) {
+                let incoming = heights.iter().map(|(from,h)| (*from,*h)).collect();
+                mismatches.push(HeightMismatch{offset: self.blocks[i].start, incoming});
+            }
+        }
+        mismatches
+    }
+
+    /// Identify every reachable `JUMP` or `JUMPI` whose target is
+    /// either known but not a `JUMPDEST`, or is unknown.  The results
+    /// depend on the precision of the analysis used to build this
+    /// disassembly.
+    pub fn invalid_jumps(&self) -> Vec<InvalidJump> {
+        let mut jumps = Vec::new();
+        for i in 0..self.blocks.len() {
+            if !self.is_block_reachable(i) { continue; }
+            let blk = &self.blocks[i];
+            for mut ctx in self.block_contexts(i) {
+                let mut pc = blk.start;
+                while pc < blk.end {
+                    let insn = self.decode(pc);
+                    ctx = self.assume_at(pc,ctx);
+                    if insn.can_branch() && self.return_targets(i,&insn,&ctx).is_none() {
+                        let target = ctx.peek(0);
+                        let jump = if !target.is_known() {
+                            Some(InvalidJump::Unknown{pc})
+                        } else if !self.is_jumpdest(target.unwrap()) {
+                            Some(InvalidJump::Target{pc,target:target.unwrap()})
+                        } else {
+                            None
+                        };
+                        if let Some(j) = jump.filter(|j| !jumps.contains(j)) { jumps.push(j); }
+                    }
+                    ctx = ctx.transfer_in(&insn,self.bytes);
+                    pc = pc + insn.length(&[]);
+                }
+            }
+        }
+        jumps
+    }
+
 
     // ================================================================
     // Helpers
@@ -225,7 +821,7 @@ where T:AbstractState {
         // Parse the block
         while pc < blk.end {
             // Decode instruction at the current position
-            let insn = Instruction::decode(pc,&self.bytes);
+            let insn = self.decode(pc);
             // Increment PC for next instruction
             pc = pc + insn.length(&[]);
             //
@@ -233,19 +829,46 @@ where T:AbstractState {
         }
     }
 
+    /// Decode the instruction at a given position, accounting for any
+    /// unlinked library addresses, pseudo-instructions and trailing
+    /// data.
+    pub(crate) fn decode(&self, pc: usize) -> Instruction {
+        if let Some(r) = self.data.iter().find(|r| r.contains(&pc)) {
+            // Everything to the end of this region is data
+            return DATA(self.bytes[pc..r.end].to_vec());
+        }
+        let insn = decode(pc,&self.bytes[..self.code_end(pc)],&self.pseudos);
+        match insn {
+            PUSH(ref bytes) if bytes.len() == 20 && bytes.iter().all(|b| *b == 0) => {
+                match self.links.iter().position(|l| *l == pc+1) {
+                    Some(i) => LINKREF(i),
+                    None => insn
+                }
+            }
+            _ => insn
+        }
+    }
+
     /// Perform a linear scan splitting out the blocks.  This is an
     /// over approximation of the truth, as some blocks may turn out
     /// to be unreachable (e.g. they are data).
-    fn scan_blocks(bytes: &[u8]) -> Vec<Block> {
+    fn scan_blocks(bytes: &[u8], pseudos: &Pseudos) -> Vec<Block> {
+        Self::scan_region(bytes,pseudos,0,bytes.len())
+    }
+
+    /// Perform a linear scan of those bytes within a given region.
+    /// Instructions are not permitted to extend beyond the region.
+    fn scan_region(bytes: &[u8], pseudos: &Pseudos, start: usize, end: usize) -> Vec<Block> {
+        let bytes = &bytes[..end];
         let mut blocks = Vec::new();
         // Current position in bytecodes
-        let mut pc = 0;
+        let mut pc = start;
         // Identifies start of current block.
-        let mut start = 0;
+        let mut start = start;
         // Parse the block
         while pc < bytes.len() {
             // Decode instruction at the current position
-            let insn = Instruction::decode(pc,&bytes);
+            let insn = decode(pc,&bytes,pseudos);
             // Increment PC for next instruction
             pc = pc + insn.length(&[]);
             // Check whether terminating instruction
@@ -275,6 +898,67 @@ where T:AbstractState {
     }
 
 
+    /// Check whether a given offset identifies a `JUMPDEST`
+    /// instruction (i.e. rather than, for example, part of a `PUSH`).
+    pub(crate) fn is_jumpdest(&self, pc: usize) -> bool {
+        // Every jumpdest begins a block
+        self.is_code(pc) && self.bytes[pc] == 0x5b
+            && self.get_enclosing_block(pc).start == pc
+    }
+
+    /// Get the bytes being disassembled.
+    pub(crate) fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Set the state on entry to the block beginning at a given
+    /// offset.
+    pub(crate) fn set_entry_state(&mut self, pc: usize, state: T) {
+        let id = self.get_enclosing_block_id(pc);
+        assert_eq!(self.blocks[id].start, pc);
+        self.contexts[id] = state;
+    }
+
+    /// Check whether a given offset lies within the code (i.e. is
+    /// within bounds and not data).
+    fn is_code(&self, pc: usize) -> bool {
+        pc < self.bytes.len() && !self.is_data(pc)
+    }
+
+    /// Check whether a given offset lies within a region of data.
+    pub(crate) fn is_data(&self, pc: usize) -> bool {
+        self.data.iter().any(|r| r.contains(&pc))
+    }
+
+    /// Get the (incoming) contexts of a given block.  When contexts
+    /// are distinguished by branch sites (see `with_context_depth()`),
+    /// there is one for each sequence of sites.  Otherwise, there is
+    /// exactly one.
+    fn block_contexts(&self, id: usize) -> Vec<T> {
+        match self.calls.get(id) {
+            Some(calls) if self.depth > 0 && !calls.is_empty() => calls.values().cloned().collect(),
+            _ => vec![self.contexts[id].clone()]
+        }
+    }
+
+    /// Determine the possible targets of a `JUMP` whose target is
+    /// unknown, when tracking return addresses.
+    fn return_targets(&self, id: usize, insn: &Instruction, ctx: &T) -> Option<Vec<usize>> {
+        match self.returns.get(id) {
+            Some(rets) if self.track_returns && *insn == JUMP && !rets.is_empty() && !ctx.peek(0).is_known() => {
+                Some(rets.iter().copied().collect())
+            }
+            _ => None
+        }
+    }
+
+    /// Determine the end of the code region containing a given
+    /// offset (i.e. the start of the next data region, or the end of
+    /// the bytes).
+    fn code_end(&self, pc: usize) -> usize {
+        self.data.iter().map(|r| r.start).filter(|s| *s > pc).min().unwrap_or(self.bytes.len())
+    }
+
     /// Determine the enclosing block number for a given bytecode
     /// address.
     fn get_enclosing_block_id(&self, pc: usize) -> usize {
@@ -291,54 +975,561 @@ impl<'a,T> Disassembly<'a,T>
 where T:AbstractState+fmt::Display {
 
     /// Apply flow analysis to refine the results of this disassembly.
-    pub fn build(mut self) -> Self {
-        let mut changed = true;
+    pub fn build(self) -> Self {
+        // Cannot be cancelled
+        self.build_with(&()).unwrap_or_else(|c| *c.0)
+    }
+
+    /// Apply flow analysis to refine the results of this disassembly,
+    /// stopping early if cancelled.  In which case, the partial
+    /// results are returned (boxed) where some reachable blocks may
+    /// not yet be identified as such.
+    pub fn build_with<C:Cancellation+?Sized>(mut self, cancel: &C) -> Result<Self,Cancelled<Box<Self>>> {
+        loop {
+            match self.iterate(cancel,&mut ()) {
+                None => { return Err(Cancelled(Box::new(self))); }
+                Some(changed) if changed.is_empty() => { return Ok(self); }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Apply flow analysis to refine the results of this disassembly,
+    /// making at most a given number of passes over the blocks.  If
+    /// this does not reach a fixpoint, the partial results are
+    /// returned (boxed) along with those blocks whose contexts were
+    /// still changing.
+    pub fn build_limited(mut self, limit: usize) -> Result<Self,Unconverged<Box<Self>>> {
+        // Initially, only the contexts of entry points are known
+        let mut changing : Vec<usize> = (0..self.blocks.len()).filter(|i| self.is_block_reachable(*i)).collect();
         //
-        while changed {
-            // Reset indicator
-            changed = false;
-            // Iterate blocks in order
-            for i in 0..self.blocks.len() {
-                // Sanity check whether block unreachable.
-                if !self.is_block_reachable(i) { continue; }
-                // Yes, is reachable so continue.
-                let blk = &self.blocks[i];
-                let mut ctx = self.contexts[i].clone();
+        for _ in 0..limit {
+            // Cannot be cancelled
+            changing = self.iterate(&(),&mut ()).unwrap();
+            if changing.is_empty() { return Ok(self); }
+        }
+        Err(Unconverged{partial: Box::new(self), changing})
+    }
+
+    /// Apply flow analysis to refine the results of this disassembly,
+    /// reporting each step of the iteration to a given logger.
+    pub fn build_logged<L:AnalysisLogger<T>+?Sized>(mut self, logger: &mut L) -> Self {
+        loop {
+            // Cannot be cancelled
+            let changed = self.iterate(&(),logger).unwrap();
+            if changed.is_empty() { return self; }
+        }
+    }
+
+    /// Make one pass over the blocks, propagating the context at the
+    /// end of each reachable block to its successors.  This returns
+    /// the blocks whose contexts changed, or `None` if cancelled.
+    fn iterate<C,L>(&mut self, cancel: &C, log: &mut L) -> Option<Vec<usize>>
+    where C:Cancellation+?Sized, L:AnalysisLogger<T>+?Sized {
+        if self.track_returns && self.returns.len() != self.blocks.len() {
+            self.returns = vec![BTreeSet::new(); self.blocks.len()];
+        }
+        if self.depth > 0 { return self.iterate_sensitive(cancel,log); }
+        let mut changed = Vec::new();
+        // Iterate blocks in order
+        for i in 0..self.blocks.len() {
+            if cancel.is_cancelled() { return None; }
+            // Sanity check whether block unreachable.
+            if !self.is_block_reachable(i) { continue; }
+            // Yes, is reachable so continue.
+            let blk = self.blocks[i];
+            let mut ctx = self.contexts[i].clone();
+            if self.memoised(i,&[],&ctx) { continue; }
+            let mut pushed = Vec::new();
+            let mut pc = blk.start;
+            let mut last = pc;
+            if log.is_enabled() { log.visit_block(i,&ctx); }
+            // Parse the block
+            while pc < blk.end {
+                // Decode instruction at the current position
+                let insn = self.decode(pc);
+                ctx = self.assume_at(pc,ctx);
+                // Check whether a branch is possible
+                if insn.can_branch() && ctx.peek(0).is_known() && self.is_code(ctx.peek(0).unwrap()) {
+                    // Determine branch target
+                    let target = ctx.peek(0).unwrap();
+                    // Determine branch context
+                    let branch_ctx = ctx.branch(target,&insn);
+                    // Convert target into block ID.
+                    let block_id = self.get_enclosing_block_id(target);
+                    // Merge in updated state
+                    if self.merge_logged(i,pc,block_id,branch_ctx,log) { changed.push(block_id); }
+                    changed.extend(self.call_returns(i,&insn,target,&ctx,&pushed));
+                } else if let Some(rets) = self.return_targets(i,&insn,&ctx) {
+                    // Return to every possible continuation
+                    for target in rets {
+                        let block_id = self.get_enclosing_block_id(target);
+                        if self.merge_logged(i,pc,block_id,ctx.branch(target,&insn),log) { changed.push(block_id); }
+                    }
+                }
+                self.record_push(&insn,&mut pushed);
+                // Apply the transfer function!
+                ctx = self.transfer_logged(pc,&insn,ctx,log);
+                // Next instruction
+                last = pc;
+                pc = pc + insn.length(&[]);
+            }
+            // Merge state into following block (unless data).
+            if (i+1) < self.blocks.len() && !self.is_data(self.blocks[i+1].start) {
+                if ctx.is_reachable() && self.add_returns(i+1,i) { changed.push(i+1); }
+                if self.merge_logged(i,last,i+1,ctx,log) { changed.push(i+1); }
+            }
+        }
+        changed.sort();
+        changed.dedup();
+        log.end_pass(&changed);
+        Some(changed)
+    }
+
+    /// Make one pass over the blocks as for `iterate()`, except that
+    /// the contexts of each block are kept apart according to the
+    /// last few branch sites through which they were reached.  The
+    /// context of each block is then the merge of these.
+    fn iterate_sensitive<C,L>(&mut self, cancel: &C, log: &mut L) -> Option<Vec<usize>>
+    where C:Cancellation+?Sized, L:AnalysisLogger<T>+?Sized {
+        // Initially, every context is reached through no branches
+        if self.calls.len() != self.blocks.len() {
+            self.calls = self.contexts.iter().map(|ctx| {
+                let mut calls = BTreeMap::new();
+                if ctx.is_reachable() { calls.insert(Vec::new(),ctx.clone()); }
+                calls
+            }).collect();
+        }
+        let mut changed = Vec::new();
+        //
+        for i in 0..self.blocks.len() {
+            if cancel.is_cancelled() { return None; }
+            if !self.is_block_reachable(i) { continue; }
+            let blk = self.blocks[i];
+            for (sites,mut ctx) in self.calls[i].clone() {
+                if self.memoised(i,&sites,&ctx) { continue; }
+                let mut pushed = Vec::new();
                 let mut pc = blk.start;
-                // println!("BLOCK (start={}, end={}): {:?}", pc, blk.end, i);
-                // println!("CONTEXT (pc={}): {}", pc, ctx);
-                // Parse the block
+                let mut last = pc;
+                if log.is_enabled() { log.visit_block(i,&ctx); }
                 while pc < blk.end {
-                    // Decode instruction at the current position
-                    let insn = Instruction::decode(pc,&self.bytes);
-                    // Check whether a branch is possible
-                    if insn.can_branch() && ctx.peek(0).is_known() {
-                        // Determine branch target
+                    let insn = self.decode(pc);
+                    ctx = self.assume_at(pc,ctx);
+                    // Record this branch site (retaining only the last few)
+                    let mut key = sites.clone();
+                    key.push(pc);
+                    if key.len() > self.depth { key.remove(0); }
+                    //
+                    if insn.can_branch() && ctx.peek(0).is_known() && self.is_code(ctx.peek(0).unwrap()) {
                         let target = ctx.peek(0).unwrap();
-                        // Determine branch context
                         let branch_ctx = ctx.branch(target,&insn);
-                        // Convert target into block ID.
                         let block_id = self.get_enclosing_block_id(target);
-                        // println!("Branch: target={} (block {})",target,block_id);
-                        // println!("Before merge (pc={}): {}", pc, self.contexts[block_id]);
-                        // Merge in updated state
-                        changed |= self.contexts[block_id].merge(branch_ctx);
-                        // println!("After merge (pc={}): {}", pc, self.contexts[block_id]);
+                        if self.merge_call(i,pc,block_id,key,branch_ctx,log) { changed.push(block_id); }
+                        changed.extend(self.call_returns(i,&insn,target,&ctx,&pushed));
+                    } else if let Some(rets) = self.return_targets(i,&insn,&ctx) {
+                        for target in rets {
+                            let block_id = self.get_enclosing_block_id(target);
+                            if self.merge_call(i,pc,block_id,key.clone(),ctx.branch(target,&insn),log) { changed.push(block_id); }
+                        }
                     }
-                    // Apply the transfer function!
-                    // print!("{:#08x}: {}",pc,ctx);
-                    ctx = ctx.transfer(&insn);
-                    // println!(" ==>\t{:?}\t==> {}",insn,ctx);
-                    // Next instruction
+                    self.record_push(&insn,&mut pushed);
+                    ctx = self.transfer_logged(pc,&insn,ctx,log);
+                    last = pc;
                     pc = pc + insn.length(&[]);
                 }
-                // Merge state into following block.
-                if (i+1) < self.blocks.len() {
-                    changed |= self.contexts[i+1].merge(ctx);
+                // Merge state into following block (unless data).
+                if (i+1) < self.blocks.len() && !self.is_data(self.blocks[i+1].start) {
+                    if ctx.is_reachable() && self.add_returns(i+1,i) { changed.push(i+1); }
+                    if self.merge_call(i,last,i+1,sites,ctx,log) { changed.push(i+1); }
                 }
             }
         }
-        self
+        changed.sort();
+        changed.dedup();
+        log.end_pass(&changed);
+        Some(changed)
+    }
+
+    /// Check whether a block was last executed (for given branch
+    /// sites) with a given context and the same pending returns,
+    /// recording them if not.  Since contexts only grow, the context
+    /// is unchanged exactly when merging it into the one recorded
+    /// changes nothing.
+    fn memoised(&mut self, id: usize, sites: &[usize], ctx: &T) -> bool {
+        if !self.memoise { return false; }
+        if self.memo.len() != self.blocks.len() {
+            self.memo = vec![BTreeMap::new(); self.blocks.len()];
+        }
+        let returns = self.returns.get(id).cloned().unwrap_or_default();
+        if let Some((prev,rets)) = self.memo[id].get(sites) {
+            if *rets == returns && !prev.clone().merge(ctx.clone()) { return true; }
+        }
+        self.memo[id].insert(sites.to_vec(),(ctx.clone(),returns));
+        false
+    }
+
+    /// Merge a context flowing from one block (at a given
+    /// instruction) into the context of another, returning whether
+    /// anything changed.
+    fn merge_logged<L:AnalysisLogger<T>+?Sized>(&mut self, from: usize, pc: usize, id: usize, ctx: T, log: &mut L) -> bool {
+        self.record_height(pc,id,&ctx);
+        if !log.is_enabled() { return self.contexts[id].merge(ctx); }
+        let state = ctx.clone();
+        let changed = self.contexts[id].merge(ctx);
+        log.merge(from,id,&state,&self.contexts[id],changed);
+        changed
+    }
+
+    /// Record the height of the stack passed on to a block from a
+    /// given instruction.
+    fn record_height(&mut self, from: usize, id: usize, ctx: &T) {
+        let Some(h) = ctx.height().filter(|_| ctx.is_reachable()) else { return; };
+        if self.heights.len() != self.blocks.len() {
+            self.heights = vec![BTreeMap::new(); self.blocks.len()];
+        }
+        let entry = self.heights[id].entry(from).or_insert(h);
+        *entry = entry.union(&h);
+    }
+
+    /// Apply a given instruction to a state, logging the outcome.
+    fn transfer_logged<L:AnalysisLogger<T>+?Sized>(&self, pc: usize, insn: &Instruction, ctx: T, log: &mut L) -> T {
+        if !log.is_enabled() { return ctx.transfer_in(insn,self.bytes); }
+        let before = ctx.clone();
+        let after = ctx.transfer_in(insn,self.bytes);
+        log.transfer(pc,insn,&before,&after);
+        after
+    }
+
+    /// Merge a context flowing from one block (at a given
+    /// instruction) into those of another for a given sequence of
+    /// branch sites, returning whether anything changed.
+    fn merge_call<L:AnalysisLogger<T>+?Sized>(&mut self, from: usize, pc: usize, id: usize, sites: Vec<usize>, ctx: T, log: &mut L) -> bool {
+        if !ctx.is_reachable() { return false; }
+        self.record_height(pc,id,&ctx);
+        self.contexts[id].merge(ctx.clone());
+        let state = if log.is_enabled() { Some(ctx.clone()) } else { None };
+        let changed = match self.calls[id].get_mut(&sites) {
+            Some(c) => c.merge(ctx),
+            None => {
+                self.calls[id].insert(sites.clone(),ctx);
+                true
+            }
+        };
+        if let Some(state) = state {
+            log.merge(from,id,&state,&self.calls[id][&sites],changed);
+        }
+        changed
+    }
+
+    /// Propagate the pending returns of a block across a branch to a
+    /// known target.  If the branch is a call (i.e. a `JUMP` where
+    /// the location of a `JUMPDEST` pushed earlier in the block remains
+    /// on the stack), then the target returns to that continuation
+    /// and the continuation inherits the pending returns instead.  If
+    /// the branch is to a pending return, nothing is propagated.  This
+    /// returns the blocks whose pending returns changed.
+    fn call_returns(&mut self, from: usize, insn: &Instruction, target: usize, ctx: &T, pushed: &[usize]) -> Vec<usize> {
+        let mut changed = Vec::new();
+        if !self.track_returns { return changed; }
+        let id = self.get_enclosing_block_id(target);
+        let cont = pushed.iter().rev().copied().filter(|l| *l != target).find(|l| {
+            (1..=16).any(|n| { let v = ctx.peek(n); v.is_known() && v.unwrap() == *l })
+        });
+        match cont {
+            Some(l) if *insn == JUMP => {
+                if self.returns[id].insert(l) { changed.push(id); }
+                let cid = self.get_enclosing_block_id(l);
+                if self.add_returns(cid,from) { changed.push(cid); }
+            }
+            // Returning to a continuation pops it
+            _ if self.returns[from].contains(&target) => {}
+            _ => {
+                if self.add_returns(id,from) { changed.push(id); }
+            }
+        }
+        changed
+    }
+
+    /// Record the location of a `JUMPDEST` pushed by a given
+    /// instruction (if applicable).
+    fn record_push(&self, insn: &Instruction, pushed: &mut Vec<usize>) {
+        if let PUSH(bs) = insn {
+            if !bs.is_empty() && bs.len() <= 8 {
+                let l = crate::util::from_be_bytes(bs) as usize;
+                if self.is_jumpdest(l) { pushed.push(l); }
+            }
+        }
+    }
+
+    /// Add the pending returns of one block to another, returning
+    /// whether anything changed.
+    fn add_returns(&mut self, to: usize, from: usize) -> bool {
+        if !self.track_returns || to == from { return false; }
+        let rets : Vec<usize> = self.returns[from].iter().copied().collect();
+        let mut changed = false;
+        for r in rets { changed |= self.returns[to].insert(r); }
+        changed
+    }
+
+    /// Explain why each block is (or is not) considered reachable.
+    /// For each reachable block, this gives the edge through which it
+    /// is first found when exploring from the entry points (breadth
+    /// first).  Hence, following these edges backwards from any
+    /// reachable block always leads to an entry point.
+    pub fn reachability(&self) -> Vec<Reachability> {
+        let n = self.blocks.len();
+        let mut reasons = vec![Reachability::NoIncomingEdge; n];
+        let mut worklist = VecDeque::new();
+        for (i,b) in self.blocks.iter().enumerate() {
+            if i == 0 || (self.entries.contains(&b.start) && !self.is_data(b.start)) {
+                reasons[i] = Reachability::Entry;
+                worklist.push_back(i);
+            }
+        }
+        //
+        while let Some(i) = worklist.pop_front() {
+            let mut edges = Vec::new();
+            for (pc,target) in self.branches(i) {
+                let condition = if self.decode(pc) == JUMPI { Condition::Taken } else { Condition::Always };
+                if let Some(t) = target { edges.push((t,pc,condition)); }
+            }
+            if self.falls_through(i) {
+                let pc = self.last_insn(i);
+                let condition = if self.decode(pc) == JUMPI { Condition::NotTaken } else { Condition::Always };
+                edges.push((i+1,pc,condition));
+            }
+            for (t,pc,condition) in edges {
+                if reasons[t] == Reachability::NoIncomingEdge {
+                    reasons[t] = Reachability::Edge{from: i, pc, condition};
+                    worklist.push_back(t);
+                }
+            }
+        }
+        //
+        reasons
+    }
+
+    /// Explain why the byte at a given offset is considered code or
+    /// data, or give `None` if the offset is out of bounds.  This
+    /// is only meaningful after `build()`.
+    pub fn explain(&self, offset: usize) -> Option<Explanation> {
+        if offset >= self.bytes.len() { return None; }
+        let id = self.get_enclosing_block_id(offset);
+        let block = self.blocks[id];
+        let mut branches : Vec<usize> = (0..self.blocks.len())
+            .flat_map(|i| self.branches(i))
+            .filter_map(|(pc,t)| if t == Some(id) { Some(pc) } else { None })
+            .collect();
+        branches.sort();
+        branches.dedup();
+        let invalid = self.invalid_jumps().into_iter().filter_map(|j| match j {
+            InvalidJump::Target{pc,target} if block.encloses(target) => Some(pc),
+            _ => None
+        }).collect();
+        // Find the nearest terminator in a preceding code block
+        let terminator = (0..id).rev().filter(|j| !self.is_data(self.blocks[*j].start)).find_map(|j| {
+            let pc = self.last_insn(j);
+            let insn = self.decode(pc);
+            if insn.fallthru() || matches!(insn,DATA(_)) { None } else { Some((pc,insn)) }
+        });
+        // Determine the metadata trailer (from its length field)
+        let metadata = metadata_end(self.bytes).map(|e| {
+            let len = ((self.bytes[e-2] as usize) << 8) | (self.bytes[e-1] as usize);
+            (e-2-len)..e
+        }).filter(|r| offset >= r.start);
+        Some(Explanation{
+            offset,
+            is_code: self.is_block_reachable(id) && !self.is_data(offset),
+            block,
+            reachability: self.reachability()[id],
+            hinted: self.is_data(offset),
+            branches,
+            invalid,
+            terminator,
+            metadata
+        })
+    }
+
+    /// Update this disassembly for a patched version of the bytecode,
+    /// reusing the results of analysis where possible.  The context
+    /// of a block is retained if the block is unchanged, and it cannot
+    /// be reached from a changed block (according to the original
+    /// analysis).  A block which reads the code itself (e.g. its size)
+    /// counts as changed whenever the bytecode does.  All other
+    /// contexts are reset, and the analysis is then rerun.  Any hints
+    /// are not retained, though links and pseudo-instructions are.
+    pub fn update<'b>(self, bytes: &'b [u8]) -> Disassembly<'b,T> {
+        let blocks = Self::scan_blocks(bytes,&self.pseudos);
+        let n = self.blocks.len();
+        // Identify blocks which have changed
+        let mut affected = vec![false; n];
+        let mut worklist = Vec::new();
+        for (i,blk) in self.blocks.iter().enumerate() {
+            let range = blk.start..blk.end;
+            if !blocks.contains(blk) || bytes.get(range.clone()) != Some(&self.bytes[range]) {
+                affected[i] = true;
+                worklist.push(i);
+            }
+        }
+        // Blocks which read the code itself (e.g. its size) are
+        // affected by any change to it
+        if !worklist.is_empty() || bytes.len() != self.bytes.len() {
+            for i in 0..n {
+                if !affected[i] && self.reads_code(i) {
+                    affected[i] = true;
+                    worklist.push(i);
+                }
+            }
+        }
+        // Identify blocks reachable from a changed block
+        while let Some(i) = worklist.pop() {
+            if !self.is_block_reachable(i) { continue; }
+            for j in self.successors(i) {
+                if !affected[j] {
+                    affected[j] = true;
+                    worklist.push(j);
+                }
+            }
+        }
+        // Retain contexts for unaffected blocks
+        let mut contexts = vec![T::bottom(); blocks.len()];
+        if let Some(c) = contexts.first_mut() { *c = T::origin(); }
+        for (i,blk) in self.blocks.iter().enumerate() {
+            if !affected[i] {
+                let j = blocks.iter().position(|b| b == blk).unwrap();
+                contexts[j] = self.contexts[i].clone();
+            }
+        }
+        //
+        Disassembly{bytes, blocks, contexts, links: self.links, data: Vec::new(), entries: Vec::new(), assumptions: Vec::new(), depth: self.depth, calls: Vec::new(), track_returns: self.track_returns, returns: Vec::new(), memoise: self.memoise, memo: Vec::new(), pseudos: self.pseudos, heights: Vec::new()}.build()
+    }
+
+    /// Check whether a given block contains an instruction whose
+    /// result depends on the code being executed (i.e. `CODESIZE` or
+    /// `CODECOPY`).
+    fn reads_code(&self, id: usize) -> bool {
+        let blk = &self.blocks[id];
+        let mut pc = blk.start;
+        while pc < blk.end {
+            let insn = self.decode(pc);
+            if matches!(insn,CODESIZE|CODECOPY) { return true; }
+            pc = pc + insn.length(&[]);
+        }
+        false
+    }
+
+    /// Determine the blocks to which a given block can transfer
+    /// control, according to the current analysis.
+    pub(crate) fn successors(&self, id: usize) -> Vec<usize> {
+        let mut succs : Vec<usize> = self.branches(id).into_iter().filter_map(|(_,t)| t).collect();
+        // Fall through (unless execution cannot continue)
+        if self.falls_through(id) {
+            succs.push(id+1);
+        }
+        //
+        succs
+    }
+
+    /// Determine the branches within a given block, according to the
+    /// current analysis.  This gives the location of each branch
+    /// along with its target block (or `None` if the target is
+    /// unknown).  Branches to an invalid target are omitted.
+    pub(crate) fn branches(&self, id: usize) -> Vec<(usize,Option<usize>)> {
+        // An unreachable block has no branches
+        if !self.is_block_reachable(id) { return Vec::new(); }
+        let blk = &self.blocks[id];
+        let mut branches = Vec::new();
+        //
+        for mut ctx in self.block_contexts(id) {
+            let mut pc = blk.start;
+            while pc < blk.end {
+                let insn = self.decode(pc);
+                ctx = self.assume_at(pc,ctx);
+                if let Some(rets) = self.return_targets(id,&insn,&ctx) {
+                    for t in rets {
+                        let b = (pc,Some(self.get_enclosing_block_id(t)));
+                        if !branches.contains(&b) { branches.push(b); }
+                    }
+                } else if insn.can_branch() {
+                    let target = ctx.peek(0);
+                    let dest = if target.is_known() { target.unwrap() } else { 0 };
+                    let branch = if !ctx.branch(dest,&insn).is_reachable() {
+                        // Branch is never taken
+                        None
+                    } else if !target.is_known() {
+                        Some((pc,None))
+                    } else if self.is_code(target.unwrap()) {
+                        Some((pc,Some(self.get_enclosing_block_id(target.unwrap()))))
+                    } else {
+                        None
+                    };
+                    if let Some(b) = branch.filter(|b| !branches.contains(b)) { branches.push(b); }
+                }
+                ctx = ctx.transfer_in(&insn,self.bytes);
+                pc = pc + insn.length(&[]);
+            }
+        }
+        branches.sort();
+        //
+        branches
+    }
+
+    /// Determine the location of the last instruction in a given block.
+    fn last_insn(&self, id: usize) -> usize {
+        let blk = &self.blocks[id];
+        let mut pc = blk.start;
+        let mut last = pc;
+        while pc < blk.end {
+            last = pc;
+            pc = pc + self.decode(pc).length(&[]);
+        }
+        last
+    }
+
+    /// Determine whether execution can continue from the end of a
+    /// given block into the following block.
+    pub(crate) fn falls_through(&self, id: usize) -> bool {
+        if !self.is_block_reachable(id) || (id+1) >= self.blocks.len() {
+            return false;
+        } else if self.is_data(self.blocks[id+1].start) {
+            return false;
+        }
+        let blk = &self.blocks[id];
+        let mut ctx = self.contexts[id].clone();
+        let mut pc = blk.start;
+        //
+        while pc < blk.end {
+            let insn = self.decode(pc);
+            ctx = self.assume_at(pc,ctx);
+            ctx = ctx.transfer_in(&insn,self.bytes);
+            pc = pc + insn.length(&[]);
+        }
+        //
+        ctx.is_reachable()
+    }
+}
+
+// ============================================================================
+// Verifier
+// ============================================================================
+
+/// Flags problems with individual instructions whilst visiting a
+/// disassembly (e.g. undefined opcodes).
+struct Verifier {
+    diags: Vec<Diagnostic>
+}
+
+impl<T> InstructionVisitor<T> for Verifier {
+    fn visit_instruction(&mut self, pc: usize, insn: &Instruction, _state: &T) {
+        if let DATA(bytes) = insn {
+            let msg = format!("undefined opcode {:#04x}",bytes[0]);
+            self.diags.push(Diagnostic::warning("D001",&msg).with_offset(pc).with_failure(FailureKind::Structural));
+        } else if let PUSHPAD(bytes,n) = insn {
+            let msg = format!("PUSH{} truncated by end of code",bytes.len());
+            self.diags.push(Diagnostic::warning("D004",&msg).with_offset(pc)
+                            .with_failure(FailureKind::Structural)
+                            .with_note(&format!("padded with {} zero byte(s)",bytes.len()-n)));
+        }
     }
 }
 
@@ -0,0 +1,170 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use crate::{AbstractState,CfaState,Disassembly,Hint,Instruction,Instruction::*};
+use crate::dfa::Symbol;
+use crate::util::from_be_bytes;
+
+// ============================================================================
+// Functions
+// ============================================================================
+
+/// A function entry point identified from the dispatcher of a
+/// contract.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Function {
+    /// The four byte selector of this function, or `None` for the
+    /// function taken when the calldata is too short to contain a
+    /// selector (i.e. `receive` or `fallback`).
+    pub selector: Option<Vec<u8>>,
+    /// Offset of the function's entry point.
+    pub entry: usize
+}
+
+impl<'a,T:AbstractState> Disassembly<'a,T> {
+    /// Identify the functions dispatched to by this bytecode.  This
+    /// recognises the sequences generated by `solc` for comparing the
+    /// selector (e.g. `PUSH4 s, EQ, PUSH t, JUMPI`) and for checking
+    /// for short calldata (e.g. `PUSH1 4, CALLDATASIZE, LT, PUSH t,
+    /// JUMPI`).  Every block is searched, since an entry point may
+    /// not be known to be reachable.
+    pub fn functions(&self) -> Vec<Function> {
+        let mut fns = Vec::new();
+        //
+        for blk in self.blocks() {
+            let mut window = Vec::new();
+            let mut pc = blk.start;
+            while pc < blk.end {
+                let insn = self.decode(pc);
+                pc = pc + insn.length(&[]);
+                window.push(insn);
+                if let Some(f) = self.match_dispatch(&window) {
+                    if !fns.contains(&f) { fns.push(f); }
+                }
+            }
+        }
+        //
+        fns
+    }
+
+    /// Treat every function identified in the dispatcher as an entry
+    /// point.  A function with a selector is entered with just the
+    /// selector on the stack, whilst the `receive` (or `fallback`)
+    /// function is entered with an empty stack.  This should be
+    /// applied before `build()`.
+    pub fn with_functions(self) -> Self {
+        let fns = self.functions();
+        let hints : Vec<Hint> = fns.iter().map(|f| Hint::Entry(f.entry)).collect();
+        let mut disasm = self.with_hints(&hints);
+        //
+        for f in &fns {
+            disasm.set_entry_state(f.entry, entry_state(f));
+        }
+        //
+        disasm
+    }
+
+    /// Check whether a given sequence of instructions ends with a
+    /// dispatch to some function.
+    fn match_dispatch(&self, insns: &[Instruction]) -> Option<Function> {
+        let n = insns.len();
+        let (selector,target) = match &insns[n.saturating_sub(5)..] {
+            [.., PUSH(s), EQ, PUSH(t), JUMPI]
+            | [PUSH(s), DUP(2), EQ, PUSH(t), JUMPI] if s.len() == 4 => (Some(s.clone()),t),
+            [PUSH(k), CALLDATASIZE, LT, PUSH(t), JUMPI] if from_be_bytes(k) == 4 => (None,t),
+            _ => { return None; }
+        };
+        let entry = from_be_bytes(target) as usize;
+        //
+        if target.len() <= 8 && self.is_jumpdest(entry) {
+            Some(Function{selector,entry})
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a,T:AbstractState+fmt::Display> Disassembly<'a,T> {
+    /// Determine the blocks which can be reached from the entry point
+    /// of a given function, according to the current analysis.  The
+    /// result is in order and includes the entry block.
+    pub fn function_blocks(&self, f: &Function) -> Vec<usize> {
+        let blocks = self.blocks();
+        let entry = match blocks.iter().position(|b| b.start == f.entry) {
+            Some(id) => id,
+            None => { return Vec::new(); }
+        };
+        let visited = self.reachable_from(&[entry],&[]);
+        //
+        (0..blocks.len()).filter(|i| visited[*i]).collect()
+    }
+
+    /// Determine which blocks can be reached from a given set of
+    /// blocks, without taking the branches at any of the given
+    /// locations.
+    fn reachable_from(&self, roots: &[usize], excluded: &[usize]) -> Vec<bool> {
+        let mut visited = vec![false; self.blocks().len()];
+        let mut worklist = roots.to_vec();
+        //
+        while let Some(id) = worklist.pop() {
+            if visited[id] { continue; }
+            visited[id] = true;
+            let branches = self.branches(id).into_iter().filter(|(pc,_)| !excluded.contains(pc));
+            worklist.extend(branches.filter_map(|(_,t)| t));
+            if self.falls_through(id) { worklist.push(id+1); }
+        }
+        //
+        visited
+    }
+}
+
+impl<'a> Disassembly<'a,CfaState> {
+    /// Determine the blocks which are only reachable when the calldata
+    /// is too short to contain a selector (i.e. `calldatasize < 4`).
+    /// These make up the `receive` (or `fallback`) function, and
+    /// exclude any blocks shared with other functions.  This relies
+    /// on the analysis constraining `CALLDATASIZE` when a branch is
+    /// taken on comparing it (e.g. `PUSH1 4, CALLDATASIZE, LT, PUSH t,
+    /// JUMPI`), such that only these blocks are known to have short
+    /// calldata.  The result is in order.
+    pub fn fallback_blocks(&self) -> Vec<usize> {
+        let size = Symbol::new(CALLDATASIZE.opcode(&[]).unwrap(),None);
+        let short = |st: &CfaState| st.bounds().iter().any(|(s,i)| *s == size && i.end < 4);
+        //
+        self.blocks().iter().enumerate()
+            .filter(|(_,b)| { let st = self.get_state(b.start); st.is_reachable() && short(&st) })
+            .map(|(i,_)| i)
+            .collect()
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Construct the state on entry to a given function.  For a function
+/// with a selector, this is the state after extracting the selector
+/// from the calldata.
+fn entry_state<T:AbstractState>(f: &Function) -> T {
+    match f.selector {
+        Some(_) => {
+            T::origin()
+                .transfer(&PUSH(vec![0]))
+                .transfer(&CALLDATALOAD)
+                .transfer(&PUSH(vec![0xe0]))
+                .transfer(&SHR)
+        }
+        None => T::origin()
+    }
+}
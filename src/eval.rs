@@ -0,0 +1,275 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec::Vec;
+use crate::{Backend,Compiler,Instruction,Term};
+use crate::util::{keccak256,u256};
+
+/// Number of instructions executed by `run()` before it gives up,
+/// which ensures it terminates.
+const MAX_STEPS : usize = 10_000;
+
+/// Upper bound on the memory used by an evaluator (in bytes).
+const MAX_MEMORY : usize = 0x10000;
+
+// ============================================================================
+// Machine
+// ============================================================================
+
+/// The state of an evaluator part way through execution.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct Machine {
+    /// Index of the next instruction to execute.
+    pub pc: usize,
+    /// The stack (with the top item last).
+    pub stack: Vec<u256>,
+    /// The contents of memory.
+    pub memory: Vec<u8>
+}
+
+impl Machine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// The result of executing instructions with a limited amount of fuel
+/// (see `Evaluator::run_with_fuel()`).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Outcome {
+    /// Execution completed, leaving the given stack (with the top
+    /// item last).
+    Halted(Vec<u256>),
+    /// An unsupported instruction was reached, the stack underflowed,
+    /// or an invalid jump or memory access occurred.
+    Failed,
+    /// The fuel ran out before execution completed, leaving the given
+    /// state (from which execution can be resumed).
+    OutOfFuel(Machine)
+}
+
+// ============================================================================
+// Evaluator
+// ============================================================================
+
+/// A backend which executes the instructions it is given, rather than
+/// encoding them.  This allows constant expressions to be evaluated
+/// at compile time using the same semantics as the EVM.  Only
+/// instructions whose outcome is determined by their operands are
+/// supported (e.g. `ADD`, but not `CALLER`), and memory starts out
+/// empty.
+#[derive(Clone,Debug,Default)]
+pub struct Evaluator {
+    insns: Vec<Instruction>,
+    labels: usize
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Execute the instructions given so far starting from an empty
+    /// stack, and return the final stack (with the top item last).
+    /// This gives `None` if an unsupported instruction is reached, the
+    /// stack underflows, or execution runs for too long.
+    pub fn run(&self) -> Option<Vec<u256>> {
+        match self.run_with_fuel(MAX_STEPS) {
+            Outcome::Halted(stack) => Some(stack),
+            _ => None
+        }
+    }
+
+    /// Execute the instructions given so far starting from an empty
+    /// stack, where each instruction executed consumes one unit of
+    /// fuel.  This ensures execution terminates, even for untrusted
+    /// instructions which loop forever.
+    pub fn run_with_fuel(&self, fuel: usize) -> Outcome {
+        self.resume(Machine::new(),fuel)
+    }
+
+    /// Continue executing from a given state (e.g. one which ran out
+    /// of fuel) with a given amount of fuel.
+    pub fn resume(&self, mut m: Machine, mut fuel: usize) -> Outcome {
+        loop {
+            match self.insns.get(m.pc) {
+                None|Some(Instruction::STOP) => { return Outcome::Halted(m.stack); }
+                Some(_) if fuel == 0 => { return Outcome::OutOfFuel(m); }
+                Some(insn) => {
+                    fuel -= 1;
+                    m.pc += 1;
+                    if self.execute(insn,&mut m).is_none() {
+                        return Outcome::Failed;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Execute a single instruction on a given state, giving `None` if
+    /// this fails.  The program counter has already been advanced.
+    fn execute(&self, insn: &Instruction, m: &mut Machine) -> Option<()> {
+        match insn {
+            Instruction::ADD => binary(&mut m.stack,|l,r| l + r)?,
+            Instruction::MUL => binary(&mut m.stack,|l,r| l * r)?,
+            Instruction::SUB => binary(&mut m.stack,|l,r| l - r)?,
+            Instruction::DIV => binary(&mut m.stack,|l,r| l / r)?,
+            Instruction::MOD => binary(&mut m.stack,|l,r| l % r)?,
+            Instruction::EXP => binary(&mut m.stack,|l,r| l.pow(r))?,
+            Instruction::LT => binary(&mut m.stack,|l,r| bit(l < r))?,
+            Instruction::GT => binary(&mut m.stack,|l,r| bit(l > r))?,
+            Instruction::EQ => binary(&mut m.stack,|l,r| bit(l == r))?,
+            Instruction::ISZERO => {
+                let v = m.stack.pop()?;
+                m.stack.push(bit(v == u256::ZERO));
+            }
+            Instruction::AND => binary(&mut m.stack,|l,r| l & r)?,
+            Instruction::OR => binary(&mut m.stack,|l,r| l | r)?,
+            Instruction::NOT => {
+                let v = m.stack.pop()?;
+                m.stack.push(!v);
+            }
+            // Observe the shift amount is on top of the stack.
+            Instruction::SHL => binary(&mut m.stack,|n,v| v << shift(n))?,
+            Instruction::SHR => binary(&mut m.stack,|n,v| v >> shift(n))?,
+            Instruction::SAR => binary(&mut m.stack,|n,v| sar(v,shift(n)))?,
+            Instruction::KECCAK256 => {
+                let offset = m.stack.pop()?;
+                let len = m.stack.pop()?;
+                let bytes = read(&mut m.memory,offset,len)?;
+                m.stack.push(u256::from_be_bytes(&keccak256(bytes)));
+            }
+            Instruction::POP => { m.stack.pop()?; }
+            Instruction::MLOAD => {
+                let offset = m.stack.pop()?;
+                let word = u256::from_be_bytes(read(&mut m.memory,offset,u256::from(32u8))?);
+                m.stack.push(word);
+            }
+            Instruction::MSTORE => {
+                let offset = m.stack.pop()?;
+                let word = m.stack.pop()?;
+                read(&mut m.memory,offset,u256::from(32u8))?.copy_from_slice(&word.to_be_bytes());
+            }
+            Instruction::JUMP => {
+                let target = m.stack.pop()?;
+                m.pc = self.destination(target)?;
+            }
+            Instruction::JUMPI => {
+                let target = m.stack.pop()?;
+                let cond = m.stack.pop()?;
+                if cond != u256::ZERO {
+                    m.pc = self.destination(target)?;
+                }
+            }
+            Instruction::JUMPDEST(_) => {}
+            Instruction::PUSH(bytes) if bytes.len() <= 32 => {
+                m.stack.push(u256::from_be_bytes(bytes));
+            }
+            Instruction::PUSHL(lab) => m.stack.push(u256::from(*lab)),
+            Instruction::DUP(n) => {
+                let k = (*n as usize).checked_sub(1)?;
+                let nth = *m.stack.iter().rev().nth(k)?;
+                m.stack.push(nth);
+            }
+            Instruction::SWAP(n) => {
+                let top = m.stack.len().checked_sub(1)?;
+                let nth = top.checked_sub(*n as usize)?;
+                m.stack.swap(top,nth);
+            }
+            _ => { return None; }
+        }
+        Some(())
+    }
+
+    /// Determine the location of the instruction marking a given label.
+    fn destination(&self, label: u256) -> Option<usize> {
+        self.insns.iter().position(|i| matches!(i, Instruction::JUMPDEST(l) if u256::from(*l) == label))
+    }
+}
+
+impl Backend for Evaluator {
+    fn fresh_label(&mut self) -> usize {
+        self.labels += 1;
+        self.labels - 1
+    }
+
+    fn emit(&mut self, insn: Instruction) {
+        self.insns.push(insn);
+    }
+}
+
+/// Evaluate an expression at compile time, giving `None` if it is not
+/// constant (e.g. it reads `msg.sender` or memory) or does not
+/// compile.
+pub fn evaluate(term: &Term) -> Option<u256> {
+    if term.width() != 1 || reads_memory(term) {
+        return None;
+    }
+    let mut evaluator = Evaluator::new();
+    Compiler::new(&mut evaluator).translate(term).ok()?;
+    match evaluator.run()?.as_slice() {
+        [v] => Some(*v),
+        _ => None
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Apply a binary operation to the top two stack items, where the
+/// first operand is the topmost.
+fn binary<F:Fn(u256,u256)->u256>(stack: &mut Vec<u256>, op: F) -> Option<()> {
+    let l = stack.pop()?;
+    let r = stack.pop()?;
+    stack.push(op(l,r));
+    Some(())
+}
+
+/// Determine whether a term reads from memory or a declared array,
+/// whose contents are not known at compile time (though an evaluator
+/// starts with memory empty).
+fn reads_memory(term: &Term) -> bool {
+    matches!(term,Term::MemoryAccess(_)|Term::Variable(_)) || term.children().into_iter().any(reads_memory)
+}
+
+/// Convert a truth value into a word.
+fn bit(b: bool) -> u256 {
+    u256::from(b as u8)
+}
+
+/// Determine a shift amount, where anything beyond the word width is
+/// treated as the word width.
+fn shift(n: u256) -> usize {
+    if n.high() != 0 || n.low() > 256 { 256 } else { n.low() as usize }
+}
+
+/// Arithmetic shift right, where the sign bit is extended.  Thus,
+/// shifting a negative word by the word width (or more) gives `-1`.
+fn sar(v: u256, n: usize) -> u256 {
+    if v.high() >> 127 == 0 { v >> n } else { !(!v >> n) }
+}
+
+/// Access a region of memory, expanding it as necessary.  This gives
+/// `None` if the region extends beyond the memory available.
+fn read(memory: &mut Vec<u8>, offset: u256, len: u256) -> Option<&mut [u8]> {
+    let limit = u256::from(MAX_MEMORY);
+    if offset > limit || len > limit || offset + len > limit {
+        return None;
+    }
+    let start = offset.low() as usize;
+    let end = start + (len.low() as usize);
+    if memory.len() < end {
+        // Expand in whole words (as for the EVM)
+        memory.resize(end.div_ceil(32) * 32, 0);
+    }
+    Some(&mut memory[start..end])
+}
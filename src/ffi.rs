@@ -0,0 +1,99 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A C interface to the disassembler and compiler.  To build a shared
+//! library, use e.g. `cargo rustc --lib --features ffi --crate-type
+//! cdylib`.  Every string or byte array returned must be released
+//! using the corresponding `evmil_free_*` function.  Functions return
+//! `NULL` on failure (including if the analysis panics, which is never
+//! allowed to unwind into the caller).
+use std::ffi::{CStr,CString};
+use std::os::raw::c_char;
+use std::panic::catch_unwind;
+use std::ptr;
+use std::slice;
+use crate::{Options,ToHexString,compile_il,disassemble_hex};
+
+/// Disassemble `len` bytes into a newly allocated (null terminated)
+/// listing.  Fails if there are no bytes.
+///
+/// # Safety
+///
+/// `bytes` must point to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn evmil_disassemble(bytes: *const u8, len: usize) -> *mut c_char {
+    if bytes.is_null() || len == 0 { return ptr::null_mut(); }
+    let hex = slice::from_raw_parts(bytes,len).to_hex_string();
+    match catch_unwind(|| disassemble_hex(&hex,&Options::default())) {
+        Ok(Ok(s)) => to_c_string(s),
+        _ => ptr::null_mut()
+    }
+}
+
+/// Compile a (null terminated) IL program into a newly allocated
+/// byte array, whose length is written to `out_len`.
+///
+/// # Safety
+///
+/// `source` must be a valid null terminated string, and `out_len`
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn evmil_compile(source: *const c_char, out_len: *mut usize) -> *mut u8 {
+    if source.is_null() || out_len.is_null() { return ptr::null_mut(); }
+    let source = match CStr::from_ptr(source).to_str() {
+        Ok(s) => s,
+        Err(_) => { return ptr::null_mut(); }
+    };
+    match catch_unwind(|| compile_il(source,&Options::default())) {
+        Ok(Ok(bytes)) => {
+            let bytes = bytes.into_boxed_slice();
+            *out_len = bytes.len();
+            Box::into_raw(bytes) as *mut u8
+        }
+        _ => ptr::null_mut()
+    }
+}
+
+/// Release a string returned from this library.
+///
+/// # Safety
+///
+/// `s` must have been returned from this library (or be `NULL`), and
+/// not already released.
+#[no_mangle]
+pub unsafe extern "C" fn evmil_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Release a byte array of a given length returned from this library.
+///
+/// # Safety
+///
+/// `bytes` must have been returned from this library with the given
+/// length (or be `NULL`), and not already released.
+#[no_mangle]
+pub unsafe extern "C" fn evmil_free_bytes(bytes: *mut u8, len: usize) {
+    if !bytes.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(bytes,len)));
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn to_c_string(s: String) -> *mut c_char {
+    // A listing never contains interior nulls
+    CString::new(s).unwrap().into_raw()
+}
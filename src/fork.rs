@@ -0,0 +1,132 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec::Vec;
+use core::fmt;
+use crate::{AbstractState,Disassembly,Instruction,Instruction::*};
+
+// ============================================================================
+// EVM Versions
+// ============================================================================
+
+/// Identifies a version (i.e. hard fork) of the EVM which introduced
+/// new instructions.  Versions are ordered from oldest to newest.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord)]
+#[non_exhaustive]
+pub enum EvmVersion {
+    Frontier,
+    Homestead,
+    Byzantium,
+    Constantinople,
+    Istanbul,
+    London,
+    Shanghai,
+    Cancun
+}
+
+impl EvmVersion {
+    /// Every version (oldest first).
+    pub const ALL : &'static [EvmVersion] = &[
+        EvmVersion::Frontier,
+        EvmVersion::Homestead,
+        EvmVersion::Byzantium,
+        EvmVersion::Constantinople,
+        EvmVersion::Istanbul,
+        EvmVersion::London,
+        EvmVersion::Shanghai,
+        EvmVersion::Cancun
+    ];
+
+    /// Get the name of this version (e.g. `"shanghai"`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvmVersion::Frontier => "frontier",
+            EvmVersion::Homestead => "homestead",
+            EvmVersion::Byzantium => "byzantium",
+            EvmVersion::Constantinople => "constantinople",
+            EvmVersion::Istanbul => "istanbul",
+            EvmVersion::London => "london",
+            EvmVersion::Shanghai => "shanghai",
+            EvmVersion::Cancun => "cancun"
+        }
+    }
+
+    /// Find the version with a given name (if any).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|v| v.name() == name)
+    }
+
+    /// Determine the version which introduced a given instruction.
+    pub fn introducing(insn: &Instruction) -> Self {
+        match insn {
+            DELEGATECALL => EvmVersion::Homestead,
+            RETURNDATASIZE|RETURNDATACOPY|STATICCALL|REVERT => EvmVersion::Byzantium,
+            SHL|SHR|SAR|EXTCODEHASH|CREATE2 => EvmVersion::Constantinople,
+            CHAINID|SELFBALANCE => EvmVersion::Istanbul,
+            BASEFEE => EvmVersion::London,
+            PUSH(bs) if bs.is_empty() => EvmVersion::Shanghai,
+            BLOBHASH|BLOBBASEFEE|TLOAD|TSTORE|MCOPY => EvmVersion::Cancun,
+            _ => EvmVersion::Frontier
+        }
+    }
+
+    /// Check whether a given instruction is defined in this version.
+    pub fn supports(&self, insn: &Instruction) -> bool {
+        *self >= Self::introducing(insn)
+    }
+}
+
+impl fmt::Display for EvmVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{}",self.name())
+    }
+}
+
+// ============================================================================
+// Fork Differences
+// ============================================================================
+
+/// Identifies an instruction which is defined in one version, but is
+/// undefined (i.e. behaves like `INVALID`) in another.
+#[derive(Clone,Debug,PartialEq)]
+pub struct ForkDifference {
+    /// Location of the instruction.
+    pub pc: usize,
+    /// The instruction itself.
+    pub insn: Instruction,
+    /// The version which introduced it.
+    pub introduced: EvmVersion
+}
+
+impl<'a,T:AbstractState> Disassembly<'a,T> {
+    /// Identify reachable instructions whose validity differs between
+    /// two versions (e.g. `PUSH0` is undefined before Shanghai).
+    /// Since every instruction introduced after Frontier is a single
+    /// byte, the bytes decode in the same way under every version.
+    /// Hence, only validity can differ.
+    pub fn fork_differences(&self, v1: EvmVersion, v2: EvmVersion) -> Vec<ForkDifference> {
+        let mut diffs = Vec::new();
+        for (i,blk) in self.blocks().iter().enumerate() {
+            if !self.is_block_reachable(i) { continue; }
+            let mut pc = blk.start;
+            while pc < blk.end {
+                let insn = self.decode(pc);
+                let len = insn.length(&[]);
+                if v1.supports(&insn) != v2.supports(&insn) {
+                    let introduced = EvmVersion::introducing(&insn);
+                    diffs.push(ForkDifference{pc,insn,introduced});
+                }
+                pc = pc + len;
+            }
+        }
+        diffs
+    }
+}
@@ -0,0 +1,301 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::{Bytecode,Instruction};
+use crate::Instruction::*;
+
+/// Maximum stack height of a generated program, which ensures every
+/// item can be reached by `DUP` and `SWAP`.
+const MAX_HEIGHT : usize = 16;
+
+/// Maximum nesting of branches and loops.
+const MAX_DEPTH : usize = 3;
+
+const ARITHMETIC : &[Instruction] = &[
+    ADD,MUL,SUB,DIV,SDIV,MOD,SMOD,EXP,SIGNEXTEND,LT,GT,SLT,SGT,EQ,ISZERO,
+    AND,OR,XOR,NOT,BYTE,SHL,SHR,SAR,ADDMOD,MULMOD
+];
+
+const ENVIRONMENT : &[Instruction] = &[
+    ADDRESS,ORIGIN,CALLER,CALLVALUE,CALLDATASIZE,CODESIZE,GASPRICE,
+    RETURNDATASIZE,COINBASE,TIMESTAMP,NUMBER,DIFFICULTY,GASLIMIT,CHAINID,
+    SELFBALANCE,MSIZE,GAS
+];
+
+// ============================================================================
+// Mix
+// ============================================================================
+
+/// Relative weights of the kinds of instruction to generate.  A kind
+/// with weight zero is never generated (though pushes and pops are
+/// always used to balance the stack).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Mix {
+    /// Arithmetic, comparison and bitwise operations.
+    pub arithmetic: u32,
+    /// Stack manipulation (i.e. `PUSH`, `POP`, `DUP` and `SWAP`).
+    pub stack: u32,
+    /// Values read from the execution environment (e.g. `CALLER`).
+    pub environment: u32,
+    /// Memory loads and stores.
+    pub memory: u32,
+    /// Storage loads and stores.
+    pub storage: u32,
+    /// Forward conditional branches.
+    pub branches: u32
+}
+
+impl Mix {
+    /// A mix which generates nothing (except as necessary).
+    pub const NONE : Mix = Mix{arithmetic: 0, stack: 0, environment: 0, memory: 0, storage: 0, branches: 0};
+}
+
+impl Default for Mix {
+    fn default() -> Self {
+        Mix{arithmetic: 4, stack: 4, environment: 2, memory: 1, storage: 1, branches: 1}
+    }
+}
+
+// ============================================================================
+// Generator
+// ============================================================================
+
+/// Generates random (but valid) bytecode programs from a given seed.
+/// Every generated program has a balanced stack (i.e. never
+/// underflows, or uses `DUP` or `SWAP` beyond the first `16` items)
+/// and every jump has a valid target.  Furthermore, every loop is
+/// bounded and, hence, every program terminates.  The same seed (and
+/// settings) always generates the same programs.
+#[derive(Clone,Debug)]
+pub struct Generator {
+    /// State of the random number generator.
+    state: u64,
+    /// Number of steps to generate.
+    size: usize,
+    /// Number of loops to generate.
+    loops: usize,
+    /// Relative weights of each kind of instruction.
+    mix: Mix
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Generator{state: seed, size: 32, loops: 0, mix: Mix::default()}
+    }
+
+    /// Set the (approximate) number of instructions to generate.
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Set the number of (bounded) loops to generate.
+    pub fn with_loops(mut self, loops: usize) -> Self {
+        self.loops = loops;
+        self
+    }
+
+    /// Set the relative weights of each kind of instruction.
+    pub fn with_mix(mut self, mix: Mix) -> Self {
+        self.mix = mix;
+        self
+    }
+
+    /// Generate the next program.
+    pub fn generate(&mut self) -> Bytecode {
+        let mut code = Bytecode::new();
+        let mut builder = Builder{code: &mut code, height: 0, loops: self.loops};
+        let size = core::cmp::max(self.size,self.loops);
+        self.block(&mut builder,size,0);
+        code.push(STOP);
+        code
+    }
+
+    /// Generate the next program, encoded as bytes.
+    pub fn generate_bytes(&mut self) -> Vec<u8> {
+        // Generated programs are always valid
+        self.generate().to_bytes().unwrap()
+    }
+
+    /// Generate a block of roughly a given number of steps, which
+    /// leaves the stack as it found it.  Items already on the stack
+    /// may be read, but not consumed.
+    fn block(&mut self, b: &mut Builder, size: usize, depth: usize) {
+        let floor = b.height;
+        let mut remaining = size;
+        //
+        while remaining > 0 {
+            // Place loops uniformly at random at the outermost level
+            if depth == 0 && b.loops > 0 && b.height + 1 < MAX_HEIGHT && self.below(remaining) < b.loops {
+                b.loops = b.loops - 1;
+                let body = self.below(remaining);
+                self.bounded_loop(b,body,depth+1);
+                remaining = remaining - (body + 1);
+            } else {
+                remaining = remaining - self.step(b,floor,remaining,depth);
+            }
+        }
+        // Rebalance the stack
+        while b.height > floor { b.emit(POP,1,0); }
+        // Place any loops not yet placed
+        while depth == 0 && b.loops > 0 {
+            b.loops = b.loops - 1;
+            self.bounded_loop(b,0,depth+1);
+        }
+    }
+
+    /// Generate a single step, returning its size.
+    fn step(&mut self, b: &mut Builder, floor: usize, remaining: usize, depth: usize) -> usize {
+        let avail = b.height - floor;
+        // Avoid exceeding the maximum height
+        if b.height >= MAX_HEIGHT {
+            b.emit(POP,1,0);
+            return 1;
+        }
+        let m = self.mix;
+        let weights = [m.arithmetic,m.stack,m.environment,m.memory,m.storage,m.branches];
+        match self.choose(&weights) {
+            Some(0) => {
+                let insn = ARITHMETIC[self.below(ARITHMETIC.len())].clone();
+                let (n,_) = insn.stack_effect();
+                if avail < n { return self.push(b); }
+                b.emit(insn,n,1);
+            }
+            Some(1) => {
+                match self.below(4) {
+                    0 if avail >= 1 => b.emit(POP,1,0),
+                    1 if b.height >= 1 => {
+                        let n = 1 + self.below(b.height);
+                        b.emit(DUP(n as u8),0,1);
+                    }
+                    2 if avail >= 2 => {
+                        let n = 1 + self.below(avail-1);
+                        b.emit(SWAP(n as u8),0,0);
+                    }
+                    _ => { return self.push(b); }
+                }
+            }
+            Some(2) => {
+                let insn = ENVIRONMENT[self.below(ENVIRONMENT.len())].clone();
+                b.emit(insn,0,1);
+            }
+            Some(3) => {
+                // Keep memory small
+                let offset = (self.below(8) * 32) as u8;
+                if avail >= 1 && self.below(2) == 0 {
+                    b.emit(PUSH(vec![offset]),0,1);
+                    b.emit(MSTORE,2,0);
+                } else {
+                    b.emit(PUSH(vec![offset]),0,1);
+                    b.emit(MLOAD,1,1);
+                }
+            }
+            Some(4) => {
+                let key = self.below(16) as u8;
+                if avail >= 1 && self.below(2) == 0 {
+                    b.emit(PUSH(vec![key]),0,1);
+                    b.emit(SSTORE,2,0);
+                } else {
+                    b.emit(PUSH(vec![key]),0,1);
+                    b.emit(SLOAD,1,1);
+                }
+            }
+            Some(_) if avail >= 1 && depth < MAX_DEPTH && remaining > 1 => {
+                // Branch over a block (using the top item as condition)
+                let body = self.below(remaining - 1);
+                let end = b.code.fresh_label();
+                b.emit(PUSHL(end),0,1);
+                b.emit(JUMPI,2,0);
+                self.block(b,body,depth+1);
+                b.emit(JUMPDEST(end),0,0);
+                return body + 1;
+            }
+            _ => { return self.push(b); }
+        }
+        1
+    }
+
+    /// Generate a loop which executes a given block between one and
+    /// four times (using a counter on the stack).
+    fn bounded_loop(&mut self, b: &mut Builder, size: usize, depth: usize) {
+        let head = b.code.fresh_label();
+        let count = 1 + self.below(4) as u8;
+        b.emit(PUSH(vec![count]),0,1);
+        b.emit(JUMPDEST(head),0,0);
+        self.block(b,size,depth);
+        // Decrement counter, and repeat unless zero
+        b.emit(PUSH(vec![1]),0,1);
+        b.emit(SWAP(1),0,0);
+        b.emit(SUB,2,1);
+        b.emit(DUP(1),0,1);
+        b.emit(PUSHL(head),0,1);
+        b.emit(JUMPI,2,0);
+        b.emit(POP,1,0);
+    }
+
+    /// Push a random value.
+    fn push(&mut self, b: &mut Builder) -> usize {
+        let n = 1 + self.below(32);
+        let bytes = (0..n).map(|_| self.next() as u8).collect();
+        b.emit(PUSH(bytes),0,1);
+        1
+    }
+
+    /// Choose an index at random, in proportion to the given weights.
+    fn choose(&mut self, weights: &[u32]) -> Option<usize> {
+        let total : u64 = weights.iter().map(|w| *w as u64).sum();
+        if total == 0 { return None; }
+        let mut r = self.next() % total;
+        for (i,w) in weights.iter().enumerate() {
+            if r < (*w as u64) { return Some(i); }
+            r = r - (*w as u64);
+        }
+        unreachable!()
+    }
+
+    /// Generate a random number below a given (non-zero) bound.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % (n as u64)) as usize
+    }
+
+    /// Generate the next random number (using SplitMix64).
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Tracks the program being generated.
+struct Builder<'a> {
+    code: &'a mut Bytecode,
+    /// Current height of the stack.
+    height: usize,
+    /// Number of loops still to be generated.
+    loops: usize
+}
+
+impl<'a> Builder<'a> {
+    /// Append an instruction which pops and then pushes a given
+    /// number of items (ignoring `DUP` and `SWAP` operands).
+    fn emit(&mut self, insn: Instruction, pops: usize, pushes: usize) {
+        self.height = self.height - pops + pushes;
+        self.code.push(insn);
+    }
+}
@@ -1,5 +1,7 @@
-use std::fmt::Write;
-use std::num::ParseIntError;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::num::ParseIntError;
 
 /// A simple trait allowing something to be converted into a hex
 /// string.
@@ -0,0 +1,119 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use core::fmt;
+use alloc::vec::Vec;
+use crate::{AbstractState,ByteClass,Disassembly};
+
+// ============================================================================
+// Hex Dump
+// ============================================================================
+
+/// A contiguous run of bytes within a row of a hex dump, all of which
+/// belong to the same block.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct HexSpan {
+    /// Offset of the first byte.
+    pub offset: usize,
+    /// Block containing these bytes.
+    pub block: usize,
+    /// Classification of these bytes.
+    pub class: ByteClass,
+    /// The bytes themselves.
+    pub bytes: Vec<u8>
+}
+
+/// A single row of a hex dump.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct HexRow {
+    /// Offset of the first byte.
+    pub offset: usize,
+    /// Spans making up this row (in order).
+    pub spans: Vec<HexSpan>
+}
+
+/// A hex dump of a disassembly, where each byte is annotated with its
+/// block and whether it is code or data.  This is intended for
+/// frontends which need to highlight where the disassembly went
+/// wrong.  When printed, each span is given as markup, such as:
+///
+/// ```text
+/// 000000: <code block="0">60 03 56</code> <data block="1">00</data>
+/// ```
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct HexDump {
+    pub rows: Vec<HexRow>
+}
+
+impl<'a,T:AbstractState> Disassembly<'a,T> {
+    /// Construct a hex dump of this (built) disassembly, with a given
+    /// number of bytes per row.
+    pub fn hexdump(&self, width: usize) -> HexDump {
+        assert!(width > 0);
+        let bytes = self.bytes();
+        let mut rows : Vec<HexRow> = Vec::new();
+        //
+        for (i,(blk,class)) in self.blocks().iter().zip(self.classify()).enumerate() {
+            let end = core::cmp::min(blk.end,bytes.len());
+            let mut pc = blk.start;
+            while pc < end {
+                // Spans cannot cross rows
+                let next = core::cmp::min(end,(pc / width + 1) * width);
+                let span = HexSpan{offset: pc, block: i, class, bytes: bytes[pc..next].to_vec()};
+                match rows.last_mut() {
+                    Some(r) if r.offset == pc - (pc % width) => r.spans.push(span),
+                    _ => rows.push(HexRow{offset: pc - (pc % width), spans: alloc::vec![span]})
+                }
+                pc = next;
+            }
+        }
+        //
+        HexDump{rows}
+    }
+}
+
+impl fmt::Display for HexDump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in &self.rows {
+            write!(f,"{:06x}:",row.offset)?;
+            for span in &row.spans {
+                let tag = span.class.name();
+                write!(f," <{} block=\"{}\">",tag,span.block)?;
+                for (i,b) in span.bytes.iter().enumerate() {
+                    if i != 0 { write!(f," ")?; }
+                    write!(f,"{:02x}",b)?;
+                }
+                write!(f,"</{}>",tag)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl HexDump {
+    /// Convert this hex dump into JSON, where each row is an object
+    /// giving its offset and spans.
+    pub fn to_json(&self) -> serde_json::Value {
+        use crate::ToHexString;
+        let rows : Vec<serde_json::Value> = self.rows.iter().map(|r| {
+            let spans : Vec<serde_json::Value> = r.spans.iter().map(|s| serde_json::json!({
+                "offset": s.offset,
+                "block": s.block,
+                "class": s.class.name(),
+                "bytes": s.bytes.to_hex_string()
+            })).collect();
+            serde_json::json!({"offset": r.offset, "spans": spans})
+        }).collect();
+        serde_json::Value::Array(rows)
+    }
+}
@@ -1,5 +1,12 @@
-use std::fmt;
+use alloc::format;
+use alloc::string::{String,ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
 use crate::hex::ToHexString;
+use crate::util::u256;
+use crate::Instruction::*;
 
 // ============================================================================
 // Label Offsets
@@ -30,10 +37,10 @@ impl Offset {
 
 #[derive(Debug)]
 pub enum Error {
-    /// A push instruction cannot push zero bytes and, likewise,
-    /// cannot push more than 32 bytes.
+    /// A push instruction cannot push more than 32 bytes.  Pushing
+    /// zero bytes gives `PUSH0`.
     InvalidPush,
-    /// A dup `n` instruction requires `n > 0` and `n <= 32`.
+    /// A dup (or swap) `n` instruction requires `n > 0` and `n <= 16`.
     InvalidDup,
     /// A label cannot exceed the 24Kb limit imposed by the EVM.
     InvalidLabelOffset
@@ -43,7 +50,8 @@ pub enum Error {
 // Bytecode Instructions
 // ============================================================================
 
-#[derive(Debug,PartialEq)]
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
+#[non_exhaustive]
 pub enum Instruction {
     // 0s: Stop and Arithmetic Operations
     STOP,
@@ -101,6 +109,9 @@ pub enum Instruction {
     GASLIMIT,
     CHAINID,
     SELFBALANCE,
+    BASEFEE,
+    BLOBHASH,
+    BLOBBASEFEE,
     // 50s: Stack, Memory, Storage and Flow Operations
     POP,
     MLOAD,
@@ -114,9 +125,14 @@ pub enum Instruction {
     MSIZE,
     GAS,
     JUMPDEST(usize),
+    TLOAD,
+    TSTORE,
+    MCOPY,
     // 60 & 70s: Push Operations
     PUSH(Vec<u8>),
+    PUSHPAD(Vec<u8>,usize), // Push running past end of code (zero padded).
     PUSHL(usize), // Push label offset.
+    LINKREF(usize), // Push library address (unlinked).
     // 80s: Duplicate Operations
     DUP(u8),
     // 90s: Exchange Operations
@@ -136,7 +152,10 @@ pub enum Instruction {
     SELFDESTRUCT,
     // Signals arbitrary data in the contract, rather than bytecode
     // instructions.
-    DATA(Vec<u8>)
+    DATA(Vec<u8>),
+    // A vendor-specific pseudo-instruction (see `Pseudos`), given by
+    // its name and the bytes it was decoded from.
+    PSEUDO(String,Vec<u8>)
 }
 
 impl Instruction {
@@ -164,9 +183,80 @@ impl Instruction {
         }
     }
 
+    /// Determine the static gas cost of this instruction.  This is the
+    /// minimum cost of executing the instruction, and excludes dynamic
+    /// costs such as memory expansion, cold account / storage access,
+    /// the size of data copied or hashed, refunds, etc.
+    pub fn static_gas(&self) -> u64 {
+        match self {
+            // Zero tier
+            STOP|RETURN|REVERT|INVALID|DATA(_)|PSEUDO(..) => 0,
+            // Base tier (i.e. PUSH0)
+            PUSH(bs) if bs.is_empty() => 2,
+            // Base tier
+            ADDRESS|ORIGIN|CALLER|CALLVALUE|CALLDATASIZE|CODESIZE|GASPRICE
+                |RETURNDATASIZE|COINBASE|TIMESTAMP|NUMBER|DIFFICULTY|GASLIMIT
+                |CHAINID|BASEFEE|BLOBBASEFEE|POP|PC|MSIZE|GAS => 2,
+            // Very low tier
+            ADD|SUB|NOT|LT|GT|SLT|SGT|EQ|ISZERO|AND|OR|XOR|BYTE|SHL|SHR|SAR
+                |CALLDATALOAD|CALLDATACOPY|CODECOPY|RETURNDATACOPY|BLOBHASH
+                |MLOAD|MSTORE|MSTORE8|MCOPY|PUSH(_)|PUSHPAD(..)|PUSHL(_)|LINKREF(_)|DUP(_)|SWAP(_) => 3,
+            // Low tier
+            MUL|DIV|SDIV|MOD|SMOD|SIGNEXTEND|SELFBALANCE => 5,
+            // Mid tier
+            ADDMOD|MULMOD|JUMP => 8,
+            // High tier
+            JUMPI|EXP => 10,
+            // Special cases
+            JUMPDEST(_) => 1,
+            BLOCKHASH => 20,
+            KECCAK256 => 30,
+            BALANCE|EXTCODESIZE|EXTCODECOPY|EXTCODEHASH|SLOAD|SSTORE => 100,
+            TLOAD|TSTORE => 100,
+            CALL|CALLCODE|DELEGATECALL|STATICCALL => 100,
+            LOG(n) => 375 + (375 * (*n as u64)),
+            SELFDESTRUCT => 5000,
+            CREATE|CREATE2 => 32000
+        }
+    }
+
+    /// Determine the effect of this instruction on the stack.  This
+    /// returns the number of items it requires (i.e. which must be on
+    /// the stack beforehand), and the number of items it leaves in
+    /// their place.
+    pub fn stack_effect(&self) -> (usize,usize) {
+        match self {
+            STOP|INVALID|JUMPDEST(_)|DATA(_)|PSEUDO(..) => (0,0),
+            ADDRESS|ORIGIN|CALLER|CALLVALUE|CALLDATASIZE|CODESIZE|GASPRICE
+                |RETURNDATASIZE|COINBASE|TIMESTAMP|NUMBER|DIFFICULTY|GASLIMIT
+                |CHAINID|SELFBALANCE|BASEFEE|BLOBBASEFEE|PC|MSIZE|GAS => (0,1),
+            PUSH(_)|PUSHPAD(..)|PUSHL(_)|LINKREF(_) => (0,1),
+            ISZERO|NOT|BALANCE|CALLDATALOAD|EXTCODESIZE|EXTCODEHASH
+                |BLOCKHASH|BLOBHASH|MLOAD|SLOAD|TLOAD => (1,1),
+            POP|JUMP|SELFDESTRUCT => (1,0),
+            ADD|MUL|SUB|DIV|SDIV|MOD|SMOD|EXP|SIGNEXTEND|LT|GT|SLT|SGT|EQ
+                |AND|OR|XOR|BYTE|SHL|SHR|SAR|KECCAK256 => (2,1),
+            MSTORE|MSTORE8|SSTORE|TSTORE|JUMPI|RETURN|REVERT => (2,0),
+            ADDMOD|MULMOD|CREATE => (3,1),
+            CALLDATACOPY|CODECOPY|RETURNDATACOPY|MCOPY => (3,0),
+            EXTCODECOPY => (4,0),
+            CREATE2 => (4,1),
+            DELEGATECALL|STATICCALL => (6,1),
+            CALL|CALLCODE => (7,1),
+            DUP(n) => (*n as usize, (*n as usize) + 1),
+            SWAP(n) => ((*n as usize) + 1, (*n as usize) + 1),
+            LOG(n) => ((*n as usize) + 2, 0)
+        }
+    }
+
     /// Encode an instruction into a byte sequence, assuming a given
     /// set of label offsets.
     pub fn encode(&self, offsets: &[Offset], bytes: &mut Vec<u8>) -> Result<(),Error> {
+        // Data (and pseudo-instructions) are emitted as is
+        if let Instruction::DATA(data)|Instruction::PSEUDO(_,data) = self {
+            bytes.extend(data);
+            return Ok(());
+        }
         // Push opcode
         bytes.push(self.opcode(&offsets)?);
         // Push operands (if applicable)
@@ -174,9 +264,17 @@ impl Instruction {
             Instruction::PUSH(args) => {
                 bytes.extend(args);
             }
+            Instruction::PUSHPAD(args,n) => {
+                // Only those bytes actually present
+                bytes.extend(&args[..*n]);
+            }
             Instruction::PUSHL(idx) => {
                 bytes.extend(offsets[*idx].to_bytes());
             }
+            Instruction::LINKREF(_) => {
+                // Placeholder until the library is linked
+                bytes.extend([0;20]);
+            }
             _ => {
                 // All other instructions have no operands.
             }
@@ -189,9 +287,11 @@ impl Instruction {
     /// given set of label offsets.
     pub fn length(&self, _offsets: &[Offset]) -> usize {
         let operands = match self {
-            Instruction::DATA(bytes) => bytes.len()-1,
+            Instruction::DATA(bytes)|Instruction::PSEUDO(_,bytes) => bytes.len()-1,
             // Push instructions
             Instruction::PUSH(bs) => bs.len(),
+            Instruction::PUSHPAD(_,n) => *n,
+            Instruction::LINKREF(_) => 20,
             Instruction::PUSHL(_) => {
                 todo!("implement me");
             }
@@ -263,6 +363,9 @@ impl Instruction {
             Instruction::GASLIMIT => 0x45,
             Instruction::CHAINID => 0x46,
             Instruction::SELFBALANCE => 0x47,
+            Instruction::BASEFEE => 0x48,
+            Instruction::BLOBHASH => 0x49,
+            Instruction::BLOBBASEFEE => 0x4a,
             // 50s: Stack, Memory, Storage and Flow Operations
             Instruction::POP => 0x50,
             Instruction::MLOAD => 0x51,
@@ -276,10 +379,13 @@ impl Instruction {
             Instruction::MSIZE => 0x59,
             Instruction::GAS => 0x5a,
             Instruction::JUMPDEST(_) => 0x5b,
+            Instruction::TLOAD => 0x5c,
+            Instruction::TSTORE => 0x5d,
+            Instruction::MCOPY => 0x5e,
             //
             // 60s & 70s: Push Operations
-            Instruction::PUSH(bs) => {
-                if bs.len() == 0 || bs.len() > 32 {
+            Instruction::PUSH(bs)|Instruction::PUSHPAD(bs,_) => {
+                if bs.len() > 32 {
                     return Err(Error::InvalidPush);
                 } else {
                     (0x5f + bs.len()) as u8
@@ -291,16 +397,17 @@ impl Instruction {
                 if offset.width() == 2 { 0x61 }
                 else { 0x60 }
             }
+            Instruction::LINKREF(_) => 0x73,
             // 80s: Duplication Operations
             Instruction::DUP(n) => {
-                if *n == 0 || *n > 32 {
+                if *n == 0 || *n > 16 {
                     return Err(Error::InvalidDup);
                 }
                 0x7f + n
             }
             // 90s: Swap Operations
             Instruction::SWAP(n) => {
-                if *n == 0 || *n > 32 {
+                if *n == 0 || *n > 16 {
                     return Err(Error::InvalidDup);
                 }
                 0x8f + n
@@ -327,6 +434,7 @@ impl Instruction {
             Instruction::DATA(_) => {
                  panic!("Invalid instruction ({:?})",self);
             }
+            Instruction::PSEUDO(_,bytes) => bytes[0],
         };
         //
         Ok(op)
@@ -397,6 +505,9 @@ impl Instruction {
             0x45 => Instruction::GASLIMIT,
             0x46 => Instruction::CHAINID,
             0x47 => Instruction::SELFBALANCE,
+            0x48 => Instruction::BASEFEE,
+            0x49 => Instruction::BLOBHASH,
+            0x4a => Instruction::BLOBBASEFEE,
             // 50s: Stack, Memory, Storage and Flow Operations
             0x50 => Instruction::POP,
             0x51 => Instruction::MLOAD,
@@ -410,7 +521,11 @@ impl Instruction {
             0x59 => Instruction::MSIZE,
             0x5a => Instruction::GAS,
             0x5b => Instruction::JUMPDEST(pc),
+            0x5c => Instruction::TLOAD,
+            0x5d => Instruction::TSTORE,
+            0x5e => Instruction::MCOPY,
             // 60s & 70s: Push Operations
+            0x5f => Instruction::PUSH(Vec::new()),
             0x60..=0x7f => {
                 let m = pc + 1;
                 let n = pc + ((opcode - 0x5e) as usize);
@@ -420,10 +535,11 @@ impl Instruction {
                 } else {
                     // Harder case: does overflow code.
                     let mut bs = bytes[m..].to_vec();
+                    let present = bs.len();
                     // Pad out with zeros
                     for _i in 0..(n-bytes.len()) { bs.push(0); }
                     // Done
-                    Instruction::PUSH(bs)
+                    Instruction::PUSHPAD(bs,present)
                 }
             }
             // 80s: Duplicate Operations
@@ -457,9 +573,86 @@ impl Instruction {
         //
         insn
     }
+
+    /// Construct an instruction from its mnemonic (e.g. `"DUP1"`) and
+    /// operand (e.g. `"0x60"` for `"PUSH1"`), returning `None` if this
+    /// is not a valid combination.  Operands are given in decimal or
+    /// in hexadecimal (prefixed with `0x`), and must fit within the
+    /// width of a push.  A `JUMPDEST` is given offset zero.
+    pub fn from_mnemonic(name: &str, operand: Option<&str>) -> Option<Instruction> {
+        let suffix = |prefix: &str| name.strip_prefix(prefix).and_then(|n| n.parse::<u8>().ok());
+        //
+        match (operand,suffix("PUSH")) {
+            (Some(arg),Some(n)) if (1..=32).contains(&n) => {
+                let val = u256::from_str(&arg.replace('_',"")).ok()?;
+                let mut bytes = val.high().to_be_bytes().to_vec();
+                bytes.extend_from_slice(&val.low().to_be_bytes());
+                let (high,low) = bytes.split_at(32 - n as usize);
+                if high.iter().any(|b| *b != 0) { return None; }
+                return Some(PUSH(low.to_vec()));
+            }
+            (Some(_),_) => { return None; }
+            (None,_) => {}
+        }
+        match (suffix("DUP"),suffix("SWAP"),suffix("LOG")) {
+            (Some(n),_,_) if (1..=16).contains(&n) => Some(DUP(n)),
+            (_,Some(n),_) if (1..=16).contains(&n) => Some(SWAP(n)),
+            (_,_,Some(n)) if n <= 4 => Some(LOG(n)),
+            _ if name == "JUMPDEST" => Some(JUMPDEST(0)),
+            _ if name == "PUSH0" => Some(PUSH(Vec::new())),
+            _ => {
+                // Search single byte instructions
+                (0..=255u8).map(|op| Instruction::decode(0,&[op]))
+                    .find(|i| !matches!(i,DATA(_)|PUSHPAD(..)|DUP(_)|SWAP(_)|LOG(_)) && format!("{:?}",i) == name)
+            }
+        }
+    }
 }
 
 
+// ============================================================================
+// Pseudo-instructions
+// ============================================================================
+
+/// A set of byte patterns which are decoded as vendor-specific
+/// pseudo-instructions, rather than as ordinary instructions.  A
+/// pseudo-instruction is opaque: it has no effect on the stack and
+/// always falls through to the next instruction.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct Pseudos {
+    patterns: Vec<(Vec<u8>,String)>
+}
+
+impl Pseudos {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a (non-empty) byte pattern to be decoded as a
+    /// pseudo-instruction with a given name.  Registering the same
+    /// pattern again replaces its name.
+    pub fn insert(&mut self, pattern: &[u8], name: &str) {
+        assert!(!pattern.is_empty());
+        self.patterns.retain(|(p,_)| p != pattern);
+        self.patterns.push((pattern.to_vec(),name.to_string()));
+    }
+
+    /// Check whether any patterns are registered.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Decode the pseudo-instruction at a given position (if any).
+    /// Where several patterns match, the longest is chosen.
+    pub fn decode(&self, pc: usize, bytes: &[u8]) -> Option<Instruction> {
+        let rest = &bytes[pc..];
+        self.patterns.iter()
+            .filter(|(p,_)| rest.starts_with(p))
+            .max_by_key(|(p,_)| p.len())
+            .map(|(p,n)| PSEUDO(n.clone(),p.clone()))
+    }
+}
+
 // ============================================================================
 // Display
 // ============================================================================
@@ -469,16 +662,22 @@ impl fmt::Display for Instruction {
 	// Use the default (debug) formatter.  Its only for certain
 	// instructions that we need to do anything different.
 	match self {
+	    Instruction::PUSH(bytes) if bytes.is_empty() => write!(f,"PUSH0"),
 	    Instruction::PUSH(bytes) => {
 		// Convert bytes into hex string
 		let hex = bytes.to_hex_string();
 		// Print!
 		write!(f,"PUSH{} {}",bytes.len(),hex)
 	    }
+	    Instruction::PUSHPAD(bytes,_) => {
+		let hex = bytes.to_hex_string();
+		write!(f,"PUSH{} {} (padded)",bytes.len(),hex)
+	    }
             Instruction::DATA(bytes) => {
                 // Print bytes as hex string
 		write!(f,"{}",bytes.to_hex_string())
             }
+            Instruction::PSEUDO(name,_) => write!(f,"{}",name),
 	    _ => write!(f,"{:?}",self)
 	}
     }
@@ -9,7 +9,9 @@ use delta_inc::lex;
 pub enum Token {
     AmpersandAmpersand,
     Assert,
+    Assume,
     BarBar,
+    Colon,
     Comma,
     Dot,
     EOF,
@@ -21,26 +23,36 @@ pub enum Token {
     Hex,
     If,
     Identifier,
+    Import,
     Integer,
     LeftAngle,
     LeftAngleEquals,
+    LeftAngleLeftAngle,
     LeftBrace,
+    LeftCurly,
     LeftSquare,
     Minus,
     NewLine,
     Percent,
     Plus,
+    Require,
     Revert,
     RightAngle,
     RightAngleEquals,
+    RightAngleRightAngle,
+    RightAngleRightAngleRightAngle,
     RightBrace,
+    RightCurly,
     RightSlash,
     RightSquare,
     SemiColon,
     ShreakEquals,
     Succeed,
     Star,
-    Stop
+    StarStar,
+    Stop,
+    String,
+    Switch
 }
 
 // ======================================================
@@ -48,12 +60,16 @@ pub enum Token {
 // ======================================================
 
 const ASSERT : &'static [char] = &['a','s','s','e','r','t'];
+const ASSUME : &'static [char] = &['a','s','s','u','m','e'];
 const FAIL : &'static [char] = &['f','a','i','l'];
 const GOTO : &'static [char] = &['g','o','t','o'];
 const IF : &'static [char] = &['i','f'];
+const IMPORT : &'static [char] = &['i','m','p','o','r','t'];
+const REQUIRE : &'static [char] = &['r','e','q','u','i','r','e'];
 const REVERT : &'static [char] = &['r','e','v','e','r','t'];
 const SUCCEED : &'static [char] = &['s','u','c','c','e','e','d'];
 const STOP : &'static [char] = &['s','t','o','p'];
+const SWITCH : &'static [char] = &['s','w','i','t','c','h'];
 
 /// Handy type alias for the result type used for all of the lexical
 /// rules.
@@ -75,6 +91,18 @@ fn scan_hex_literal(input: &[char]) -> ScannerResult {
     }
 }
 
+/// Scan a string literal (e.g. `"lib.il"`).  Escape sequences are
+/// not supported.
+fn scan_string_literal(input: &[char]) -> ScannerResult {
+    if input.len() == 0 || input[0] != '"' {
+        Err(())
+    } else {
+        let n = input[1..].iter().position(|c| *c == '"' || *c == '\n').ok_or(())?;
+        if input[n+1] != '"' { return Err(()); }
+        Ok(Span::new(Token::String,0..n+2))
+    }
+}
+
 /// Scan a keyword, which is simple identifier matching a predefined
 /// pattern.
 fn scan_keyword(input: &[char]) -> ScannerResult {
@@ -83,12 +111,16 @@ fn scan_keyword(input: &[char]) -> ScannerResult {
     // Attempt to match it
     let t = match &input[r.range()] {
         ASSERT => Token::Assert,
+        ASSUME => Token::Assume,
         FAIL => Token::Fail,
         GOTO => Token::Goto,
         IF => Token::If,
+        IMPORT => Token::Import,
+        REQUIRE => Token::Require,
         REVERT => Token::Revert,
         SUCCEED => Token::Succeed,
         STOP => Token::Stop,
+        SWITCH => Token::Switch,
         _ => { return Err(()); }
     };
     // Success!
@@ -112,17 +144,20 @@ fn scan_single_operators(input: &[char]) -> ScannerResult {
         Err(())
     } else {
         let t = match input[0] {
+            ':' => Token::Colon,
             ',' => Token::Comma,
             '.' => Token::Dot,
             '=' => Token::Equals,
             '<' => Token::LeftAngle,
             '(' => Token::LeftBrace,
+            '{' => Token::LeftCurly,
             '[' => Token::LeftSquare,
             '-' => Token::Minus,
             '%' => Token::Percent,
             '+' => Token::Plus,
             '>' => Token::RightAngle,
             ')' => Token::RightBrace,
+            '}' => Token::RightCurly,
             '/' => Token::RightSlash,
             ']' => Token::RightSquare,
             ';' => Token::SemiColon,
@@ -134,6 +169,20 @@ fn scan_single_operators(input: &[char]) -> ScannerResult {
     }
 }
 
+/// Scan all triple-character operators.
+fn scan_triple_operators(input: &[char]) -> ScannerResult {
+    if input.len() <= 2 {
+        Err(())
+    } else {
+        let t = match (input[0], input[1], input[2]) {
+            ('>','>','>') => Token::RightAngleRightAngleRightAngle,
+            _ => { return Err(()); }
+        };
+        //
+        Ok(Span::new(t,0..3))
+    }
+}
+
 /// Scan all double-character operators.
 fn scan_double_operators(input: &[char]) -> ScannerResult {
     if input.len() <= 1 {
@@ -144,8 +193,11 @@ fn scan_double_operators(input: &[char]) -> ScannerResult {
             ('|','|') => Token::BarBar,
             ('=','=') => Token::EqualsEquals,
             ('<','=') => Token::LeftAngleEquals,
+            ('<','<') => Token::LeftAngleLeftAngle,
             ('>','=') => Token::RightAngleEquals,
+            ('>','>') => Token::RightAngleRightAngle,
             ('!','=') => Token::ShreakEquals,
+            ('*','*') => Token::StarStar,
             _ => { return Err(()); }
         };
         //
@@ -210,12 +262,14 @@ fn scan_one(input: &[char], t: Token, c: char) -> ScannerResult {
 
 /// The set of rules used for lexing.
 static RULES : &'static [Scanner<char,Token>] = &[
+    scan_triple_operators,
     scan_double_operators,
     scan_single_operators,
     scan_keyword,
     scan_identifier,
     scan_hex_literal,
     scan_uint_literal,
+    scan_string_literal,
     scan_gap,
     scan_newline,
     scan_eof
@@ -469,6 +523,15 @@ mod tests {
         assert_ok!(l.snap(Token::EOF));
     }
 
+    #[test]
+    fn test_31() {
+        let mut l = Lexer::new("require requires");
+        assert_ok!(l.snap(Token::Require));
+        assert_ok!(l.snap(Token::Gap));
+        assert_ok!(l.snap(Token::Identifier));
+        assert_ok!(l.snap(Token::EOF));
+    }
+
     // Operators
 
     #[test]
@@ -550,6 +613,30 @@ mod tests {
         assert_ok!(l.snap(Token::EOF));
     }
 
+    #[test]
+    fn test_51() {
+        let mut l = Lexer::new("<<>>");
+        assert_ok!(l.snap(Token::LeftAngleLeftAngle));
+        assert_ok!(l.snap(Token::RightAngleRightAngle));
+        assert_ok!(l.snap(Token::EOF));
+    }
+
+    #[test]
+    fn test_52() {
+        let mut l = Lexer::new("***");
+        assert_ok!(l.snap(Token::StarStar));
+        assert_ok!(l.snap(Token::Star));
+        assert_ok!(l.snap(Token::EOF));
+    }
+
+    #[test]
+    fn test_53() {
+        let mut l = Lexer::new(">>>>>");
+        assert_ok!(l.snap(Token::RightAngleRightAngleRightAngle));
+        assert_ok!(l.snap(Token::RightAngleRightAngle));
+        assert_ok!(l.snap(Token::EOF));
+    }
+
     #[test]
     fn test_61() {
         let mut l = Lexer::new("12345(");
@@ -1,21 +1,111 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod artifact;
+mod annotate;
+#[cfg(feature = "std")]
+mod asm_json;
+mod assembly;
+mod backend;
+mod bisect;
 mod bytecode;
+mod cancel;
+#[cfg(feature = "std")]
+mod cache;
 mod cfa;
 mod compiler;
+mod corpus;
+mod diagnostic;
 mod disassembler;
+mod dispatch;
+mod eval;
+mod fork;
+mod generator;
 mod hex;
+mod hexdump;
 mod instruction;
+#[cfg(feature = "std")]
 mod lexer;
+mod logger;
+mod macros;
+mod metadata;
+#[cfg(feature = "std")]
 mod parser;
+mod pipeline;
+mod proxy;
+mod report;
+#[cfg(feature = "std")]
+mod sarif;
+#[cfg(feature = "std")]
+mod shared;
+mod sink;
+mod slice;
+mod stats;
+mod summary;
 mod term;
+#[cfg(feature = "std")]
+mod trace;
+mod visit;
 // public
 pub mod dfa;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod lints;
+pub mod opt;
+pub mod prelude;
+#[cfg(feature = "python")]
+pub mod python;
 pub mod util;
 
-pub use crate::bytecode::*;
-pub use crate::instruction::*;
-pub use crate::hex::*;
-pub use crate::term::*;
-pub use crate::parser::*;
-pub use crate::compiler::*;
-pub use crate::disassembler::*;
-pub use crate::cfa::*;
+// The public API is listed explicitly, so that nothing is exported
+// by accident.  The most commonly used items are also gathered in
+// `prelude`.
+#[cfg(feature = "std")]
+pub use crate::artifact::{Artifact,ArtifactError,LinkReference,link};
+pub use crate::annotate::{Addresses,Annotator,Selectors,StorageNames};
+pub use crate::assembly::{Assembly,Item,PatchError};
+pub use crate::backend::Backend;
+pub use crate::bisect::{Bisection,bisect};
+#[cfg(feature = "std")]
+pub use crate::bisect::cfa_panics;
+pub use crate::bytecode::{Bytecode,OffsetMap};
+pub use crate::cancel::{Cancellation,Cancelled,Unconverged};
+#[cfg(feature = "std")]
+pub use crate::cancel::Deadline;
+#[cfg(feature = "std")]
+pub use crate::cache::{Analysis,AnalysisCache,ANALYSIS_VERSION};
+pub use crate::cfa::CfaState;
+pub use crate::compiler::{Compiler,Error as CompileError,TermId};
+pub use crate::corpus::{Corpus,CorpusReport};
+pub use crate::diagnostic::{Diagnostic,FailureKind,Location,Severity};
+pub use crate::disassembler::{AbstractState,AbstractWord,Block,Condition,Disassemble,Disassembly};
+pub use crate::disassembler::{Explanation,HeightMismatch,Hint,InvalidJump,Mismatch,Reachability,metadata_end};
+pub use crate::dispatch::Function;
+pub use crate::eval::{Evaluator,Machine,Outcome,evaluate};
+pub use crate::fork::{EvmVersion,ForkDifference};
+pub use crate::generator::{Generator,Mix};
+pub use crate::hex::{FromHexString,ToHexString};
+pub use crate::hexdump::{HexDump,HexRow,HexSpan};
+pub use crate::instruction::{Error as InstructionError,Instruction,Offset,Pseudos};
+pub use crate::logger::{AnalysisLogger,WriteLogger};
+pub use crate::macros::{from_mnemonics,to_bytes};
+pub use crate::metadata::Metadata;
+#[cfg(feature = "std")]
+pub use crate::parser::{Error as ParseError,ErrorCode,FileLoader,Loader,Parser};
+pub use crate::pipeline::{Options,Pipeline,Report,analyze,analyze_with,disassemble_hex,disassemble_hex_with,hexdump_hex};
+#[cfg(feature = "std")]
+pub use crate::pipeline::{compile_il,compile_il_with};
+pub use crate::proxy::{BEACON_SLOT,IMPLEMENTATION_SLOT,Proxy};
+pub use crate::report::{CostDelta,CostReport,FunctionCost,FunctionDelta,compare_costs,compare_function_costs};
+#[cfg(feature = "std")]
+pub use crate::sarif::to_sarif;
+#[cfg(feature = "std")]
+pub use crate::shared::SharedDisassembly;
+pub use crate::sink::{AssemblySink,CodeSink,HexSink,SizeSink};
+pub use crate::stats::{ByteClass,Coverage,Statistics};
+pub use crate::summary::BlockSummary;
+pub use crate::term::{BinOp,Intrinsic,Region,Rewrite,Term,rewrite_all};
+#[cfg(feature = "std")]
+pub use crate::trace::{Inconsistency,Replay,Step,Trace};
+pub use crate::visit::{BlockVisitor,InstructionVisitor};
@@ -0,0 +1,167 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec::Vec;
+use core::fmt;
+use crate::{AbstractState,Diagnostic,Disassembly,Instruction::*};
+
+// ============================================================================
+// Findings
+// ============================================================================
+
+/// Identifies a kind of (potential) security problem.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord)]
+#[non_exhaustive]
+pub enum Lint {
+    /// A `SELFDESTRUCT` is reachable.
+    ReachableSelfdestruct,
+    /// The target of a `DELEGATECALL` is read from the call data.
+    UserDelegatecall,
+    /// The success flag returned by a call is never used.
+    UncheckedCall,
+    /// A branch depends on comparing `tx.origin` (i.e. `ORIGIN`)
+    /// against something other than `msg.sender`.
+    TxOriginAuth
+}
+
+impl Lint {
+    /// Get the diagnostic code for this lint.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lint::ReachableSelfdestruct => "S001",
+            Lint::UserDelegatecall => "S002",
+            Lint::UncheckedCall => "S003",
+            Lint::TxOriginAuth => "S004"
+        }
+    }
+}
+
+/// A potential security problem found at a given location.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord)]
+pub struct Finding {
+    pub pc: usize,
+    pub lint: Lint
+}
+
+impl Finding {
+    /// Convert this finding into a (warning) diagnostic.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let msg = match self.lint {
+            Lint::ReachableSelfdestruct => "reachable SELFDESTRUCT",
+            Lint::UserDelegatecall => "DELEGATECALL to an address from call data",
+            Lint::UncheckedCall => "return value of call is never checked",
+            Lint::TxOriginAuth => "authentication using tx.origin"
+        };
+        Diagnostic::warning(self.lint.code(),msg).with_offset(self.pc)
+    }
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Check the reachable code of a (built) disassembly for potential
+/// security problems, returning findings in order of location.  The
+/// flow of values is only tracked within each block, hence values
+/// passed between blocks are not considered.
+pub fn check<T:AbstractState+fmt::Display>(disasm: &Disassembly<T>) -> Vec<Finding> {
+    let live = disasm.liveness();
+    let mut findings = Vec::new();
+    //
+    for id in 0..disasm.blocks().len() {
+        if !disasm.is_block_reachable(id) { continue; }
+        let insns = disasm.block_liveness(id,&live.live_in);
+        let mut stack : Vec<Taint> = Vec::new();
+        //
+        for (pc,insn,after) in &insns {
+            let pc = *pc;
+            let (n,m) = insn.stack_effect();
+            // Read operands (where items from before the block are untainted)
+            let len = stack.len();
+            let operand = |i: usize| if i < len { stack[len-1-i] } else { Taint::default() };
+            match insn {
+                SELFDESTRUCT => findings.push(Finding{pc,lint: Lint::ReachableSelfdestruct}),
+                DELEGATECALL if operand(1).calldata => findings.push(Finding{pc,lint: Lint::UserDelegatecall}),
+                JUMPI if operand(1).auth => findings.push(Finding{pc,lint: Lint::TxOriginAuth}),
+                _ => {}
+            }
+            if matches!(insn,CALL|CALLCODE|DELEGATECALL|STATICCALL) && !after.is_live(0) {
+                findings.push(Finding{pc,lint: Lint::UncheckedCall});
+            }
+            // Update stack
+            let result = match insn {
+                DUP(k) => {
+                    let v = operand(*k as usize - 1);
+                    stack.push(v);
+                    continue;
+                }
+                SWAP(k) => {
+                    let k = *k as usize;
+                    while stack.len() <= k { stack.insert(0,Taint::default()); }
+                    let len = stack.len();
+                    stack.swap(len-1,len-1-k);
+                    continue;
+                }
+                CALLDATALOAD => Taint{calldata: true, ..Taint::default()},
+                ORIGIN => Taint{origin: true, ..Taint::default()},
+                CALLER => Taint{caller: true, ..Taint::default()},
+                EQ => {
+                    let (l,r) = (operand(0),operand(1));
+                    let auth = (l.origin && !r.caller) || (r.origin && !l.caller);
+                    Taint{auth: auth || l.auth || r.auth, ..l.join(r)}
+                }
+                _ if is_pure(insn) => (0..n).map(operand).fold(Taint::default(),Taint::join),
+                _ => Taint::default()
+            };
+            stack.truncate(len.saturating_sub(n));
+            for _ in 0..m { stack.push(result); }
+        }
+    }
+    //
+    findings.sort();
+    findings
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Describes where a value may have come from.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+struct Taint {
+    /// Derived from the call data.
+    calldata: bool,
+    /// Derived from `tx.origin`.
+    origin: bool,
+    /// Derived from `msg.sender`.
+    caller: bool,
+    /// Derived from comparing `tx.origin`.
+    auth: bool
+}
+
+impl Taint {
+    fn join(self, other: Taint) -> Taint {
+        Taint {
+            calldata: self.calldata || other.calldata,
+            origin: self.origin || other.origin,
+            caller: self.caller || other.caller,
+            auth: self.auth || other.auth
+        }
+    }
+}
+
+/// Check whether an instruction computes its result purely from its
+/// operands (i.e. arithmetic, comparison and bitwise operations).
+fn is_pure(insn: &crate::Instruction) -> bool {
+    matches!(insn,
+             ADD|MUL|SUB|DIV|SDIV|MOD|SMOD|ADDMOD|MULMOD|EXP|SIGNEXTEND
+             |LT|GT|SLT|SGT|EQ|ISZERO|AND|OR|XOR|NOT|BYTE|SHL|SHR|SAR)
+}
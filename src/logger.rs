@@ -0,0 +1,109 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use core::fmt;
+use crate::Instruction;
+
+// ============================================================================
+// Analysis Logger
+// ============================================================================
+
+/// Observes the fixpoint iteration performed when building a
+/// disassembly (see `Disassembly::build_logged()`).  This is useful
+/// for understanding why the context of some block does not
+/// stabilise.
+pub trait AnalysisLogger<T> {
+    /// Determine whether anything should be logged.  When not, states
+    /// are not copied for logging.
+    fn is_enabled(&self) -> bool { true }
+
+    /// A reachable block is about to be visited with a given context.
+    fn visit_block(&mut self, _id: usize, _ctx: &T) {}
+
+    /// An instruction was applied to the state before it, giving the
+    /// state after it.
+    fn transfer(&mut self, _pc: usize, _insn: &Instruction, _before: &T, _after: &T) {}
+
+    /// A state flowing from one block was merged into the context of
+    /// another, giving the result and whether it changed.
+    fn merge(&mut self, _from: usize, _to: usize, _state: &T, _result: &T, _changed: bool) {}
+
+    /// A pass over the blocks finished, where the contexts of a given
+    /// set of blocks changed.
+    fn end_pass(&mut self, _changed: &[usize]) {}
+}
+
+/// Logs nothing.
+impl<T> AnalysisLogger<T> for () {
+    fn is_enabled(&self) -> bool { false }
+}
+
+// ============================================================================
+// Write Logger
+// ============================================================================
+
+/// Writes each event as a line of text (e.g. into a `String`).  This
+/// can be restricted to the events concerning a single block.
+pub struct WriteLogger<W> {
+    out: W,
+    /// The only block of interest (if any).
+    block: Option<usize>,
+    /// The block currently being visited.
+    current: usize
+}
+
+impl<W:fmt::Write> WriteLogger<W> {
+    pub fn new(out: W) -> Self {
+        WriteLogger{out, block: None, current: 0}
+    }
+
+    /// Log only those events concerning a given block.  That is,
+    /// visits to it, and merges into it.
+    pub fn for_block(mut self, id: usize) -> Self {
+        self.block = Some(id);
+        self
+    }
+
+    /// Get the text written so far.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn includes(&self, id: usize) -> bool {
+        self.block.map_or(true,|b| b == id)
+    }
+}
+
+impl<W:fmt::Write,T:fmt::Display> AnalysisLogger<T> for WriteLogger<W> {
+    fn visit_block(&mut self, id: usize, ctx: &T) {
+        self.current = id;
+        if self.includes(id) {
+            writeln!(self.out,"block {}: {}",id,ctx).unwrap();
+        }
+    }
+
+    fn transfer(&mut self, pc: usize, insn: &Instruction, before: &T, after: &T) {
+        if self.includes(self.current) {
+            writeln!(self.out,"{:#08x}: {} ==> {} ==> {}",pc,before,insn,after).unwrap();
+        }
+    }
+
+    fn merge(&mut self, from: usize, to: usize, state: &T, result: &T, changed: bool) {
+        if self.includes(to) {
+            let outcome = if changed { "changed" } else { "unchanged" };
+            writeln!(self.out,"merge {} into {}: {} ==> {} ({})",from,to,state,result,outcome).unwrap();
+        }
+    }
+
+    fn end_pass(&mut self, changed: &[usize]) {
+        writeln!(self.out,"changed: {:?}",changed).unwrap();
+    }
+}
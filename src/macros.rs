@@ -0,0 +1,69 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec::Vec;
+use crate::Instruction;
+
+/// Construct a sequence of instructions from their mnemonics, such as
+/// `insns![PUSH1 0x60, DUP1, MSTORE]`.  Every `JUMPDEST` is given its
+/// offset within the sequence (as for a disassembly).  This panics on
+/// an unknown mnemonic, or an operand which does not fit.
+#[macro_export]
+macro_rules! insns {
+    ($($op:ident $($arg:literal)?),* $(,)?) => {
+        $crate::from_mnemonics(&[$((stringify!($op), $crate::__operand!($($arg)?))),*])
+    }
+}
+
+/// Construct a sequence of bytes from the mnemonics of instructions,
+/// such as `bytecode![PUSH1 0x60, DUP1, MSTORE]` (see `insns!`).
+#[macro_export]
+macro_rules! bytecode {
+    ($($tokens:tt)*) => {
+        $crate::to_bytes(&$crate::insns![$($tokens)*])
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __operand {
+    () => { None };
+    ($arg:literal) => { Some(stringify!($arg)) }
+}
+
+/// Construct a sequence of instructions from pairs of mnemonics and
+/// (optional) operands.  This panics if any pair is invalid.
+pub fn from_mnemonics(items: &[(&str,Option<&str>)]) -> Vec<Instruction> {
+    let mut insns = Vec::new();
+    let mut pc = 0;
+    //
+    for (name,operand) in items {
+        let insn = match Instruction::from_mnemonic(name,*operand) {
+            Some(Instruction::JUMPDEST(_)) => Instruction::JUMPDEST(pc),
+            Some(insn) => insn,
+            None => panic!("invalid instruction \"{} {}\"",name,operand.unwrap_or(""))
+        };
+        pc = pc + insn.length(&[]);
+        insns.push(insn);
+    }
+    //
+    insns
+}
+
+/// Encode a sequence of instructions into bytes.  This panics if any
+/// instruction cannot be encoded without label offsets.
+pub fn to_bytes(insns: &[Instruction]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for insn in insns {
+        insn.encode(&[],&mut bytes).unwrap();
+    }
+    bytes
+}
@@ -0,0 +1,113 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::string::{String,ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::util::keccak256;
+
+/// Key identifying the compiler version in the metadata map.
+const VERSION_KEY : &[u8] = b"evmil";
+
+/// Key identifying the source hash in the metadata map.
+const SOURCE_KEY : &[u8] = b"source";
+
+// ============================================================================
+// Metadata
+// ============================================================================
+
+/// Identifies the compiler and source which produced a bytecode
+/// sequence.  This is appended as a trailer using the same format as
+/// `solc` (i.e. a CBOR-encoded map followed by its two byte length),
+/// and hence is recognised by `metadata_end()`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Metadata {
+    /// Version of the compiler.
+    pub version: String,
+    /// Keccak-256 hash of the IL source.
+    pub source_hash: [u8;32]
+}
+
+impl Metadata {
+    /// Construct the metadata for a given IL source, compiled with
+    /// this version of the compiler.
+    pub fn new(source: &str) -> Self {
+        let version = env!("CARGO_PKG_VERSION").to_string();
+        Metadata{version, source_hash: keccak256(source.as_bytes())}
+    }
+
+    /// Encode this metadata as a trailer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0xa2];
+        encode_header(0x60,VERSION_KEY.len(),&mut bytes);
+        bytes.extend_from_slice(VERSION_KEY);
+        encode_header(0x60,self.version.len(),&mut bytes);
+        bytes.extend_from_slice(self.version.as_bytes());
+        encode_header(0x60,SOURCE_KEY.len(),&mut bytes);
+        bytes.extend_from_slice(SOURCE_KEY);
+        encode_header(0x40,self.source_hash.len(),&mut bytes);
+        bytes.extend_from_slice(&self.source_hash);
+        // Append length
+        let len = bytes.len() as u16;
+        bytes.extend_from_slice(&len.to_be_bytes());
+        bytes
+    }
+
+    /// Decode the metadata trailer at the end of a given bytecode
+    /// sequence, returning `None` if there is no such trailer (or it
+    /// was not produced by this compiler).
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let n = bytes.len();
+        let len = ((*bytes.get(n.checked_sub(2)?)? as usize) << 8) | (bytes[n-1] as usize);
+        let start = n.checked_sub(2 + len)?;
+        let mut cbor = &bytes[start..n-2];
+        //
+        if cbor.first() != Some(&0xa2) { return None; }
+        cbor = &cbor[1..];
+        if decode_item(0x60,&mut cbor)? != VERSION_KEY { return None; }
+        let version = core::str::from_utf8(decode_item(0x60,&mut cbor)?).ok()?.to_string();
+        if decode_item(0x60,&mut cbor)? != SOURCE_KEY { return None; }
+        let source_hash = decode_item(0x40,&mut cbor)?.try_into().ok()?;
+        //
+        if cbor.is_empty() { Some(Metadata{version,source_hash}) } else { None }
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Encode the header of a CBOR byte or text string of a given length
+/// (where `major` identifies which).
+fn encode_header(major: u8, len: usize, bytes: &mut Vec<u8>) {
+    if len < 24 {
+        bytes.push(major | (len as u8));
+    } else {
+        assert!(len <= 0xff);
+        bytes.push(major | 24);
+        bytes.push(len as u8);
+    }
+}
+
+/// Decode a CBOR byte or text string (where `major` identifies which)
+/// from the front of a given slice.
+fn decode_item<'a>(major: u8, cbor: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let header = *cbor.first()?;
+    if header & 0xe0 != major { return None; }
+    let (len,skip) = match header & 0x1f {
+        24 => (*cbor.get(1)? as usize, 2),
+        n if n < 24 => (n as usize, 1),
+        _ => { return None; }
+    };
+    let item = cbor.get(skip..skip+len)?;
+    *cbor = &cbor[skip+len..];
+    Some(item)
+}
@@ -0,0 +1,126 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::{Region,Term};
+
+// ============================================================================
+// Field Coalescing
+// ============================================================================
+
+/// Merge consecutive assignments to disjoint bit fields of the same
+/// word into a single tuple assignment, such that the word is read
+/// and written only once.  For example, `storage[0][0:8] = x;
+/// storage[0][8:8] = y;` becomes `(storage[0][0:8],storage[0][8:8]) =
+/// (x,y);`.  This is done only when no merged value (nor the index of
+/// the word) reads from the region being written.
+pub fn coalesce_fields(terms: Vec<Term>) -> Vec<Term> {
+    coalesce_fields_with(terms).0
+}
+
+/// Merge assignments as for `coalesce_fields()`, additionally giving
+/// the indices of the statements from which each resulting statement
+/// came.
+pub(super) fn coalesce_fields_with(terms: Vec<Term>) -> (Vec<Term>,Vec<Vec<usize>>) {
+    let mut result : Vec<Term> = Vec::new();
+    let mut origins : Vec<Vec<usize>> = Vec::new();
+    let mut group : Vec<(Term,Term)> = Vec::new();
+    let n = terms.len();
+    for (i,term) in terms.into_iter().enumerate() {
+        if let Term::Assignment(lhs,rhs) = &term {
+            if can_extend(&group,lhs,rhs) {
+                group.push((*lhs.clone(),*rhs.clone()));
+                continue;
+            }
+            flush(&mut group,&mut result,&mut origins,i);
+            if is_field(lhs,rhs) {
+                group.push((*lhs.clone(),*rhs.clone()));
+                continue;
+            }
+        } else {
+            flush(&mut group,&mut result,&mut origins,i);
+        }
+        result.push(term);
+        origins.push(vec![i]);
+    }
+    flush(&mut group,&mut result,&mut origins,n);
+    (result,origins)
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Determine whether an assignment is to a bit field of some word in
+/// memory or storage, and whose index does not read from the same
+/// region.
+fn is_field(lhs: &Term, rhs: &Term) -> bool {
+    match lhs {
+        Term::BitField(word,_,_) => match &**word {
+            Term::ArrayAccess(src,index) => match &**src {
+                Term::MemoryAccess(r) => rhs.width() == 1 && !reads(index,*r),
+                _ => false
+            },
+            _ => false
+        },
+        _ => false
+    }
+}
+
+/// Determine whether an assignment can be merged into a (non-empty)
+/// group.  That is, it must assign a field of the same word which is
+/// disjoint from all others, and its value cannot read the region
+/// being written.
+fn can_extend(group: &[(Term,Term)], lhs: &Term, rhs: &Term) -> bool {
+    let Some((Term::BitField(word,_,_),_)) = group.first() else { return false; };
+    let Term::BitField(w,offset,width) = lhs else { return false; };
+    let Term::ArrayAccess(src,_) = &**word else { return false; };
+    let Term::MemoryAccess(region) = &**src else { return false; };
+    if w != word || rhs.width() != 1 || reads(rhs,*region) {
+        return false;
+    }
+    group.iter().all(|(l,_)| match l {
+        Term::BitField(_,o,n) => offset + width <= *o || o + n <= *offset,
+        _ => false
+    })
+}
+
+/// Emit a group of field assignments, merging them if there is more
+/// than one.  Since a group consists of consecutive statements, these
+/// originate from those immediately before a given index.
+fn flush(group: &mut Vec<(Term,Term)>, result: &mut Vec<Term>, origins: &mut Vec<Vec<usize>>, end: usize) {
+    if !group.is_empty() {
+        origins.push((end - group.len()..end).collect());
+    }
+    if group.len() == 1 {
+        let (lhs,rhs) = group.pop().unwrap();
+        result.push(Term::Assignment(Box::new(lhs),Box::new(rhs)));
+    } else if group.len() > 1 {
+        let (lhs,rhs) : (Vec<Term>,Vec<Term>) = group.drain(..).unzip();
+        result.push(Term::Assignment(Box::new(Term::Tuple(lhs)),Box::new(Term::Tuple(rhs))));
+    }
+}
+
+/// Determine whether a term reads from a given region.
+fn reads(term: &Term, region: Region) -> bool {
+    match term {
+        Term::MemoryAccess(r) => *r == region,
+        // The region of a declared array is not known here
+        Term::Variable(_) => true,
+        Term::Binary(_,l,r) => reads(l,region) || reads(r,region),
+        Term::ArrayAccess(s,i) => reads(s,region) || reads(i,region),
+        Term::BitField(e,_,_) => reads(e,region),
+        Term::Intrinsic(_,es)|Term::Tuple(es) => es.iter().any(|e| reads(e,region)),
+        _ => false
+    }
+}
@@ -0,0 +1,105 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::format;
+use alloc::vec::Vec;
+use crate::{BinOp,Rewrite,Term,evaluate,rewrite_all};
+use crate::util::u256;
+
+// ============================================================================
+// Constant Folding
+// ============================================================================
+
+/// Evaluate arithmetic and comparisons whose operands are constants
+/// (e.g. `1 + 2` becomes `0x3`).  Arithmetic wraps on overflow (as
+/// for the EVM), whilst comparisons give either `0` or `1`.
+/// Literals which do not fit into a word are left as is, so they are
+/// still reported by the compiler.
+pub fn fold_constants(terms: Vec<Term>) -> Vec<Term> {
+    rewrite_all(terms,&mut ConstantFolding)
+}
+
+/// The rewrite underlying `fold_constants()`, which can be combined
+/// with others.
+#[derive(Clone,Copy,Debug,Default)]
+pub struct ConstantFolding;
+
+impl Rewrite for ConstantFolding {
+    fn apply(&mut self, term: &Term) -> Option<Term> {
+        let Term::Binary(bop,lhs,rhs) = term else { return None; };
+        let l = literal(lhs)?;
+        let r = literal(rhs)?;
+        let v = match bop {
+            BinOp::Add => l + r,
+            BinOp::Subtract => l - r,
+            BinOp::Multiply => l * r,
+            BinOp::Equals => bit(l == r),
+            BinOp::NotEquals => bit(l != r),
+            BinOp::LessThan => bit(l < r),
+            BinOp::LessThanOrEquals => bit(l <= r),
+            BinOp::GreaterThan => bit(l > r),
+            BinOp::GreaterThanOrEquals => bit(l >= r),
+            _ => { return None; }
+        };
+        Some(word(v))
+    }
+}
+
+// ============================================================================
+// Constant Evaluation
+// ============================================================================
+
+/// Evaluate any constant expression by executing it (see
+/// `evaluate()`).  Unlike `fold_constants()`, this covers every
+/// operator (e.g. `/`, `**` and `<<`), as well as bit fields and
+/// logical connectives.
+pub fn evaluate_constants(terms: Vec<Term>) -> Vec<Term> {
+    rewrite_all(terms,&mut ConstantEvaluation)
+}
+
+/// The rewrite underlying `evaluate_constants()`, which can be
+/// combined with others.
+#[derive(Clone,Copy,Debug,Default)]
+pub struct ConstantEvaluation;
+
+impl Rewrite for ConstantEvaluation {
+    fn apply(&mut self, term: &Term) -> Option<Term> {
+        match term {
+            Term::Binary(..)|Term::BitField(..) => Some(word(evaluate(term)?)),
+            _ => None
+        }
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Convert a word into a hex literal.
+fn word(v: u256) -> Term {
+    let digits = format!("{:x}",v).chars().map(|c| c.to_digit(16).unwrap() as u8).collect();
+    Term::Hex(digits)
+}
+
+/// Convert a truth value into a word.
+fn bit(b: bool) -> u256 {
+    u256::from(b as u8)
+}
+
+/// Determine the value of a literal term, or `None` if it is not a
+/// literal (or does not fit into a word).
+fn literal(term: &Term) -> Option<u256> {
+    match term {
+        Term::Int(digits) => u256::from_be_digits(digits,10),
+        Term::Hex(digits) => u256::from_be_digits(digits,16),
+        _ => None
+    }
+}
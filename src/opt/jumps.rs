@@ -0,0 +1,191 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::collections::{BTreeMap,BTreeSet};
+use alloc::vec::Vec;
+use crate::{Bytecode,Instruction,Instruction::*};
+
+// ============================================================================
+// Jump Chains
+// ============================================================================
+
+/// Remove chains of jumps from a bytecode sequence.  A _trampoline_
+/// is a block consisting only of `JUMPDEST(l); PUSHL(m); JUMP`.  Any
+/// jump to `l` can instead go directly to `m`, after which the
+/// trampoline itself is often unreachable and can be removed.  This
+/// returns a flag indicating whether anything changed.
+pub fn remove_jump_chains(bytecode: &mut Bytecode) -> bool {
+    remove_jump_chains_with(bytecode,&mut origins(bytecode))
+}
+
+/// Remove chains of jumps as for `remove_jump_chains()`, whilst
+/// maintaining the origin of each instruction (see `origins()`).
+pub(super) fn remove_jump_chains_with(bytecode: &mut Bytecode, origins: &mut Vec<Option<usize>>) -> bool {
+    let insns = bytecode.instructions_mut();
+    // Identify all trampolines
+    let mut targets = BTreeMap::new();
+    for i in 2..insns.len() {
+        if let (JUMPDEST(l),PUSHL(m),JUMP) = (&insns[i-2],&insns[i-1],&insns[i]) {
+            if l != m { targets.insert(*l,*m); }
+        }
+    }
+    // Retarget all uses of a trampoline
+    let mut changed = false;
+    for insn in insns.iter_mut() {
+        if let PUSHL(l) = insn {
+            let m = resolve(&targets,*l);
+            if m != *l {
+                *l = m;
+                changed = true;
+            }
+        }
+    }
+    // Clean up trampolines which are now unreachable.
+    remove_unreachable(insns,origins) || changed
+}
+
+/// Follow a chain of trampolines to its final destination.  Since
+/// trampolines can form a cycle (e.g. `goto a; .a goto b; .b goto
+/// a;`), the number of steps taken is bounded.
+fn resolve(targets: &BTreeMap<usize,usize>, mut label: usize) -> usize {
+    let mut steps = 0;
+    //
+    while let Some(next) = targets.get(&label) {
+        if steps > targets.len() { break; }
+        label = *next;
+        steps = steps + 1;
+    }
+    //
+    label
+}
+
+// ============================================================================
+// Identical Blocks
+// ============================================================================
+
+/// Merge blocks which have identical bodies.  Here, a candidate block
+/// starts with a `JUMPDEST` which cannot be reached by falling
+/// through from the preceding instruction, and ends with a
+/// terminating instruction (e.g. `JUMP`, `REVERT`, etc).  When two
+/// candidates are identical (ignoring their labels), all jumps to the
+/// latter are redirected to the former, and the latter is removed.
+/// This returns a flag indicating whether anything changed.
+pub fn merge_identical_blocks(bytecode: &mut Bytecode) -> bool {
+    merge_identical_blocks_with(bytecode,&mut origins(bytecode))
+}
+
+/// Merge identical blocks as for `merge_identical_blocks()`, whilst
+/// maintaining the origin of each instruction (see `origins()`).
+pub(super) fn merge_identical_blocks_with(bytecode: &mut Bytecode, origins: &mut Vec<Option<usize>>) -> bool {
+    let insns = bytecode.instructions_mut();
+    let blocks = candidate_blocks(insns);
+    // Identify duplicates
+    let mut renames = BTreeMap::new();
+    for j in 0..blocks.len() {
+        let (lj,sj,ej) = blocks[j];
+        for (li,si,ei) in &blocks[0..j] {
+            if renames.contains_key(li) { continue; }
+            if insns[si+1..*ei] == insns[sj+1..ej] {
+                renames.insert(lj,*li);
+                break;
+            }
+        }
+    }
+    // Retarget jumps to duplicates
+    for insn in insns.iter_mut() {
+        if let PUSHL(l) = insn {
+            if let Some(m) = renames.get(l) { *l = *m; }
+        }
+    }
+    // Duplicates are now unreachable
+    remove_unreachable(insns,origins)
+}
+
+/// Identify all blocks which can only be reached by a jump, and which
+/// end in a terminating instruction.  Each block is returned as a
+/// triple `(label,start,end)` where `start` is the index of its
+/// `JUMPDEST`, and `end` is one past its final instruction.
+fn candidate_blocks(insns: &[Instruction]) -> Vec<(usize,usize,usize)> {
+    let mut blocks = Vec::new();
+    //
+    for start in 1..insns.len() {
+        if let JUMPDEST(l) = insns[start] {
+            // Check block cannot be reached by falling through.
+            if insns[start-1].fallthru() { continue; }
+            // Scan to end of block
+            let mut end = start + 1;
+            while end < insns.len() && !matches!(insns[end],JUMPDEST(_)) {
+                end = end + 1;
+                if !insns[end-1].fallthru() {
+                    blocks.push((l,start,end));
+                    break;
+                }
+            }
+        }
+    }
+    //
+    blocks
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Remove all instructions which are unreachable.  An instruction is
+/// unreachable if it follows an instruction which cannot fall through,
+/// and is not preceded by a `JUMPDEST` whose label is the target of
+/// some `PUSHL`.  Since removing code can remove uses of labels, this
+/// iterates to a fixed point.  The origins of the instructions which
+/// remain are retained (see `origins()`).
+pub(super) fn remove_unreachable(insns: &mut Vec<Instruction>, origins: &mut Vec<Option<usize>>) -> bool {
+    let n = insns.len();
+    //
+    loop {
+        let m = insns.len();
+        // Determine set of labels which are referenced
+        let mut refs = BTreeSet::new();
+        for insn in insns.iter() {
+            if let PUSHL(l) = insn { refs.insert(*l); }
+        }
+        // Sweep out unreachable instructions
+        let mut reachable = true;
+        retain_with(insns,origins,|insn| {
+            if let JUMPDEST(l) = insn {
+                reachable |= refs.contains(l);
+            }
+            let keep = reachable;
+            if keep { reachable = insn.fallthru(); }
+            keep
+        });
+        // Check whether anything changed
+        if insns.len() == m { break; }
+    }
+    //
+    insns.len() != n
+}
+
+/// Determine the initial origins of a bytecode sequence to be
+/// optimised.  After a pass, the _origin_ of each instruction is the
+/// index of the instruction beforehand from which it came (which may
+/// since have been moved or rewritten), or `None` if the pass
+/// inserted it.
+pub(super) fn origins(bytecode: &Bytecode) -> Vec<Option<usize>> {
+    (0..bytecode.instructions().len()).map(Some).collect()
+}
+
+/// Retain only those instructions satisfying a given predicate (in
+/// order), along with their origins.
+pub(super) fn retain_with<F:FnMut(&Instruction)->bool>(insns: &mut Vec<Instruction>, origins: &mut Vec<Option<usize>>, mut f: F) {
+    let mut keep = Vec::new();
+    insns.retain(|insn| { let k = f(insn); keep.push(k); k });
+    let mut keep = keep.into_iter();
+    origins.retain(|_| keep.next().unwrap());
+}
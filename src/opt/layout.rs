@@ -0,0 +1,144 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::collections::{BTreeMap,BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use crate::{Bytecode,Instruction,Instruction::*};
+use super::jumps::{origins,remove_unreachable,retain_with};
+
+// ============================================================================
+// Block Layout
+// ============================================================================
+
+/// Reorder the blocks of a bytecode sequence to reduce its size.  Here,
+/// a _segment_ is a sequence of blocks which must remain adjacent
+/// because each falls through into the next.  Segments are laid out
+/// greedily from the entry: when a segment ends in an unconditional
+/// jump to a segment not yet placed, that segment is placed next and
+/// the jump removed.  Segments which end in `REVERT` or `INVALID` are
+/// considered _cold_ and are moved to the end.  Finally, any
+/// `JUMPDEST` which is no longer the target of a jump (and is reached
+/// by falling through) is removed.  This returns a flag indicating
+/// whether anything changed.
+pub fn reorder_blocks(bytecode: &mut Bytecode) -> bool {
+    reorder_blocks_with(bytecode,&mut origins(bytecode))
+}
+
+/// Reorder blocks as for `reorder_blocks()`, whilst maintaining the
+/// origin of each instruction (see `origins()`).
+pub(super) fn reorder_blocks_with(bytecode: &mut Bytecode, origins: &mut Vec<Option<usize>>) -> bool {
+    let insns = bytecode.instructions_mut();
+    let mut changed = remove_unreachable(insns,origins);
+    let mut segments = split_segments(mem::take(insns));
+    let n = segments.len();
+    // Split the origins in the same way
+    let mut rest = mem::take(origins).into_iter();
+    let mut sources : Vec<Vec<_>> = segments.iter().map(|s| rest.by_ref().take(s.len()).collect()).collect();
+    // Determine whether the last segment runs off the end of the code.
+    let falls_off = segments[n-1].last().map_or(false,|i| i.fallthru());
+    // Determine the segment which begins with each label.
+    let mut heads = BTreeMap::new();
+    for i in 1..n {
+        if let JUMPDEST(l) = segments[i][0] { heads.insert(l,i); }
+    }
+    // Place hot segments first, then cold ones.
+    let mut placed = vec![false; n];
+    let mut order = Vec::new();
+    for cold in [false,true] {
+        for i in 0..n {
+            if placed[i] || (i != 0 && is_cold(&segments[i])) != cold { continue; }
+            let mut j = i;
+            loop {
+                placed[j] = true;
+                order.push(j);
+                // Follow an unconditional jump to an unplaced segment
+                match jump_target(&segments[j]).and_then(|l| heads.get(&l)) {
+                    Some(&k) if !placed[k] => {
+                        let len = segments[j].len();
+                        segments[j].truncate(len - 2);
+                        sources[j].truncate(len - 2);
+                        changed = true;
+                        j = k;
+                    }
+                    _ => { break; }
+                }
+            }
+        }
+    }
+    changed |= order.iter().enumerate().any(|(i,j)| i != *j);
+    // Rebuild the instruction sequence
+    for (i,j) in order.iter().enumerate() {
+        insns.append(&mut segments[*j]);
+        origins.append(&mut sources[*j]);
+        if *j == n-1 && falls_off && i+1 != n {
+            // No longer at the end, so must stop explicitly.
+            insns.push(STOP);
+            origins.push(None);
+        }
+    }
+    remove_redundant_jumpdests(insns,origins) || changed
+}
+
+/// Split a sequence of instructions into segments.  A new segment
+/// begins at each `JUMPDEST` which cannot be reached by falling
+/// through from the preceding instruction.
+fn split_segments(insns: Vec<Instruction>) -> Vec<Vec<Instruction>> {
+    let mut segments = vec![Vec::new()];
+    //
+    for insn in insns {
+        let last = segments.last().unwrap().last();
+        if matches!(insn,JUMPDEST(_)) && last.map_or(false,|i:&Instruction| !i.fallthru()) {
+            segments.push(Vec::new());
+        }
+        segments.last_mut().unwrap().push(insn);
+    }
+    //
+    segments
+}
+
+/// Determine whether a segment ends in an instruction which aborts
+/// execution.  Such segments are rarely executed.
+fn is_cold(segment: &[Instruction]) -> bool {
+    matches!(segment.last(),Some(REVERT|INVALID))
+}
+
+/// Determine the target of the unconditional jump ending a segment
+/// (if there is one).
+fn jump_target(segment: &[Instruction]) -> Option<usize> {
+    match segment {
+        [..,PUSHL(l),JUMP] => Some(*l),
+        _ => None
+    }
+}
+
+/// Remove any `JUMPDEST` which is not the target of a jump and which
+/// is only reached by falling through (or at the start of the code).
+fn remove_redundant_jumpdests(insns: &mut Vec<Instruction>, origins: &mut Vec<Option<usize>>) -> bool {
+    let n = insns.len();
+    let mut refs = BTreeSet::new();
+    for insn in insns.iter() {
+        if let PUSHL(l) = insn { refs.insert(*l); }
+    }
+    //
+    let mut fallthru = true;
+    retain_with(insns,origins,|insn| {
+        let keep = match insn {
+            JUMPDEST(l) => !fallthru || refs.contains(l),
+            _ => true
+        };
+        if keep { fallthru = insn.fallthru(); }
+        keep
+    });
+    //
+    insns.len() != n
+}
@@ -0,0 +1,410 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::format;
+use alloc::string::{String,ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use crate::{Assembly,Backend,Bytecode,Compiler,Diagnostic,Instruction,Term};
+use super::*;
+use super::jumps::{merge_identical_blocks_with,origins,remove_jump_chains_with};
+use super::layout::reorder_blocks_with;
+use super::fields::coalesce_fields_with;
+
+// ============================================================================
+// Passes
+// ============================================================================
+
+/// Identifies an optimisation pass.  Passes either transform IL
+/// statements (before they are compiled), or the resulting bytecode.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+#[non_exhaustive]
+pub enum Pass {
+    /// Evaluate constant expressions (see `fold_constants()`).
+    FoldConstants,
+    /// Execute constant expressions (see `evaluate_constants()`).
+    EvaluateConstants,
+    /// Merge assignments to fields of a word (see `coalesce_fields()`).
+    CoalesceFields,
+    /// Bypass trampolines (see `remove_jump_chains()`).
+    RemoveJumpChains,
+    /// Merge duplicate blocks (see `merge_identical_blocks()`).
+    MergeIdenticalBlocks,
+    /// Replace jumps with fall through (see `reorder_blocks()`).
+    ReorderBlocks,
+    /// Push zero using `PUSH0` (see `use_push0()`).
+    UsePush0
+}
+
+impl Pass {
+    /// Every pass.
+    pub const ALL : &'static [Pass] = &[
+        Pass::FoldConstants,
+        Pass::EvaluateConstants,
+        Pass::CoalesceFields,
+        Pass::RemoveJumpChains,
+        Pass::MergeIdenticalBlocks,
+        Pass::ReorderBlocks,
+        Pass::UsePush0
+    ];
+
+    /// The passes applied by default, in order.  This excludes those
+    /// which depend on the target fork (e.g. `UsePush0`), or which
+    /// change the order of evaluation (e.g. `CoalesceFields`).
+    pub const DEFAULT : &'static [Pass] = &[
+        Pass::FoldConstants,
+        Pass::RemoveJumpChains,
+        Pass::MergeIdenticalBlocks,
+        Pass::ReorderBlocks
+    ];
+
+    /// Get the name of this pass (e.g. `"fold-constants"`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pass::FoldConstants => "fold-constants",
+            Pass::EvaluateConstants => "evaluate-constants",
+            Pass::CoalesceFields => "coalesce-fields",
+            Pass::RemoveJumpChains => "jump-chains",
+            Pass::MergeIdenticalBlocks => "merge-blocks",
+            Pass::ReorderBlocks => "reorder-blocks",
+            Pass::UsePush0 => "push0"
+        }
+    }
+
+    /// Find the pass with a given name (if any).
+    pub fn from_name(name: &str) -> Option<Pass> {
+        Pass::ALL.iter().find(|p| p.name() == name).copied()
+    }
+
+    /// Check whether this pass operates on IL (rather than bytecode).
+    pub fn is_il(&self) -> bool {
+        matches!(self, Pass::FoldConstants|Pass::EvaluateConstants|Pass::CoalesceFields)
+    }
+}
+
+/// Statistics for a single run of a pass.  The size of IL is that of
+/// its compiled bytecode (or zero if it does not yet compile).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct PassStats {
+    pub pass: Pass,
+    /// Indicates whether the pass changed anything.
+    pub changed: bool,
+    /// Number of instructions before and after the pass.
+    pub instructions: (usize,usize),
+    /// Number of bytes before and after the pass.
+    pub bytes: (usize,usize)
+}
+
+impl PassStats {
+    /// Number of instructions removed by the pass (which is negative
+    /// if instructions were added).
+    pub fn instructions_removed(&self) -> isize {
+        self.instructions.0 as isize - self.instructions.1 as isize
+    }
+
+    /// Number of bytes saved by the pass (which is negative if the
+    /// code grew).
+    pub fn bytes_saved(&self) -> isize {
+        self.bytes.0 as isize - self.bytes.1 as isize
+    }
+}
+
+// ============================================================================
+// Provenance
+// ============================================================================
+
+/// Identifies where an instruction rewritten by a pass came from.  An
+/// instruction is attributed to an IL pass when it was compiled from
+/// a statement which that pass rewrote.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Provenance {
+    /// The (last) pass which rewrote this instruction.
+    pub pass: Pass,
+    /// Indices of the instructions it replaces within the code given
+    /// to the bytecode passes (i.e. once compiled).  This is empty for
+    /// instructions which replace nothing, including those attributed
+    /// to IL passes.
+    pub origins: Vec<usize>
+}
+
+/// Print the pass and its origins (e.g. `jump-chains from #3,#4`).
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"{}",self.pass.name())?;
+        for (i,o) in self.origins.iter().enumerate() {
+            let sep = if i == 0 { " from " } else { "," };
+            write!(f,"{}#{}",sep,o)?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Pass Manager
+// ============================================================================
+
+/// Runs an ordered list of optimisation passes whilst compiling IL,
+/// recording statistics for each.  IL passes are always run before
+/// bytecode passes (though otherwise in the order given).  In debug
+/// mode, the code produced by each pass is also recorded, along with
+/// the provenance of any instructions rewritten by a pass.  Passes
+/// report where each instruction (or statement) they produce came
+/// from, so instructions which are only moved (e.g. by
+/// `ReorderBlocks`) keep their provenance.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct PassManager {
+    passes: Vec<Pass>,
+    debug: bool,
+    stats: Vec<PassStats>,
+    dumps: Vec<(Pass,String)>,
+    /// Indices of the original instructions underlying each current
+    /// instruction (in debug mode).
+    origins: Vec<Vec<usize>>,
+    /// Provenance of each current instruction (in debug mode).
+    provenance: Vec<Option<Provenance>>
+}
+
+impl PassManager {
+    /// Construct a manager with no passes.
+    pub fn new() -> Self {
+        PassManager{passes: Vec::new(), debug: false, stats: Vec::new(), dumps: Vec::new(),
+                    origins: Vec::new(), provenance: Vec::new()}
+    }
+
+    /// Append a pass to be run.
+    pub fn with_pass(mut self, pass: Pass) -> Self {
+        self.passes.push(pass);
+        self
+    }
+
+    /// Enable (or disable) recording of the code produced by each pass.
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Get the passes to be run (in order).
+    pub fn passes(&self) -> &[Pass] {
+        &self.passes
+    }
+
+    /// Get the statistics for each pass run so far.
+    pub fn stats(&self) -> &[PassStats] {
+        &self.stats
+    }
+
+    /// Get the code produced by each pass run so far (in debug mode).
+    /// IL is given one statement per line, and bytecode as assembly.
+    pub fn dumps(&self) -> &[(Pass,String)] {
+        &self.dumps
+    }
+
+    /// Get the provenance of each instruction in the code produced by
+    /// the last run (in debug mode).  This is `None` for instructions
+    /// which no pass has rewritten.
+    pub fn provenance(&self) -> &[Option<Provenance>] {
+        &self.provenance
+    }
+
+    /// Convert the code produced by the last run into an assembly,
+    /// where each instruction rewritten by a bytecode pass records its
+    /// provenance (in debug mode).
+    pub fn assembly(&self, code: &Bytecode) -> Assembly {
+        let mut asm = Assembly::from(code);
+        for (item,p) in asm.items_mut().iter_mut().zip(&self.provenance) {
+            item.provenance = p.clone();
+        }
+        asm
+    }
+
+    /// Run every pass whilst compiling a sequence of IL statements.
+    pub fn run(&mut self, mut terms: Vec<Term>) -> Result<Bytecode,Diagnostic> {
+        let passes = self.passes.clone();
+        // The (last) IL pass which rewrote each statement
+        let mut rewritten = vec![None; terms.len()];
+        // Apply IL passes
+        for pass in passes.iter().filter(|p| p.is_il()) {
+            let before = measure_il(&terms);
+            let (after,sources) = match pass {
+                Pass::FoldConstants => one_for_one(fold_constants(terms.clone())),
+                Pass::EvaluateConstants => one_for_one(evaluate_constants(terms.clone())),
+                Pass::CoalesceFields => coalesce_fields_with(terms.clone()),
+                _ => unreachable!()
+            };
+            let changed = after != terms;
+            // Statements which the pass left unchanged keep their provenance
+            rewritten = after.iter().zip(&sources).map(|(t,is)| match is.as_slice() {
+                [i] if *t == terms[*i] => rewritten[*i],
+                _ => Some(*pass)
+            }).collect();
+            terms = after;
+            self.record(*pass,changed,before,measure_il(&terms),|_| dump_il(&terms));
+        }
+        // Compile and apply bytecode passes
+        if !self.debug {
+            let mut code = Bytecode::try_from(terms.as_slice())?;
+            self.run_bytecode(&mut code);
+            return Ok(code);
+        }
+        let (mut code,statements) = compile_traced(&terms)?;
+        let provenance = statements.iter().map(|s| rewritten[*s].map(|pass| Provenance{pass, origins: Vec::new()})).collect();
+        self.run_passes(&mut code,provenance);
+        Ok(code)
+    }
+
+    /// Run every bytecode pass over a given bytecode sequence.
+    pub fn run_bytecode(&mut self, code: &mut Bytecode) {
+        let provenance = vec![None; code.instructions().len()];
+        self.run_passes(code,provenance);
+    }
+
+    /// Run every bytecode pass over a given bytecode sequence, whose
+    /// instructions have a given provenance (in debug mode).
+    fn run_passes(&mut self, code: &mut Bytecode, provenance: Vec<Option<Provenance>>) {
+        let passes = self.passes.clone();
+        if self.debug {
+            self.origins = (0..code.instructions().len()).map(|i| vec![i]).collect();
+            self.provenance = provenance;
+        }
+        for pass in passes.iter().filter(|p| !p.is_il()) {
+            let before = measure(code);
+            let insns = if self.debug { code.instructions().to_vec() } else { Vec::new() };
+            let mut sources = origins(code);
+            let changed = match pass {
+                Pass::RemoveJumpChains => remove_jump_chains_with(code,&mut sources),
+                Pass::MergeIdenticalBlocks => merge_identical_blocks_with(code,&mut sources),
+                Pass::ReorderBlocks => reorder_blocks_with(code,&mut sources),
+                // Instructions are rewritten in place
+                Pass::UsePush0 => use_push0(code),
+                _ => unreachable!()
+            };
+            if self.debug && changed {
+                self.trace(*pass,&insns,code.instructions(),&sources);
+            }
+            self.record(*pass,changed,before,measure(code),|pm| pm.assembly(code).to_string());
+        }
+    }
+
+    /// Update the provenance of each instruction after a given pass,
+    /// given the index of the instruction beforehand from which each
+    /// came (if any).  Instructions which the pass left unchanged keep
+    /// their provenance (even if moved), whilst the others are
+    /// attributed to the pass.  Instructions rewritten by the pass
+    /// originate from those they replace, whilst those inserted by the
+    /// pass originate from nothing.
+    fn trace(&mut self, pass: Pass, before: &[Instruction], after: &[Instruction], sources: &[Option<usize>]) {
+        let mut origins = Vec::new();
+        let mut provenance = Vec::new();
+        for (insn,source) in after.iter().zip(sources) {
+            match *source {
+                Some(k) if before[k] == *insn => {
+                    origins.push(self.origins[k].clone());
+                    provenance.push(self.provenance[k].clone());
+                }
+                Some(k) => {
+                    origins.push(self.origins[k].clone());
+                    provenance.push(Some(Provenance{pass, origins: self.origins[k].clone()}));
+                }
+                None => {
+                    origins.push(Vec::new());
+                    provenance.push(Some(Provenance{pass, origins: Vec::new()}));
+                }
+            }
+        }
+        self.origins = origins;
+        self.provenance = provenance;
+    }
+
+    fn record<F:FnOnce(&Self)->String>(&mut self, pass: Pass, changed: bool, before: (usize,usize), after: (usize,usize), dump: F) {
+        let instructions = (before.0,after.0);
+        let bytes = (before.1,after.1);
+        self.stats.push(PassStats{pass,changed,instructions,bytes});
+        if self.debug {
+            let d = dump(self);
+            self.dumps.push((pass,d));
+        }
+    }
+
+}
+
+/// The standard optimisation pipeline, which runs the default passes.
+impl Default for PassManager {
+    fn default() -> Self {
+        PassManager{passes: Pass::DEFAULT.to_vec(), ..PassManager::new()}
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Pair the statements produced by an IL pass which rewrites each
+/// statement in place with the statements from which they came.
+fn one_for_one(terms: Vec<Term>) -> (Vec<Term>,Vec<Vec<usize>>) {
+    let sources = (0..terms.len()).map(|i| vec![i]).collect();
+    (terms,sources)
+}
+
+/// Compile a sequence of IL statements, additionally giving the index
+/// of the statement from which each instruction was compiled.
+fn compile_traced(terms: &[Term]) -> Result<(Bytecode,Vec<usize>),Diagnostic> {
+    let current = Cell::new(0);
+    let mut backend = Tracing{code: Bytecode::new(), current: &current, statements: Vec::new()};
+    let mut compiler = Compiler::new(&mut backend);
+    for (i,t) in terms.iter().enumerate() {
+        current.set(i);
+        compiler.translate(t)?;
+    }
+    Ok((backend.code,backend.statements))
+}
+
+/// A backend which records the statement being compiled when each
+/// instruction is emitted.
+struct Tracing<'a> {
+    code: Bytecode,
+    /// The statement currently being compiled.
+    current: &'a Cell<usize>,
+    statements: Vec<usize>
+}
+
+impl<'a> Backend for Tracing<'a> {
+    fn fresh_label(&mut self) -> usize {
+        self.code.fresh_label()
+    }
+
+    fn emit(&mut self, insn: Instruction) {
+        self.code.push(insn);
+        self.statements.push(self.current.get());
+    }
+
+    fn assume(&mut self, cond: Term) {
+        self.code.assume(cond)
+    }
+}
+
+/// Determine the number of instructions and bytes in some bytecode.
+fn measure(code: &Bytecode) -> (usize,usize) {
+    let bytes = code.to_bytes().map_or(0,|bs| bs.len());
+    (code.instructions().len(),bytes)
+}
+
+/// Determine the size of some IL, once compiled.
+fn measure_il(terms: &[Term]) -> (usize,usize) {
+    Bytecode::try_from(terms).map_or((0,0),|c| measure(&c))
+}
+
+/// Print some IL, one statement per line.
+fn dump_il(terms: &[Term]) -> String {
+    let lines : Vec<String> = terms.iter().map(|t| format!("{:?}",t)).collect();
+    lines.join("\n")
+}
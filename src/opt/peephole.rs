@@ -0,0 +1,34 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec::Vec;
+use crate::{Bytecode,Instruction::*};
+
+// ============================================================================
+// PUSH0
+// ============================================================================
+
+/// Replace every push of zero (e.g. `PUSH1 0x00`) with `PUSH0`, which
+/// is both smaller and cheaper.  This is only valid when targeting
+/// Shanghai (or later).  This returns a flag indicating whether
+/// anything changed.
+pub fn use_push0(bytecode: &mut Bytecode) -> bool {
+    let mut changed = false;
+    for insn in bytecode.instructions_mut() {
+        if let PUSH(bs) = insn {
+            if !bs.is_empty() && bs.iter().all(|b| *b == 0) {
+                *bs = Vec::new();
+                changed = true;
+            }
+        }
+    }
+    changed
+}
@@ -1,5 +1,11 @@
+use std::collections::{BTreeMap,HashMap};
 use std::fmt;
-use crate::{BinOp,Region,Term};
+use std::fs;
+use std::mem;
+use std::ops::Range;
+use std::path::PathBuf;
+use crate::{BinOp,Diagnostic,Intrinsic,Region,Term};
+use crate::util::from_be_digits;
 use crate::lexer;
 use crate::lexer::{Lexer,Span,Token};
 
@@ -24,11 +30,15 @@ pub const ARITHMETIC_COMPARATORS : &'static [Token] = &[
 /// Defines the set of tokens which are considered to identify
 /// arithmetic operators (e.g. `+`, `-`, `*`, etc).
 pub const ARITHMETIC_OPERATORS : &'static [Token] = &[
+    Token::LeftAngleLeftAngle,
     Token::Minus,
     Token::Percent,
     Token::Plus,
+    Token::RightAngleRightAngle,
+    Token::RightAngleRightAngleRightAngle,
     Token::RightSlash,
-    Token::Star
+    Token::Star,
+    Token::StarStar
 ];
 
 pub const BINARY_CONNECTIVES : &'static [ &'static [Token] ] = &[
@@ -37,6 +47,17 @@ pub const BINARY_CONNECTIVES : &'static [ &'static [Token] ] = &[
     &LOGICAL_CONNECTIVES,
 ];
 
+/// Identifiers with a built-in meaning, which cannot be used to name
+/// an array.
+pub const BUILTINS : &'static [&'static str] = &[
+    "balance",
+    "calldata",
+    "calldatasize",
+    "memory",
+    "msg",
+    "storage"
+];
+
 // =========================================================================
 // Error
 // =========================================================================
@@ -46,19 +67,23 @@ pub enum ErrorCode {
     UnexpectedToken,
     UnexpectedEof,
     ExpectedToken(Token),
-    ExpectedTokenIn(Vec<Token>)
+    ExpectedTokenIn(Vec<Token>),
+    UnknownImport(String)
 }
 
 /// Identifies possible errors stemming from the parser.
 #[derive(Debug)]
 pub struct Error {
     pub span: Span<Token>,
-    pub code: ErrorCode
+    pub code: ErrorCode,
+    /// The (resolved) path of the imported file containing the span,
+    /// or `None` for the file being parsed.
+    pub file: Option<String>
 }
 
 impl Error {
     pub fn new(span: Span<Token>, code: ErrorCode) -> Error {
-	Error{span,code}
+	Error{span,code,file:None}
     }
 }
 
@@ -75,10 +100,10 @@ impl From<lexer::Error<Token>> for Error {
     fn from(p:lexer::Error<Token>) -> Error {
         match p {
             lexer::Error::Expected(t,s) => {
-                Error{span:s,code:ErrorCode::ExpectedToken(t)}
+                Error::new(s,ErrorCode::ExpectedToken(t))
             }
             lexer::Error::ExpectedIn(ts,s) => {
-                Error{span:s,code:ErrorCode::ExpectedTokenIn(ts)}
+                Error::new(s,ErrorCode::ExpectedTokenIn(ts))
             }
         }
     }
@@ -86,29 +111,151 @@ impl From<lexer::Error<Token>> for Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+// =========================================================================
+// Loaders
+// =========================================================================
+
+/// Provides the source of files imported by a program (e.g. using
+/// `import "lib.il";`).  Paths are resolved relative to the directory
+/// of the importing file (using `/` as the separator) before being
+/// loaded, such that they are relative to the program itself.
+pub trait Loader {
+    /// Load the source of the file at a given path, or return `None`
+    /// if there is no such file.
+    fn load(&mut self, path: &str) -> Option<String>;
+}
+
+/// Loads imported files from an in-memory map of paths to sources.
+impl Loader for BTreeMap<String,String> {
+    fn load(&mut self, path: &str) -> Option<String> {
+        self.get(path).cloned()
+    }
+}
+
+/// Loads imported files from an in-memory map of paths to sources.
+impl Loader for HashMap<String,String> {
+    fn load(&mut self, path: &str) -> Option<String> {
+        self.get(path).cloned()
+    }
+}
+
+/// Loads imported files from the filesystem, where paths are relative
+/// to a given root directory.
+#[derive(Clone,Debug)]
+pub struct FileLoader {
+    root: PathBuf
+}
+
+impl FileLoader {
+    pub fn new<P:Into<PathBuf>>(root: P) -> Self {
+        FileLoader{root: root.into()}
+    }
+}
+
+impl Loader for FileLoader {
+    fn load(&mut self, path: &str) -> Option<String> {
+        fs::read_to_string(self.root.join(path)).ok()
+    }
+}
+
 // =========================================================================
 // Parser
 // =========================================================================
 
 pub struct Parser {
     /// Provides access to our token stream.
-    lexer: Lexer
+    lexer: Lexer,
+    /// Provides the source of imported files (if any).
+    loader: Option<Box<dyn Loader>>,
+    /// Paths of files imported so far.
+    imported: Vec<String>,
+    /// The (resolved) path of the file being parsed, or `None` for the
+    /// program itself.
+    file: Option<String>,
+    /// Names of arrays declared so far.
+    arrays: Vec<String>,
+    /// Span of each statement parsed so far (see `spans()`).
+    spans: Vec<Range<usize>>
 }
 
 impl Parser {
     pub fn new(input: &str) -> Self {
-        Self { lexer: Lexer::new(input) }
+        Self { lexer: Lexer::new(input), loader: None, imported: Vec::new(), file: None, arrays: Vec::new(), spans: Vec::new() }
     }
 
-    /// Parse a line of text into a term.
-    pub fn parse(&mut self) -> Result<Vec<Term>> {
+    /// Use a given loader to resolve any imports.  Without a loader,
+    /// every import is reported as unknown.
+    pub fn with_loader<L:Loader+'static>(mut self, loader: L) -> Self {
+        self.loader = Some(Box::new(loader));
+        self
+    }
+
+    /// Parse a line of text into a term.  The statements of any
+    /// imported file are included in place of the import (though a
+    /// file imported more than once is only included once).
+    pub fn parse(&mut self) -> std::result::Result<Vec<Term>,Diagnostic> {
+        self.parse_all().map_err(Diagnostic::from)
+    }
+
+    /// Get the span of characters in the input from which each
+    /// statement parsed so far came, in order.  Statements included
+    /// from an imported file are given the span of the import.
+    pub fn spans(&self) -> &[Range<usize>] {
+        &self.spans
+    }
+
+    /// Parse every statement remaining in the input.
+    fn parse_all(&mut self) -> Result<Vec<Term>> {
         let mut terms = Vec::new();
         while !self.lexer.is_eof() {
-            terms.push(self.parse_stmt()?);
+            self.skip_whitespace();
+            // Allow trailing whitespace
+            if self.lexer.is_eof() { break; }
+            let start = self.lexer.peek().range().start;
+            let n = terms.len();
+            if self.lexer.peek().kind == Token::Import {
+                terms.extend(self.parse_stmt_import()?);
+            } else {
+                terms.push(self.parse_stmt()?);
+            }
+            let span = start..self.lexer.peek().range().start;
+            self.spans.extend((n..terms.len()).map(|_| span.clone()));
         }
         Ok(terms)
     }
 
+    /// Parse an import statement, such as `import "lib.il";`, and
+    /// then parse the imported file.  Note that errors in an imported
+    /// file are located within that file (see `Error::file`).
+    pub fn parse_stmt_import(&mut self) -> Result<Vec<Term>> {
+        self.lexer.snap(Token::Import)?;
+        self.skip_whitespace();
+        let token = self.lexer.snap(Token::String)?;
+        self.lexer.snap(Token::SemiColon)?;
+        // Strip quotes
+        let path = self.lexer.get_str(token);
+        let path = resolve(self.file.as_deref(),&path[1..path.len()-1]);
+        if self.imported.contains(&path) { return Ok(Vec::new()); }
+        // Load imported file
+        let input = match self.loader.as_mut().and_then(|l| l.load(&path)) {
+            Some(input) => input,
+            None => { return Err(Error::new(token,ErrorCode::UnknownImport(path))); }
+        };
+        self.imported.push(path.clone());
+        // Parse imported file using the same loader
+        let mut parser = Parser::new(&input);
+        parser.loader = self.loader.take();
+        parser.imported = mem::take(&mut self.imported);
+        parser.arrays = mem::take(&mut self.arrays);
+        parser.file = Some(path.clone());
+        let terms = parser.parse_all();
+        self.loader = parser.loader;
+        self.imported = parser.imported;
+        self.arrays = parser.arrays;
+        // Errors are located in the innermost file
+        terms.map_err(|mut e| { e.file.get_or_insert(path); e })
+    }
+
     // =========================================================================
     // Statements
     // =========================================================================
@@ -119,13 +266,16 @@ impl Parser {
     	// Dispatch on lookahead
     	match self.lexer.peek().kind {
     	    Token::Assert => self.parse_stmt_assert(),
+            Token::Assume => self.parse_stmt_assume(),
     	    Token::Fail => self.parse_stmt_fail(),
     	    Token::Stop => self.parse_stmt_stop(),
     	    Token::Goto => self.parse_stmt_goto(),
     	    Token::If => self.parse_stmt_if(),
             Token::Dot => self.parse_stmt_label(),
+    	    Token::Require => self.parse_stmt_require(),
     	    Token::Revert => self.parse_stmt_revert(),
             Token::Succeed => self.parse_stmt_succeed(),
+            Token::Switch => self.parse_stmt_switch(),
             _ => self.parse_stmt_assign()
         }
     }
@@ -137,15 +287,47 @@ impl Parser {
         Ok(Term::Assert(Box::new(expr)))
     }
 
+    /// Parse an assumption, such as `assume (calldatasize() >= 4);`.
+    pub fn parse_stmt_assume(&mut self) -> Result<Term> {
+        self.lexer.snap(Token::Assume)?;
+        let expr = self.parse_expr()?;
+        self.lexer.snap(Token::SemiColon)?;
+        Ok(Term::Assume(Box::new(expr)))
+    }
+
     pub fn parse_stmt_assign(&mut self) -> Result<Term> {
     	let lhs = self.parse_expr()?;
         self.skip_whitespace();
+        // Check for an array declaration
+        if let (Term::MemoryAccess(r),Token::Identifier) = (&lhs,self.lexer.peek().kind) {
+            return self.parse_stmt_array(*r);
+        }
         self.lexer.snap(Token::Equals)?;
     	let rhs = self.parse_expr()?;
         self.lexer.snap(Token::SemiColon)?;
         Ok(Term::Assignment(Box::new(lhs),Box::new(rhs)))
     }
 
+    /// Parse an array declaration, such as `memory arr[10];`.  The
+    /// region has already been consumed, and the name cannot be that
+    /// of a built-in.
+    pub fn parse_stmt_array(&mut self, region: Region) -> Result<Term> {
+        let tok = self.lexer.snap(Token::Identifier)?;
+        let name = self.lexer.get_str(tok);
+        if BUILTINS.contains(&name.as_str()) {
+            return Err(Error::new(tok,ErrorCode::UnexpectedToken));
+        }
+        self.lexer.snap(Token::LeftSquare)?;
+        let tok = self.lexer.snap(Token::Integer)?;
+        let Ok(len) = self.lexer.get_str(tok).parse() else {
+            return Err(Error::new(tok,ErrorCode::UnexpectedToken));
+        };
+        self.lexer.snap(Token::RightSquare)?;
+        self.lexer.snap(Token::SemiColon)?;
+        self.arrays.push(name.clone());
+        Ok(Term::Array(region,name,len))
+    }
+
     pub fn parse_stmt_fail(&mut self) -> Result<Term> {
     	self.lexer.snap(Token::Fail)?;
     	self.lexer.snap(Token::SemiColon)?;
@@ -183,6 +365,13 @@ impl Parser {
         Ok(Term::Label(self.lexer.get_str(target)))
     }
 
+    pub fn parse_stmt_require(&mut self) -> Result<Term> {
+    	self.lexer.snap(Token::Require)?;
+    	let expr = self.parse_expr()?;
+        self.lexer.snap(Token::SemiColon)?;
+        Ok(Term::Require(Box::new(expr)))
+    }
+
     pub fn parse_stmt_revert(&mut self) -> Result<Term> {
     	self.lexer.snap(Token::Revert)?;
         let exprs = self.parse_expr_list(Token::SemiColon)?;
@@ -197,6 +386,38 @@ impl Parser {
         Ok(Term::Succeed(exprs))
     }
 
+    /// Parse a switch statement, such as `switch x { 1: goto a; 2:
+    /// goto b; }`.  Every case must be a literal.
+    pub fn parse_stmt_switch(&mut self) -> Result<Term> {
+    	self.lexer.snap(Token::Switch)?;
+    	let expr = self.parse_expr()?;
+        self.skip_whitespace();
+    	self.lexer.snap(Token::LeftCurly)?;
+        let mut cases = Vec::new();
+        self.skip_whitespace();
+        while self.lexer.peek().kind != Token::RightCurly {
+    	    let lookahead = self.lexer.peek();
+            let value = match lookahead.kind {
+    	        Token::Integer => self.parse_literal_int()?,
+    	        Token::Hex => self.parse_literal_hex()?,
+    	        _ => {
+    		    return Err(Error::new(lookahead,ErrorCode::UnexpectedToken));
+    	        }
+            };
+            self.skip_whitespace();
+    	    self.lexer.snap(Token::Colon)?;
+            self.skip_whitespace();
+    	    self.lexer.snap(Token::Goto)?;
+            self.skip_whitespace();
+    	    let target = self.lexer.snap(Token::Identifier)?;
+            self.lexer.snap(Token::SemiColon)?;
+            cases.push((value,self.lexer.get_str(target)));
+            self.skip_whitespace();
+        }
+    	self.lexer.snap(Token::RightCurly)?;
+        Ok(Term::Switch(Box::new(expr),cases))
+    }
+
     // =========================================================================
     // Expressions
     // =========================================================================
@@ -236,27 +457,52 @@ impl Parser {
 
     pub fn parse_expr_postfix(&mut self) -> Result<Term> {
         let mut expr = self.parse_expr_term()?;
-        // Check for postfix unary operator.
-    	let lookahead = self.lexer.peek();
-    	// FIXME: managed nested operators
-        expr = match lookahead.kind {
-            Token::LeftSquare => self.parse_expr_arrayaccess(expr)?,
-            //TokenType::LeftBrace => self.parse_expr_invoke(expr)?,
-            _ => expr
-        };
+        // Check for postfix unary operators (which may be nested).
+        while self.lexer.peek().kind == Token::LeftSquare {
+            expr = self.parse_expr_arrayaccess(expr)?;
+        }
         // Done
         Ok(expr)
     }
 
+    /// Parse either an array access (e.g. `storage[0]`), or a bit
+    /// field (e.g. `storage[0][8:16]`) whose offset and width are
+    /// integer literals.
     pub fn parse_expr_arrayaccess(&mut self, src: Term) -> Result<Term> {
         self.lexer.snap(Token::LeftSquare)?;
+        self.skip_whitespace();
+        let start = self.lexer.peek();
         let index = self.parse_expr()?;
+        self.skip_whitespace();
+        // Check for a bit field
+        let expr = if self.lexer.peek().kind == Token::Colon {
+            self.lexer.snap(Token::Colon)?;
+            self.skip_whitespace();
+            let offset = Self::parse_bits(start,&index)?;
+            let end = self.lexer.peek();
+            let width = self.parse_expr()?;
+            let width = Self::parse_bits(end,&width)?;
+            self.skip_whitespace();
+            Term::BitField(Box::new(src),offset,width)
+        } else {
+            Term::ArrayAccess(Box::new(src),Box::new(index))
+        };
         self.lexer.snap(Token::RightSquare)?;
-        let expr = Term::ArrayAccess(Box::new(src),Box::new(index));
         // Done
         Ok(expr)
     }
 
+    /// Extract the number of bits given by an integer literal (e.g.
+    /// the offset or width of a bit field).
+    fn parse_bits(tok: Span<Token>, term: &Term) -> Result<usize> {
+        match term {
+            Term::Int(digits) if digits.len() <= 3 => {
+                Ok(from_be_digits(digits,10) as usize)
+            }
+            _ => Err(Error::new(tok,ErrorCode::UnexpectedToken))
+        }
+    }
+
     pub fn parse_expr_term(&mut self) -> Result<Term> {
         // Skip whitespace
         self.skip_whitespace();
@@ -305,6 +551,10 @@ impl Parser {
             "memory" => Term::MemoryAccess(Region::Memory),
             "storage" => Term::MemoryAccess(Region::Storage),
             "calldata" => Term::MemoryAccess(Region::CallData),
+            "msg" => self.parse_intrinsic_msg()?,
+            "balance" => self.parse_intrinsic_call(Intrinsic::Balance)?,
+            "calldatasize" => self.parse_intrinsic_call(Intrinsic::CallDataSize)?,
+            _ if self.arrays.contains(&chars) => Term::Variable(chars),
     	    _ => {
     		return Err(Error::new(tok,ErrorCode::UnexpectedToken));
     	    }
@@ -313,11 +563,61 @@ impl Parser {
         Ok(expr)
     }
 
+    /// Parse the field of a `msg` intrinsic (e.g. `msg.sender`).  The
+    /// `msg` identifier itself has already been consumed.
+    pub fn parse_intrinsic_msg(&mut self) -> Result<Term> {
+        self.lexer.snap(Token::Dot)?;
+    	let tok = self.lexer.snap(Token::Identifier)?;
+        //
+        let intrinsic = match self.lexer.get_str(tok).as_str() {
+            "sender" => Intrinsic::MsgSender,
+            "value" => Intrinsic::MsgValue,
+    	    _ => {
+    		return Err(Error::new(tok,ErrorCode::UnexpectedToken));
+    	    }
+        };
+        //
+        Ok(Term::Intrinsic(intrinsic,Vec::new()))
+    }
+
+    /// Parse the arguments of an intrinsic invocation (e.g.
+    /// `balance(addr)`).  The intrinsic's name has already been
+    /// consumed, and exactly the right number of arguments must be
+    /// given.
+    pub fn parse_intrinsic_call(&mut self, intrinsic: Intrinsic) -> Result<Term> {
+        let mut args = Vec::new();
+    	self.lexer.snap(Token::LeftBrace)?;
+        for i in 0..intrinsic.arity() {
+            if i > 0 {
+                self.skip_whitespace();
+                self.lexer.snap(Token::Comma)?;
+            }
+            args.push(self.parse_expr()?);
+        }
+        self.skip_whitespace();
+    	self.lexer.snap(Token::RightBrace)?;
+        //
+        Ok(Term::Intrinsic(intrinsic,args))
+    }
+
     pub fn parse_expr_bracketed(&mut self) -> Result<Term> {
     	self.lexer.snap(Token::LeftBrace)?;
-    	let expr = self.parse_expr();
+    	let expr = self.parse_expr()?;
+        self.skip_whitespace();
+        // Check for a tuple
+        let expr = if self.lexer.peek().kind == Token::Comma {
+            let mut exprs = vec![expr];
+            while self.lexer.peek().kind == Token::Comma {
+                self.lexer.snap(Token::Comma)?;
+                exprs.push(self.parse_expr()?);
+                self.skip_whitespace();
+            }
+            Term::Tuple(exprs)
+        } else {
+            expr
+        };
     	self.lexer.snap(Token::RightBrace)?;
-        expr
+        Ok(expr)
     }
 
     /// Parse a sequence of expression separated by a comma.
@@ -370,6 +670,11 @@ impl Parser {
 	    Token::Plus => BinOp::Add,
             Token::RightSlash => BinOp::Divide,
             Token::Star => BinOp::Multiply,
+            Token::StarStar => BinOp::Exponent,
+            // Bitwise
+            Token::LeftAngleLeftAngle => BinOp::ShiftLeft,
+            Token::RightAngleRightAngle => BinOp::ShiftRight,
+            Token::RightAngleRightAngleRightAngle => BinOp::SignedShiftRight,
             // // Logical
             Token::AmpersandAmpersand => BinOp::LogicalAnd,
             Token::BarBar => BinOp::LogicalOr,
@@ -379,3 +684,26 @@ impl Parser {
         Some(bop)
     }
 }
+
+// =========================================================================
+// Helpers
+// =========================================================================
+
+/// Resolve a path imported from a given file (or from the program
+/// itself), such that it is relative to the program rather than the
+/// importing file.  For example, `"b.il"` imported from `"lib/a.il"`
+/// resolves to `"lib/b.il"`.  Absolute paths are left unchanged.
+fn resolve(from: Option<&str>, path: &str) -> String {
+    if path.starts_with('/') { return path.to_string(); }
+    let mut parts : Vec<&str> = from.map_or(Vec::new(),|f| f.split('/').collect());
+    // Drop the name of the importing file
+    parts.pop();
+    for p in path.split('/') {
+        match p {
+            ""|"." => {}
+            ".." if parts.last().is_some_and(|l| *l != "..") => { parts.pop(); }
+            _ => parts.push(p)
+        }
+    }
+    parts.join("/")
+}
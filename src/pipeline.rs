@@ -0,0 +1,316 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::string::{String,ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+use crate::{AbstractState,Annotator,Block,BlockSummary,BlockVisitor,CfaState,Diagnostic,Disassembly,EvmVersion,FailureKind};
+use crate::{Coverage,FromHexString,Instruction,InstructionVisitor,InvalidJump,Pseudos,Reachability,Statistics,metadata_end};
+use crate::util::u256;
+
+// ============================================================================
+// Options
+// ============================================================================
+
+/// Options controlling the high-level pipelines below.  Compilation
+/// depends only on the input (including any imports) and these
+/// options, and is deterministic: the same input always compiles to
+/// byte-for-byte identical output.  In particular, labels are always
+/// allocated in order of first use, and only ordered collections are
+/// iterated when compiling.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct Options {
+    /// Treat all bytes from this offset as data.
+    pub data_from: Option<usize>,
+    /// Treat any bytes after the Solidity metadata as constructor
+    /// arguments (i.e. data).  This is ignored if `data_from` is
+    /// given.
+    pub constructor_args: bool,
+    /// Apply the optimisation passes when compiling.
+    pub optimise: bool,
+    /// Append a metadata trailer identifying the compiler version
+    /// and source when compiling.  This follows an `INVALID`
+    /// instruction, so that code cannot fall through into it.
+    pub metadata: bool,
+    /// The version of the EVM targeted when compiling (if any).  For
+    /// example, zero is pushed using `PUSH0` only when targeting
+    /// Shanghai (or later).
+    pub evm_version: Option<EvmVersion>,
+    /// Report values which are pushed but never used, and potential
+    /// security problems, when analysing.
+    pub lint: bool,
+    /// Annotate each block with why it is considered reachable when
+    /// disassembling.
+    pub explain: bool,
+    /// Distinguish the states of each block by the last few branch
+    /// sites through which it is reached (see
+    /// `Disassembly::with_context_depth()`).  Zero disables this.
+    pub context_depth: usize,
+    /// Resolve function returns by tracking return addresses (see
+    /// `Disassembly::with_return_tracking()`).
+    pub track_returns: bool,
+    /// Byte patterns to decode as pseudo-instructions when
+    /// disassembling (see `Disassembly::with_pseudos()`).
+    pub pseudos: Pseudos
+}
+
+// ============================================================================
+// Pipelines
+// ============================================================================
+
+/// Disassemble a hex string into a human-readable listing, with one
+/// instruction per line.
+pub fn disassemble_hex(hex: &str, options: &Options) -> Result<String,Diagnostic> {
+    disassemble_hex_with(hex,options,&())
+}
+
+/// Disassemble a hex string into a human-readable listing, using a
+/// given annotator to describe pushed constants (e.g. `PUSH4
+/// sel:transfer(address,uint256)`).
+pub fn disassemble_hex_with<A:Annotator+?Sized>(hex: &str, options: &Options, annotator: &A) -> Result<String,Diagnostic> {
+    let bytes = parse_hex(hex)?;
+    Ok(Pipeline::new(&bytes,options).listing(annotator))
+}
+
+/// Render a hex string as a hex dump, where each byte is marked with
+/// its block and whether it is code or data (see `HexDump`).
+pub fn hexdump_hex(hex: &str, options: &Options) -> Result<String,Diagnostic> {
+    let bytes = parse_hex(hex)?;
+    let disasm = disassemble(&bytes,options);
+    Ok(disasm.hexdump(16).to_string())
+}
+
+/// Parse, compile and assemble a given IL program into bytes.
+#[cfg(feature = "std")]
+pub fn compile_il(input: &str, options: &Options) -> Result<Vec<u8>,Diagnostic> {
+    compile_with(crate::Parser::new(input),input,options)
+}
+
+/// Parse, compile and assemble a given IL program into bytes, using a
+/// given loader to resolve any imports.
+#[cfg(feature = "std")]
+pub fn compile_il_with<L:crate::Loader+'static>(input: &str, options: &Options, loader: L) -> Result<Vec<u8>,Diagnostic> {
+    compile_with(crate::Parser::new(input).with_loader(loader),input,options)
+}
+
+#[cfg(feature = "std")]
+fn compile_with(mut parser: crate::Parser, input: &str, options: &Options) -> Result<Vec<u8>,Diagnostic> {
+    use crate::{Bytecode,Metadata};
+    use crate::opt;
+    // Parse program
+    let terms = parser.parse()?;
+    // Translate statements into bytecode instructions, optimising
+    // (if requested) along the way.
+    let mut code = if options.optimise {
+        opt::PassManager::default().run(terms)?
+    } else {
+        Bytecode::try_from(terms.as_slice())?
+    };
+    // Use PUSH0 (if supported)
+    if options.evm_version.is_some_and(|v| v.supports(&Instruction::PUSH(Vec::new()))) {
+        opt::use_push0(&mut code);
+    }
+    // Translate instructions into bytes
+    let mut bytes = code.to_bytes()?;
+    // Append metadata (if requested), separated from the code by
+    // INVALID (as for solc) so it can never be executed.
+    if options.metadata {
+        bytes.push(0xfe);
+        bytes.extend(Metadata::new(input).to_bytes());
+    }
+    Ok(bytes)
+}
+
+/// The results of analysing a bytecode sequence.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Report {
+    /// Problems found in the reachable code.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Jumps whose targets could not be verified.
+    pub invalid_jumps: Vec<InvalidJump>,
+    /// Statistics for the reachable code.
+    pub statistics: Statistics,
+    /// Classification of bytes as code, data or unknown.
+    pub coverage: Coverage,
+    /// A summary of every block (in order).
+    pub summaries: Vec<BlockSummary>
+}
+
+impl Report {
+    /// Determine the most serious kind of analysis failure reported
+    /// (if any).  That is, a structural failure if there is one, and
+    /// otherwise a precision failure if there is one.
+    pub fn failure(&self) -> Option<FailureKind> {
+        self.diagnostics.iter().filter_map(|d| d.failure).max()
+    }
+
+    /// Get the diagnostics reporting analysis failures of a given
+    /// kind.
+    pub fn failures(&self, kind: FailureKind) -> Vec<&Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.failure == Some(kind)).collect()
+    }
+}
+
+/// Analyse a given bytecode sequence, collecting everything which can
+/// be determined about it.
+pub fn analyze(bytes: &[u8]) -> Report {
+    analyze_with(bytes,&Options::default())
+}
+
+/// Analyse a given bytecode sequence using the given options.
+pub fn analyze_with(bytes: &[u8], options: &Options) -> Report {
+    Pipeline::new(bytes,options).report()
+}
+
+// ============================================================================
+// Pipeline
+// ============================================================================
+
+/// A bytecode sequence which has been analysed once, from which both
+/// a report and a listing can be produced (e.g. by a tool printing
+/// both).
+pub struct Pipeline<'a> {
+    disasm: Disassembly<'a,CfaState>,
+    options: &'a Options
+}
+
+impl<'a> Pipeline<'a> {
+    /// Analyse a given bytecode sequence using the given options.
+    pub fn new(bytes: &'a [u8], options: &'a Options) -> Self {
+        Self{disasm: disassemble(bytes,options), options}
+    }
+
+    /// Get the underlying disassembly.
+    pub fn disassembly(&self) -> &Disassembly<'a,CfaState> {
+        &self.disasm
+    }
+
+    /// Collect everything which can be determined about the bytecode
+    /// sequence (as for `analyze_with()`).
+    pub fn report(&self) -> Report {
+        let disasm = &self.disasm;
+        let mut diagnostics = disasm.diagnostics();
+        if self.options.lint {
+            diagnostics.extend(disasm.unused_values());
+            diagnostics.extend(crate::lints::check(disasm).iter().map(|f| f.to_diagnostic()));
+        }
+        //
+        Report {
+            diagnostics,
+            invalid_jumps: disasm.invalid_jumps(),
+            statistics: disasm.statistics(),
+            coverage: disasm.coverage(),
+            summaries: disasm.summaries()
+        }
+    }
+
+    /// Produce a human-readable listing, using a given annotator to
+    /// describe pushed constants (as for `disassemble_hex_with()`).
+    pub fn listing<A:Annotator+?Sized>(&self, annotator: &A) -> String {
+        let reasons = if self.options.explain { self.disasm.reachability() } else { Vec::new() };
+        let mut printer = Printer{annotator, reasons, reason: None, out: String::new()};
+        self.disasm.visit(&mut printer);
+        printer.out
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Prints a human-readable listing whilst visiting a disassembly.
+struct Printer<'b,A:?Sized> {
+    annotator: &'b A,
+    /// Reason each block is reachable (if explaining).
+    reasons: Vec<Reachability>,
+    /// Reason for the current block, which is yet to be printed.
+    reason: Option<Reachability>,
+    out: String
+}
+
+impl<'b,A:Annotator+?Sized> Printer<'b,A> {
+    fn print_reason(&mut self) {
+        if let Some(r) = self.reason.take() { writeln!(self.out,"// {}",r).unwrap(); }
+    }
+}
+
+impl<'b,A:Annotator+?Sized> InstructionVisitor<CfaState> for Printer<'b,A> {
+    fn visit_instruction(&mut self, pc: usize, insn: &Instruction, state: &CfaState) {
+        // Explain reachability at the start of each block
+        if !matches!(insn,Instruction::JUMPDEST(_)) { self.print_reason(); }
+        match insn {
+            Instruction::JUMPDEST(_) => {
+                let len = state.stack().len();
+                writeln!(self.out).unwrap();
+                if len.is_constant() {
+                    writeln!(self.out,"// Stack +{}",len.unwrap()).unwrap();
+                } else {
+                    writeln!(self.out,"// Stack +{}",len).unwrap();
+                }
+                self.print_reason();
+                writeln!(self.out,"{:#08x}: {}",pc,insn).unwrap();
+            }
+            Instruction::JUMP|Instruction::JUMPI => {
+                writeln!(self.out,"{:#08x}: {} // {}",pc,insn,state.peek(0)).unwrap();
+            }
+            Instruction::SLOAD|Instruction::SSTORE => {
+                let key = state.peek(0);
+                match key.is_known().then(|| self.annotator.annotate_slot(u256::from(key.unwrap()))).flatten() {
+                    Some(name) => writeln!(self.out,"{:#08x}: {} // {}",pc,insn,name).unwrap(),
+                    None => writeln!(self.out,"{:#08x}: {}",pc,insn).unwrap()
+                }
+            }
+            Instruction::PUSH(bytes) if self.annotator.annotate(bytes).is_some() => {
+                let note = self.annotator.annotate(bytes).unwrap();
+                writeln!(self.out,"{:#08x}: PUSH{} {}",pc,bytes.len(),note).unwrap();
+            }
+            _ => {
+                writeln!(self.out,"{:#08x}: {}",pc,insn).unwrap();
+            }
+        }
+    }
+}
+
+impl<'b,A:Annotator+?Sized> BlockVisitor<CfaState> for Printer<'b,A> {
+    fn enter_block(&mut self, id: usize, _blk: &Block) {
+        self.reason = self.reasons.get(id).copied();
+    }
+
+    fn visit_data(&mut self, id: usize, blk: &Block, bytes: &[u8]) {
+        self.reason = self.reasons.get(id).copied();
+        self.print_reason();
+        writeln!(self.out,"{:#08x}: {}",blk.start,Instruction::DATA(bytes.to_vec())).unwrap();
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>,Diagnostic> {
+    let hex = hex.trim();
+    let err = Diagnostic::error("H001","invalid hex string");
+    // Every byte requires two digits
+    if hex.len() % 2 != 0 { return Err(err); }
+    hex.from_hex_string().map_err(|_| err)
+}
+
+fn disassemble<'a>(bytes: &'a [u8], options: &Options) -> Disassembly<'a,CfaState> {
+    let mut disasm = Disassembly::new(bytes)
+        .with_context_depth(options.context_depth)
+        .with_return_tracking(options.track_returns)
+        .with_pseudos(&options.pseudos);
+    let data = match options.data_from {
+        Some(start) => Some(start),
+        None if options.constructor_args => metadata_end(bytes),
+        None => None
+    };
+    if let Some(start) = data.filter(|s| *s > 0) {
+        disasm = disasm.with_data_from(start);
+    }
+    disasm.build()
+}
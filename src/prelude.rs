@@ -0,0 +1,20 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The most commonly used items, which can be imported together using
+//! `use evmil::prelude::*`.  Items are only ever added here (never
+//! removed or renamed) within a major version.
+pub use crate::{AbstractState,Assembly,Block,Bytecode,CfaState,Diagnostic,Disassemble,Disassembly};
+pub use crate::{FromHexString,Instruction,Options,Report,Term,ToHexString};
+pub use crate::{analyze,analyze_with,disassemble_hex};
+#[cfg(feature = "std")]
+pub use crate::{Parser,compile_il};
@@ -0,0 +1,112 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec::Vec;
+use crate::{AbstractState,Disassembly,Instruction::*};
+
+/// Code preceding the implementation address in an EIP-1167 minimal
+/// proxy.
+const MINIMAL_PREFIX : [u8;9] = [0x36,0x3d,0x3d,0x37,0x3d,0x3d,0x3d,0x36,0x3d];
+
+/// Code following the implementation address in an EIP-1167 minimal
+/// proxy, up to (but excluding) the target of the final jump.
+const MINIMAL_SUFFIX : [u8;10] = [0x5a,0xf4,0x3d,0x82,0x80,0x3e,0x90,0x3d,0x91,0x60];
+
+/// Storage slot holding the implementation address (EIP-1967).
+pub const IMPLEMENTATION_SLOT : [u8;32] = [
+    0x36,0x08,0x94,0xa1,0x3b,0xa1,0xa3,0x21,0x06,0x67,0xc8,0x28,0x49,0x2d,0xb9,0x8d,
+    0xca,0x3e,0x20,0x76,0xcc,0x37,0x35,0xa9,0x20,0xa3,0xca,0x50,0x5d,0x38,0x2b,0xbc
+];
+
+/// Storage slot holding the beacon address (EIP-1967).
+pub const BEACON_SLOT : [u8;32] = [
+    0xa3,0xf0,0xad,0x74,0xe5,0x42,0x3a,0xeb,0xfd,0x80,0xd3,0xef,0x43,0x46,0x57,0x83,
+    0x35,0xa9,0xa7,0x2a,0xea,0xee,0x59,0xff,0x6c,0xb3,0x58,0x2b,0x35,0x13,0x3d,0x50
+];
+
+/// Selector of `proxiableUUID()`, as implemented by UUPS contracts
+/// (EIP-1822).
+const PROXIABLE_UUID : [u8;4] = [0x52,0xd1,0x90,0x2d];
+
+// ============================================================================
+// Proxies
+// ============================================================================
+
+/// Identifies a common proxy pattern.
+#[derive(Clone,Debug,PartialEq,Eq)]
+#[non_exhaustive]
+pub enum Proxy {
+    /// An EIP-1167 minimal proxy, which delegates to a fixed
+    /// implementation address.
+    Minimal(Vec<u8>),
+    /// An EIP-1967 proxy, which delegates to an address held in a
+    /// given storage slot.  For a beacon proxy, the slot holds the
+    /// address of the beacon (rather than the implementation).
+    Eip1967{slot: Vec<u8>, beacon: bool},
+    /// A UUPS (EIP-1822) contract, which uses the EIP-1967
+    /// implementation slot and can upgrade itself.
+    Uups{slot: Vec<u8>}
+}
+
+impl<'a,T:AbstractState> Disassembly<'a,T> {
+    /// Determine whether this bytecode matches a common proxy pattern
+    /// and, if so, where the implementation is found.
+    pub fn proxy(&self) -> Option<Proxy> {
+        let n = self.blocks().last().map_or(0,|b| b.end);
+        let bytes = self.read_bytes(0,n);
+        if let Some(addr) = minimal_proxy(&bytes) {
+            return Some(Proxy::Minimal(addr));
+        }
+        // Look for EIP-1967 slots
+        let mut slot = None;
+        let mut delegates = false;
+        for blk in self.blocks() {
+            let mut pc = blk.start;
+            while pc < blk.end {
+                let insn = self.decode(pc);
+                match &insn {
+                    PUSH(bs) if bs[..] == IMPLEMENTATION_SLOT => slot = slot.or(Some(false)),
+                    PUSH(bs) if bs[..] == BEACON_SLOT => slot = Some(true),
+                    DELEGATECALL => delegates = true,
+                    _ => {}
+                }
+                pc = pc + insn.length(&[]);
+            }
+        }
+        match slot {
+            Some(false) if self.functions().iter().any(|f| f.selector.as_deref() == Some(&PROXIABLE_UUID[..])) => {
+                Some(Proxy::Uups{slot: IMPLEMENTATION_SLOT.to_vec()})
+            }
+            Some(false) if delegates => Some(Proxy::Eip1967{slot: IMPLEMENTATION_SLOT.to_vec(), beacon: false}),
+            Some(true) if delegates => Some(Proxy::Eip1967{slot: BEACON_SLOT.to_vec(), beacon: true}),
+            _ => None
+        }
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Extract the implementation address from an EIP-1167 minimal proxy
+/// (including variants with shortened addresses).
+fn minimal_proxy(bytes: &[u8]) -> Option<Vec<u8>> {
+    let rest = bytes.strip_prefix(&MINIMAL_PREFIX[..])?;
+    // Address is pushed using PUSH1 .. PUSH20
+    let len = (*rest.first()? as usize).checked_sub(0x5f).filter(|l| (1..=20).contains(l))?;
+    let addr = rest.get(1..1+len)?;
+    let rest = rest[1+len..].strip_prefix(&MINIMAL_SUFFIX[..])?;
+    // Final jump target is the JUMPDEST preceding RETURN
+    match rest {
+        [target,0x57,0xfd,0x5b,0xf3] if (*target as usize) == bytes.len() - 2 => Some(addr.to_vec()),
+        _ => None
+    }
+}
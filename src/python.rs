@@ -0,0 +1,221 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Python bindings for the disassembler and compiler.  To build an
+//! extension module, use e.g. `cargo rustc --lib --release --features
+//! python --crate-type cdylib` and rename the result to `evmil.so`.
+//! Python objects hold copies of the results of analysis, since a
+//! `Disassembly` borrows the bytes being disassembled.
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::PyBytes;
+use crate::{CfaState,Disassembly,FromHexString,Instruction,Options,compile_il};
+
+// ============================================================================
+// Instruction
+// ============================================================================
+
+#[pyclass(name = "Instruction")]
+#[derive(Clone)]
+pub struct PyInstruction {
+    insn: Instruction,
+    /// Byte offset of this instruction.
+    #[pyo3(get)]
+    pc: usize
+}
+
+#[pymethods]
+impl PyInstruction {
+    /// The opcode of this instruction, or `None` for data.
+    #[getter]
+    fn opcode(&self) -> Option<u8> {
+        match self.insn {
+            Instruction::DATA(_) => None,
+            _ => self.insn.opcode(&[]).ok()
+        }
+    }
+
+    /// The length of this instruction (in bytes).
+    #[getter]
+    fn length(&self) -> usize {
+        self.insn.length(&[])
+    }
+
+    /// The static gas cost of this instruction.
+    #[getter]
+    fn gas(&self) -> u64 {
+        self.insn.static_gas()
+    }
+
+    /// The operand bytes of a push (if applicable).
+    #[getter]
+    fn operand<'py>(&self, py: Python<'py>) -> Option<&'py PyBytes> {
+        match &self.insn {
+            Instruction::PUSH(bs)|Instruction::PUSHPAD(bs,_) => Some(PyBytes::new(py,bs)),
+            _ => None
+        }
+    }
+
+    fn __str__(&self) -> String {
+        self.insn.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Instruction({:#x}, {})",self.pc,self.insn)
+    }
+}
+
+// ============================================================================
+// Block
+// ============================================================================
+
+#[pyclass(name = "Block")]
+#[derive(Clone)]
+pub struct PyBlock {
+    #[pyo3(get)]
+    start: usize,
+    #[pyo3(get)]
+    end: usize,
+    #[pyo3(get)]
+    reachable: bool,
+    /// Indices of the blocks to which this block can transfer control.
+    #[pyo3(get)]
+    successors: Vec<usize>
+}
+
+#[pymethods]
+impl PyBlock {
+    fn __repr__(&self) -> String {
+        format!("Block({:#x}, {:#x})",self.start,self.end)
+    }
+}
+
+// ============================================================================
+// Disassembly
+// ============================================================================
+
+#[pyclass(name = "Disassembly")]
+pub struct PyDisassembly {
+    #[pyo3(get)]
+    instructions: Vec<PyInstruction>,
+    /// The blocks of the control-flow graph (in order).
+    #[pyo3(get)]
+    blocks: Vec<PyBlock>,
+    #[pyo3(get)]
+    diagnostics: Vec<String>
+}
+
+#[pymethods]
+impl PyDisassembly {
+    #[new]
+    fn new(bytes: &[u8]) -> Self {
+        let disasm : Disassembly<CfaState> = Disassembly::new(bytes).build();
+        // Extract instructions
+        let mut instructions = Vec::new();
+        let mut pc = 0;
+        for insn in disasm.to_vec() {
+            let len = insn.length(&[]);
+            instructions.push(PyInstruction{insn,pc});
+            pc = pc + len;
+        }
+        // Extract control-flow graph
+        let blocks = disasm.blocks().iter().enumerate().map(|(i,b)| {
+            let reachable = disasm.is_block_reachable(i);
+            let successors = if reachable { disasm.successors(i) } else { Vec::new() };
+            PyBlock{start: b.start, end: b.end, reachable, successors}
+        }).collect();
+        //
+        let diagnostics = disasm.diagnostics().iter().map(|d| d.to_string()).collect();
+        PyDisassembly{instructions, blocks, diagnostics}
+    }
+
+    /// Disassemble a hex string.
+    #[staticmethod]
+    fn from_hex(hex: &str) -> PyResult<Self> {
+        match hex.from_hex_string() {
+            Ok(bytes) => Ok(Self::new(&bytes)),
+            Err(e) => Err(PyValueError::new_err(e.to_string()))
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.instructions.len()
+    }
+}
+
+// ============================================================================
+// Compiler
+// ============================================================================
+
+/// Compile an IL program into bytes.
+#[pyfunction]
+#[pyo3(signature = (source, optimise=false))]
+fn compile<'py>(py: Python<'py>, source: &str, optimise: bool) -> PyResult<&'py PyBytes> {
+    match compile_bytes(source,optimise) {
+        Ok(bytes) => Ok(PyBytes::new(py,&bytes)),
+        Err(e) => Err(PyValueError::new_err(e))
+    }
+}
+
+/// Compile an IL program into bytes, or describe why it failed.  This
+/// does not require the Python interpreter.
+fn compile_bytes(source: &str, optimise: bool) -> Result<Vec<u8>,String> {
+    let options = Options{optimise, ..Default::default()};
+    compile_il(source,&options).map_err(|d| d.to_string())
+}
+
+// ============================================================================
+// Module
+// ============================================================================
+
+#[pymodule]
+fn evmil(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyInstruction>()?;
+    m.add_class::<PyBlock>()?;
+    m.add_class::<PyDisassembly>()?;
+    m.add_function(wrap_pyfunction!(compile,m)?)?;
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+/// These exercise the bindings without the Python interpreter (which
+/// an extension module does not link against).
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_01() {
+        assert_eq!(compile_bytes("stop;",false), Ok(vec![0x00]));
+        assert_eq!(compile_bytes("stop;",true), Ok(vec![0x00]));
+        assert!(compile_bytes("assert 1",false).unwrap_err().contains("P003"));
+    }
+
+    #[test]
+    fn test_disassemble_01() {
+        let d = PyDisassembly::new(&[0x60,0x04,0x56,0x00,0x5b,0x00]);
+        assert_eq!(d.__len__(), 5);
+        let pcs : Vec<usize> = d.instructions.iter().map(|i| i.pc).collect();
+        assert_eq!(pcs, vec![0,2,3,4,5]);
+        assert_eq!(d.instructions[0].opcode(), Some(0x60));
+        assert_eq!(d.instructions[0].length(), 2);
+        assert_eq!(d.instructions[1].__repr__(), "Instruction(0x2, JUMP)");
+        assert_eq!(d.blocks.len(), 3);
+        assert_eq!(d.blocks[0].successors, vec![2]);
+        assert!(!d.blocks[1].reachable);
+        assert!(d.blocks[1].successors.is_empty());
+        assert!(d.diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+use crate::{Bytecode,CfaState,Diagnostic,Disassembly};
+
+// ============================================================================
+// Cost Report
+// ============================================================================
+
+/// Summarises the size and estimated cost of a compiled `Bytecode`
+/// artifact.  Reports for two artifacts (e.g. from different runs of
+/// the compiler) can be compared to track the impact of changes.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+pub struct CostReport {
+    /// Size of the encoded bytecode (in bytes).
+    pub bytes: usize,
+    /// Number of instructions in the bytecode.
+    pub instructions: usize,
+    /// Sum of the static gas costs of all instructions.  This is not
+    /// the cost of any particular execution but, since every
+    /// instruction contributes, it tracks changes in cost.
+    pub gas: u64
+}
+
+impl CostReport {
+    /// Construct a cost report for a given bytecode sequence.  This
+    /// can fail if the bytecode cannot be encoded.
+    pub fn new(code: &Bytecode) -> Result<Self,Diagnostic> {
+        let bytes = code.to_bytes()?.len();
+        let instructions = code.instructions().len();
+        let gas = code.instructions().iter().map(|i| i.static_gas()).sum();
+        //
+        Ok(CostReport{bytes,instructions,gas})
+    }
+
+    /// Construct a cost report for each function dispatched to by a
+    /// given bytecode sequence (see `Disassembly::functions()`),
+    /// ordered by entry point.  The cost of a function covers every
+    /// block reachable from its entry, hence blocks shared between
+    /// functions (e.g. internal functions) count towards each.
+    pub fn functions(code: &Bytecode) -> Result<Vec<FunctionCost>,Diagnostic> {
+        let bytes = code.to_bytes()?;
+        let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_functions().build();
+        let mut fns = disasm.functions();
+        fns.sort_by_key(|f| f.entry);
+        //
+        Ok(fns.into_iter().map(|f| {
+            let mut cost = CostReport::default();
+            for id in disasm.function_blocks(&f) {
+                let blk = disasm.blocks()[id];
+                let mut pc = blk.start;
+                while pc < blk.end {
+                    let insn = disasm.decode(pc);
+                    cost.instructions += 1;
+                    cost.gas += insn.static_gas();
+                    pc = pc + insn.length(&[]);
+                }
+                cost.bytes += blk.end - blk.start;
+            }
+            FunctionCost{selector: f.selector, entry: f.entry, cost}
+        }).collect())
+    }
+
+    /// Determine the change from this report to another (i.e. where
+    /// positive values indicate the other is larger).
+    pub fn delta(&self, other: &CostReport) -> CostDelta {
+        CostDelta {
+            bytes: (other.bytes as i64) - (self.bytes as i64),
+            instructions: (other.instructions as i64) - (self.instructions as i64),
+            gas: (other.gas as i64) - (self.gas as i64)
+        }
+    }
+}
+
+/// Identifies the difference between two cost reports.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct CostDelta {
+    pub bytes: i64,
+    pub instructions: i64,
+    pub gas: i64
+}
+
+impl fmt::Display for CostDelta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"bytes {:+}, instructions {:+}, gas {:+}",self.bytes,self.instructions,self.gas)
+    }
+}
+
+/// Compare two bytecode artifacts, producing the change in cost from
+/// `before` to `after`.
+pub fn compare_costs(before: &Bytecode, after: &Bytecode) -> Result<CostDelta,Diagnostic> {
+    let r1 = CostReport::new(before)?;
+    let r2 = CostReport::new(after)?;
+    Ok(r1.delta(&r2))
+}
+
+// ============================================================================
+// Function Costs
+// ============================================================================
+
+/// The estimated cost of a function dispatched to by a compiled
+/// artifact (see `CostReport::functions()`).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct FunctionCost {
+    /// The four byte selector of the function, or `None` for the
+    /// `receive` (or `fallback`) function.
+    pub selector: Option<Vec<u8>>,
+    /// Offset of the function's entry point.
+    pub entry: usize,
+    /// The cost of every block reachable from the function's entry.
+    pub cost: CostReport
+}
+
+/// The change in cost of a function between two compiled artifacts
+/// (see `compare_function_costs()`).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct FunctionDelta {
+    /// The four byte selector of the function, or `None` for the
+    /// `receive` (or `fallback`) function.
+    pub selector: Option<Vec<u8>>,
+    pub delta: CostDelta
+}
+
+/// Compare the functions of two bytecode artifacts, producing the
+/// change in cost of each from `before` to `after`.  Functions are
+/// matched by selector and ordered by selector (with the `receive`
+/// or `fallback` function first).  Since several functions may lack
+/// a selector (e.g. both `receive` and `fallback`), these are matched
+/// by their order of entry.  A function found in only one artifact
+/// is compared against an empty report.
+pub fn compare_function_costs(before: &Bytecode, after: &Bytecode) -> Result<Vec<FunctionDelta>,Diagnostic> {
+    let mut costs : BTreeMap<FunctionKey,(CostReport,CostReport)> = BTreeMap::new();
+    for (key,cost) in function_keys(CostReport::functions(before)?) {
+        costs.entry(key).or_default().0 = cost;
+    }
+    for (key,cost) in function_keys(CostReport::functions(after)?) {
+        costs.entry(key).or_default().1 = cost;
+    }
+    Ok(costs.into_iter().map(|((selector,_),(r1,r2))| FunctionDelta{selector, delta: r1.delta(&r2)}).collect())
+}
+
+/// Identifies a function by its selector, along with the number of
+/// functions before it (by entry) having the same selector.
+type FunctionKey = (Option<Vec<u8>>,usize);
+
+/// Determine the key of each function (ordered by entry).
+fn function_keys(fns: Vec<FunctionCost>) -> Vec<(FunctionKey,CostReport)> {
+    let mut keys : Vec<(FunctionKey,CostReport)> = Vec::new();
+    for f in fns {
+        let n = keys.iter().filter(|((s,_),_)| *s == f.selector).count();
+        keys.push(((f.selector,n),f.cost));
+    }
+    keys
+}
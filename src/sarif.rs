@@ -0,0 +1,113 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde_json::{json,Value};
+use crate::{Diagnostic,Location,Report,Severity};
+
+/// The version of SARIF produced.
+const SARIF_VERSION : &str = "2.1.0";
+
+/// The schema for the version of SARIF produced.
+const SARIF_SCHEMA : &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+// ============================================================================
+// SARIF
+// ============================================================================
+
+/// Convert diagnostics into a SARIF log (i.e. the Static Analysis
+/// Results Interchange Format), as understood by code review tools.
+/// Each diagnostic becomes a result located within the artifact at
+/// the given `uri`.  Byte offsets are given as one byte regions, and
+/// source spans as character regions, whilst diagnostics without a
+/// location are given no location.  Each distinct code becomes a
+/// rule, in order of first appearance.
+pub fn to_sarif(diagnostics: &[Diagnostic], uri: &str) -> Value {
+    let mut rules : Vec<&Diagnostic> = Vec::new();
+    let mut results = Vec::new();
+    for d in diagnostics {
+        let index = match rules.iter().position(|r| r.code == d.code) {
+            Some(i) => i,
+            None => {
+                rules.push(d);
+                rules.len() - 1
+            }
+        };
+        let mut result = json!({
+            "ruleId": d.code,
+            "ruleIndex": index,
+            "level": level(d.severity),
+            "message": {"text": d.message}
+        });
+        if let Some(region) = region(&d.location) {
+            result["locations"] = json!([{
+                "physicalLocation": {
+                    "artifactLocation": {"uri": uri},
+                    "region": region
+                }
+            }]);
+        }
+        if !d.notes.is_empty() {
+            result["properties"] = json!({"notes": d.notes});
+        }
+        results.push(result);
+    }
+    let rules : Vec<Value> = rules.iter().map(|d| json!({
+        "id": d.code,
+        "shortDescription": {"text": d.message},
+        "defaultConfiguration": {"level": level(d.severity)}
+    })).collect();
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "evmil",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules
+                }
+            },
+            "artifacts": [{"location": {"uri": uri}}],
+            "results": results
+        }]
+    })
+}
+
+impl Report {
+    /// Convert the diagnostics of this report into a SARIF log (see
+    /// `to_sarif()`), where `uri` identifies the bytecode analysed.
+    pub fn to_sarif(&self, uri: &str) -> Value {
+        to_sarif(&self.diagnostics,uri)
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Determine the SARIF level corresponding to a given severity.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Note => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error"
+    }
+}
+
+/// Determine the SARIF region corresponding to a given location (if
+/// any).
+fn region(location: &Location) -> Option<Value> {
+    match location {
+        Location::Unknown|Location::Term(_) => None,
+        Location::Span(r) => Some(json!({"charOffset": r.start, "charLength": r.end - r.start})),
+        Location::Offset(pc) => Some(json!({"byteOffset": pc, "byteLength": 1}))
+    }
+}
@@ -0,0 +1,123 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::fmt;
+use std::sync::OnceLock;
+use crate::{AbstractState,Disassembly,Instruction};
+
+// ============================================================================
+// Shared Disassembly
+// ============================================================================
+
+/// A finished disassembly which can no longer be changed, and which
+/// can be queried concurrently from many threads (e.g. behind an
+/// `Arc`).  Commonly requested information (e.g. the decoded
+/// instructions, and the control-flow graph) is computed on first
+/// use and then cached.  This is `Send` and `Sync` whenever the
+/// abstract state is.
+pub struct SharedDisassembly<'a,T = ()> {
+    disasm: Disassembly<'a,T>,
+    /// Every instruction (with its offset) in order.
+    insns: OnceLock<Vec<(usize,Instruction)>>,
+    /// Starting offset of each block in order.
+    starts: OnceLock<Vec<usize>>,
+    /// Successors of each block.
+    successors: OnceLock<Vec<Vec<usize>>>
+}
+
+impl<'a,T> SharedDisassembly<'a,T>
+where T:AbstractState+fmt::Display {
+    /// Get the underlying disassembly (e.g. for printing).
+    pub fn disassembly(&self) -> &Disassembly<'a,T> {
+        &self.disasm
+    }
+
+    /// Get every instruction (with its offset) in order.  Unreachable
+    /// blocks are given as data.
+    pub fn instructions(&self) -> &[(usize,Instruction)] {
+        self.insns.get_or_init(|| {
+            let mut insns = Vec::new();
+            for (i,blk) in self.disasm.blocks().iter().enumerate() {
+                if !self.disasm.is_block_reachable(i) {
+                    let data = self.disasm.read_bytes(blk.start,blk.end);
+                    insns.push((blk.start,Instruction::DATA(data)));
+                    continue;
+                }
+                let mut pc = blk.start;
+                while pc < blk.end {
+                    let insn = self.disasm.decode(pc);
+                    let len = insn.length(&[]);
+                    insns.push((pc,insn));
+                    pc += len;
+                }
+            }
+            insns
+        })
+    }
+
+    /// Get the instruction beginning at a given offset (if any).
+    pub fn instruction_at(&self, pc: usize) -> Option<&Instruction> {
+        let insns = self.instructions();
+        insns.binary_search_by_key(&pc,|(o,_)| *o).ok().map(|i| &insns[i].1)
+    }
+
+    /// Determine the block enclosing a given offset (if any).
+    pub fn block_of(&self, pc: usize) -> Option<usize> {
+        let starts = self.starts.get_or_init(|| {
+            self.disasm.blocks().iter().map(|b| b.start).collect()
+        });
+        let i = match starts.binary_search(&pc) {
+            Ok(i) => i,
+            Err(0) => { return None; }
+            Err(i) => i - 1
+        };
+        self.disasm.blocks()[i].encloses(pc).then_some(i)
+    }
+
+    /// Get the state at a given program location.
+    pub fn get_state(&self, loc: usize) -> T {
+        let id = self.block_of(loc).expect("invalid bytecode address");
+        // Unreachable blocks are not decoded
+        if !self.disasm.is_block_reachable(id) { return self.disasm.get_state(loc); }
+        let blk = &self.disasm.blocks()[id];
+        let insns = self.instructions();
+        let mut i = insns.partition_point(|(o,_)| *o < blk.start);
+        let mut ctx = self.disasm.get_state(blk.start);
+        // Reconstruct state
+        while i < insns.len() && insns[i].0 < loc {
+            ctx = self.disasm.assume_at(insns[i].0,ctx).transfer_in(&insns[i].1,self.disasm.bytes());
+            i += 1;
+        }
+        self.disasm.assume_at(loc,ctx)
+    }
+
+    /// Get the blocks to which a given block can transfer control.
+    pub fn successors(&self, id: usize) -> &[usize] {
+        let succs = self.successors.get_or_init(|| {
+            (0..self.disasm.blocks().len()).map(|i| self.disasm.successors(i)).collect()
+        });
+        &succs[id]
+    }
+}
+
+impl<'a,T> Disassembly<'a,T>
+where T:AbstractState {
+    /// Freeze this (built) disassembly so that it can be shared
+    /// between threads.
+    pub fn freeze(self) -> SharedDisassembly<'a,T> {
+        SharedDisassembly {
+            disasm: self,
+            insns: OnceLock::new(),
+            starts: OnceLock::new(),
+            successors: OnceLock::new()
+        }
+    }
+}
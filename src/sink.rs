@@ -0,0 +1,120 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use crate::{Bytecode,Diagnostic,Instruction,ToHexString};
+
+// ============================================================================
+// Code Sinks
+// ============================================================================
+
+/// A destination for encoded instructions.  This allows a `Bytecode`
+/// sequence to be encoded once, whilst producing several outputs
+/// (e.g. using a pair of sinks).
+pub trait CodeSink {
+    /// Accept an instruction located at a given byte offset, along
+    /// with its encoding.
+    fn emit(&mut self, offset: usize, insn: &Instruction, bytes: &[u8]);
+}
+
+/// Collects the raw bytes.
+impl CodeSink for Vec<u8> {
+    fn emit(&mut self, _offset: usize, _insn: &Instruction, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// Forwards every instruction to both sinks.
+impl<S:CodeSink,T:CodeSink> CodeSink for (S,T) {
+    fn emit(&mut self, offset: usize, insn: &Instruction, bytes: &[u8]) {
+        self.0.emit(offset,insn,bytes);
+        self.1.emit(offset,insn,bytes);
+    }
+}
+
+impl<S:CodeSink+?Sized> CodeSink for &mut S {
+    fn emit(&mut self, offset: usize, insn: &Instruction, bytes: &[u8]) {
+        (**self).emit(offset,insn,bytes);
+    }
+}
+
+/// Writes the bytes as a hex string (prefixed with `0x`).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct HexSink(pub String);
+
+impl HexSink {
+    pub fn new() -> Self {
+        HexSink(String::from("0x"))
+    }
+}
+
+impl Default for HexSink {
+    fn default() -> Self { Self::new() }
+}
+
+impl CodeSink for HexSink {
+    fn emit(&mut self, _offset: usize, _insn: &Instruction, bytes: &[u8]) {
+        for b in bytes {
+            write!(self.0,"{:02x}",b).unwrap();
+        }
+    }
+}
+
+/// Writes one line per instruction, giving its offset, its encoding
+/// and the instruction itself.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct AssemblySink(pub String);
+
+impl CodeSink for AssemblySink {
+    fn emit(&mut self, offset: usize, insn: &Instruction, bytes: &[u8]) {
+        writeln!(self.0,"{:#06x}: {} {}",offset,bytes.to_hex_string(),insn).unwrap();
+    }
+}
+
+/// Measures the number of bytes (and instructions) without storing
+/// them.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+pub struct SizeSink {
+    pub bytes: usize,
+    pub instructions: usize
+}
+
+impl CodeSink for SizeSink {
+    fn emit(&mut self, _offset: usize, _insn: &Instruction, bytes: &[u8]) {
+        self.bytes = self.bytes + bytes.len();
+        self.instructions = self.instructions + 1;
+    }
+}
+
+// ============================================================================
+// Encoding
+// ============================================================================
+
+impl Bytecode {
+    /// Encode this sequence of bytecode instructions into a given
+    /// sink.  This can fail in the same ways as `to_bytes()`.
+    pub fn encode_into<S:CodeSink>(&self, mut sink: S) -> Result<(),Diagnostic> {
+        let offsets = self.determine_offsets();
+        let mut bytes = Vec::new();
+        let mut offset = 0;
+        //
+        for insn in self.instructions() {
+            bytes.clear();
+            insn.encode(&offsets,&mut bytes).map_err(|e| Diagnostic::from(e).with_offset(offset))?;
+            sink.emit(offset,insn,&bytes);
+            offset = offset + bytes.len();
+        }
+        //
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::collections::BTreeMap;
+use core::fmt;
+use crate::{AbstractState,Bytecode,Disassembly,Function,Instruction::*};
+use crate::util::from_be_bytes;
+
+// ============================================================================
+// Slicing
+// ============================================================================
+
+impl<'a,T:AbstractState+fmt::Display> Disassembly<'a,T> {
+    /// Extract the blocks of a given function as a standalone bytecode
+    /// sequence, starting from its entry point.  Jumps are relinked
+    /// using labels, where any push of the location of a `JUMPDEST`
+    /// is assumed to give a jump target.  Jumps to a `JUMPDEST`
+    /// outside the function are redirected to a stub which stops
+    /// execution.  Other uses of code locations (e.g. by `CODECOPY`)
+    /// are not relinked.
+    pub fn extract_function(&self, f: &Function) -> Bytecode {
+        let blocks = self.blocks();
+        let mut code = Bytecode::new();
+        // Order blocks with the entry first
+        let mut ids = self.function_blocks(f);
+        if let Some(i) = ids.iter().position(|id| blocks[*id].start == f.entry) {
+            let entry = ids.remove(i);
+            ids.insert(0,entry);
+        }
+        // Allocate a label for every block in the function
+        let mut labels = BTreeMap::new();
+        for id in &ids {
+            labels.insert(blocks[*id].start,code.fresh_label());
+        }
+        let mut stubs = BTreeMap::new();
+        //
+        for (i,id) in ids.iter().enumerate() {
+            let blk = &blocks[*id];
+            let mut pc = blk.start;
+            // Every block begins with a JUMPDEST, since it may be
+            // reached by a jump after relinking.
+            code.push(JUMPDEST(labels[&blk.start]));
+            while pc < blk.end {
+                let insn = self.decode(pc);
+                pc = pc + insn.length(&[]);
+                match insn {
+                    JUMPDEST(_) => {}
+                    PUSH(bs) if bs.len() <= 8 && self.is_jumpdest(from_be_bytes(&bs) as usize) => {
+                        let target = from_be_bytes(&bs) as usize;
+                        let lab = match labels.get(&target) {
+                            Some(lab) => *lab,
+                            None => *stubs.entry(target).or_insert_with(|| code.fresh_label())
+                        };
+                        code.push(PUSHL(lab));
+                    }
+                    _ => code.push(insn)
+                }
+            }
+            // Preserve fall through (if necessary)
+            if self.falls_through(*id) && ids.get(i+1) != Some(&(id+1)) {
+                code.push(PUSHL(labels[&blocks[id+1].start]));
+                code.push(JUMP);
+            }
+        }
+        // Append stubs
+        for lab in stubs.values() {
+            code.push(JUMPDEST(*lab));
+            code.push(STOP);
+        }
+        //
+        code
+    }
+}
@@ -0,0 +1,238 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::collections::{BTreeMap,BTreeSet};
+use alloc::vec::Vec;
+use core::ops::Range;
+use crate::{AbstractState,Block,BlockVisitor,Disassembly,Instruction,InstructionVisitor,InvalidJump,Instruction::*};
+
+// ============================================================================
+// Statistics
+// ============================================================================
+
+/// Summary statistics for the reachable code of a disassembly, as
+/// used for studying large numbers of contracts.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct Statistics {
+    /// Number of occurrences of each opcode.
+    pub opcodes: BTreeMap<u8,usize>,
+    /// Number of occurrences of each value pushed.
+    pub push_values: BTreeMap<Vec<u8>,usize>,
+    /// Number of instructions in each reachable block (in order).
+    pub block_sizes: Vec<usize>,
+    /// Number of bytes in unreachable blocks (i.e. data).
+    pub data_bytes: usize,
+    /// Number of distinct function selectors tested by the dispatcher.
+    /// That is, four byte values which are pushed and then compared
+    /// using `EQ`.
+    pub selectors: usize
+}
+
+impl Statistics {
+    /// Determine the total number of reachable instructions.
+    pub fn instructions(&self) -> usize {
+        self.block_sizes.iter().sum()
+    }
+
+    /// Determine the number of times a given opcode occurs.
+    pub fn count(&self, opcode: u8) -> usize {
+        self.opcodes.get(&opcode).copied().unwrap_or(0)
+    }
+
+    /// Add the statistics of another disassembly to these (e.g. to
+    /// summarise many contracts).  Selectors are counted separately
+    /// for each, even when they coincide.
+    pub fn merge(&mut self, other: &Statistics) {
+        for (opcode,n) in &other.opcodes {
+            *self.opcodes.entry(*opcode).or_insert(0) += n;
+        }
+        for (value,n) in &other.push_values {
+            *self.push_values.entry(value.clone()).or_insert(0) += n;
+        }
+        self.block_sizes.extend_from_slice(&other.block_sizes);
+        self.data_bytes += other.data_bytes;
+        self.selectors += other.selectors;
+    }
+}
+
+impl<'a,T:AbstractState> Disassembly<'a,T> {
+    /// Compute statistics for the reachable code of this disassembly.
+    pub fn statistics(&self) -> Statistics {
+        let mut collector = Collector{bytes: self.bytes(), stats: Statistics::default(), selectors: BTreeSet::new(), size: 0, last: None};
+        self.visit(&mut collector);
+        collector.stats.selectors = collector.selectors.len();
+        collector.stats
+    }
+}
+
+/// Collects statistics whilst visiting a disassembly.
+struct Collector<'a> {
+    bytes: &'a [u8],
+    stats: Statistics,
+    selectors: BTreeSet<Vec<u8>>,
+    /// Number of instructions in the current block.
+    size: usize,
+    /// Last instruction in the current block.
+    last: Option<Instruction>
+}
+
+impl<'a,T> InstructionVisitor<T> for Collector<'a> {
+    fn visit_instruction(&mut self, pc: usize, insn: &Instruction, _state: &T) {
+        let opcode = self.bytes.get(pc).copied().unwrap_or(0);
+        *self.stats.opcodes.entry(opcode).or_insert(0) += 1;
+        if let PUSH(bytes)|PUSHPAD(bytes,_) = insn {
+            *self.stats.push_values.entry(bytes.clone()).or_insert(0) += 1;
+        }
+        if let (Some(PUSH(bytes)),EQ) = (&self.last,insn) {
+            if bytes.len() == 4 { self.selectors.insert(bytes.clone()); }
+        }
+        self.size = self.size + 1;
+        self.last = Some(insn.clone());
+    }
+}
+
+impl<'a,T> BlockVisitor<T> for Collector<'a> {
+    fn enter_block(&mut self, _id: usize, _blk: &Block) {
+        self.size = 0;
+        self.last = None;
+    }
+
+    fn exit_block(&mut self, _id: usize, _blk: &Block) {
+        self.stats.block_sizes.push(self.size);
+    }
+
+    fn visit_data(&mut self, _id: usize, _blk: &Block, bytes: &[u8]) {
+        self.stats.data_bytes += bytes.len();
+    }
+}
+
+// ============================================================================
+// Coverage
+// ============================================================================
+
+/// Classifies the bytes of a disassembly as code, data or unknown, as
+/// used for monitoring the quality of the analysis.  Code is anything
+/// in a reachable block, whilst data is anything hinted as data or in
+/// an unreachable block.  However, an unreachable block beginning
+/// with a `JUMPDEST` is unknown when some reachable jump could not be
+/// resolved, since it may be the target of that jump.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct Coverage {
+    /// Number of bytes classified as code.
+    pub code: usize,
+    /// Number of bytes classified as data.
+    pub data: usize,
+    /// Number of bytes which could be either code or data.
+    pub unknown: usize,
+    /// Regions of bytes not covered by any block.  This should always
+    /// be empty, and indicates a problem with block scanning
+    /// otherwise.
+    pub gaps: Vec<Range<usize>>
+}
+
+impl Coverage {
+    /// Determine the total number of bytes covered.
+    pub fn total(&self) -> usize {
+        self.code + self.data + self.unknown + self.gaps.iter().map(|g| g.len()).sum::<usize>()
+    }
+
+    /// Determine the fraction of bytes classified as code.
+    pub fn code_fraction(&self) -> f64 {
+        self.fraction(self.code)
+    }
+
+    /// Determine the fraction of bytes classified as data.
+    pub fn data_fraction(&self) -> f64 {
+        self.fraction(self.data)
+    }
+
+    /// Determine the fraction of bytes which could not be classified.
+    pub fn unknown_fraction(&self) -> f64 {
+        self.fraction(self.unknown)
+    }
+
+    /// Add the classification of another disassembly to this one.
+    /// Gaps are not added, since they are offsets within the other.
+    pub fn merge(&mut self, other: &Coverage) {
+        self.code += other.code;
+        self.data += other.data;
+        self.unknown += other.unknown;
+    }
+
+    fn fraction(&self, n: usize) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            t => (n as f64) / (t as f64)
+        }
+    }
+}
+
+/// Classifies the bytes of a block (see `Coverage`).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum ByteClass {
+    Code,
+    Data,
+    Unknown
+}
+
+impl ByteClass {
+    /// Get the name of this classification (e.g. `"code"`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            ByteClass::Code => "code",
+            ByteClass::Data => "data",
+            ByteClass::Unknown => "unknown"
+        }
+    }
+}
+
+impl<'a,T:AbstractState> Disassembly<'a,T> {
+    /// Classify the bytes of this disassembly as code, data or
+    /// unknown.  This should be applied after `build()`.
+    pub fn coverage(&self) -> Coverage {
+        let mut coverage = Coverage::default();
+        let n = self.bytes().len();
+        let classes = self.classify();
+        let mut pc = 0;
+        //
+        for (blk,class) in self.blocks().iter().zip(classes) {
+            // Record bytes skipped over
+            if blk.start > pc { coverage.gaps.push(pc..blk.start); }
+            // Only count bytes which exist (e.g. not a truncated push)
+            let size = core::cmp::min(blk.end,n).saturating_sub(core::cmp::max(blk.start,pc));
+            match class {
+                ByteClass::Code => coverage.code += size,
+                ByteClass::Data => coverage.data += size,
+                ByteClass::Unknown => coverage.unknown += size
+            }
+            pc = core::cmp::max(pc,blk.end);
+        }
+        if pc < n { coverage.gaps.push(pc..n); }
+        //
+        coverage
+    }
+
+    /// Classify the bytes of each block (in order) as code, data or
+    /// unknown.  This should be applied after `build()`.
+    pub fn classify(&self) -> Vec<ByteClass> {
+        let unresolved = self.invalid_jumps().iter().any(|j| matches!(j,InvalidJump::Unknown{..}));
+        (0..self.blocks().len()).map(|i| {
+            let start = self.blocks()[i].start;
+            if self.is_block_reachable(i) {
+                ByteClass::Code
+            } else if unresolved && !self.is_data(start) && self.is_jumpdest(start) {
+                ByteClass::Unknown
+            } else {
+                ByteClass::Data
+            }
+        }).collect()
+    }
+}
@@ -0,0 +1,97 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::vec::Vec;
+use crate::{AbstractState,Disassembly,Instruction,Instruction::*};
+
+// ============================================================================
+// Block Summary
+// ============================================================================
+
+/// Summarises the effect of executing a block from start to finish,
+/// independently of the state in which it is entered.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+pub struct BlockSummary {
+    /// Net change in stack height.
+    pub stack_delta: isize,
+    /// Minimum stack height required on entry to avoid underflow.
+    pub stack_needed: usize,
+    /// Whether the block reads from storage.
+    pub reads_storage: bool,
+    /// Whether the block writes to storage.
+    pub writes_storage: bool,
+    /// Whether the block reads from memory.
+    pub reads_memory: bool,
+    /// Whether the block writes to memory.
+    pub writes_memory: bool,
+    /// Whether the block can terminate execution (e.g. via `STOP`,
+    /// `RETURN`, `REVERT`, etc).
+    pub can_terminate: bool
+}
+
+impl BlockSummary {
+    /// Update this summary to account for a given instruction being
+    /// executed at the end of the block.
+    fn append(&mut self, insn: &Instruction) {
+        let (needed,produced) = insn.stack_effect();
+        // Items needed beyond those already produced by the block.
+        let deficit = (needed as isize) - self.stack_delta;
+        if deficit > 0 {
+            self.stack_needed = core::cmp::max(self.stack_needed,deficit as usize);
+        }
+        self.stack_delta = self.stack_delta - (needed as isize) + (produced as isize);
+        //
+        match insn {
+            SLOAD => self.reads_storage = true,
+            SSTORE => self.writes_storage = true,
+            MLOAD|KECCAK256|LOG(_)|CREATE|CREATE2 => self.reads_memory = true,
+            MSTORE|MSTORE8|CALLDATACOPY|CODECOPY|EXTCODECOPY|RETURNDATACOPY => {
+                self.writes_memory = true
+            }
+            MCOPY => {
+                self.reads_memory = true;
+                self.writes_memory = true;
+            }
+            CALL|CALLCODE|DELEGATECALL|STATICCALL => {
+                self.reads_memory = true;
+                self.writes_memory = true;
+            }
+            RETURN|REVERT => {
+                self.reads_memory = true;
+                self.can_terminate = true;
+            }
+            STOP|INVALID|SELFDESTRUCT|DATA(_) => self.can_terminate = true,
+            _ => {}
+        }
+    }
+}
+
+impl<'a,T:AbstractState> Disassembly<'a,T> {
+    /// Summarise a given block.
+    pub fn summary(&self, id: usize) -> BlockSummary {
+        let blk = &self.blocks()[id];
+        let mut summary = BlockSummary::default();
+        let mut pc = blk.start;
+        //
+        while pc < blk.end {
+            let insn = self.decode(pc);
+            summary.append(&insn);
+            pc = pc + insn.length(&[]);
+        }
+        //
+        summary
+    }
+
+    /// Summarise every block (in order).
+    pub fn summaries(&self) -> Vec<BlockSummary> {
+        (0..self.blocks().len()).map(|i| self.summary(i)).collect()
+    }
+}
@@ -1,33 +1,249 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
 // ============================================================================
 // Terms
 // ============================================================================
 
-#[derive(Clone)]
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
+#[non_exhaustive]
 pub enum Term {
     // Statements
     Assert(Box<Term>),
+    /// A condition which is assumed to hold at this point.  This
+    /// compiles to nothing, but constrains the analysis of the
+    /// resulting bytecode (see `Bytecode::hints()`).
+    Assume(Box<Term>),
     Assignment(Box<Term>,Box<Term>),
     Goto(String),
     IfGoto(Box<Term>,String),
     Label(String),
+    Require(Box<Term>),
     Succeed(Vec<Term>),
+    Switch(Box<Term>,Vec<(Term,String)>),
     Revert(Vec<Term>),
     Fail,
     Stop,
+    /// Declares a fixed-size array of words in a given region (e.g.
+    /// `memory arr[10];`).  Arrays are allocated one after another
+    /// from the start of their region (i.e. memory offset `0`, using
+    /// 32 bytes per element, or storage slot `0`), in order of
+    /// declaration.  Hence, a region holding arrays cannot also be
+    /// accessed directly (e.g. `storage[1]`), since they would
+    /// overlap.
+    Array(Region,String,usize),
     // Expressions
     Binary(BinOp,Box<Term>,Box<Term>),
     ArrayAccess(Box<Term>,Box<Term>),
+    /// A field of a word given by its bit offset and width (e.g.
+    /// `storage[0][8:16]` is the 16 bits starting from bit 8).  Bits
+    /// are numbered from the least significant.
+    BitField(Box<Term>,usize,usize),
+    Intrinsic(Intrinsic,Vec<Term>),
+    Tuple(Vec<Term>),
     MemoryAccess(Region),
+    /// A declared array, which can only be indexed (e.g. `arr[i]`).
+    Variable(String),
     // Values
     Int(Vec<u8>),
     Hex(Vec<u8>),
 }
 
+impl Term {
+    /// Determine the number of words produced by this term.  Tuples
+    /// produce one word per (flattened) component, whilst statements
+    /// produce none.
+    pub fn width(&self) -> usize {
+        match self {
+            Term::Tuple(es) => es.iter().map(|e| e.width()).sum(),
+            Term::Binary(_,_,_)|Term::ArrayAccess(_,_)|Term::BitField(_,_,_)|Term::MemoryAccess(_) => 1,
+            Term::Intrinsic(_,_)|Term::Int(_)|Term::Hex(_) => 1,
+            _ => 0
+        }
+    }
+
+    /// Apply a given function to each immediate child of this term,
+    /// producing a term of the same kind.  Labels and operators are
+    /// left unchanged.
+    pub fn map_children<F:FnMut(Term)->Term>(self, mut f: F) -> Term {
+        let mut g = |t: Box<Term>| Box::new(f(*t));
+        match self {
+            Term::Assert(e) => Term::Assert(g(e)),
+            Term::Assume(e) => Term::Assume(g(e)),
+            Term::Assignment(l,r) => {
+                let l = g(l);
+                Term::Assignment(l,g(r))
+            }
+            Term::IfGoto(e,l) => Term::IfGoto(g(e),l),
+            Term::Require(e) => Term::Require(g(e)),
+            Term::Succeed(es) => Term::Succeed(es.into_iter().map(f).collect()),
+            Term::Switch(e,cases) => {
+                let e = g(e);
+                Term::Switch(e,cases.into_iter().map(|(v,l)| (f(v),l)).collect())
+            }
+            Term::Revert(es) => Term::Revert(es.into_iter().map(f).collect()),
+            Term::Binary(bop,l,r) => {
+                let l = g(l);
+                Term::Binary(bop,l,g(r))
+            }
+            Term::ArrayAccess(s,i) => {
+                let s = g(s);
+                Term::ArrayAccess(s,g(i))
+            }
+            Term::BitField(e,offset,width) => Term::BitField(g(e),offset,width),
+            Term::Intrinsic(i,es) => Term::Intrinsic(i,es.into_iter().map(f).collect()),
+            Term::Tuple(es) => Term::Tuple(es.into_iter().map(f).collect()),
+            t => t
+        }
+    }
+
+    /// Get the immediate children of this term, in the same order as
+    /// for `map_children()`.
+    pub fn children(&self) -> Vec<&Term> {
+        match self {
+            Term::Assert(e)|Term::Assume(e)|Term::IfGoto(e,_)|Term::Require(e) => vec![e],
+            Term::BitField(e,_,_) => vec![e],
+            Term::Assignment(l,r)|Term::Binary(_,l,r)|Term::ArrayAccess(l,r) => vec![l,r],
+            Term::Succeed(es)|Term::Revert(es)|Term::Intrinsic(_,es)|Term::Tuple(es) => es.iter().collect(),
+            Term::Switch(e,cases) => {
+                let mut children = vec![e.as_ref()];
+                children.extend(cases.iter().map(|(v,_)| v));
+                children
+            }
+            _ => Vec::new()
+        }
+    }
+
+    /// Get the `n`th term in a pre-order traversal of this term, where
+    /// this term itself is the `0`th.
+    pub fn subterm(&self, n: usize) -> Option<&Term> {
+        let mut worklist = vec![self];
+        let mut i = 0;
+        while let Some(t) = worklist.pop() {
+            if i == n { return Some(t); }
+            i += 1;
+            worklist.extend(t.children().into_iter().rev());
+        }
+        None
+    }
+
+    /// Rewrite this term bottom-up using a given rewrite.  That is,
+    /// the children of a term are rewritten before the term itself,
+    /// and each term is rewritten repeatedly until no further changes
+    /// apply.  Hence, the rewrite must eventually stop producing
+    /// changes, otherwise this will not terminate.
+    pub fn rewrite<R:Rewrite+?Sized>(self, r: &mut R) -> Term {
+        let mut term = self.map_children(|t| t.rewrite(r));
+        while let Some(t) = r.apply(&term) {
+            term = t.map_children(|c| c.rewrite(r));
+        }
+        term
+    }
+}
+
+/// Prints a term using the syntax of the IL.  Binary expressions are
+/// always bracketed, so the result parses back to the same term.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Term::Assert(e) => write!(f,"assert {};",e),
+            Term::Assume(e) => write!(f,"assume {};",e),
+            Term::Assignment(l,r) => write!(f,"{} = {};",l,r),
+            Term::Goto(l) => write!(f,"goto {};",l),
+            Term::IfGoto(e,l) => write!(f,"if {} goto {};",e,l),
+            Term::Label(l) => write!(f,".{}",l),
+            Term::Require(e) => write!(f,"require {};",e),
+            Term::Succeed(es) => {
+                write!(f,"succeed")?;
+                write_list(f," ",es)?;
+                write!(f,";")
+            }
+            Term::Switch(e,cases) => {
+                write!(f,"switch {} {{",e)?;
+                for (v,l) in cases { write!(f," {}: goto {};",v,l)?; }
+                write!(f," }}")
+            }
+            Term::Revert(es) => {
+                write!(f,"revert")?;
+                write_list(f," ",es)?;
+                write!(f,";")
+            }
+            Term::Fail => write!(f,"fail;"),
+            Term::Stop => write!(f,"stop;"),
+            Term::Array(r,name,len) => write!(f,"{} {}[{}];",r,name,len),
+            Term::Binary(bop,l,r) => write!(f,"({} {} {})",l,bop,r),
+            Term::ArrayAccess(s,i) => write!(f,"{}[{}]",s,i),
+            Term::BitField(e,offset,width) => write!(f,"{}[{}:{}]",e,offset,width),
+            Term::Intrinsic(Intrinsic::MsgSender,_) => write!(f,"msg.sender"),
+            Term::Intrinsic(Intrinsic::MsgValue,_) => write!(f,"msg.value"),
+            Term::Intrinsic(i,es) => {
+                write!(f,"{}(",i)?;
+                write_list(f,"",es)?;
+                write!(f,")")
+            }
+            Term::Tuple(es) => {
+                write!(f,"(")?;
+                write_list(f,"",es)?;
+                write!(f,")")
+            }
+            Term::MemoryAccess(r) => write!(f,"{}",r),
+            Term::Variable(name) => write!(f,"{}",name),
+            Term::Int(digits) => {
+                for d in digits { write!(f,"{}",d)?; }
+                Ok(())
+            }
+            Term::Hex(digits) => {
+                write!(f,"0x")?;
+                for d in digits { write!(f,"{:x}",d)?; }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Write a comma-separated list of terms (if any) after a given
+/// prefix.
+fn write_list(f: &mut fmt::Formatter, prefix: &str, terms: &[Term]) -> fmt::Result {
+    for (i,t) in terms.iter().enumerate() {
+        if i == 0 { write!(f,"{}",prefix)?; } else { write!(f,", ")?; }
+        write!(f,"{}",t)?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Rewriting
+// ============================================================================
+
+/// A transformation over terms, such as an IL-level optimisation.
+/// This is applied to one term at a time by `Term::rewrite()`, which
+/// takes care of visiting the children of each term.
+pub trait Rewrite {
+    /// Rewrite a given term (whose children have already been
+    /// rewritten), or return `None` if this does not apply.
+    fn apply(&mut self, term: &Term) -> Option<Term>;
+}
+
+impl<F:FnMut(&Term)->Option<Term>> Rewrite for F {
+    fn apply(&mut self, term: &Term) -> Option<Term> {
+        self(term)
+    }
+}
+
+/// Rewrite a sequence of statements using a given rewrite.
+pub fn rewrite_all<R:Rewrite+?Sized>(terms: Vec<Term>, r: &mut R) -> Vec<Term> {
+    terms.into_iter().map(|t| t.rewrite(r)).collect()
+}
+
 // ============================================================================
 // Binary Operators
 // ============================================================================
 
-#[derive(Copy,Clone,PartialEq,Debug)]
+#[derive(Copy,Clone,PartialEq,Eq,Hash,Debug)]
+#[non_exhaustive]
 pub enum BinOp {
     // Arithmetic
     Add,
@@ -35,6 +251,13 @@ pub enum BinOp {
     Divide,
     Multiply,
     Remainder,
+    Exponent,
+    // Bitwise
+    ShiftLeft,
+    ShiftRight,
+    /// Shift right, treating the left-hand side as a signed (two's
+    /// complement) word whose sign bit is extended (i.e. `SAR`).
+    SignedShiftRight,
     // Comparators
     Equals,
     NotEquals,
@@ -47,13 +270,130 @@ pub enum BinOp {
     LogicalOr
 }
 
+impl BinOp {
+    /// Determine the operator which gives the same result when its
+    /// operands are swapped.  That is, the operator `op'` such that
+    /// `x op y == y op' x` (if one exists).  For example, `<` becomes
+    /// `>`, whilst `+` is unchanged.  Logical connectives have no such
+    /// operator, since their operands are evaluated in order.
+    pub fn commute(&self) -> Option<BinOp> {
+        let bop = match self {
+            BinOp::Add => BinOp::Add,
+            BinOp::Multiply => BinOp::Multiply,
+            BinOp::Equals => BinOp::Equals,
+            BinOp::NotEquals => BinOp::NotEquals,
+            BinOp::LessThan => BinOp::GreaterThan,
+            BinOp::LessThanOrEquals => BinOp::GreaterThanOrEquals,
+            BinOp::GreaterThan => BinOp::LessThan,
+            BinOp::GreaterThanOrEquals => BinOp::LessThanOrEquals,
+            _ => { return None; }
+        };
+        Some(bop)
+    }
+
+    /// Determine the comparison which gives the opposite result.  That
+    /// is, the operator `op'` such that `x op y == !(x op' y)` (if one
+    /// exists).  For example, `<=` becomes `>`.
+    pub fn negate(&self) -> Option<BinOp> {
+        let bop = match self {
+            BinOp::Equals => BinOp::NotEquals,
+            BinOp::NotEquals => BinOp::Equals,
+            BinOp::LessThan => BinOp::GreaterThanOrEquals,
+            BinOp::LessThanOrEquals => BinOp::GreaterThan,
+            BinOp::GreaterThan => BinOp::LessThanOrEquals,
+            BinOp::GreaterThanOrEquals => BinOp::LessThan,
+            _ => { return None; }
+        };
+        Some(bop)
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            BinOp::Add => "+",
+            BinOp::Subtract => "-",
+            BinOp::Divide => "/",
+            BinOp::Multiply => "*",
+            BinOp::Remainder => "%",
+            BinOp::Exponent => "**",
+            BinOp::ShiftLeft => "<<",
+            BinOp::ShiftRight => ">>",
+            BinOp::SignedShiftRight => ">>>",
+            BinOp::Equals => "==",
+            BinOp::NotEquals => "!=",
+            BinOp::LessThan => "<",
+            BinOp::LessThanOrEquals => "<=",
+            BinOp::GreaterThan => ">",
+            BinOp::GreaterThanOrEquals => ">=",
+            BinOp::LogicalAnd => "&&",
+            BinOp::LogicalOr => "||"
+        };
+        write!(f,"{}",s)
+    }
+}
+
 // ============================================================================
 // Memory Regions
 // ============================================================================
 
-#[derive(Copy,Clone,PartialEq,Debug)]
+#[derive(Copy,Clone,PartialEq,Eq,Hash,Debug)]
+#[non_exhaustive]
 pub enum Region {
     Memory,
     Storage,
     CallData
 }
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Region::Memory => "memory",
+            Region::Storage => "storage",
+            Region::CallData => "calldata"
+        };
+        write!(f,"{}",s)
+    }
+}
+
+// ============================================================================
+// Intrinsics
+// ============================================================================
+
+/// Identifies a built-in operation which provides direct access to
+/// some aspect of the execution environment (e.g. `msg.sender`).
+/// Each intrinsic corresponds to a single EVM instruction.
+#[derive(Copy,Clone,PartialEq,Eq,Hash,Debug)]
+#[non_exhaustive]
+pub enum Intrinsic {
+    /// Balance of a given account (i.e. `balance(addr)`).
+    Balance,
+    /// Size of the call data (i.e. `calldatasize()`).
+    CallDataSize,
+    /// Address of the caller (i.e. `msg.sender`).
+    MsgSender,
+    /// Value transferred with the call (i.e. `msg.value`).
+    MsgValue
+}
+
+impl Intrinsic {
+    /// Determine the number of arguments this intrinsic requires.
+    pub fn arity(&self) -> usize {
+        match self {
+            Intrinsic::Balance => 1,
+            _ => 0
+        }
+    }
+}
+
+impl fmt::Display for Intrinsic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Intrinsic::Balance => "balance",
+            Intrinsic::CallDataSize => "calldatasize",
+            Intrinsic::MsgSender => "msg.sender",
+            Intrinsic::MsgValue => "msg.value"
+        };
+        write!(f,"{}",s)
+    }
+}
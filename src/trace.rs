@@ -0,0 +1,142 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde_json::Value;
+use crate::{CfaState,Disassembly};
+
+// ============================================================================
+// Traces
+// ============================================================================
+
+/// A single step of an execution trace.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Step {
+    /// Location of the instruction executed.
+    pub pc: usize,
+    /// Name of the instruction executed (e.g. `"PUSH1"`).
+    pub op: String,
+    /// Call depth (starting from `1`).
+    pub depth: usize,
+    /// Height of the stack before the instruction, if recorded.
+    pub stack: Option<usize>
+}
+
+/// An execution trace, as produced by geth's `debug_traceTransaction`
+/// (i.e. a sequence of `structLogs`).
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct Trace {
+    pub steps: Vec<Step>
+}
+
+impl Trace {
+    /// Convert geth's JSON output into a trace, returning `None` if
+    /// it is malformed.  This accepts the `structLogs` array itself,
+    /// the object containing it, or a JSON-RPC response containing
+    /// that object.
+    pub fn from_json(json: &Value) -> Option<Self> {
+        let logs = match json {
+            Value::Array(logs) => logs,
+            _ if json["result"].is_object() => json["result"]["structLogs"].as_array()?,
+            _ => json["structLogs"].as_array()?
+        };
+        let mut steps = Vec::new();
+        for log in logs {
+            let pc = log["pc"].as_u64()? as usize;
+            let op = log["op"].as_str()?.to_string();
+            let depth = log.get("depth").map_or(Some(1),|d| d.as_u64())? as usize;
+            let stack = match log.get("stack") {
+                Some(Value::Array(items)) => Some(items.len()),
+                Some(Value::Null)|None => None,
+                Some(_) => { return None; }
+            };
+            steps.push(Step{pc,op,depth,stack});
+        }
+        Some(Trace{steps})
+    }
+}
+
+// ============================================================================
+// Replay
+// ============================================================================
+
+/// Identifies a step of a trace which is inconsistent with the
+/// analysis of the code executed.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Inconsistency {
+    /// The step executes a location which is not the start of an
+    /// instruction.
+    Pc{step: usize, pc: usize},
+    /// The step executes a block which the analysis considers
+    /// unreachable.
+    Unreachable{step: usize, pc: usize},
+    /// The height of the stack is outside the bounds determined by
+    /// the analysis.
+    StackHeight{step: usize, pc: usize, height: usize}
+}
+
+/// The result of replaying a trace against a disassembly.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Replay {
+    /// Number of times each block was entered.
+    pub counts: Vec<usize>,
+    /// Steps which are inconsistent with the analysis.
+    pub inconsistencies: Vec<Inconsistency>
+}
+
+impl<'a> Disassembly<'a,CfaState> {
+    /// Replay an execution trace of this (built) disassembly, counting
+    /// how often each block is executed and checking the trace is
+    /// consistent with the analysis.  Only steps at the depth of the
+    /// first step are considered, as others execute different code.
+    pub fn replay(&self, trace: &Trace) -> Replay {
+        let blocks = self.blocks();
+        let mut counts = vec![0; blocks.len()];
+        let mut inconsistencies = Vec::new();
+        let depth = trace.steps.first().map_or(1,|s| s.depth);
+        //
+        for (i,s) in trace.steps.iter().enumerate() {
+            if s.depth != depth { continue; }
+            let pc = s.pc;
+            // Find enclosing block
+            let id = match blocks.iter().position(|b| b.encloses(pc)) {
+                Some(id) if self.is_boundary(id,pc) => id,
+                _ => {
+                    inconsistencies.push(Inconsistency::Pc{step: i, pc});
+                    continue;
+                }
+            };
+            if blocks[id].start == pc { counts[id] = counts[id] + 1; }
+            if !self.is_block_reachable(id) {
+                inconsistencies.push(Inconsistency::Unreachable{step: i, pc});
+                continue;
+            }
+            // Check stack height
+            if let Some(height) = s.stack {
+                let (lower,upper) = self.stack_bounds(pc);
+                if height < lower || upper.map_or(false,|u| height > u) {
+                    inconsistencies.push(Inconsistency::StackHeight{step: i, pc, height});
+                }
+            }
+        }
+        //
+        Replay{counts,inconsistencies}
+    }
+
+    /// Check whether a given location within a given block is the
+    /// start of an instruction.
+    fn is_boundary(&self, id: usize, pc: usize) -> bool {
+        let mut i = self.blocks()[id].start;
+        while i < pc {
+            i = i + self.decode(i).length(&[]);
+        }
+        i == pc
+    }
+}
@@ -9,6 +9,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use alloc::vec::Vec;
 
 /// Convert a 128bit value into the smallest possible byte sequence
 /// (in big endian order).
@@ -0,0 +1,425 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::string::{String,ToString};
+use alloc::vec::Vec;
+use alloc::format;
+use core::fmt;
+use core::ops::{Add,BitAnd,BitOr,Div,Mul,Not,Rem,Shl,Shr,Sub};
+use core::str::FromStr;
+use crate::hex::ToHexString;
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Identifies the ways in which parsing a `u256` from a string can
+/// fail.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum ParseError {
+    /// The string contained no digits.
+    Empty,
+    /// The string contained a character which is not a valid digit.
+    InvalidDigit,
+    /// The value represented by the string exceeds `2^256`.
+    Overflow
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f,"cannot parse integer from empty string"),
+            ParseError::InvalidDigit => write!(f,"invalid digit found in string"),
+            ParseError::Overflow => write!(f,"number too large to fit in u256")
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError { }
+
+// ============================================================================
+// Unsigned 256bit Integers
+// ============================================================================
+
+/// An unsigned 256bit integer, which corresponds to a single word of
+/// the EVM.  This is represented using two 128bit halves.  Observe
+/// that, since the `high` half is compared first, the derived
+/// ordering coincides with the numeric ordering.
+#[allow(non_camel_case_types)]
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Hash,PartialOrd,Ord)]
+pub struct u256 {
+    high: u128,
+    low: u128
+}
+
+impl u256 {
+    /// The smallest value representable by a `u256` (i.e. `0`).
+    pub const ZERO : u256 = u256{high:0, low:0};
+    /// The value `1`.
+    pub const ONE : u256 = u256{high:0, low:1};
+    /// The largest value representable by a `u256` (i.e. `2^256 - 1`).
+    pub const MAX : u256 = u256{high:u128::MAX, low:u128::MAX};
+
+    /// Construct a `u256` from its high and low halves.
+    pub const fn from_halves(high: u128, low: u128) -> Self {
+        u256{high,low}
+    }
+
+    /// Access the upper 128 bits of this value.
+    pub fn high(&self) -> u128 { self.high }
+
+    /// Access the lower 128 bits of this value.
+    pub fn low(&self) -> u128 { self.low }
+
+    /// Parse a `u256` from a string of digits in a given radix
+    /// (which must be either `10` or `16`).  No prefix (e.g. `0x`) is
+    /// permitted.
+    pub fn from_str_radix(digits: &str, radix: u32) -> Result<u256,ParseError> {
+        assert!(radix == 10 || radix == 16);
+        //
+        if digits.is_empty() { return Err(ParseError::Empty); }
+        let mut acc = u256::ZERO;
+        //
+        for c in digits.chars() {
+            let d = c.to_digit(radix).ok_or(ParseError::InvalidDigit)?;
+            // Multiply accumulator by radix
+            acc = if radix == 16 {
+                acc.checked_shl(4)
+            } else {
+                // x*10 == x*8 + x*2
+                acc.checked_shl(3).zip(acc.checked_shl(1)).and_then(|(x,y)| x.checked_add(y))
+            }.ok_or(ParseError::Overflow)?;
+            // Add digit
+            acc = acc.checked_add(u256::from(d)).ok_or(ParseError::Overflow)?;
+        }
+        //
+        Ok(acc)
+    }
+
+    /// Convert a sequence of digits (most significant first) in a given
+    /// radix (which must be either `10` or `16`) into a value, giving
+    /// `None` if it exceeds `2^256`.
+    pub fn from_be_digits(digits: &[u8], radix: u32) -> Option<u256> {
+        let s : String = digits.iter().map(|d| char::from_digit(*d as u32,radix)).collect::<Option<_>>()?;
+        u256::from_str_radix(&s,radix).ok()
+    }
+
+    /// Add two values together, returning `None` on overflow.
+    fn checked_add(self, rhs: u256) -> Option<u256> {
+        let (low,carry) = self.low.overflowing_add(rhs.low);
+        let high = self.high.checked_add(rhs.high)?.checked_add(carry as u128)?;
+        Some(u256{high,low})
+    }
+
+    /// Shift this value left by a small number of bits (less than
+    /// `128`), returning `None` if any set bits are shifted out.
+    fn checked_shl(self, n: u32) -> Option<u256> {
+        assert!(n < 128);
+        if n == 0 {
+            Some(self)
+        } else if (self.high >> (128 - n)) != 0 {
+            None
+        } else {
+            let high = (self.high << n) | (self.low >> (128 - n));
+            Some(u256{high, low: self.low << n})
+        }
+    }
+
+    /// Divide this value by a small divisor (no larger than `2^64`),
+    /// returning the quotient and the remainder.
+    fn div_rem_small(self, divisor: u128) -> (u256,u128) {
+        assert!(divisor != 0 && divisor <= (1u128 << 64));
+        let limbs = [self.high >> 64, self.high & (u64::MAX as u128),
+                     self.low >> 64, self.low & (u64::MAX as u128)];
+        let mut quotient = [0u128;4];
+        let mut rem = 0u128;
+        // Long division one 64bit limb at a time.  Since the
+        // remainder is always less than the divisor, this cannot
+        // overflow.
+        for i in 0..4 {
+            let cur = (rem << 64) | limbs[i];
+            quotient[i] = cur / divisor;
+            rem = cur % divisor;
+        }
+        let high = (quotient[0] << 64) | quotient[1];
+        let low = (quotient[2] << 64) | quotient[3];
+        (u256{high,low},rem)
+    }
+}
+
+// ============================================================================
+// Arithmetic
+// ============================================================================
+
+/// Addition modulo `2^256` (as for the EVM).
+impl Add for u256 {
+    type Output = u256;
+
+    fn add(self, rhs: u256) -> u256 {
+        let (low,carry) = self.low.overflowing_add(rhs.low);
+        let high = self.high.wrapping_add(rhs.high).wrapping_add(carry as u128);
+        u256{high,low}
+    }
+}
+
+/// Subtraction modulo `2^256` (as for the EVM).
+impl Sub for u256 {
+    type Output = u256;
+
+    fn sub(self, rhs: u256) -> u256 {
+        let (low,borrow) = self.low.overflowing_sub(rhs.low);
+        let high = self.high.wrapping_sub(rhs.high).wrapping_sub(borrow as u128);
+        u256{high,low}
+    }
+}
+
+/// Multiplication modulo `2^256` (as for the EVM).
+impl Mul for u256 {
+    type Output = u256;
+
+    fn mul(self, rhs: u256) -> u256 {
+        let a = self.limbs();
+        let b = rhs.limbs();
+        let mut r = [0u64;4];
+        // Schoolbook multiplication using 64bit limbs, ignoring those
+        // products which only affect bits beyond 2^256.  Each step
+        // fits within 128 bits, since (2^64-1)^2 + 2(2^64-1) is
+        // 2^128-1.
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..(4-i) {
+                let t = (a[i] as u128) * (b[j] as u128) + (r[i+j] as u128) + carry;
+                r[i+j] = t as u64;
+                carry = t >> 64;
+            }
+        }
+        let high = ((r[3] as u128) << 64) | (r[2] as u128);
+        let low = ((r[1] as u128) << 64) | (r[0] as u128);
+        u256{high,low}
+    }
+}
+
+/// Division rounding towards zero, where dividing by zero gives zero
+/// (as for the EVM).
+impl Div for u256 {
+    type Output = u256;
+
+    fn div(self, rhs: u256) -> u256 {
+        self.div_rem(rhs).0
+    }
+}
+
+/// Remainder, where dividing by zero gives zero (as for the EVM).
+impl Rem for u256 {
+    type Output = u256;
+
+    fn rem(self, rhs: u256) -> u256 {
+        self.div_rem(rhs).1
+    }
+}
+
+/// Shift left, where shifting by the word width (or more) gives zero
+/// (as for the EVM).
+impl Shl<usize> for u256 {
+    type Output = u256;
+
+    fn shl(self, n: usize) -> u256 {
+        match n {
+            0 => self,
+            1..=127 => u256{high: (self.high << n) | (self.low >> (128 - n)), low: self.low << n},
+            128..=255 => u256{high: self.low << (n - 128), low: 0},
+            _ => u256::ZERO
+        }
+    }
+}
+
+/// Logical shift right, where shifting by the word width (or more)
+/// gives zero (as for the EVM).
+impl Shr<usize> for u256 {
+    type Output = u256;
+
+    fn shr(self, n: usize) -> u256 {
+        match n {
+            0 => self,
+            1..=127 => u256{high: self.high >> n, low: (self.low >> n) | (self.high << (128 - n))},
+            128..=255 => u256{high: 0, low: self.high >> (n - 128)},
+            _ => u256::ZERO
+        }
+    }
+}
+
+impl BitAnd for u256 {
+    type Output = u256;
+
+    fn bitand(self, rhs: u256) -> u256 {
+        u256{high: self.high & rhs.high, low: self.low & rhs.low}
+    }
+}
+
+impl BitOr for u256 {
+    type Output = u256;
+
+    fn bitor(self, rhs: u256) -> u256 {
+        u256{high: self.high | rhs.high, low: self.low | rhs.low}
+    }
+}
+
+impl Not for u256 {
+    type Output = u256;
+
+    fn not(self) -> u256 {
+        u256{high: !self.high, low: !self.low}
+    }
+}
+
+impl u256 {
+    /// Raise this value to a given power modulo `2^256` (as for the
+    /// EVM).
+    pub fn pow(self, exp: u256) -> u256 {
+        let mut acc = u256::ONE;
+        // Square and multiply, from the most significant bit down.
+        for i in (0..256).rev() {
+            acc = acc * acc;
+            if (exp >> i) & u256::ONE == u256::ONE {
+                acc = acc * self;
+            }
+        }
+        acc
+    }
+
+    /// Convert this value into its big endian representation.
+    pub fn to_be_bytes(&self) -> [u8;32] {
+        let mut bytes = [0u8;32];
+        bytes[..16].copy_from_slice(&self.high.to_be_bytes());
+        bytes[16..].copy_from_slice(&self.low.to_be_bytes());
+        bytes
+    }
+
+    /// Convert a big endian sequence of at most `32` bytes into a
+    /// value.
+    pub fn from_be_bytes(bytes: &[u8]) -> u256 {
+        assert!(bytes.len() <= 32);
+        bytes.iter().fold(u256::ZERO,|acc,b| (acc << 8) | u256::from(*b))
+    }
+
+    /// Divide this value by another, returning the quotient and the
+    /// remainder (which are both zero when dividing by zero).
+    fn div_rem(self, divisor: u256) -> (u256,u256) {
+        if divisor == u256::ZERO {
+            return (u256::ZERO,u256::ZERO);
+        }
+        let mut quotient = u256::ZERO;
+        let mut rem = u256::ZERO;
+        // Long division one bit at a time.  Since the remainder is
+        // always less than the divisor, shifting it cannot overflow
+        // unless the top bit is set (in which case it exceeds the
+        // divisor regardless).
+        for i in (0..256).rev() {
+            let carry = rem.high >> 127;
+            rem = (rem << 1) | ((self >> i) & u256::ONE);
+            if carry != 0 || rem >= divisor {
+                rem = rem - divisor;
+                quotient = quotient | (u256::ONE << i);
+            }
+        }
+        (quotient,rem)
+    }
+
+    /// Split this value into 64bit limbs (least significant first).
+    fn limbs(&self) -> [u64;4] {
+        [self.low as u64, (self.low >> 64) as u64, self.high as u64, (self.high >> 64) as u64]
+    }
+}
+
+// ============================================================================
+// Conversions
+// ============================================================================
+
+macro_rules! impl_from {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for u256 {
+                fn from(val: $t) -> u256 {
+                    u256{high: 0, low: val as u128}
+                }
+            }
+        )*
+    }
+}
+
+impl_from!(u8,u16,u32,u64,u128,usize);
+
+impl FromStr for u256 {
+    type Err = ParseError;
+
+    /// Parse a `u256` from either a decimal string (e.g. `"1234"`) or
+    /// a hexadecimal string prefixed with `0x` (e.g. `"0x4d2"`).
+    fn from_str(s: &str) -> Result<u256,ParseError> {
+        match s.strip_prefix("0x") {
+            Some(hex) => u256::from_str_radix(hex,16),
+            None => u256::from_str_radix(s,10)
+        }
+    }
+}
+
+impl TryFrom<&str> for u256 {
+    type Error = ParseError;
+
+    fn try_from(s: &str) -> Result<u256,ParseError> {
+        u256::from_str(s)
+    }
+}
+
+impl ToHexString for u256 {
+    fn to_hex_string(&self) -> String {
+        format!("{:#x}",self)
+    }
+}
+
+// ============================================================================
+// Formatting
+// ============================================================================
+
+impl fmt::Display for u256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.high == 0 {
+            return fmt::Display::fmt(&self.low,f);
+        }
+        // Split into chunks of 19 decimal digits (least significant
+        // first), since 10^19 is the largest power of ten below 2^64.
+        const CHUNK : u128 = 10_000_000_000_000_000_000;
+        let mut chunks = Vec::new();
+        let mut val = *self;
+        while val != u256::ZERO {
+            let (q,r) = val.div_rem_small(CHUNK);
+            chunks.push(r);
+            val = q;
+        }
+        // Most significant chunk is not padded
+        let mut digits = chunks.pop().unwrap().to_string();
+        for c in chunks.iter().rev() {
+            digits.push_str(&format!("{:019}",c));
+        }
+        f.pad_integral(true,"",&digits)
+    }
+}
+
+impl fmt::LowerHex for u256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = if self.high == 0 {
+            format!("{:x}",self.low)
+        } else {
+            format!("{:x}{:032x}",self.high,self.low)
+        };
+        f.pad_integral(true,"0x",&digits)
+    }
+}
@@ -9,11 +9,11 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use std::{cmp,fmt};
-use std::ops::Range;
+use core::{cmp,fmt};
+use core::ops::Range;
 
 /// Represents the maximum possible interval
-pub const MAX_INTERVAL : Interval = Interval{start:0,end:std::usize::MAX};
+pub const MAX_INTERVAL : Interval = Interval{start:0,end:usize::MAX};
 
 /// Represents an interval of values `x..y` (much like `Range<usize>`)
 /// which supports various arithmetic operations.
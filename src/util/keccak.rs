@@ -0,0 +1,90 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Number of bytes absorbed per permutation (for a 256bit digest).
+const RATE : usize = 136;
+
+const ROUND_CONSTANTS : [u64;24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008
+];
+
+const ROTATIONS : [u32;24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44
+];
+
+const LANES : [usize;24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1
+];
+
+/// Compute the Keccak-256 hash of a given byte sequence (i.e. as used
+/// by the `KECCAK256` instruction, rather than the standardised
+/// SHA3-256).
+pub fn keccak256(bytes: &[u8]) -> [u8;32] {
+    let mut state = [0u64;25];
+    // Pad input to a multiple of the rate
+    let mut input = bytes.to_vec();
+    input.push(0x01);
+    while input.len() % RATE != 0 { input.push(0); }
+    let n = input.len();
+    input[n-1] |= 0x80;
+    // Absorb
+    for chunk in input.chunks(RATE) {
+        for i in 0..(RATE/8) {
+            let mut lane = [0u8;8];
+            lane.copy_from_slice(&chunk[i*8..(i+1)*8]);
+            state[i] ^= u64::from_le_bytes(lane);
+        }
+        permute(&mut state);
+    }
+    // Squeeze
+    let mut digest = [0u8;32];
+    for i in 0..4 {
+        digest[i*8..(i+1)*8].copy_from_slice(&state[i].to_le_bytes());
+    }
+    digest
+}
+
+/// The Keccak-f[1600] permutation.
+fn permute(st: &mut [u64;25]) {
+    for rc in ROUND_CONSTANTS {
+        // Theta
+        let mut bc = [0u64;5];
+        for i in 0..5 {
+            bc[i] = st[i] ^ st[i+5] ^ st[i+10] ^ st[i+15] ^ st[i+20];
+        }
+        for i in 0..5 {
+            let t = bc[(i+4)%5] ^ bc[(i+1)%5].rotate_left(1);
+            for j in (0..25).step_by(5) { st[j+i] ^= t; }
+        }
+        // Rho and Pi
+        let mut t = st[1];
+        for i in 0..24 {
+            let tmp = st[LANES[i]];
+            st[LANES[i]] = t.rotate_left(ROTATIONS[i]);
+            t = tmp;
+        }
+        // Chi
+        for j in (0..25).step_by(5) {
+            let row = [st[j], st[j+1], st[j+2], st[j+3], st[j+4]];
+            for i in 0..5 {
+                st[j+i] ^= (!row[(i+1)%5]) & row[(i+2)%5];
+            }
+        }
+        // Iota
+        st[0] ^= rc;
+    }
+}
@@ -10,7 +10,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 mod byte_utils;
+mod int;
 mod interval;
+mod keccak;
 
 pub use byte_utils::*;
+pub use int::*;
 pub use interval::*;
+pub use keccak::*;
@@ -0,0 +1,83 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{AbstractState,Block,Disassembly,Instruction};
+
+// ============================================================================
+// Visitors
+// ============================================================================
+
+/// Visits the reachable instructions of a disassembly in order.  Each
+/// instruction is given with its offset, and the abstract state
+/// immediately before it.
+pub trait InstructionVisitor<T> {
+    /// Visit a reachable instruction.
+    fn visit_instruction(&mut self, _pc: usize, _insn: &Instruction, _state: &T) {}
+}
+
+/// Visits the blocks of a disassembly in order.  The instructions of
+/// a reachable block are visited between `enter_block()` and
+/// `exit_block()`, whilst an unreachable block is visited only as
+/// data.
+pub trait BlockVisitor<T> : InstructionVisitor<T> {
+    /// Visit the start of a reachable block.
+    fn enter_block(&mut self, _id: usize, _blk: &Block) {}
+
+    /// Visit the end of a reachable block.
+    fn exit_block(&mut self, _id: usize, _blk: &Block) {}
+
+    /// Visit an unreachable block, whose bytes are treated as data.
+    fn visit_data(&mut self, _id: usize, _blk: &Block, _bytes: &[u8]) {}
+}
+
+impl<'a,T:AbstractState> Disassembly<'a,T> {
+    /// Traverse this (built) disassembly using a given block visitor.
+    pub fn visit<V:BlockVisitor<T>+?Sized>(&self, visitor: &mut V) {
+        for (i,blk) in self.blocks().iter().enumerate() {
+            if !self.is_block_reachable(i) {
+                visitor.visit_data(i,blk,&self.read_bytes(blk.start,blk.end));
+                continue;
+            }
+            visitor.enter_block(i,blk);
+            let mut ctx = self.get_state(blk.start);
+            let mut pc = blk.start;
+            while pc < blk.end {
+                let insn = self.decode(pc);
+                ctx = self.assume_at(pc,ctx);
+                visitor.visit_instruction(pc,&insn,&ctx);
+                ctx = ctx.transfer_in(&insn,self.bytes());
+                pc = pc + insn.length(&[]);
+            }
+            visitor.exit_block(i,blk);
+        }
+    }
+
+    /// Traverse the reachable instructions of this (built)
+    /// disassembly using a given instruction visitor.
+    pub fn visit_instructions<V:InstructionVisitor<T>+?Sized>(&self, visitor: &mut V) {
+        self.visit(&mut Instructions(visitor));
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Adapts an instruction visitor to visit blocks.
+struct Instructions<'v,V:?Sized>(&'v mut V);
+
+impl<'v,T,V:InstructionVisitor<T>+?Sized> InstructionVisitor<T> for Instructions<'v,V> {
+    fn visit_instruction(&mut self, pc: usize, insn: &Instruction, state: &T) {
+        self.0.visit_instruction(pc,insn,state)
+    }
+}
+
+impl<'v,T,V:InstructionVisitor<T>+?Sized> BlockVisitor<T> for Instructions<'v,V> {}
@@ -0,0 +1,60 @@
+use evmil::dfa::{AbstractValue,EMPTY_MEMORY};
+
+const ZERO : AbstractValue = AbstractValue::Known(0);
+const ONE : AbstractValue = AbstractValue::Known(1);
+const TWO : AbstractValue = AbstractValue::Known(2);
+const WORD : AbstractValue = AbstractValue::Known(32);
+const TWO_WORDS : AbstractValue = AbstractValue::Known(64);
+const UNKNOWN : AbstractValue = AbstractValue::Unknown;
+
+#[test]
+fn test_abstract_memory_01() {
+    let mut mem = EMPTY_MEMORY;
+    assert_eq!(mem.read(ZERO),UNKNOWN);
+    mem.write(ZERO,TWO);
+    assert_eq!(mem.read(ZERO),TWO);
+    assert_eq!(mem.read(ONE),UNKNOWN);
+    assert_eq!(mem.read(UNKNOWN),UNKNOWN);
+}
+
+#[test]
+fn test_abstract_memory_02() {
+    let mut mem = EMPTY_MEMORY;
+    mem.write(ZERO,TWO);
+    mem.write(WORD,ONE);
+    mem.write(TWO_WORDS,ONE);
+    // Overlapping write (bytes 1..33)
+    mem.write(ONE,UNKNOWN);
+    assert_eq!(mem.read(ZERO),UNKNOWN);
+    assert_eq!(mem.read(WORD),UNKNOWN);
+    assert_eq!(mem.read(TWO_WORDS),ONE);
+    // Unknown write
+    mem.write(UNKNOWN,TWO);
+    assert!(mem.is_empty());
+}
+
+#[test]
+fn test_abstract_memory_03() {
+    let mut mem = EMPTY_MEMORY;
+    mem.write(ZERO,TWO);
+    mem.write(WORD,ONE);
+    mem.invalidate(AbstractValue::Known(31),ONE);
+    assert_eq!(mem.read(ZERO),UNKNOWN);
+    assert_eq!(mem.read(WORD),ONE);
+    mem.invalidate(ZERO,UNKNOWN);
+    assert!(mem.is_empty());
+}
+
+#[test]
+fn test_abstract_memory_04() {
+    let mut m1 = EMPTY_MEMORY;
+    let mut m2 = EMPTY_MEMORY;
+    m1.write(ZERO,TWO);
+    m1.write(WORD,ONE);
+    m2.write(ZERO,TWO);
+    m2.write(WORD,TWO);
+    assert!(m1.merge_into(&m2));
+    assert_eq!(m1.read(ZERO),TWO);
+    assert_eq!(m1.read(WORD),UNKNOWN);
+    assert!(!m1.merge_into(&m2));
+}
@@ -0,0 +1,124 @@
+use evmil::{Addresses,Annotator,FromHexString,Options,Selectors,StorageNames,disassemble_hex_with};
+use evmil::util::{keccak256,u256};
+
+const TRANSFER : &str = "transfer(address,uint256)";
+
+// ============================================================================
+// Selectors
+// ============================================================================
+
+#[test]
+pub fn test_selectors_01() {
+    let sels = Selectors::from(&[TRANSFER][..]);
+    assert_eq!(sels.len(), 1);
+    assert_eq!(sels.get(&[0xa9,0x05,0x9c,0xbb]), Some(TRANSFER));
+    assert_eq!(sels.get(&[0xa9,0x05,0x9c]), None);
+}
+
+#[test]
+pub fn test_selectors_02() {
+    let sels = Selectors::from(&[TRANSFER][..]);
+    check(&sels, "0x63a9059cbb00", "0x000000: PUSH4 sel:transfer(address,uint256)\n0x000005: STOP\n");
+}
+
+#[test]
+pub fn test_selectors_03() {
+    // Unknown selector
+    let sels = Selectors::from(&[TRANSFER][..]);
+    check(&sels, "0x631234567800", "0x000000: PUSH4 0x12345678\n0x000005: STOP\n");
+}
+
+// ============================================================================
+// Addresses
+// ============================================================================
+
+#[test]
+pub fn test_addresses_01() {
+    let addr = "dead000000000000000000000000000000000001";
+    let hex = format!("0x73{}00",addr);
+    check(&Addresses, &hex, &format!("0x000000: PUSH20 addr:0x{}\n0x000015: STOP\n",addr));
+}
+
+#[test]
+pub fn test_addresses_02() {
+    // Address masks are not addresses
+    let hex = format!("0x73{}00","ff".repeat(20));
+    check(&Addresses, &hex, &format!("0x000000: PUSH20 0x{}\n0x000015: STOP\n","ff".repeat(20)));
+    assert_eq!(Addresses.annotate(&[0xde,0xad]), None);
+}
+
+#[test]
+pub fn test_annotators_01() {
+    // Combined annotators
+    let both = (Selectors::from(&[TRANSFER][..]),Some(Addresses));
+    let hex = format!("0x63a9059cbb73{}00","11".repeat(20));
+    let out = format!("0x000000: PUSH4 sel:{}\n0x000005: PUSH20 addr:0x{}\n0x00001a: STOP\n",TRANSFER,"11".repeat(20));
+    check(&both, &hex, &out);
+}
+
+// ============================================================================
+// Storage Names
+// ============================================================================
+
+#[test]
+pub fn test_storage_names_01() {
+    let names = owner();
+    check(&names, "0x60005400", "0x000000: PUSH1 0x00\n0x000002: SLOAD // owner\n0x000003: STOP\n");
+}
+
+#[test]
+pub fn test_storage_names_02() {
+    let names = owner();
+    check(&names, "0x600160005500", "0x000000: PUSH1 0x01\n0x000002: PUSH1 0x00\n0x000004: SSTORE // owner\n0x000005: STOP\n");
+}
+
+#[test]
+pub fn test_storage_names_03() {
+    // Unnamed or unknown slots
+    let names = owner();
+    check(&names, "0x60015400", "0x000000: PUSH1 0x01\n0x000002: SLOAD\n0x000003: STOP\n");
+    check(&names, "0x33545000", "0x000000: CALLER\n0x000001: SLOAD\n0x000002: POP\n0x000003: STOP\n");
+}
+
+#[test]
+pub fn test_storage_names_04() {
+    // Hashed slots
+    let mut names = StorageNames::new();
+    names.insert_hashed("my.app.config","config");
+    let hash = keccak256(b"my.app.config");
+    assert_eq!(names.annotate(&hash), Some("slot:config".to_string()));
+    assert_eq!(names.annotate(&hash[1..]), None);
+}
+
+#[test]
+pub fn test_storage_names_05() {
+    let mut names = StorageNames::new();
+    names.insert(u256::MAX,"last");
+    let hex = format!("0x7f{}5400","ff".repeat(32));
+    check(&names, &hex, "0x000000: PUSH32 slot:last\n0x000021: SLOAD\n0x000022: STOP\n");
+}
+
+#[test]
+pub fn test_storage_names_06() {
+    // EIP-1967 slots are the hash less one
+    let mut names = StorageNames::new();
+    names.insert_hashed_less("eip1967.proxy.implementation",1,"implementation");
+    let slot = "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc".from_hex_string().unwrap();
+    assert_eq!(names.annotate(&slot), Some("slot:implementation".to_string()));
+    assert_eq!(names.annotate(&keccak256(b"eip1967.proxy.implementation")), None);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check<A:Annotator>(annotator: &A, hex: &str, expected: &str) {
+    let out = disassemble_hex_with(hex, &Options::default(), annotator).unwrap();
+    assert_eq!(out, expected);
+}
+
+fn owner() -> StorageNames {
+    let mut names = StorageNames::new();
+    names.insert(u256::from(0u8),"owner");
+    names
+}
@@ -0,0 +1,179 @@
+use evmil::{Bytecode,Compiler,Parser,Term,ToHexString};
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+#[test]
+pub fn test_parse_01() {
+    let p = "memory arr[10];\narr[1] = 2;";
+    assert_eq!(show(&p), p);
+}
+
+#[test]
+pub fn test_parse_02() {
+    let p = "storage xs[2];\nmemory[0] = xs[memory[1]];";
+    assert_eq!(show(&p), p);
+}
+
+#[test]
+pub fn test_parse_03() {
+    // Undeclared array
+    assert!(Parser::new("memory[0] = arr[0];").parse().is_err());
+}
+
+#[test]
+pub fn test_parse_04() {
+    // Name of a built-in
+    assert!(Parser::new("memory msg[2];").parse().is_err());
+}
+
+#[test]
+pub fn test_parse_05() {
+    // Declared arrays cannot be used without an index
+    assert!(Parser::new("memory arr[2];\nmemory[0] = arr;").parse().is_ok());
+    check_err("memory arr[2];\nmemory[0] = arr;");
+}
+
+// ============================================================================
+// Constant indices
+// ============================================================================
+
+#[test]
+pub fn test_constant_01() {
+    let p = "memory xs[2];\nmemory ys[4];\nstorage[0] = ys[1];";
+    check(&p, "0x606051600055");
+}
+
+#[test]
+pub fn test_constant_02() {
+    let p = "storage xs[3];\nstorage ys[3];\nys[2] = 1;";
+    check(&p, "0x6001600555");
+}
+
+#[test]
+pub fn test_constant_03() {
+    // Memory and storage are allocated separately
+    let p = "storage xs[3];\nmemory ys[3];\nys[0] = xs[0];";
+    check(&p, "0x600054600052");
+}
+
+#[test]
+pub fn test_constant_04() {
+    let p = "memory xs[2];\nmemory[0] = xs[2];";
+    check_err(&p);
+}
+
+// ============================================================================
+// Runtime checks
+// ============================================================================
+
+#[test]
+pub fn test_checks_01() {
+    let p = "memory xs[2];\nmemory ys[4];\nstorage[0] = ys[msg.value];";
+    check(&p, "0x3480600411600957fe5b60200260400151600055");
+}
+
+#[test]
+pub fn test_checks_02() {
+    let p = "memory xs[2];\nmemory ys[4];\nstorage[0] = ys[msg.value];";
+    check_unchecked(&p, "0x3460200260400151600055");
+}
+
+#[test]
+pub fn test_checks_03() {
+    // Index read from memory is not known at compile time
+    let p = "storage xs[4];\nxs[0] = xs[memory[0]];";
+    check_unchecked(&p, "0x60005154600055");
+}
+
+// ============================================================================
+// Declarations
+// ============================================================================
+
+#[test]
+pub fn test_declare_01() {
+    check_err("memory xs[2];\nstorage xs[2];");
+}
+
+#[test]
+pub fn test_declare_02() {
+    check_err("memory xs[0];");
+}
+
+#[test]
+pub fn test_declare_03() {
+    check_err("calldata xs[2];");
+}
+
+#[test]
+pub fn test_declare_04() {
+    // Too large for the region
+    check_err("memory xs[1000000000000000000];\nxs[999999999999999999] = 1;");
+    check_err(&format!("storage xs[{}];\nstorage ys[2];",usize::MAX));
+}
+
+// ============================================================================
+// Direct access
+// ============================================================================
+
+#[test]
+pub fn test_direct_01() {
+    // Arrays would overlap with direct accesses
+    check_err("storage xs[2];\nstorage[0] = 1;");
+    check_err("storage[0] = 1;\nstorage xs[2];");
+    check_err("memory xs[2];\nmemory[0] = xs[0];");
+}
+
+#[test]
+pub fn test_direct_02() {
+    // Other regions can still be accessed directly
+    check("storage xs[2];\nmemory[0] = xs[calldata[0]];", "0x60003580600211600b57fe5b54600052");
+}
+
+// ============================================================================
+// Return values
+// ============================================================================
+
+#[test]
+pub fn test_return_01() {
+    // Elements are read before any return value is stored over them
+    check("memory xs[2];\nxs[0] = 1;\nxs[1] = 2;\nsucceed xs[1], xs[0];",
+          "0x6001600052600260205260205160005160205260005260406000f3");
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn parse(stmts: &str) -> Vec<Term> {
+    Parser::new(stmts).parse().unwrap()
+}
+
+fn show(stmts: &str) -> String {
+    parse(stmts).iter().map(|t| t.to_string()).collect::<Vec<_>>().join("\n")
+}
+
+/// Check that compiling a given program produces a given hex string.
+fn check(stmts: &str, hex: &str) {
+    let code = Bytecode::try_from(parse(stmts).as_slice()).unwrap();
+    let bytes : Vec<u8> = code.try_into().unwrap();
+    assert_eq!(hex, bytes.to_hex_string());
+}
+
+/// Check that compiling a given program without bounds checks
+/// produces a given hex string.
+fn check_unchecked(stmts: &str, hex: &str) {
+    let mut code = Bytecode::new();
+    let mut compiler = Compiler::new(&mut code).with_bounds_checks(false);
+    for t in parse(stmts) {
+        compiler.translate(&t).unwrap();
+    }
+    let bytes : Vec<u8> = code.try_into().unwrap();
+    assert_eq!(hex, bytes.to_hex_string());
+}
+
+/// Check that compiling a given program fails.
+fn check_err(stmts: &str) {
+    assert!(Bytecode::try_from(parse(stmts).as_slice()).is_err());
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use evmil::{Artifact,LinkReference,FromHexString};
+
+#[test]
+pub fn test_artifact_01() {
+    // No placeholders
+    let json = r#"{"bytecode":"0x6001600201","deployedBytecode":"0x00"}"#;
+    let a = Artifact::from_json(json,&BTreeMap::new()).unwrap();
+    assert_eq!(a.bytecode, "0x6001600201".from_hex_string().unwrap());
+    assert_eq!(a.deployed_bytecode, vec![0]);
+    assert!(a.unresolved.is_empty());
+}
+
+#[test]
+pub fn test_artifact_02() {
+    // Truffle placeholder resolved by name
+    let json = r#"{"bytecode":"0x73__Lib___________________________________00"}"#;
+    let a = Artifact::from_json(json,&libraries()).unwrap();
+    let mut expected = vec![0x73];
+    expected.extend_from_slice(&[0x11;20]);
+    expected.push(0x00);
+    assert_eq!(a.bytecode, expected);
+    assert!(a.unresolved.is_empty());
+}
+
+#[test]
+pub fn test_artifact_03() {
+    // Hardhat placeholder named by link references, but unresolved
+    let json = r#"{
+       "bytecode":"0x6073__$7c9a3fa5b6d6e2e0a7e1b2c3d4e5f6a7b8$__00",
+       "linkReferences":{"contracts/Other.sol":{"Other":[{"start":2,"length":20}]}}
+    }"#;
+    let a = Artifact::from_json(json,&libraries()).unwrap();
+    assert_eq!(a.bytecode.len(), 23);
+    assert_eq!(&a.bytecode[2..22], &[0;20]);
+    assert_eq!(a.unresolved, vec![LinkReference{library: "Other".to_string(), offset: 2}]);
+}
+
+#[test]
+pub fn test_artifact_04() {
+    // Hardhat placeholder named by link references, and resolved
+    let json = r#"{
+       "bytecode":"0x6073__$7c9a3fa5b6d6e2e0a7e1b2c3d4e5f6a7b8$__00",
+       "linkReferences":{"contracts/Lib.sol":{"Lib":[{"start":2,"length":20}]}}
+    }"#;
+    let a = Artifact::from_json(json,&libraries()).unwrap();
+    assert_eq!(&a.bytecode[2..22], &[0x11;20]);
+    assert!(a.unresolved.is_empty());
+}
+
+#[test]
+pub fn test_artifact_05() {
+    // Invalid artifacts
+    assert!(Artifact::from_json("{}",&libraries()).is_err());
+    assert!(Artifact::from_json(r#"{"bytecode":"0x6"}"#,&libraries()).is_err());
+    assert!(Artifact::from_json(r#"{"bytecode":"0x6g"}"#,&libraries()).is_err());
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn libraries() -> BTreeMap<String,[u8;20]> {
+    let mut libs = BTreeMap::new();
+    libs.insert("Lib".to_string(),[0x11;20]);
+    libs
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "std")]
+use serde_json::json;
+use evmil::{Bytecode,Instruction,Term,ToHexString};
+use evmil::Instruction::*;
+
+// ============================================================================
+// Export
+// ============================================================================
+
+#[test]
+pub fn test_asm_json_01() {
+    let mut code = Bytecode::new();
+    let l = code.fresh_label();
+    code.push(JUMPDEST(l));
+    code.push(PUSH(vec![0x00,0x80]));
+    code.push(DUP(1));
+    code.push(PUSHL(l));
+    code.push(JUMP);
+    let expected = json!({".code": [
+        {"name": "tag", "value": "0"},
+        {"name": "JUMPDEST"},
+        {"name": "PUSH", "value": "0080"},
+        {"name": "DUP1"},
+        {"name": "PUSH [tag]", "value": "0"},
+        {"name": "JUMP"}
+    ]});
+    assert_eq!(code.to_asm_json(), expected);
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+#[test]
+pub fn test_asm_json_02() {
+    // As produced by solc
+    let json = json!({".code": [
+        {"begin": 0, "end": 10, "name": "PUSH", "source": 0, "value": "80"},
+        {"begin": 0, "end": 10, "name": "PUSH", "source": 0, "value": "40"},
+        {"begin": 0, "end": 10, "name": "MSTORE", "source": 0},
+        {"name": "tag", "value": "1"},
+        {"name": "JUMPDEST"},
+        {"name": "PUSH [tag]", "value": "1"},
+        {"name": "JUMP"}
+    ]});
+    check(&json, "0x60806040525b600556");
+}
+
+#[test]
+pub fn test_asm_json_03() {
+    // Items only, with aliases and short values
+    let json = json!([{"name": "PUSH", "value": "A"}, {"name": "PREVRANDAO"}, {"name": "KECCAK256"}]);
+    check(&json, "0x600a4420");
+}
+
+#[test]
+pub fn test_asm_json_04() {
+    // Unsupported or malformed items
+    for item in [json!({"name": "PUSH #[$]", "value": "0"}), json!({"name": "PUSH", "value": "zz"}), json!({"value": "1"})] {
+        assert!(Bytecode::from_asm_json(&json!([item])).is_none());
+    }
+}
+
+// ============================================================================
+// Round Trip
+// ============================================================================
+
+#[test]
+pub fn test_asm_json_05() {
+    let terms = [
+        Term::IfGoto(Box::new(Term::Int(vec![1])),"a".to_string()),
+        Term::Revert(vec![]),
+        Term::Label("a".to_string()),
+        Term::Stop
+    ];
+    let code = Bytecode::try_from(&terms).unwrap();
+    let json = serde_json::to_string(&code.to_asm_json()).unwrap();
+    let code2 = Bytecode::from_asm_json(&serde_json::from_str(&json).unwrap()).unwrap();
+    assert_eq!(code.to_bytes().unwrap(), code2.to_bytes().unwrap());
+}
+
+#[test]
+pub fn test_asm_json_06() {
+    // Every single byte instruction
+    for op in 0..=255u8 {
+        let insn = Instruction::decode(0,&[op]);
+        if matches!(insn,DATA(_)|PUSHPAD(..)|JUMPDEST(_)) { continue; }
+        let mut code = Bytecode::new();
+        code.push(insn.clone());
+        let code2 = Bytecode::from_asm_json(&code.to_asm_json()).unwrap();
+        assert_eq!(code2.instructions(), &[insn]);
+    }
+}
+
+#[test]
+pub fn test_asm_json_07() {
+    // Truncated pushes and pseudo-instructions are preserved
+    let mut code = Bytecode::new();
+    code.push(PSEUDO("INCR".to_string(),vec![0x60,0x01,0x01]));
+    code.push(PUSHPAD(vec![0xff,0x00],1));
+    let json = code.to_asm_json();
+    assert_eq!(json, json!({".code": [
+        {"name": "PSEUDO", "value": "600101", "pseudo": "INCR"},
+        {"name": "PUSHPAD", "value": "FF00", "present": "1"}
+    ]}));
+    let code2 = Bytecode::from_asm_json(&json).unwrap();
+    assert_eq!(code2.instructions(), code.instructions());
+    assert_eq!(code2.to_bytes().unwrap(), vec![0x60,0x01,0x01,0x61,0xff]);
+    // Malformed
+    for item in [json!({"name": "PUSHPAD", "value": "FF00", "present": "2"}), json!({"name": "PSEUDO", "value": "00"})] {
+        assert!(Bytecode::from_asm_json(&json!([item])).is_none());
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(json: &serde_json::Value, hex: &str) {
+    let code = Bytecode::from_asm_json(json).unwrap();
+    assert_eq!(code.to_bytes().unwrap().to_hex_string(), hex);
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use evmil::{Assembly,CfaState,Disassembly,FromHexString,Instruction,Item,Location,Parser,PatchError,ToHexString};
+use evmil::Instruction::*;
+use evmil::Term::*;
+
+// ============================================================================
+// Compile
+// ============================================================================
+
+#[test]
+pub fn test_compile_01() {
+    let asm = Assembly::compile(&[Goto("exit".to_string()),Label("exit".to_string())]).unwrap();
+    assert_eq!(asm.label_name(0), Some("exit"));
+    assert_eq!(asm.to_string(), "PUSHL exit\nJUMP\nJUMPDEST exit\n");
+    check_bytes(&asm,"0x6003565b");
+}
+
+#[test]
+pub fn test_compile_02() {
+    // Labels introduced by the compiler are unnamed
+    let asm = Assembly::compile(&[Assert(Box::new(Int(vec![1])))]).unwrap();
+    assert_eq!(asm.label_name(0), None);
+    assert_eq!(asm.to_string(), "PUSH1 0x01\nPUSHL(0)\nJUMPI\nINVALID\nJUMPDEST(0)\n");
+    check_bytes(&asm,"0x6001600657fe5b");
+}
+
+#[test]
+pub fn test_compile_03() {
+    let mut asm = Assembly::compile(&[Stop]).unwrap();
+    asm.items_mut()[0].comment = Some("done".to_string());
+    asm.push_item(Item::new(INVALID).with_comment("unreachable").with_span(4..9));
+    assert_eq!(asm.to_string(), "STOP ; done\nINVALID ; unreachable (at 4..9)\n");
+    check_bytes(&asm,"0x00fe");
+}
+
+#[test]
+pub fn test_compile_04() {
+    // Instructions are located at their statements
+    let input = "stop;\ngoto exit;\n.exit";
+    let mut parser = Parser::new(input);
+    let terms = parser.parse().unwrap();
+    assert_eq!(parser.spans(), &[0..5,6..16,17..22]);
+    let asm = Assembly::compile_with_spans(&terms,parser.spans()).unwrap();
+    let spans : Vec<Location> = asm.items().iter().map(|i| i.location.clone()).collect();
+    assert_eq!(spans, vec![Location::Span(0..5),Location::Span(6..16),Location::Span(6..16),Location::Span(17..22)]);
+}
+
+#[test]
+pub fn test_compile_05() {
+    // Errors are located at their statements
+    let input = "stop;\n1 = 2;";
+    let mut parser = Parser::new(input);
+    let terms = parser.parse().unwrap();
+    let d = Assembly::compile_with_spans(&terms,parser.spans()).unwrap_err();
+    assert_eq!(d.code, "C003");
+    assert_eq!(d.location, Location::Span(6..12));
+    // Unless no spans are given
+    let d = Assembly::compile(&terms).unwrap_err();
+    assert_eq!(d.location, Location::Unknown);
+}
+
+// ============================================================================
+// Lift
+// ============================================================================
+
+#[test]
+pub fn test_lift_01() {
+    let asm = check_lift("0x6003565b00", &[PUSH(vec![3]),JUMP,JUMPDEST(0),STOP]);
+    assert_eq!(asm.label_name(0), Some("_0x3"));
+    assert_eq!(asm.to_string(), "PUSH1 0x03 (at 0x0)\nJUMP (at 0x2)\nJUMPDEST _0x3 (at 0x3)\nSTOP (at 0x4)\n");
+}
+
+#[test]
+pub fn test_lift_02() {
+    // Unreachable code
+    check_lift("0x00600100", &[STOP,DATA(vec![0x60,0x01,0x00])]);
+}
+
+#[test]
+pub fn test_lift_03() {
+    check_lift("0x600160075760025b00", &[PUSH(vec![1]),PUSH(vec![7]),JUMPI,PUSH(vec![2]),JUMPDEST(0),STOP]);
+}
+
+// ============================================================================
+// Patch
+// ============================================================================
+
+#[test]
+pub fn test_patch_01() {
+    // Jump targets become labels
+    let asm = check_patch("0x6003565b00", &[PUSHL(0),JUMP,JUMPDEST(0),STOP]);
+    check_relayout(&asm, "0x6003565b00", &[(0,0),(2,2),(3,3),(4,4)]);
+}
+
+#[test]
+pub fn test_patch_02() {
+    let mut asm = check_patch("0x6003565b00", &[PUSHL(0),JUMP,JUMPDEST(0),STOP]);
+    asm.insert(2, &[PUSH(vec![1]),POP]).unwrap();
+    check_relayout(&asm, "0x6006600150565b00", &[(0,0),(2,5),(3,6),(4,7)]);
+}
+
+#[test]
+pub fn test_patch_03() {
+    let mut asm = check_patch("0x600160075760025b00", &[PUSH(vec![1]),PUSHL(0),JUMPI,PUSH(vec![2]),JUMPDEST(0),STOP]);
+    asm.delete(5).unwrap();
+    check_relayout(&asm, "0x60016005575b00", &[(0,0),(2,2),(4,4),(7,5),(8,6)]);
+}
+
+#[test]
+pub fn test_patch_04() {
+    let mut asm = check_patch("0x6001506006565b00", &[PUSH(vec![1]),POP,PUSHL(0),JUMP,JUMPDEST(0),STOP]);
+    asm.replace(0, PUSH(vec![0x12,0x34])).unwrap();
+    check_relayout(&asm, "0x611234506007565b00", &[(0,0),(2,3),(3,4),(5,6),(6,7),(7,8)]);
+}
+
+#[test]
+pub fn test_patch_05() {
+    // Offsets must identify an instruction
+    let mut asm = check_patch("0x6003565b00", &[PUSHL(0),JUMP,JUMPDEST(0),STOP]);
+    assert_eq!(asm.delete(1), Err(PatchError::InvalidOffset(1)));
+    assert_eq!(asm.insert(5, &[STOP]), Err(PatchError::InvalidOffset(5)));
+    assert_eq!(PatchError::InvalidOffset(1).to_string(), "no instruction at 0x1");
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Check that an assembly assembles to a given hex string.
+fn check_bytes(asm: &Assembly, hex: &str) {
+    let bytes = asm.to_bytecode().to_bytes().unwrap();
+    assert_eq!(bytes.to_hex_string(), hex);
+}
+
+/// Check that lifting the disassembly of a given hex string produces
+/// a given sequence of instructions, and assembles back to the
+/// original bytes.
+fn check_lift(hex: &str, insns: &[Instruction]) -> Assembly {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let asm = Assembly::lift(&disasm);
+    let lifted : Vec<Instruction> = asm.items().iter().map(|i| i.insn.clone()).collect();
+    assert_eq!(lifted, insns);
+    check_bytes(&asm,hex);
+    asm
+}
+
+/// Check that lifting the disassembly of a given hex string for
+/// patching produces a given sequence of instructions.
+fn check_patch(hex: &str, insns: &[Instruction]) -> Assembly {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let asm = Assembly::lift_relocatable(&disasm);
+    let lifted : Vec<Instruction> = asm.items().iter().map(|i| i.insn.clone()).collect();
+    assert_eq!(lifted, insns);
+    asm
+}
+
+/// Check that laying out an assembly produces a given hex string and
+/// mapping of old offsets to new.
+fn check_relayout(asm: &Assembly, hex: &str, offsets: &[(usize,usize)]) {
+    let (bytes,map) = asm.relayout().unwrap();
+    assert_eq!(bytes.to_hex_string(), hex);
+    assert_eq!(map, offsets.iter().copied().collect::<BTreeMap<usize,usize>>());
+}
@@ -152,6 +152,13 @@ pub fn test_mul_02() {
     check(&p, "0x6003600202600102600c57fe5b");
 }
 
+#[test]
+pub fn test_mul_03() {
+    // Left-hand side scheduled first
+    let p = "assert (1+2)*3;";
+    check(&p, "0x6002600101600302600c57fe5b");
+}
+
 #[test]
 pub fn test_div_01() {
     let p = "assert 1 / 2;";
@@ -170,6 +177,57 @@ pub fn test_mod_01() {
     check(&p, "0x6002600106600957fe5b");
 }
 
+#[test]
+pub fn test_exp_01() {
+    let p = "assert 1 ** 2;";
+    check(&p, "0x600260010a600957fe5b");
+}
+
+#[test]
+pub fn test_shl_01() {
+    let p = "assert 1 << 2;";
+    check(&p, "0x600160021b600957fe5b");
+}
+
+#[test]
+pub fn test_shl_02() {
+    let p = "assert 1 << 0xff;";
+    check(&p, "0x600160ff1b600957fe5b");
+}
+
+#[test]
+pub fn test_shl_03() {
+    // Constant shift too large
+    let ts = Parser::new("assert 1 << 256;").parse().unwrap();
+    assert!(Bytecode::try_from(ts.as_slice()).is_err());
+}
+
+#[test]
+pub fn test_shr_01() {
+    let p = "assert 8 >> 2;";
+    check(&p, "0x600860021c600957fe5b");
+}
+
+#[test]
+pub fn test_shr_02() {
+    // Constant shift too large
+    let ts = Parser::new("assert 8 >> 0x0100;").parse().unwrap();
+    assert!(Bytecode::try_from(ts.as_slice()).is_err());
+}
+
+#[test]
+pub fn test_sar_01() {
+    let p = "assert (0 - 8) >>> 2;";
+    check(&p, "0x600860000360021d600c57fe5b");
+}
+
+#[test]
+pub fn test_sar_02() {
+    // Constant shift too large
+    let ts = Parser::new("assert 8 >>> 256;").parse().unwrap();
+    assert!(Bytecode::try_from(ts.as_slice()).is_err());
+}
+
 // ============================================================================
 // Binary Comparators
 // ============================================================================
@@ -180,6 +238,13 @@ pub fn test_lt_01() {
     check(&p, "0x6002600110600957fe5b");
 }
 
+#[test]
+pub fn test_lt_02() {
+    // Left-hand side scheduled first
+    let p = "assert (1+2) < 3;";
+    check(&p, "0x6002600101600311600c57fe5b");
+}
+
 #[test]
 pub fn test_lteq_01() {
     let p = "assert 1 <= 2;";
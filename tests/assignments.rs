@@ -44,6 +44,37 @@ pub fn test_storage_03() {
     check(&p, "0x6002600101600055");
 }
 
+// ============================================================================
+// Tuples
+// ============================================================================
+
+#[test]
+pub fn test_tuple_01() {
+    let p = "(memory[0], memory[0x20]) = (1, 2);";
+    check(&p, "0x60026001600052602052");
+}
+
+#[test]
+pub fn test_tuple_02() {
+    // Right-hand side evaluated before assignment
+    let p = "(memory[0], storage[0]) = (1, memory[0]);";
+    check(&p, "0x6000516001600052600055");
+}
+
+#[test]
+pub fn test_tuple_03() {
+    // Mismatched widths
+    let ts = Parser::new("(memory[0], memory[1]) = 1;").parse().unwrap();
+    assert!(Bytecode::try_from(ts.as_slice()).is_err());
+}
+
+#[test]
+pub fn test_tuple_04() {
+    // Tuple used as a single value
+    let ts = Parser::new("memory[0] = (1, 2);").parse().unwrap();
+    assert!(Bytecode::try_from(ts.as_slice()).is_err());
+}
+
 // ============================================================================
 // Helpers
 // ============================================================================
@@ -0,0 +1,139 @@
+use evmil::{Bytecode,CfaState,Disassembly,Hint,Parser,Term};
+
+// ============================================================================
+// Compilation
+// ============================================================================
+
+#[test]
+pub fn test_assume_01() {
+    let ts = parse("assume (calldatasize() >= 4);");
+    assert_eq!(ts[0].to_string(), "assume (calldatasize() >= 4);");
+    assert_eq!(ts, parse(&ts[0].to_string()));
+}
+
+#[test]
+pub fn test_assume_02() {
+    // Assumptions produce no code
+    let code = compile("assume (msg.value == 0);\nstop;");
+    assert_eq!(code.to_bytes().unwrap(), vec![0x00]);
+    assert_eq!(code.hints().unwrap(), vec![Hint::Assume(0,parse("assume (msg.value == 0);").remove(0).into_cond())]);
+}
+
+#[test]
+pub fn test_assume_03() {
+    // Located at the following instruction
+    let code = compile("memory[0] = 1;\nassume (calldatasize() > 3);\nstop;");
+    let hints = code.hints().unwrap();
+    assert_eq!(hints.len(), 1);
+    assert!(matches!(hints[0], Hint::Assume(5,_)));
+}
+
+#[test]
+pub fn test_assume_04() {
+    // Nothing follows
+    let code = compile("stop;\nassume (calldatasize() > 3);");
+    assert_eq!(code.hints().unwrap(), vec![]);
+}
+
+// ============================================================================
+// Analysis
+// ============================================================================
+
+#[test]
+pub fn test_analysis_01() {
+    let p = "assume (calldatasize() >= 4);\nif calldatasize() < 4 goto short;\nstop;\n.short\nrevert;";
+    check(&p, &[true,true], &[true,false]);
+}
+
+#[test]
+pub fn test_analysis_02() {
+    let p = "assume (msg.value == 0);\nif msg.value goto paid;\nstop;\n.paid\nrevert;";
+    check(&p, &[true,true], &[true,false]);
+}
+
+#[test]
+pub fn test_analysis_03() {
+    // Operands reversed
+    let p = "assume (4 <= calldatasize());\nif calldatasize() < 4 goto short;\nstop;\n.short\nrevert;";
+    check(&p, &[true,true], &[true,false]);
+}
+
+#[test]
+pub fn test_analysis_04() {
+    // Conjunction
+    let p = "assume (calldatasize() >= 4 && calldatasize() < 68);\nif calldatasize() == 100 goto x;\nstop;\n.x\nrevert;";
+    check(&p, &[true,true], &[true,false]);
+}
+
+#[test]
+pub fn test_analysis_05() {
+    // Not strong enough
+    let p = "assume (calldatasize() >= 2);\nif calldatasize() < 4 goto short;\nstop;\n.short\nrevert;";
+    check(&p, &[true,true], &[true,true]);
+}
+
+#[test]
+pub fn test_analysis_06() {
+    // Contradictory assumptions
+    let p = "assume (calldatasize() < 4);\nassume (calldatasize() >= 4);\ngoto end;\n.end\nstop;";
+    check(&p, &[true,true], &[true,false]);
+}
+
+#[test]
+pub fn test_analysis_07() {
+    // Bounds are shown with the state
+    let code = compile("assume (calldatasize() >= 4 && calldatasize() <= 0x43);\nstop;");
+    let bytes = code.to_bytes().unwrap();
+    let hints = code.hints().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_hints(&hints).build();
+    assert_eq!(disasm.get_state(0).to_string(), "(0..0)[]{CALLDATASIZE:0x4..0x43}");
+}
+
+#[test]
+pub fn test_analysis_08() {
+    // Constants wider than sixteen bytes are not truncated
+    let p = format!("assume (calldatasize() < 4);\nif calldatasize() < 0x1{} goto big;\nstop;\n.big\nrevert;","0".repeat(32));
+    check(&p, &[true,true], &[true,true]);
+    let p = format!("assume (calldatasize() < 4);\nif calldatasize() < 0x1{} goto big;\nstop;\n.big\nrevert;","0".repeat(30));
+    check(&p, &[true,true], &[true,true]);
+    let p = format!("if calldatasize() == 0x1{} goto big;\nstop;\n.big\nrevert;","0".repeat(32));
+    check(&p, &[true,true], &[true,true]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+trait IntoCond {
+    fn into_cond(self) -> Term;
+}
+
+impl IntoCond for Term {
+    fn into_cond(self) -> Term {
+        match self {
+            Term::Assume(e) => *e,
+            _ => panic!("expected assumption")
+        }
+    }
+}
+
+fn parse(stmts: &str) -> Vec<Term> {
+    Parser::new(stmts).parse().unwrap()
+}
+
+fn compile(stmts: &str) -> Bytecode {
+    Bytecode::try_from(parse(stmts).as_slice()).unwrap()
+}
+
+/// Check which blocks are reachable when a given program is analysed
+/// without, and then with, its assumptions.
+fn check(stmts: &str, without: &[bool], with: &[bool]) {
+    let code = compile(stmts);
+    let bytes = code.to_bytes().unwrap();
+    let hints = code.hints().unwrap();
+    for (hs,expected) in [(&hints[..0],without),(&hints[..],with)] {
+        let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_hints(hs).build();
+        let reachable : Vec<bool> = (0..disasm.blocks().len()).map(|i| disasm.is_block_reachable(i)).collect();
+        assert_eq!(reachable, expected);
+    }
+}
@@ -0,0 +1,77 @@
+use evmil::{Backend,Bytecode,Compiler,Instruction,Parser,Term,ToHexString};
+use evmil::Instruction::*;
+
+// ============================================================================
+// Backends
+// ============================================================================
+
+#[test]
+pub fn test_backend_01() {
+    // The EVM backend is the default
+    let mut code = Bytecode::new();
+    compile(&mut code, "if 1 goto a;\n.a\nstop;");
+    assert_eq!(code.to_bytes().unwrap().to_hex_string(), "0x60016005575b00");
+}
+
+#[test]
+pub fn test_backend_02() {
+    let mut backend = Recorder::default();
+    compile(&mut backend, "memory[0] = 1;\nstop;");
+    assert_eq!(backend.insns, vec![PUSH(vec![1]),PUSH(vec![0]),MSTORE,STOP]);
+    assert_eq!(backend.labels, 0);
+}
+
+#[test]
+pub fn test_backend_03() {
+    // Labels are allocated through the backend
+    let mut backend = Recorder::default();
+    compile(&mut backend, "goto a;\n.a\nassume (msg.value == 0);\nstop;");
+    assert_eq!(backend.insns, vec![PUSHL(0),JUMP,JUMPDEST(0),STOP]);
+    assert_eq!(backend.labels, 1);
+    assert_eq!(backend.assumptions, vec![3]);
+}
+
+#[test]
+pub fn test_backend_04() {
+    // Through a trait object
+    let mut backend = Recorder::default();
+    let dynamic : &mut dyn Backend = &mut backend;
+    compile(dynamic, "revert;");
+    assert_eq!(backend.insns, vec![PUSH(vec![0]),PUSH(vec![0]),REVERT]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// A backend which records what it is given.
+#[derive(Default)]
+struct Recorder {
+    insns: Vec<Instruction>,
+    labels: usize,
+    /// Number of instructions preceding each assumption.
+    assumptions: Vec<usize>
+}
+
+impl Backend for Recorder {
+    fn fresh_label(&mut self) -> usize {
+        self.labels += 1;
+        self.labels - 1
+    }
+
+    fn emit(&mut self, insn: Instruction) {
+        self.insns.push(insn);
+    }
+
+    fn assume(&mut self, _cond: Term) {
+        self.assumptions.push(self.insns.len());
+    }
+}
+
+fn compile<B:Backend+?Sized>(backend: &mut B, stmts: &str) {
+    let terms = Parser::new(stmts).parse().unwrap();
+    let mut compiler = Compiler::new(backend);
+    for t in &terms {
+        compiler.translate(t).unwrap();
+    }
+}
@@ -0,0 +1,41 @@
+use evmil::{Bisection,FromHexString,bisect,cfa_panics};
+use evmil::Instruction::*;
+
+#[test]
+pub fn test_bisect_01() {
+    // Nothing fails
+    let bytes = "0x600160020100".from_hex_string().unwrap();
+    assert_eq!(bisect(&bytes,cfa_panics), None);
+}
+
+#[test]
+pub fn test_bisect_02() {
+    // Second add underflows the stack
+    let bytes = "0x6001600201015b00".from_hex_string().unwrap();
+    let b = Bisection{prefix: 6, pc: 5, insn: ADD};
+    assert_eq!(bisect(&bytes,cfa_panics), Some(b));
+}
+
+#[test]
+pub fn test_bisect_03() {
+    // Arbitrary check
+    let bytes = "0x6001600201015b00".from_hex_string().unwrap();
+    let b = Bisection{prefix: 4, pc: 2, insn: PUSH(vec![2])};
+    assert_eq!(bisect(&bytes,|bs| bs.len() >= 3), Some(b));
+}
+
+#[test]
+pub fn test_bisect_04() {
+    // Empty sequence fails
+    let b = Bisection{prefix: 0, pc: 0, insn: STOP};
+    assert_eq!(bisect(&[],|_| true), Some(b));
+    assert_eq!(bisect(&[],|_| false), None);
+}
+
+#[test]
+pub fn test_bisect_05() {
+    // Empty prefix fails
+    let bytes = "0x600160020100".from_hex_string().unwrap();
+    let b = Bisection{prefix: 0, pc: 0, insn: STOP};
+    assert_eq!(bisect(&bytes,|_| true), Some(b));
+}
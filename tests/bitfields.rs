@@ -0,0 +1,144 @@
+use evmil::{Bytecode,Parser,Term,ToHexString};
+use evmil::opt::coalesce_fields;
+
+// ============================================================================
+// Reads
+// ============================================================================
+
+#[test]
+pub fn test_read_01() {
+    let p = "storage[0] = storage[1][8:16];";
+    check(&p, "0x60015460081c61ffff16600055");
+}
+
+#[test]
+pub fn test_read_02() {
+    // Nested fields
+    let p = "memory[0] = storage[0][0:8][4:4];";
+    check(&p, "0x60005460ff1660041c600f16600052");
+}
+
+#[test]
+pub fn test_read_03() {
+    // Whole word
+    let p = "memory[0] = storage[0][0:256];";
+    check(&p, "0x600054600052");
+}
+
+// ============================================================================
+// Writes
+// ============================================================================
+
+#[test]
+pub fn test_write_01() {
+    let p = "storage[0][8:16] = 1;";
+    check(&p, "0x600161ffff1660081b6000547fffffffffffffffffffffffffffffffffffffffffffffffffffffffffff0000ff1617600055");
+}
+
+#[test]
+pub fn test_write_02() {
+    // Whole word, so nothing is retained
+    let p = "storage[0][0:256] = 1;";
+    check(&p, "0x6001600055");
+}
+
+#[test]
+pub fn test_write_03() {
+    // Disjoint fields written together
+    let p = "(storage[0][0:8],storage[0][8:8]) = (1,2);";
+    check(&p, "0x6002600160ff169060ff1660081b176000547fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff00001617600055");
+}
+
+#[test]
+pub fn test_write_04() {
+    // Overlapping fields written separately
+    let p = "(storage[0][0:8],storage[0][4:8]) = (1,2);";
+    check(&p, "0x6002600160ff166000547fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff00161760005560ff1660041b6000547ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff00f1617600055");
+}
+
+#[test]
+pub fn test_write_05() {
+    // Field extends beyond word
+    let p = "storage[0][250:8] = 1;";
+    check_err(&p);
+}
+
+#[test]
+pub fn test_write_06() {
+    // Empty field
+    let p = "memory[0] = storage[0][8:0];";
+    check_err(&p);
+}
+
+// ============================================================================
+// Coalescing
+// ============================================================================
+
+#[test]
+pub fn test_coalesce_01() {
+    let p = "storage[0][0:8] = 1;\nstorage[0][8:8] = 2;";
+    check_coalesce(&p, "(storage[0][0:8],storage[0][8:8]) = (1,2);");
+}
+
+#[test]
+pub fn test_coalesce_02() {
+    // Different words
+    let p = "storage[0][0:8] = 1;\nstorage[1][8:8] = 2;";
+    check_coalesce(&p, "storage[0][0:8] = 1;\nstorage[1][8:8] = 2;");
+}
+
+#[test]
+pub fn test_coalesce_03() {
+    // Second value reads storage
+    let p = "storage[0][0:8] = 1;\nstorage[0][8:8] = storage[1];";
+    check_coalesce(&p, "storage[0][0:8] = 1;\nstorage[0][8:8] = storage[1];");
+}
+
+#[test]
+pub fn test_coalesce_04() {
+    // Overlapping fields
+    let p = "storage[0][0:8] = 1;\nstorage[0][4:8] = 2;\nstorage[0][16:8] = 3;";
+    check_coalesce(&p, "storage[0][0:8] = 1;\n(storage[0][4:8],storage[0][16:8]) = (2,3);");
+}
+
+#[test]
+pub fn test_coalesce_05() {
+    // Separated by a label
+    let p = "storage[0][0:8] = 1;\n.l\nstorage[0][8:8] = 2;";
+    check_coalesce(&p, "storage[0][0:8] = 1;\n.l\nstorage[0][8:8] = 2;");
+}
+
+#[test]
+pub fn test_coalesce_06() {
+    let p = "storage[0][0:8] = 1;\nstorage[0][8:8] = 2;";
+    let ts = coalesce_fields(parse(&p));
+    let code = Bytecode::try_from(ts.as_slice()).unwrap();
+    let bytes : Vec<u8> = code.try_into().unwrap();
+    assert_eq!(bytes.to_hex_string(), "0x6002600160ff169060ff1660081b176000547fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff00001617600055");
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn parse(stmts: &str) -> Vec<Term> {
+    Parser::new(stmts).parse().unwrap()
+}
+
+/// Check that compiling a given program produces a given hex string.
+fn check(stmts: &str, hex: &str) {
+    let code = Bytecode::try_from(parse(stmts).as_slice()).unwrap();
+    let bytes : Vec<u8> = code.try_into().unwrap();
+    assert_eq!(hex, bytes.to_hex_string());
+}
+
+/// Check that compiling a given program fails.
+fn check_err(stmts: &str) {
+    assert!(Bytecode::try_from(parse(stmts).as_slice()).is_err());
+}
+
+/// Check that coalescing the fields of a given program produces
+/// another.
+fn check_coalesce(stmts: &str, expected: &str) {
+    assert_eq!(coalesce_fields(parse(stmts)), parse(expected));
+}
@@ -56,6 +56,55 @@ pub fn test_ifgoto_05() {
     check(&p, "0x6001600a576000600a575b");
 }
 
+#[test]
+pub fn test_ifgoto_06() {
+    // Negated comparison fused into branch
+    let p = "if msg.value <= 1 && msg.value != 2 goto lab; .lab";
+    check(&p, "0x60013411600f5760023414156010575b5b");
+}
+
+#[test]
+pub fn test_ifgoto_07() {
+    // Negated comparison with true target
+    let p = "if msg.value >= 1 || msg.value == 2 goto lab; .lab";
+    check(&p, "0x6001341015600f5760023414600f575b");
+}
+
+// ============================================================================
+// Switch
+// ============================================================================
+
+#[test]
+pub fn test_switch_01() {
+    let p = "switch calldatasize() { 1: goto a; } .a";
+    check(&p, "0x3680600114600c57506011565b506012565b5b");
+}
+
+#[test]
+pub fn test_switch_02() {
+    // No cases
+    let p = "switch calldatasize() { } stop;";
+    check(&p, "0x365000");
+}
+
+#[test]
+pub fn test_switch_03() {
+    // Binary search
+    let p = "switch calldatasize() {
+  9: goto a; 2: goto b; 7: goto c; 4: goto d; 1: goto e; 5: goto f; 3: goto g;
+}
+revert;
+.a succeed 0; .b succeed 1; .c succeed 2; .d succeed 3; .e succeed 4; .f succeed 5; .g succeed 6;";
+    check(&p, "0x36600481106027578060041460515780600514605b5780600714604c5780600914604257603d565b8060011460565780600214604757806003146060575b506065565b50606b565b506076565b506081565b50608c565b506097565b5060a2565b5060ad565b60006000fd5b600060005260206000f35b600160005260206000f35b600260005260206000f35b600360005260206000f35b600460005260206000f35b600560005260206000f35b600660005260206000f3");
+}
+
+#[test]
+pub fn test_switch_04() {
+    // Duplicate cases
+    let ts = Parser::new("switch calldatasize() { 1: goto a; 0x1: goto b; }").parse().unwrap();
+    assert!(Bytecode::try_from(ts.as_slice()).is_err());
+}
+
 // ============================================================================
 // Helpers
 // ============================================================================
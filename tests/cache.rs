@@ -0,0 +1,93 @@
+#![cfg(feature = "std")]
+use std::path::PathBuf;
+use serde_json::json;
+use evmil::{Analysis,AnalysisCache,ANALYSIS_VERSION,Block,FromHexString};
+
+#[test]
+fn test_cache_01() {
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let mut cache = AnalysisCache::new();
+    assert!(cache.get(&bytes).is_none());
+    let analysis = cache.analyse(&bytes).unwrap();
+    assert_eq!(analysis.blocks, vec![Block::new(0,3),Block::new(3,5)]);
+    assert_eq!(analysis.reachable, vec![true,true]);
+    assert_eq!(analysis.successors, vec![vec![1],vec![]]);
+    assert_eq!(cache.get(&bytes), Some(&analysis));
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn test_cache_02() {
+    // Unreachable code
+    let bytes = "0x00600100".from_hex_string().unwrap();
+    let analysis = AnalysisCache::new().analyse(&bytes).unwrap();
+    assert_eq!(analysis.reachable, vec![true,false]);
+    assert_eq!(Analysis::from_json(&analysis.to_json()), Some(analysis));
+}
+
+#[test]
+fn test_cache_03() {
+    // Persists across caches
+    let dir = temp_dir("03");
+    let bytes = "0x600160075760025b00".from_hex_string().unwrap();
+    let analysis = AnalysisCache::with_dir(&dir).unwrap().analyse(&bytes).unwrap();
+    let mut cache = AnalysisCache::with_dir(&dir).unwrap();
+    assert!(cache.is_empty());
+    assert_eq!(cache.get(&bytes), Some(&analysis));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cache_04() {
+    // Corrupt entries are ignored
+    let dir = temp_dir("04");
+    let bytes = "0x00".from_hex_string().unwrap();
+    let mut cache = AnalysisCache::with_dir(&dir).unwrap();
+    cache.analyse(&bytes).unwrap();
+    for f in std::fs::read_dir(&dir).unwrap() {
+        std::fs::write(f.unwrap().path(),"{").unwrap();
+    }
+    assert!(AnalysisCache::with_dir(&dir).unwrap().get(&bytes).is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cache_05() {
+    // Entries from a different version are discarded
+    let dir = temp_dir("05");
+    let bytes = "0x00".from_hex_string().unwrap();
+    let mut cache = AnalysisCache::with_dir(&dir).unwrap();
+    let analysis = cache.analyse(&bytes).unwrap();
+    for f in std::fs::read_dir(&dir).unwrap() {
+        let mut json = analysis.to_json();
+        json["version"] = json!("0.0.0/0");
+        std::fs::write(f.unwrap().path(),json.to_string()).unwrap();
+    }
+    assert!(AnalysisCache::with_dir(&dir).unwrap().get(&bytes).is_none());
+    // Reanalysing overwrites the stale entry
+    assert_eq!(AnalysisCache::with_dir(&dir).unwrap().analyse(&bytes).unwrap(), analysis);
+    assert_eq!(AnalysisCache::with_dir(&dir).unwrap().get(&bytes), Some(&analysis));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cache_06() {
+    // Successor ids must identify blocks
+    let json = json!({"version": ANALYSIS_VERSION, "blocks": [[0,1]], "reachable": [true], "successors": [[0]]});
+    assert!(Analysis::from_json(&json).is_some());
+    let json = json!({"version": ANALYSIS_VERSION, "blocks": [[0,1]], "reachable": [true], "successors": [[1]]});
+    assert!(Analysis::from_json(&json).is_none());
+    // Version must be present
+    let json = json!({"blocks": [[0,1]], "reachable": [true], "successors": [[0]]});
+    assert!(Analysis::from_json(&json).is_none());
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("evmil_cache_{}_{}",name,std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
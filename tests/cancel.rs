@@ -0,0 +1,88 @@
+use std::sync::atomic::AtomicBool;
+use evmil::{CfaState,Disassembly,FromHexString};
+#[cfg(feature = "std")]
+use evmil::Deadline;
+
+// ============================================================================
+// Build
+// ============================================================================
+
+#[test]
+pub fn test_cancel_build_01() {
+    // Not cancelled
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let d : Disassembly<CfaState> = Disassembly::new(&bytes).build_with(&AtomicBool::new(false)).ok().unwrap();
+    assert!(d.is_block_reachable(1));
+}
+
+#[test]
+pub fn test_cancel_build_02() {
+    // Cancelled before starting, giving partial results
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let r = Disassembly::<CfaState>::new(&bytes).build_with(&AtomicBool::new(true));
+    let d = r.err().unwrap().0;
+    assert!(d.is_block_reachable(0));
+    assert!(!d.is_block_reachable(1));
+}
+
+#[test]
+#[cfg(feature = "std")]
+pub fn test_cancel_build_03() {
+    // Deadline already passed
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let deadline = Deadline::after(std::time::Duration::ZERO);
+    assert!(Disassembly::<CfaState>::new(&bytes).build_with(&deadline).is_err());
+    // Deadline far away
+    let deadline = Deadline::after(std::time::Duration::from_secs(3600));
+    assert!(Disassembly::<CfaState>::new(&bytes).build_with(&deadline).is_ok());
+}
+
+// ============================================================================
+// Liveness
+// ============================================================================
+
+#[test]
+pub fn test_cancel_liveness_01() {
+    let bytes = "0x6001600557005b5000".from_hex_string().unwrap();
+    let d : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(d.liveness_with(&AtomicBool::new(false)).unwrap(), d.liveness());
+    assert!(d.liveness_with(&(AtomicBool::new(false),AtomicBool::new(true))).is_err());
+}
+
+// ============================================================================
+// Iteration Limits
+// ============================================================================
+
+#[test]
+pub fn test_build_limited_01() {
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let d = Disassembly::<CfaState>::new(&bytes).build_limited(2).ok().unwrap();
+    assert!(d.is_block_reachable(1));
+}
+
+#[test]
+pub fn test_build_limited_02() {
+    // Still changing after one pass
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let r = Disassembly::<CfaState>::new(&bytes).build_limited(1).err().unwrap();
+    assert_eq!(r.changing, vec![1]);
+    assert!(r.partial.is_block_reachable(1));
+}
+
+#[test]
+pub fn test_build_limited_03() {
+    // No passes
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let r = Disassembly::<CfaState>::new(&bytes).build_limited(0).err().unwrap();
+    assert_eq!(r.changing, vec![0]);
+    assert!(!r.partial.is_block_reachable(1));
+}
+
+#[test]
+pub fn test_build_limited_04() {
+    // Loop which converges
+    let bytes = "0x60035b6001900380600257".from_hex_string().unwrap();
+    let d1 = Disassembly::<CfaState>::new(&bytes).build();
+    let d2 = Disassembly::<CfaState>::new(&bytes).build_limited(100).ok().unwrap();
+    assert_eq!(d1.to_vec(), d2.to_vec());
+}
@@ -0,0 +1,64 @@
+use evmil::{AbstractState,CfaState,Disassembly,FromHexString};
+use evmil::dfa::AbstractValue;
+
+const UNKNOWN : AbstractValue = AbstractValue::Unknown;
+
+#[test]
+fn test_codecopy_01() {
+    // CODESIZE
+    check("0x3800", 1, AbstractValue::Known(2));
+    check("0x38600100", 1, AbstractValue::Known(4));
+}
+
+#[test]
+fn test_codecopy_02() {
+    // CODECOPY(0,0xd,0x20); MLOAD(0); JUMP
+    let hex = format!("0x6020600d600039600051565b00{}0b","00".repeat(31));
+    check(&hex, 10, AbstractValue::Known(11));
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert!(disasm.is_block_reachable(1));
+    assert_eq!(disasm.invalid_jumps(), vec![]);
+}
+
+#[test]
+fn test_codecopy_03() {
+    // Words too large to track are unknown
+    let hex = format!("0x6020600b6000396000510000{}","ff".repeat(32));
+    check(&hex, 10, UNKNOWN);
+}
+
+#[test]
+fn test_codecopy_04() {
+    // Bytes beyond the end of the code are zero
+    check("0x6020604060003960005100", 10, AbstractValue::Known(0));
+}
+
+#[test]
+fn test_codecopy_05() {
+    // CODECOPY(0,0xd,0x20)
+    let hex = format!("0x6020600d600039600051565b00{}0b","00".repeat(31));
+    assert_eq!(copies(&hex), vec![(6,13..45)]);
+    // CODECOPY(0,0,CALLDATASIZE)
+    assert_eq!(copies("0x36600060003900"), vec![]);
+    // CODECOPY(0,0,CODESIZE)
+    assert_eq!(copies("0x38600060003900"), vec![(5,0..7)]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Check the value on top of the stack at a given program point.
+fn check(hex: &str, pc: usize, expected: AbstractValue) {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.get_state(pc).peek(0), expected);
+}
+
+/// Identify the regions copied by CODECOPY for a given hex string.
+fn copies(hex: &str) -> Vec<(usize,std::ops::Range<usize>)> {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    disasm.code_copies()
+}
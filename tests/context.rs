@@ -0,0 +1,103 @@
+use evmil::{AbstractState,CfaState,Disassembly,FromHexString,InvalidJump};
+use evmil::Instruction::*;
+
+// Internal function (at 0x0d) called from two sites (returning to 0x05
+// and 0x0b).
+const CALLS : &str = "0x6005600d565b600b600d565b005b56";
+
+// ============================================================================
+// Context Sensitivity
+// ============================================================================
+
+#[test]
+pub fn test_context_01() {
+    // Return jump cannot be resolved when callers are merged
+    let bytes = CALLS.from_hex_string().unwrap();
+    let disasm = check(&bytes,0);
+    assert_eq!(disasm.invalid_jumps(), vec![InvalidJump::Unknown{pc: 14}]);
+    assert!(!disasm.is_block_reachable(2));
+}
+
+#[test]
+pub fn test_context_02() {
+    let bytes = CALLS.from_hex_string().unwrap();
+    let disasm = check(&bytes,1);
+    assert!(disasm.invalid_jumps().is_empty());
+    assert!((0..disasm.blocks().len()).all(|i| disasm.is_block_reachable(i)));
+    assert_eq!(disasm.to_vec(), vec![PUSH(vec![5]),PUSH(vec![13]),JUMP,JUMPDEST(5),PUSH(vec![11]),PUSH(vec![13]),JUMP,JUMPDEST(11),STOP,JUMPDEST(13),JUMP]);
+}
+
+#[test]
+pub fn test_context_03() {
+    // Merged state at the function head
+    let bytes = CALLS.from_hex_string().unwrap();
+    let disasm = check(&bytes,2);
+    assert!(!disasm.get_state(14).peek(0).is_known());
+}
+
+#[test]
+pub fn test_context_04() {
+    // No change without calls
+    let bytes = "0x600160075760025b00".from_hex_string().unwrap();
+    let d1 = check(&bytes,0);
+    let d2 = check(&bytes,3);
+    assert_eq!(d1.to_vec(), d2.to_vec());
+    assert_eq!(d1.invalid_jumps(), d2.invalid_jumps());
+}
+
+#[test]
+pub fn test_context_05() {
+    // Nested calls (where 0x0d calls 0x15) need more context
+    let bytes = "0x6005600d565b600b600d565b005b60136015565b565b56".from_hex_string().unwrap();
+    assert_eq!(check(&bytes,2).invalid_jumps(), vec![InvalidJump::Unknown{pc: 20}]);
+    assert!(check(&bytes,3).invalid_jumps().is_empty());
+}
+
+// ============================================================================
+// Return Tracking
+// ============================================================================
+
+#[test]
+pub fn test_returns_01() {
+    let bytes = CALLS.from_hex_string().unwrap();
+    let disasm = check_returns(&bytes,0);
+    assert!(disasm.invalid_jumps().is_empty());
+    assert!((0..disasm.blocks().len()).all(|i| disasm.is_block_reachable(i)));
+}
+
+#[test]
+pub fn test_returns_02() {
+    // Nested calls (where 0x0d calls 0x15)
+    let bytes = "0x6005600d565b600b600d565b005b60136015565b565b56".from_hex_string().unwrap();
+    let disasm = check_returns(&bytes,0);
+    assert!(disasm.invalid_jumps().is_empty());
+    assert_eq!(disasm.to_vec().iter().filter(|i| matches!(i,DATA(_))).count(), 0);
+}
+
+#[test]
+pub fn test_returns_03() {
+    // Combined with context sensitivity
+    let bytes = "0x6005600d565b600b600d565b005b60136015565b565b56".from_hex_string().unwrap();
+    let disasm = check_returns(&bytes,1);
+    assert!(disasm.invalid_jumps().is_empty());
+}
+
+#[test]
+pub fn test_returns_04() {
+    // Unknown jumps outside of calls remain unresolved
+    let bytes = "0x600035565b00".from_hex_string().unwrap();
+    let disasm = check_returns(&bytes,0);
+    assert_eq!(disasm.invalid_jumps(), vec![InvalidJump::Unknown{pc: 3}]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(bytes: &[u8], k: usize) -> Disassembly<'_,CfaState> {
+    Disassembly::new(bytes).with_context_depth(k).build()
+}
+
+fn check_returns(bytes: &[u8], k: usize) -> Disassembly<'_,CfaState> {
+    Disassembly::new(bytes).with_context_depth(k).with_return_tracking(true).build()
+}
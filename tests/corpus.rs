@@ -0,0 +1,78 @@
+use evmil::{Corpus,CorpusReport,FailureKind,FromHexString,Options};
+
+#[test]
+pub fn test_corpus_01() {
+    let report = analyze(&[]);
+    assert_eq!(report, CorpusReport::default());
+}
+
+#[test]
+pub fn test_corpus_02() {
+    let report = analyze(&[("a","0x00"),("b","0x6001600201005b00")]);
+    let labels : Vec<&str> = report.reports.iter().map(|(l,_)| l.as_str()).collect();
+    assert_eq!(labels, vec!["a","b"]);
+    assert!(report.failures.is_empty());
+    assert_eq!(report.statistics.instructions(), 5);
+    assert_eq!(report.statistics.count(0x00), 2);
+    assert_eq!(report.statistics.block_sizes, vec![1,4]);
+    assert_eq!(report.statistics.data_bytes, 2);
+    assert_eq!(report.coverage.code, 7);
+    assert_eq!(report.coverage.data, 2);
+}
+
+#[test]
+pub fn test_corpus_03() {
+    // Lints only included when requested
+    let units = [("a","0x00"),("b","0x33ff"),("c","0x33ff")];
+    assert_eq!(analyze(&units).counts.get("S001"), None);
+    let options = Options{lint: true, ..Options::default()};
+    let report = corpus(&units).analyze(&options);
+    assert_eq!(report.counts.get("S001"), Some(&2));
+    assert_eq!(report.labels_with("S001"), vec!["b","c"]);
+    assert!(report.labels_with("S002").is_empty());
+}
+
+#[test]
+#[cfg(feature = "std")]
+pub fn test_corpus_04() {
+    // Parallel analysis gives the same results
+    let units = [("a","0x00"),("b","0x33ff"),("c","0x6001600201005b00"),("d","0x6000358063aabbccdd14600f5700015b00")];
+    let options = Options{lint: true, ..Options::default()};
+    let c = corpus(&units);
+    let expected = c.analyze(&options);
+    for threads in 0..6 {
+        assert_eq!(c.analyze_parallel(&options,threads), expected);
+    }
+}
+
+#[test]
+pub fn test_corpus_05() {
+    let c = corpus(&[("a","0x00"),("a","0x01")]);
+    assert_eq!(c.len(), 2);
+    let units : Vec<(&str,&[u8])> = c.iter().collect();
+    assert_eq!(units, vec![("a",&[0x00][..]),("a",&[0x01][..])]);
+}
+
+#[test]
+pub fn test_corpus_06() {
+    // Triage by the most serious failure
+    let report = analyze(&[("a","0x00"),("b","0x60003556"),("c","0x0c"),("d","0x600035600a57600035565b0c")]);
+    assert_eq!(report.labels_failing(FailureKind::Precision), vec!["b"]);
+    assert_eq!(report.labels_failing(FailureKind::Structural), vec!["c","d"]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn corpus(units: &[(&str,&str)]) -> Corpus {
+    let mut corpus = Corpus::new();
+    for (label,hex) in units {
+        corpus.insert(*label,hex.from_hex_string().unwrap());
+    }
+    corpus
+}
+
+fn analyze(units: &[(&str,&str)]) -> CorpusReport {
+    corpus(units).analyze(&Options::default())
+}
@@ -0,0 +1,67 @@
+use evmil::{Bytecode,Compiler,Parser,Term,TermId};
+
+// ============================================================================
+// Term Costs
+// ============================================================================
+
+#[test]
+pub fn test_costs_01() {
+    let (_,costs) = compile("memory[0] = 1;\nstop;");
+    assert_eq!(costs, vec![(id(0,0),5,9),(id(0,3),2,3),(id(0,4),2,3),(id(1,0),1,0)]);
+}
+
+#[test]
+pub fn test_costs_02() {
+    // Costs map back onto terms
+    let (terms,costs) = compile("storage[0] = msg.value + 1;");
+    let cost = |t: &str| costs.iter().find(|(i,_,_)| terms[i.statement].subterm(i.index).unwrap().to_string() == t).map(|(_,s,g)| (*s,*g));
+    assert_eq!(cost("(msg.value + 1)"), Some((4,8)));
+    assert_eq!(cost("msg.value"), Some((1,2)));
+    assert_eq!(cost("1"), Some((2,3)));
+    assert_eq!(cost("storage[0] = (msg.value + 1);"), Some((7,111)));
+}
+
+#[test]
+pub fn test_costs_03() {
+    // Labels are assumed to require two bytes
+    let (_,costs) = compile("goto a;\n.a\nstop;");
+    assert_eq!(costs, vec![(id(0,0),4,11),(id(1,0),1,1),(id(2,0),1,0)]);
+}
+
+#[test]
+pub fn test_costs_05() {
+    // Comparisons branched on, including those fused with the branch
+    for op in ["<","<=",">",">=","==","!="] {
+        let (terms,costs) = compile(&format!("if msg.value {} 1 goto l;\n.l\nstop;",op));
+        let expr = format!("(msg.value {} 1)",op);
+        let cost = costs.iter().find(|(i,_,_)| terms[i.statement].subterm(i.index).unwrap().to_string() == expr);
+        assert_eq!(cost.map(|(_,s,g)| (*s,*g)), Some((4,8)), "{}", op);
+    }
+}
+
+#[test]
+pub fn test_costs_04() {
+    let terms = Parser::new("memory[0] = 1;").parse().unwrap();
+    assert_eq!(terms[0].subterm(0), Some(&terms[0]));
+    assert_eq!(terms[0].subterm(4).map(|t| t.to_string()), Some("1".to_string()));
+    assert_eq!(terms[0].subterm(5), None);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn compile(stmts: &str) -> (Vec<Term>,Vec<(TermId,usize,u64)>) {
+    let terms = Parser::new(stmts).parse().unwrap();
+    let mut code = Bytecode::new();
+    let mut compiler = Compiler::new(&mut code);
+    for t in &terms {
+        compiler.translate(t).unwrap();
+    }
+    let costs = compiler.term_costs();
+    (terms,costs)
+}
+
+fn id(statement: usize, index: usize) -> TermId {
+    TermId{statement,index}
+}
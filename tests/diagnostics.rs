@@ -0,0 +1,159 @@
+use evmil::{Bytecode,CfaState,Diagnostic,Disassembly,FromHexString,HeightMismatch,Instruction,Location,Parser,Severity,Term};
+use evmil::{FailureKind,InvalidJump,analyze};
+use evmil::FailureKind::{Precision,Structural};
+use evmil::util::Interval;
+
+#[test]
+pub fn test_diagnostic_01() {
+    // Missing semi-colon
+    let d = Parser::new("assert 1").parse().unwrap_err();
+    assert_eq!(d.severity, Severity::Error);
+    assert_eq!(d.code, "P003");
+    assert_eq!(d.location, Location::Span(8..8));
+}
+
+#[test]
+pub fn test_diagnostic_02() {
+    // Cannot assign to a literal
+    let lhs = Box::new(Term::Int(vec![1]));
+    let rhs = Box::new(Term::Int(vec![2]));
+    let d = Bytecode::try_from(&[Term::Assignment(lhs,rhs)]).err().unwrap();
+    assert!(d.is_error());
+    assert_eq!(d.code, "C003");
+    assert_eq!(d.location, Location::Unknown);
+}
+
+#[test]
+pub fn test_diagnostic_03() {
+    let d = Diagnostic::error("A001","invalid push").with_offset(0x1f).with_note("detail");
+    assert_eq!(d.to_string(), "error[A001]: invalid push (at 0x1f)\n  note: detail");
+}
+
+#[test]
+pub fn test_diagnostic_04() {
+    // Undefined opcode in reachable code
+    check("0x600c", &[]);
+    check("0x0c", &[Diagnostic::warning("D001","undefined opcode 0x0c").with_offset(0).with_failure(Structural)]);
+    check("0x60010c", &[Diagnostic::warning("D001","undefined opcode 0x0c").with_offset(2).with_failure(Structural)]);
+}
+
+#[test]
+pub fn test_diagnostic_05() {
+    // Undefined opcode in unreachable code
+    check("0x00", &[]);
+    check("0x005b0c", &[]);
+}
+
+#[test]
+pub fn test_diagnostic_06() {
+    // Invalid jump target
+    check("0x6003565b", &[]);
+    check("0x60035600", &[Diagnostic::error("D002","invalid jump target 0x3").with_offset(2).with_failure(Structural)]);
+}
+
+#[test]
+pub fn test_diagnostic_07() {
+    // Unknown jump target
+    check("0x60003556", &[Diagnostic::warning("D003","unknown jump target").with_offset(3).with_failure(Precision)]);
+}
+
+#[test]
+pub fn test_diagnostic_08() {
+    // Truncated push
+    check("0x6001", &[]);
+    check("0x61ff", &[Diagnostic::warning("D004","PUSH2 truncated by end of code").with_offset(0)
+                      .with_failure(Structural)
+                      .with_note("padded with 1 zero byte(s)")]);
+}
+
+#[test]
+pub fn test_diagnostic_09() {
+    // Stack heights differ at join
+    check("0x346007576001505b00", &[]);
+    check("0x3460065760015b00", &[Diagnostic::warning("D006","stack heights differ at join").with_offset(6)
+                                  .with_failure(Precision)
+                                  .with_note("height 0 from 0x3").with_note("height 1 from 0x4")]);
+}
+
+#[test]
+pub fn test_diagnostic_10() {
+    // Missing semi-colon on second line
+    let d = Parser::new("fail;\nfail fail;").parse().unwrap_err();
+    assert_eq!(d.code, "P003");
+    assert_eq!(d.location, Location::Span(10..11));
+}
+
+#[test]
+pub fn test_diagnostic_11() {
+    // Invalid dup, located at its offset
+    let mut code = Bytecode::new();
+    code.push(Instruction::PUSH(vec![1]));
+    code.push(Instruction::DUP(17));
+    let d = code.to_bytes().unwrap_err();
+    assert_eq!(d.code, "A002");
+    assert_eq!(d.location, Location::Offset(2));
+    let d = code.to_bytes_with_map().unwrap_err();
+    assert_eq!(d.location, Location::Offset(2));
+}
+
+#[test]
+pub fn test_height_mismatch_01() {
+    let bytes = "0x3460065760015b00".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let mismatch = HeightMismatch{offset: 6, incoming: vec![(3,Interval::new(0,0)),(4,Interval::new(1,1))]};
+    assert_eq!(disasm.height_mismatches(), vec![mismatch.clone()]);
+    assert_eq!(mismatch.to_string(), "stack heights differ at 0x6: 0 from 0x3, 1 from 0x4");
+}
+
+#[test]
+pub fn test_height_mismatch_02() {
+    // Unreachable predecessors are ignored
+    let bytes = "0x600756346001345b00".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert!(disasm.height_mismatches().is_empty());
+}
+
+#[test]
+pub fn test_failure_01() {
+    assert_eq!(InvalidJump::Target{pc: 2, target: 3}.failure(), Structural);
+    assert_eq!(InvalidJump::Unknown{pc: 3}.failure(), Precision);
+    assert_eq!(Structural.to_string(), "structural");
+    assert_eq!(Precision.to_string(), "precision");
+}
+
+#[test]
+pub fn test_failure_02() {
+    assert_eq!(failure("0x00"), None);
+    assert_eq!(failure("0x60003556"), Some(Precision));
+    assert_eq!(failure("0x60035600"), Some(Structural));
+}
+
+#[test]
+pub fn test_failure_03() {
+    // Structural failures are more serious
+    let report = analyze(&"0x600035600a57600035565b0c".from_hex_string().unwrap());
+    assert_eq!(report.failures(Precision).len(), 1);
+    assert_eq!(report.failures(Structural).len(), 1);
+    assert_eq!(report.failure(), Some(Structural));
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Determine the most serious kind of failure found when analysing a
+/// given hex string.
+fn failure(hex: &str) -> Option<FailureKind> {
+    analyze(&hex.from_hex_string().unwrap()).failure()
+}
+
+/// Check that disassembling a given hex string produces a given
+/// sequence of diagnostics.
+fn check(hex: &str, diags: &[Diagnostic]) {
+    // Parse hex string into bytes
+    let bytes = hex.from_hex_string().unwrap();
+    // Disassemble bytes
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    // Check against expected diagnostics
+    assert_eq!(diags, disasm.diagnostics());
+}
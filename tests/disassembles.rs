@@ -1,5 +1,7 @@
-use evmil::{Instruction,FromHexString,CfaState};
-use evmil::{Disassembly};
+use evmil::{AbstractState,Bytecode,Instruction,FromHexString,CfaState,ToHexString};
+use evmil::dfa::AbstractValue;
+use evmil::{Condition,Disassembly,Hint,InvalidJump,Mismatch,Reachability,metadata_end};
+use evmil::Reachability::*;
 use evmil::Instruction::*;
 
 // ============================================================================
@@ -319,6 +321,24 @@ pub fn test_disassemble_insn_47() {
     check(&bytecode, &[PUSH(vec![0x05]),SELFBALANCE,POP,JUMP,JUMPDEST(5)]);
 }
 
+#[test]
+pub fn test_disassemble_insn_48() {
+    let bytecode = format!("0x6005{}50565b","48");
+    check(&bytecode, &[PUSH(vec![0x05]),BASEFEE,POP,JUMP,JUMPDEST(5)]);
+}
+
+#[test]
+pub fn test_disassemble_insn_49() {
+    let bytecode = format!("0x60076000{}50565b","49");
+    check(&bytecode, &[PUSH(vec![0x07]),PUSH(vec![0x00]),BLOBHASH,POP,JUMP,JUMPDEST(7)]);
+}
+
+#[test]
+pub fn test_disassemble_insn_4a() {
+    let bytecode = format!("0x6005{}50565b","4a");
+    check(&bytecode, &[PUSH(vec![0x05]),BLOBBASEFEE,POP,JUMP,JUMPDEST(5)]);
+}
+
 // 50s
 
 #[test]
@@ -391,6 +411,30 @@ pub fn test_disassemble_insn_5b() {
     check(&bytecode, &[PUSH(vec![0x04]),JUMPDEST(2),JUMP,JUMPDEST(4)]);
 }
 
+#[test]
+pub fn test_disassemble_insn_5c() {
+    let bytecode = format!("0x60076001{}50565b","5c");
+    check(&bytecode, &[PUSH(vec![0x07]),PUSH(vec![0x01]),TLOAD,POP,JUMP,JUMPDEST(7)]);
+}
+
+#[test]
+pub fn test_disassemble_insn_5d() {
+    let bytecode = format!("0x6007600180{}565b","5d");
+    check(&bytecode, &[PUSH(vec![0x07]),PUSH(vec![0x01]),DUP(1),TSTORE,JUMP,JUMPDEST(7)]);
+}
+
+#[test]
+pub fn test_disassemble_insn_5e() {
+    let bytecode = format!("0x600860018080{}565b","5e");
+    check(&bytecode, &[PUSH(vec![0x08]),PUSH(vec![0x01]),DUP(1),DUP(1),MCOPY,JUMP,JUMPDEST(8)]);
+}
+
+#[test]
+pub fn test_disassemble_insn_5f() {
+    let bytecode = format!("0x{}600556005b","5f");
+    check(&bytecode, &[PUSH(vec![]),PUSH(vec![0x05]),JUMP,DATA(vec![0x00]),JUMPDEST(5)]);
+}
+
 // 60s
 
 #[test]
@@ -890,6 +934,340 @@ pub fn test_disassemble_split_04() {
     check("0x60055601025b", &[PUSH(vec![5]),JUMP,DATA(vec![1,2]),JUMPDEST(5)]);
 }
 
+// ============================================================================
+// Memory Tests
+// ============================================================================
+
+#[test]
+pub fn test_disassemble_memory_01() {
+    // Jump target round-trips through memory
+    check("0x600b6000526000515601025b00", &[PUSH(vec![0x0b]),PUSH(vec![0]),MSTORE,PUSH(vec![0]),MLOAD,JUMP,DATA(vec![1,2]),JUMPDEST(11),STOP]);
+}
+
+#[test]
+pub fn test_disassemble_memory_02() {
+    // Jump target overwritten by an unaligned store
+    check("0x600e6000526001601f52600051565b00", &[PUSH(vec![0x0e]),PUSH(vec![0]),MSTORE,PUSH(vec![1]),PUSH(vec![0x1f]),MSTORE,PUSH(vec![0]),MLOAD,JUMP,DATA(vec![0x5b,0])]);
+}
+
+// ============================================================================
+// Link References
+// ============================================================================
+
+#[test]
+pub fn test_disassemble_linkref_01() {
+    let hex = format!("0x73{}5000","00".repeat(20));
+    let bytes = hex.from_hex_string().unwrap();
+    // Without link references
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.to_vec(), vec![PUSH(vec![0;20]),POP,STOP]);
+    assert_eq!(disasm.get_state(21).peek(0), AbstractValue::Known(0));
+    // With link references
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_links(&[1]).build();
+    assert_eq!(disasm.to_vec(), vec![LINKREF(0),POP,STOP]);
+    assert_eq!(disasm.get_state(21).peek(0), AbstractValue::Unknown);
+}
+
+#[test]
+pub fn test_disassemble_linkref_02() {
+    // Round trip through the assembler
+    let mut code = Bytecode::new();
+    for insn in [PUSH(vec![1]),LINKREF(0),POP,STOP] { code.push(insn); }
+    let bytes = code.to_bytes().unwrap();
+    assert_eq!(bytes.to_hex_string(), format!("0x600173{}5000","00".repeat(20)));
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_links(&[3]).build();
+    assert_eq!(disasm.to_vec(), vec![PUSH(vec![1]),LINKREF(0),POP,STOP]);
+}
+
+#[test]
+pub fn test_disassemble_linkref_03() {
+    // Addresses already present are preserved
+    let hex = format!("0x73{}5000","01".repeat(20));
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_links(&[1]).build();
+    assert_eq!(disasm.to_vec(), vec![PUSH(vec![1;20]),POP,STOP]);
+    assert_eq!(disasm.reassemble(), Ok(bytes));
+}
+
+// ============================================================================
+// Round Trip
+// ============================================================================
+
+#[test]
+pub fn test_reassemble_01() {
+    // Data blocks and truncated pushes
+    check_reassemble("0x600556fe005b00");
+    check_reassemble("0x600456fe5b61ff");
+    check_reassemble("0x00010203");
+    check_reassemble(&format!("0x00{}","ff".repeat(40)));
+}
+
+#[test]
+pub fn test_reassemble_02() {
+    // Data given by hints
+    let bytes = "0x60035600aabb5b00".from_hex_string().unwrap();
+    for hints in [vec![Hint::Data(3..6)],vec![Hint::Data(1..2)],vec![Hint::Data(4..100)]] {
+        let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_hints(&hints).build();
+        assert_eq!(disasm.reassemble(), Ok(bytes.clone()));
+    }
+}
+
+#[test]
+pub fn test_reassemble_03() {
+    // Arbitrary bytes
+    let mut seed = 1u64;
+    for n in 0..1000 {
+        let bytes : Vec<u8> = (0..(n % 70) + 1).map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 56) as u8
+        }).collect();
+        // Without analysis, every block is reachable
+        let disasm : Disassembly<()> = Disassembly::new(&bytes);
+        assert_eq!(disasm.reassemble(), Ok(bytes));
+    }
+}
+
+#[test]
+pub fn test_reassemble_04() {
+    let m = Mismatch{offset: 1, expected: vec![0x01,0x02], actual: vec![0x00]};
+    assert_eq!(m.to_string(), "at 0x1: expected 0x0102, found 0x00");
+}
+
+// ============================================================================
+// Stack Bounds
+// ============================================================================
+
+#[test]
+pub fn test_stack_bounds_01() {
+    let bytes = "0x600160075760025b00".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.stack_bounds(0), (0,Some(0)));
+    assert_eq!(disasm.stack_bounds(4), (2,Some(2)));
+    assert_eq!(disasm.stack_bounds(5), (0,Some(0)));
+    // Join point
+    assert_eq!(disasm.stack_bounds(7), (0,Some(1)));
+    assert_eq!(disasm.stack_bounds(8), (0,Some(1)));
+}
+
+#[test]
+pub fn test_stack_bounds_02() {
+    // Unreachable
+    let bytes = "0x005b00".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.stack_bounds(0), (0,Some(0)));
+    assert_eq!(disasm.stack_bounds(1), (0,None));
+}
+
+// ============================================================================
+// Truncated Push
+// ============================================================================
+
+#[test]
+pub fn test_disassemble_padded_01() {
+    check("0x61ff", &[PUSHPAD(vec![0xff,0x00],1)]);
+}
+
+#[test]
+pub fn test_disassemble_padded_02() {
+    check("0x600163123456", &[PUSH(vec![1]),PUSHPAD(vec![0x12,0x34,0x56,0x00],3)]);
+    assert_eq!(PUSHPAD(vec![0x12,0x34,0x56,0x00],3).to_string(), "PUSH4 0x12345600 (padded)");
+}
+
+#[test]
+pub fn test_disassemble_padded_03() {
+    // Encoding reproduces only bytes present
+    let mut code = Bytecode::new();
+    code.push(PUSHPAD(vec![0x12,0x00],1));
+    assert_eq!(code.to_bytes().unwrap().to_hex_string(), "0x6112");
+}
+
+// ============================================================================
+// Constructor Arguments
+// ============================================================================
+
+#[test]
+pub fn test_disassemble_data_01() {
+    // Without the suffix, arguments are executed
+    check("0x60005b50", &[PUSH(vec![0]),JUMPDEST(2),POP]);
+    check_data("0x60005b50", 2, &[PUSH(vec![0]),DATA(vec![0x5b,0x50])]);
+}
+
+#[test]
+pub fn test_disassemble_data_02() {
+    // Jump into arguments
+    check_data("0x600456005b", 4, &[PUSH(vec![4]),JUMP,DATA(vec![0x00]),DATA(vec![0x5b])]);
+    let bytes = "0x600456005b".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_data_from(4).build();
+    assert_eq!(disasm.invalid_jumps(), vec![InvalidJump::Target{pc:2,target:4}]);
+}
+
+#[test]
+pub fn test_disassemble_data_03() {
+    // Push truncated by arguments
+    check_data("0x611234", 2, &[PUSHPAD(vec![0x12,0x00],1),DATA(vec![0x34])]);
+}
+
+#[test]
+pub fn test_metadata_end_01() {
+    let code = "6080fe";
+    let metadata = format!("a264697066735822{}64736f6c6343000811","00".repeat(34));
+    let args = "00".repeat(32);
+    let bytes = format!("0x{}{}0033{}",code,metadata,args).from_hex_string().unwrap();
+    assert_eq!(metadata_end(&bytes), Some(bytes.len() - 32));
+    assert_eq!(metadata_end(&bytes[..bytes.len()-32]), Some(bytes.len() - 32));
+    assert_eq!(metadata_end(&bytes[..3]), None);
+}
+
+// ============================================================================
+// Hints
+// ============================================================================
+
+#[test]
+pub fn test_disassemble_hint_01() {
+    // Target of computed jump
+    check("0x600035565b600100", &[PUSH(vec![0]),CALLDATALOAD,JUMP,DATA(vec![0x5b,0x60,0x01,0x00])]);
+    check_hints("0x600035565b600100", &[Hint::Entry(4)], &[PUSH(vec![0]),CALLDATALOAD,JUMP,JUMPDEST(4),PUSH(vec![1]),STOP]);
+}
+
+#[test]
+pub fn test_disassemble_hint_02() {
+    // Entry within block
+    check_hints("0x60010060026003fe", &[Hint::Entry(5)], &[PUSH(vec![1]),STOP,DATA(vec![0x60,0x02]),PUSH(vec![3]),INVALID]);
+}
+
+#[test]
+pub fn test_disassemble_hint_03() {
+    // Data within code
+    check("0x600456615b00", &[PUSH(vec![4]),JUMP,PUSH(vec![0x5b,0x00])]);
+    check_hints("0x600456615b00", &[Hint::Data(3..4)], &[PUSH(vec![4]),JUMP,DATA(vec![0x61]),JUMPDEST(4),STOP]);
+}
+
+#[test]
+pub fn test_disassemble_hint_04() {
+    // Overlapping data regions
+    check_hints("0x600456615b00", &[Hint::Data(3..4),Hint::Data(3..4)], &[PUSH(vec![4]),JUMP,DATA(vec![0x61]),JUMPDEST(4),STOP]);
+    check_hints("0x6000616162", &[Hint::Data(2..4),Hint::Data(3..9)], &[PUSH(vec![0]),DATA(vec![0x61,0x61,0x62])]);
+}
+
+#[test]
+pub fn test_disassemble_hint_05() {
+    // Data at offset zero is ignored
+    check_hints("0x600100", &[Hint::Data(0..2)], &[PUSH(vec![1]),STOP]);
+    check_hints("0x600100", &[Hint::Data(0..2),Hint::Data(2..3)], &[PUSH(vec![1]),DATA(vec![0x00])]);
+    check_data("0x600100", 0, &[PUSH(vec![1]),STOP]);
+}
+
+// ============================================================================
+// Invalid Jumps
+// ============================================================================
+
+#[test]
+pub fn test_invalid_jumps_01() {
+    // Valid jump
+    check_jumps("0x6003565b00", &[]);
+}
+
+#[test]
+pub fn test_invalid_jumps_02() {
+    // Jump to non-jumpdest
+    check_jumps("0x60035600", &[InvalidJump::Target{pc:2,target:3}]);
+}
+
+#[test]
+pub fn test_invalid_jumps_03() {
+    // Jump into push data
+    check_jumps("0x6004566000605b", &[InvalidJump::Target{pc:2,target:4}]);
+}
+
+#[test]
+pub fn test_invalid_jumps_04() {
+    // Jump out of bounds
+    check_jumps("0x600160ff5700", &[InvalidJump::Target{pc:4,target:0xff}]);
+}
+
+#[test]
+pub fn test_invalid_jumps_05() {
+    // Unknown target
+    check_jumps("0x60003556", &[InvalidJump::Unknown{pc:3}]);
+}
+
+// ============================================================================
+// Reachability
+// ============================================================================
+
+#[test]
+pub fn test_reachability_01() {
+    check_reachability("0x6003565b00", &[], &[Entry, Edge{from:0,pc:2,condition:Condition::Always}]);
+}
+
+#[test]
+pub fn test_reachability_02() {
+    // Conditional branch
+    let taken = Edge{from:0,pc:4,condition:Condition::Taken};
+    let not_taken = Edge{from:0,pc:4,condition:Condition::NotTaken};
+    check_reachability("0x60016007575b005b00", &[], &[Entry, not_taken, taken]);
+}
+
+#[test]
+pub fn test_reachability_03() {
+    // Unreachable blocks
+    check_reachability("0x00600556005b00", &[], &[Entry, NoIncomingEdge, NoIncomingEdge, NoIncomingEdge]);
+}
+
+#[test]
+pub fn test_reachability_04() {
+    // Hinted entry point
+    let edge = Edge{from:1,pc:3,condition:Condition::Always};
+    check_reachability("0x00600556005b00", &[Hint::Entry(1)], &[Entry, Entry, NoIncomingEdge, edge]);
+}
+
+// ============================================================================
+// Incremental
+// ============================================================================
+
+#[test]
+pub fn test_update_01() {
+    // Patch push in final block
+    check_update("0x600556600160015b600100","0x600556600160015b600200");
+}
+
+#[test]
+pub fn test_update_02() {
+    // Patch jump target
+    check_update("0x600556600160015b600100","0x600356600160015b600100");
+}
+
+#[test]
+pub fn test_update_03() {
+    // Patch block before join point
+    check_update("0x600160075760025b00","0x600160075760035b00");
+}
+
+#[test]
+pub fn test_update_04() {
+    // Change code size
+    check_update("0x600160075760025b00","0x6001600857600260035b00");
+}
+
+#[test]
+pub fn test_update_05() {
+    // Unreachable block becomes reachable
+    check_update("0x00600556005b00","0x60035600600556005b00");
+}
+
+#[test]
+pub fn test_update_06() {
+    // Change code size read by CODESIZE
+    check_update("0x38600556005b00aa","0x38600556005b00aabb");
+}
+
+#[test]
+pub fn test_update_07() {
+    // Change code copied by CODECOPY
+    check_update("0x6020600f600039600051565b005b00000000000000000000000000000000000000000000000000000000000000000b",
+                 "0x6020600f600039600051565b005b00000000000000000000000000000000000000000000000000000000000000000d");
+}
+
 // ============================================================================
 // Helpers
 // ============================================================================
@@ -904,3 +1282,62 @@ fn check(hex: &str, insns: &[Instruction]) {
     // Check against expected instruction sequence
     assert_eq!(insns, disasm.to_vec());
 }
+
+/// Check that updating the disassembly of one hex string for another
+/// gives the same result as disassembling the latter from scratch.
+fn check_update(before: &str, after: &str) {
+    let b1 = before.from_hex_string().unwrap();
+    let b2 = after.from_hex_string().unwrap();
+    let d1 : Disassembly<CfaState> = Disassembly::new(&b1).build();
+    let d2 : Disassembly<CfaState> = Disassembly::new(&b2).build();
+    let d3 = d1.update(&b2);
+    assert_eq!(d2.to_vec(), d3.to_vec());
+    for (i,blk) in d2.blocks().iter().enumerate() {
+        assert_eq!(d2.is_block_reachable(i), d3.is_block_reachable(i));
+        if d2.is_block_reachable(i) {
+            for pc in blk.start..blk.end {
+                assert_eq!(d2.get_state(pc).to_string(), d3.get_state(pc).to_string());
+            }
+        }
+    }
+}
+
+/// Check that disassembling a given hex string identifies a given set
+/// of invalid jumps.
+fn check_jumps(hex: &str, jumps: &[InvalidJump]) {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(jumps, disasm.invalid_jumps());
+}
+
+/// Check that disassembling a given hex string, with everything from
+/// a given offset treated as data, produces a given sequence of
+/// instructions.
+fn check_data(hex: &str, start: usize, insns: &[Instruction]) {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_data_from(start).build();
+    assert_eq!(insns, disasm.to_vec());
+}
+
+/// Check the reachability of each block, when disassembling a given
+/// hex string with a given set of hints.
+fn check_reachability(hex: &str, hints: &[Hint], reasons: &[Reachability]) {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_hints(hints).build();
+    assert_eq!(reasons, disasm.reachability());
+}
+
+/// Check that disassembling a given hex string with a given set of
+/// hints produces a given sequence of instructions.
+fn check_hints(hex: &str, hints: &[Hint], insns: &[Instruction]) {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_hints(hints).build();
+    assert_eq!(insns, disasm.to_vec());
+}
+
+/// Check that disassembling a given hex string reproduces it exactly.
+fn check_reassemble(hex: &str) {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.reassemble(), Ok(bytes));
+}
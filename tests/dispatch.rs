@@ -0,0 +1,126 @@
+use evmil::{CfaState,Disassembly,FromHexString,Function,Instruction};
+use evmil::Instruction::*;
+
+#[test]
+pub fn test_functions_01() {
+    let fns = check("0x6004361060175760003560e01c806312345678146019575b005b5000");
+    assert_eq!(fns, vec![Function{selector: None, entry: 0x17},
+                         Function{selector: Some(vec![0x12,0x34,0x56,0x78]), entry: 0x19}]);
+}
+
+#[test]
+pub fn test_functions_02() {
+    // Selector pushed before duplication
+    let fns = check("0x60003560e01c63123456788114601157005b5000");
+    assert_eq!(fns, vec![Function{selector: Some(vec![0x12,0x34,0x56,0x78]), entry: 0x11}]);
+}
+
+#[test]
+pub fn test_functions_03() {
+    // Target is not a jump destination
+    let fns = check("0x60003560e01c8063123456781460185700");
+    assert_eq!(fns, vec![]);
+}
+
+#[test]
+pub fn test_function_blocks_01() {
+    let bytes = "0x6004361060175760003560e01c806312345678146019575b005b5000".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_functions().build();
+    let fns = disasm.functions();
+    assert_eq!(disasm.function_blocks(&fns[0]), vec![1]);
+    assert_eq!(disasm.function_blocks(&fns[1]), vec![2]);
+}
+
+#[test]
+pub fn test_fallback_blocks_01() {
+    let bytes = "0x60043610601c5760003560e01c806312345678146019575b005b50005b600160005500".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.fallback_blocks(), vec![3]);
+}
+
+#[test]
+pub fn test_fallback_blocks_02() {
+    // Fallback shares its block with unmatched selectors
+    let bytes = "0x6004361060175760003560e01c806312345678146019575b005b5000".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.fallback_blocks(), Vec::<usize>::new());
+}
+
+#[test]
+pub fn test_fallback_blocks_03() {
+    // No guard on calldata size
+    let bytes = "0x60003560e01c63123456788114601157005b5000".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.fallback_blocks(), Vec::<usize>::new());
+}
+
+#[test]
+pub fn test_fallback_blocks_04() {
+    // Guard negated, so the fallback is not taken
+    let bytes = "0x6004361015600b57600d565b005b00".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.fallback_blocks(), vec![2]);
+}
+
+#[test]
+pub fn test_fallback_blocks_05() {
+    // Repeating the guard within the fallback is decided
+    let bytes = "0x60043610600857005b6004361015601257005bfe".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.fallback_blocks(), vec![1]);
+    assert!(!disasm.is_block_reachable(2));
+}
+
+#[test]
+pub fn test_fallback_blocks_06() {
+    // Guards on constants wider than sixteen bytes are not truncated,
+    // whether or not the branch is taken.
+    let wide = format!("7001{}04","00".repeat(15));
+    for hex in [format!("0x{}3610601857005b6004361015602257005bfe",wide),
+                format!("0x{}3610156023576004361015602157005b005b00",wide)] {
+        let bytes = hex.from_hex_string().unwrap();
+        let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+        assert!((0..disasm.blocks().len()).all(|i| disasm.is_block_reachable(i)));
+        assert_eq!(disasm.fallback_blocks(), Vec::<usize>::new());
+    }
+}
+
+#[test]
+pub fn test_with_functions_01() {
+    // Dispatcher only reachable via computed jump
+    let hex = "0x600035565b631234567814600f57005b5000";
+    check_insns(hex, false, &[PUSH(vec![0]),CALLDATALOAD,JUMP,
+                              DATA(vec![0x5b,0x63,0x12,0x34,0x56,0x78,0x14,0x60,0x0f,0x57,0x00]),
+                              DATA(vec![0x5b,0x50,0x00])]);
+    check_insns(hex, true, &[PUSH(vec![0]),CALLDATALOAD,JUMP,
+                             DATA(vec![0x5b,0x63,0x12,0x34,0x56,0x78,0x14,0x60,0x0f,0x57,0x00]),
+                             JUMPDEST(0x0f),POP,STOP]);
+}
+
+#[test]
+pub fn test_with_functions_02() {
+    // Selector is on the stack on entry
+    let bytes = "0x600035565b631234567814600f57005b5000".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_functions().build();
+    assert_eq!(disasm.get_state(0x10).stack().len().unwrap(), 1);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(hex: &str) -> Vec<Function> {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly = Disassembly::new(&bytes);
+    disasm.functions()
+}
+
+/// Check that disassembling a given hex string (with or without the
+/// dispatched functions as entry points) produces a given sequence of
+/// instructions.
+fn check_insns(hex: &str, functions: bool, insns: &[Instruction]) {
+    let bytes = hex.from_hex_string().unwrap();
+    let mut disasm : Disassembly<CfaState> = Disassembly::new(&bytes);
+    if functions { disasm = disasm.with_functions(); }
+    assert_eq!(insns, disasm.build().to_vec());
+}
@@ -0,0 +1,228 @@
+use evmil::{Backend,Evaluator,Instruction,Machine,Outcome,Parser,Term,ToHexString,evaluate};
+use evmil::opt::{Pass,PassManager,evaluate_constants};
+use evmil::util::u256;
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+#[test]
+pub fn test_evaluate_01() {
+    check("1 + 2", Some("0x3"));
+    check("0 - 1", Some(&format!("0x{}","f".repeat(64))));
+    check("(1 + 2) * 3 == 9", Some("0x1"));
+}
+
+#[test]
+pub fn test_evaluate_02() {
+    // Operators not covered by folding
+    check("7 / 2", Some("0x3"));
+    check("7 % 2", Some("0x1"));
+    check("7 / 0", Some("0x0"));
+    check("2 ** 10", Some("0x400"));
+    check("0x12345678 << 224", Some(&format!("0x12345678{}","0".repeat(56))));
+    check("0x12345678 >> 16", Some("0x1234"));
+}
+
+#[test]
+pub fn test_evaluate_03() {
+    // Logical connectives
+    check("3 && 5", Some("0x5"));
+    check("0 && 5", Some("0x0"));
+    check("0 || 5", Some("0x5"));
+    check("(1 < 2) && (2 < 1)", Some("0x0"));
+}
+
+#[test]
+pub fn test_evaluate_04() {
+    // Bit fields
+    let t = Term::BitField(Box::new(Term::Hex(vec![0xa,0xb,0xc,0xd])),4,8);
+    assert_eq!(evaluate(&t), Some(u256::from(0xbcu8)));
+}
+
+#[test]
+pub fn test_evaluate_05() {
+    // Not constant
+    check("msg.value + 1", None);
+    check("calldatasize() == 0", None);
+    check("storage[1]", None);
+    // Invalid shift
+    check("1 << 256", None);
+}
+
+#[test]
+pub fn test_evaluate_06() {
+    // Memory is local to the evaluator
+    let mut e = Evaluator::new();
+    for insn in [Instruction::PUSH(vec![0x2a]),Instruction::PUSH(vec![0x20]),Instruction::MSTORE,
+                 Instruction::PUSH(vec![0x20]),Instruction::MLOAD] {
+        e.emit(insn);
+    }
+    assert_eq!(e.run(), Some(vec![u256::from(0x2au8)]));
+}
+
+#[test]
+pub fn test_evaluate_07() {
+    // Hash of the empty string
+    let mut e = Evaluator::new();
+    for insn in [Instruction::PUSH(vec![0]),Instruction::PUSH(vec![0]),Instruction::KECCAK256] {
+        e.emit(insn);
+    }
+    let hash = e.run().unwrap()[0];
+    assert_eq!(hash.to_hex_string(), "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+}
+
+#[test]
+pub fn test_evaluate_08() {
+    // Unsupported instructions, or stack underflow
+    for insns in [vec![Instruction::CALLER],vec![Instruction::ADD],vec![Instruction::PUSHL(0),Instruction::JUMP]] {
+        let mut e = Evaluator::new();
+        insns.into_iter().for_each(|i| e.emit(i));
+        assert_eq!(e.run(), None);
+    }
+}
+
+#[test]
+pub fn test_evaluate_09() {
+    // Infinite loops are abandoned
+    let mut e = Evaluator::new();
+    let l = e.fresh_label();
+    for insn in [Instruction::JUMPDEST(l),Instruction::PUSHL(l),Instruction::JUMP] {
+        e.emit(insn);
+    }
+    assert_eq!(e.run(), None);
+}
+
+// ============================================================================
+// Fuel
+// ============================================================================
+
+#[test]
+pub fn test_fuel_01() {
+    // Enough fuel
+    let e = evaluator(vec![Instruction::PUSH(vec![1]),Instruction::PUSH(vec![2]),Instruction::ADD,Instruction::STOP]);
+    assert_eq!(e.run_with_fuel(3), Outcome::Halted(vec![u256::from(3u8)]));
+    assert_eq!(e.run_with_fuel(100), Outcome::Halted(vec![u256::from(3u8)]));
+}
+
+#[test]
+pub fn test_fuel_02() {
+    // Not enough fuel
+    let e = evaluator(vec![Instruction::PUSH(vec![1]),Instruction::PUSH(vec![2]),Instruction::ADD]);
+    let m = Machine{pc: 2, stack: vec![u256::from(1u8),u256::from(2u8)], memory: Vec::new()};
+    assert_eq!(e.run_with_fuel(2), Outcome::OutOfFuel(m.clone()));
+    assert_eq!(e.run_with_fuel(0), Outcome::OutOfFuel(Machine::new()));
+    // Resume from where it stopped
+    assert_eq!(e.resume(m,1), Outcome::Halted(vec![u256::from(3u8)]));
+}
+
+#[test]
+pub fn test_fuel_03() {
+    // Infinite loops run out of fuel
+    let mut e = Evaluator::new();
+    let l = e.fresh_label();
+    for insn in [Instruction::JUMPDEST(l),Instruction::PUSHL(l),Instruction::JUMP] {
+        e.emit(insn);
+    }
+    let Outcome::OutOfFuel(m) = e.run_with_fuel(1000) else { panic!() };
+    assert_eq!(m.pc, 1);
+    assert!(m.stack.is_empty());
+    assert!(matches!(e.resume(m,1000), Outcome::OutOfFuel(_)));
+}
+
+#[test]
+pub fn test_fuel_04() {
+    // Failure is distinct from running out of fuel
+    let e = evaluator(vec![Instruction::PUSH(vec![1]),Instruction::CALLER]);
+    assert_eq!(e.run_with_fuel(1), Outcome::OutOfFuel(Machine{pc: 1, stack: vec![u256::from(1u8)], memory: Vec::new()}));
+    assert_eq!(e.run_with_fuel(2), Outcome::Failed);
+}
+
+// ============================================================================
+// Constant Evaluation
+// ============================================================================
+
+#[test]
+pub fn test_evaluate_constants_01() {
+    // Selectors can be written symbolically
+    let terms = evaluate_constants(parse("storage[(2 ** 8) + 1] = 0x70a08231 << 224;"));
+    assert_eq!(terms, parse(&format!("storage[0x101] = 0x70a08231{};","0".repeat(56))));
+}
+
+#[test]
+pub fn test_evaluate_constants_02() {
+    // Non-constant operands are left
+    let stmts = "if calldata[0] >> 224 == 0x70a08231 goto l;\n.l\nstop;";
+    assert_eq!(evaluate_constants(parse(stmts)), parse(stmts));
+    let terms = evaluate_constants(parse("storage[msg.value / (4 / 2)] = 1;"));
+    assert_eq!(terms, parse("storage[msg.value / 0x2] = 1;"));
+}
+
+#[test]
+pub fn test_evaluate_constants_03() {
+    // Intermediate values wider than 128 bits
+    check_pass("memory[0] = 2 ** 200;", &format!("0x7901{}600052","00".repeat(25)));
+    check_pass("memory[0] = (1 << 255) >> 255;", "0x6001600052");
+    check_pass("memory[0] = (2 ** 128) / 2;", &format!("0x6f80{}600052","00".repeat(15)));
+}
+
+#[test]
+pub fn test_evaluate_constants_04() {
+    // Memory is not known at compile time
+    let stmts = "memory[0] = 5;\nstorage[0] = memory[0] + 1;";
+    assert_eq!(evaluate_constants(parse(stmts)), parse(stmts));
+    check("memory[0] + 1", None);
+    check("memory[0] == memory[0]", None);
+}
+
+#[test]
+pub fn test_evaluate_10() {
+    // Literals wider than a word do not compile
+    check(&format!("0x1{} + 1","0".repeat(64)), None);
+    check(&format!("0x1{} + 1","0".repeat(63)), Some(&format!("0x1{}1","0".repeat(62))));
+}
+
+#[test]
+pub fn test_evaluate_11() {
+    // Arithmetic shift right
+    check("0x12345678 >>> 16", Some("0x1234"));
+    check("(0 - 8) >>> 1", Some(&format!("0x{}c","f".repeat(63))));
+    check("(0 - 8) >>> 255", Some(&format!("0x{}","f".repeat(64))));
+    check("(0 - 8) >> 1", Some(&format!("0x7{}c","f".repeat(62))));
+    check("(0 - 1) >>> 0", Some(&format!("0x{}","f".repeat(64))));
+    check("(0 - 1) >>> 255", Some(&format!("0x{}","f".repeat(64))));
+    check("(1 << 255) >>> 255", Some(&format!("0x{}","f".repeat(64))));
+    check("(1 << 254) >>> 254", Some("0x1"));
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn evaluator(insns: Vec<Instruction>) -> Evaluator {
+    let mut e = Evaluator::new();
+    insns.into_iter().for_each(|i| e.emit(i));
+    e
+}
+
+fn parse(stmts: &str) -> Vec<Term> {
+    Parser::new(stmts).parse().unwrap()
+}
+
+/// Check that evaluating the constants of a given program, and then
+/// compiling it, produces a given hex string.
+fn check_pass(stmts: &str, hex: &str) {
+    let mut pm = PassManager::new().with_pass(Pass::EvaluateConstants);
+    let code = pm.run(parse(stmts)).unwrap();
+    assert_eq!(code.to_bytes().unwrap().to_hex_string(), hex);
+}
+
+/// Check the value of a given expression, by using it as the operand
+/// of an assertion.
+fn check(expr: &str, expected: Option<&str>) {
+    let term = match parse(&format!("assert {};",expr)).remove(0) {
+        Term::Assert(e) => *e,
+        _ => unreachable!()
+    };
+    assert_eq!(evaluate(&term).map(|v| v.to_hex_string()), expected.map(|s| s.to_string()));
+}
@@ -0,0 +1,76 @@
+use evmil::{Block,CfaState,Condition,Disassembly,Explanation,FromHexString,Instruction,Reachability};
+
+// ============================================================================
+// Explanations
+// ============================================================================
+
+#[test]
+pub fn test_explain_01() {
+    // Unreachable after a jump
+    let e = explain("0x600456005b00", 3);
+    assert!(!e.is_code);
+    assert_eq!(e.block, Block::new(3,4));
+    assert_eq!(e.reachability, Reachability::NoIncomingEdge);
+    assert!(e.branches.is_empty() && e.invalid.is_empty());
+    assert_eq!(e.terminator, Some((2,Instruction::JUMP)));
+    assert_eq!(e.metadata, None);
+    assert_eq!(e.to_string(), "0x3 is data\n  in block 0x3..0x4 (no incoming edge)\n  not targeted by any branch\n  follows JUMP at 0x2");
+}
+
+#[test]
+pub fn test_explain_02() {
+    // Reached by a jump
+    let e = explain("0x600456005b00", 5);
+    assert!(e.is_code);
+    assert_eq!(e.block, Block::new(4,6));
+    assert_eq!(e.reachability, Reachability::Edge{from: 0, pc: 2, condition: Condition::Always});
+    assert_eq!(e.branches, vec![2]);
+    assert_eq!(e.to_string(), "0x5 is code\n  in block 0x4..0x6 (reached from 0x000002)\n  targeted by branches at 0x2\n  follows STOP at 0x3");
+}
+
+#[test]
+pub fn test_explain_03() {
+    // Jump to something other than a JUMPDEST
+    let e = explain("0x60035600", 3);
+    assert_eq!(e.branches, vec![2]);
+    assert_eq!(e.invalid, vec![2]);
+    assert!(e.to_string().contains("targeted by jumps at 0x2 (not at a JUMPDEST)"));
+}
+
+#[test]
+pub fn test_explain_04() {
+    // Data given as a hint
+    let bytes = "0x6001600200".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_data_from(2).build();
+    let e = disasm.explain(3).unwrap();
+    assert!(!e.is_code && e.hinted);
+    assert_eq!(e.terminator, None);
+    assert!(e.to_string().contains("within a data region given as a hint"));
+    assert_eq!(disasm.explain(5), None);
+}
+
+#[test]
+pub fn test_explain_05() {
+    // Metadata followed by constructor arguments
+    let mut bytes = vec![0x00];
+    bytes.extend(b"\xa2\x64ipfs\x01");
+    bytes.extend([0x00,0x07,0xff,0xff]);
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let e = disasm.explain(3).unwrap();
+    assert_eq!(e.metadata, Some(1..10));
+    assert!(e.to_string().ends_with("within the metadata trailer (0x1..0xa)"));
+    let e = disasm.explain(11).unwrap();
+    assert_eq!(e.metadata, Some(1..10));
+    assert!(e.to_string().ends_with("after the metadata trailer (0x1..0xa)"));
+    assert_eq!(disasm.explain(0).unwrap().metadata, None);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn explain(hex: &str, offset: usize) -> Explanation {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    disasm.explain(offset).unwrap()
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "ffi")]
+use std::ffi::{CStr,CString};
+use std::ptr;
+use evmil::ffi::*;
+
+#[test]
+pub fn test_ffi_disassemble_01() {
+    let bytes = [0x60,0x01,0x00];
+    unsafe {
+        let s = evmil_disassemble(bytes.as_ptr(),bytes.len());
+        assert!(!s.is_null());
+        let listing = CStr::from_ptr(s).to_str().unwrap().to_string();
+        assert_eq!(listing, "0x000000: PUSH1 0x01\n0x000002: STOP\n");
+        evmil_free_string(s);
+    }
+}
+
+#[test]
+pub fn test_ffi_disassemble_02() {
+    unsafe {
+        assert!(evmil_disassemble(ptr::null(),0).is_null());
+    }
+}
+
+#[test]
+pub fn test_ffi_disassemble_03() {
+    // No bytes
+    let bytes = [0x00];
+    unsafe {
+        assert!(evmil_disassemble(bytes.as_ptr(),0).is_null());
+    }
+}
+
+#[test]
+pub fn test_ffi_disassemble_04() {
+    // A panic in the analysis must not unwind into the caller
+    let bytes = [0x60,0x00,0xff,0xff];
+    unsafe {
+        evmil_free_string(evmil_disassemble(bytes.as_ptr(),bytes.len()));
+    }
+}
+
+#[test]
+pub fn test_ffi_compile_01() {
+    let src = CString::new("goto lab; .lab").unwrap();
+    let mut len = 0;
+    unsafe {
+        let bytes = evmil_compile(src.as_ptr(),&mut len);
+        assert!(!bytes.is_null());
+        assert_eq!(std::slice::from_raw_parts(bytes,len), &[0x60,0x03,0x56,0x5b]);
+        evmil_free_bytes(bytes,len);
+    }
+}
+
+#[test]
+pub fn test_ffi_compile_02() {
+    // Syntax error
+    let src = CString::new("goto").unwrap();
+    let mut len = 0;
+    unsafe {
+        assert!(evmil_compile(src.as_ptr(),&mut len).is_null());
+    }
+}
@@ -0,0 +1,83 @@
+use evmil::{CfaState,Disassembly,EvmVersion,ForkDifference,FromHexString};
+use evmil::Instruction::*;
+
+// ============================================================================
+// Versions
+// ============================================================================
+
+#[test]
+pub fn test_version_01() {
+    for v in EvmVersion::ALL {
+        assert_eq!(EvmVersion::from_name(v.name()), Some(*v));
+    }
+    assert_eq!(EvmVersion::from_name("unknown"), None);
+}
+
+#[test]
+pub fn test_version_02() {
+    assert!(EvmVersion::Frontier.supports(&PUSH(vec![0])));
+    assert!(!EvmVersion::Istanbul.supports(&PUSH(vec![])));
+    assert!(EvmVersion::Shanghai.supports(&PUSH(vec![])));
+    assert!(!EvmVersion::Shanghai.supports(&TSTORE));
+    assert!(EvmVersion::Cancun.supports(&TSTORE));
+    assert_eq!(EvmVersion::introducing(&REVERT), EvmVersion::Byzantium);
+}
+
+#[test]
+pub fn test_version_03() {
+    // London introduced BASEFEE
+    assert_eq!(EvmVersion::introducing(&BASEFEE), EvmVersion::London);
+    assert!(!EvmVersion::Istanbul.supports(&BASEFEE));
+    assert!(EvmVersion::London.supports(&BASEFEE));
+    assert!(!EvmVersion::London.supports(&PUSH(vec![])));
+    assert!(EvmVersion::Istanbul < EvmVersion::London && EvmVersion::London < EvmVersion::Shanghai);
+}
+
+// ============================================================================
+// Differences
+// ============================================================================
+
+#[test]
+pub fn test_fork_differences_01() {
+    let diffs = check("0x5f5f5200", EvmVersion::Istanbul, EvmVersion::Shanghai);
+    assert_eq!(diffs, vec![
+        ForkDifference{pc: 0, insn: PUSH(vec![]), introduced: EvmVersion::Shanghai},
+        ForkDifference{pc: 1, insn: PUSH(vec![]), introduced: EvmVersion::Shanghai}
+    ]);
+}
+
+#[test]
+pub fn test_fork_differences_02() {
+    // Order of versions is irrelevant
+    let diffs = check("0x5f5f5d00", EvmVersion::Cancun, EvmVersion::Shanghai);
+    assert_eq!(diffs, vec![ForkDifference{pc: 2, insn: TSTORE, introduced: EvmVersion::Cancun}]);
+}
+
+#[test]
+pub fn test_fork_differences_03() {
+    // Unreachable code is ignored
+    let diffs = check("0x005f5f5200", EvmVersion::Istanbul, EvmVersion::Shanghai);
+    assert!(diffs.is_empty());
+}
+
+#[test]
+pub fn test_fork_differences_04() {
+    let diffs = check("0x5f5f5200", EvmVersion::Shanghai, EvmVersion::Cancun);
+    assert!(diffs.is_empty());
+}
+
+#[test]
+pub fn test_fork_differences_05() {
+    let diffs = check("0x485000", EvmVersion::Istanbul, EvmVersion::London);
+    assert_eq!(diffs, vec![ForkDifference{pc: 0, insn: BASEFEE, introduced: EvmVersion::London}]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(hex: &str, v1: EvmVersion, v2: EvmVersion) -> Vec<ForkDifference> {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    disasm.fork_differences(v1,v2)
+}
@@ -0,0 +1,56 @@
+use evmil::{CfaState,Disassembly,Generator,Instruction,Mix};
+use evmil::Instruction::*;
+
+#[test]
+pub fn test_generator_01() {
+    // Same seed gives same programs
+    let mut g1 = Generator::new(1);
+    let mut g2 = Generator::new(1);
+    for _ in 0..10 {
+        assert_eq!(g1.generate_bytes(), g2.generate_bytes());
+    }
+    assert_ne!(Generator::new(1).generate_bytes(), Generator::new(2).generate_bytes());
+}
+
+#[test]
+pub fn test_generator_02() {
+    for seed in 0..100 {
+        check(Generator::new(seed));
+    }
+}
+
+#[test]
+pub fn test_generator_03() {
+    for seed in 0..20 {
+        let insns = check(Generator::new(seed).with_size(200).with_loops(3));
+        assert!(insns.len() >= 200);
+    }
+}
+
+#[test]
+pub fn test_generator_04() {
+    // Only loops can branch
+    let mix = Mix{storage: 1, ..Mix::NONE};
+    for seed in 0..20 {
+        let insns = check(Generator::new(seed).with_size(20).with_loops(2).with_mix(mix));
+        assert_eq!(insns.iter().filter(|i| **i == JUMPI).count(), 2);
+        assert!(insns.iter().any(|i| *i == SLOAD || *i == SSTORE));
+        assert!(!insns.iter().any(|i| *i == ADD || *i == MLOAD || *i == CALLER));
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Check a generated program is valid, returning its instructions.
+fn check(mut gen: Generator) -> Vec<Instruction> {
+    let bytes = gen.generate_bytes();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.invalid_jumps(), vec![]);
+    assert_eq!(disasm.diagnostics(), vec![]);
+    for i in 0..disasm.blocks().len() {
+        assert!(disasm.is_block_reachable(i));
+    }
+    disasm.to_vec()
+}
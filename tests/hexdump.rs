@@ -0,0 +1,69 @@
+use evmil::{ByteClass,CfaState,Disassembly,FromHexString,HexDump,HexSpan};
+
+// ============================================================================
+// Hex Dump
+// ============================================================================
+
+#[test]
+pub fn test_hexdump_01() {
+    let dump = check("0x6003565b00", 16);
+    assert_eq!(dump.rows.len(), 1);
+    assert_eq!(dump.rows[0].spans, vec![
+        HexSpan{offset: 0, block: 0, class: ByteClass::Code, bytes: vec![0x60,0x03,0x56]},
+        HexSpan{offset: 3, block: 1, class: ByteClass::Code, bytes: vec![0x5b,0x00]}
+    ]);
+    assert_eq!(dump.to_string(), "000000: <code block=\"0\">60 03 56</code> <code block=\"1\">5b 00</code>\n");
+}
+
+#[test]
+pub fn test_hexdump_02() {
+    // Unreachable code is data
+    let dump = check("0x00600100", 16);
+    assert_eq!(dump.to_string(), "000000: <code block=\"0\">00</code> <data block=\"1\">60 01 00</data>\n");
+}
+
+#[test]
+pub fn test_hexdump_03() {
+    // Blocks split across rows
+    let dump = check("0x6003565b00", 2);
+    assert_eq!(dump.to_string(), "000000: <code block=\"0\">60 03</code>\n000002: <code block=\"0\">56</code> <code block=\"1\">5b</code>\n000004: <code block=\"1\">00</code>\n");
+}
+
+#[test]
+pub fn test_hexdump_04() {
+    // Unresolved jump, so unreachable jumpdest could be code
+    let dump = check("0x600035565b00fe", 16);
+    assert_eq!(dump.to_string(), "000000: <code block=\"0\">60 00 35 56</code> <unknown block=\"1\">5b 00</unknown> <data block=\"2\">fe</data>\n");
+}
+
+#[test]
+pub fn test_hexdump_05() {
+    // Truncated push
+    let dump = check("0x610a", 16);
+    assert_eq!(dump.to_string(), "000000: <code block=\"0\">61 0a</code>\n");
+}
+
+#[test]
+#[cfg(feature = "std")]
+pub fn test_hexdump_06() {
+    let dump = check("0x6003565b00", 4);
+    assert_eq!(dump.to_json(), serde_json::json!([
+        {"offset": 0, "spans": [
+            {"offset": 0, "block": 0, "class": "code", "bytes": "0x600356"},
+            {"offset": 3, "block": 1, "class": "code", "bytes": "0x5b"}
+        ]},
+        {"offset": 4, "spans": [
+            {"offset": 4, "block": 1, "class": "code", "bytes": "0x00"}
+        ]}
+    ]));
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(hex: &str, width: usize) -> HexDump {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    disasm.hexdump(width)
+}
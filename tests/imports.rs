@@ -0,0 +1,78 @@
+#![cfg(feature = "std")]
+use std::collections::BTreeMap;
+use evmil::{Diagnostic,Location,Options,compile_il,compile_il_with};
+
+#[test]
+pub fn test_import_01() {
+    let files = [("lib.il", ".lib\nstop;\n")];
+    check(&files, "goto lib;\nimport \"lib.il\";\n", "goto lib;\n.lib\nstop;\n");
+}
+
+#[test]
+pub fn test_import_02() {
+    // Nested imports
+    let files = [("a.il", "import \"b.il\";\n.a\ngoto b;\n"), ("b.il", ".b\nstop;\n")];
+    check(&files, "import \"a.il\";\n", ".b\nstop;\n.a\ngoto b;\n");
+}
+
+#[test]
+pub fn test_import_03() {
+    // Imported twice
+    let files = [("a.il", "import \"c.il\";\n"), ("b.il", "import \"c.il\";\n"), ("c.il", ".c\nstop;\n")];
+    check(&files, "import \"a.il\";\nimport \"b.il\";\ngoto c;\n", ".c\nstop;\ngoto c;\n");
+}
+
+#[test]
+pub fn test_import_04() {
+    // Cyclic imports
+    let files = [("a.il", "import \"b.il\";\n.a\nstop;\n"), ("b.il", "import \"a.il\";\n.b\nstop;\n")];
+    check(&files, "import \"a.il\";\n", ".b\nstop;\n.a\nstop;\n");
+}
+
+#[test]
+pub fn test_import_05() {
+    let files = [("lib.il", ".lib\nstop;\n")];
+    let err = compile(&files, "import \"other.il\";\n").unwrap_err();
+    assert_eq!(err, Diagnostic::error("P005","unknown import").with_note("cannot load \"other.il\"").with_span(7..17));
+}
+
+#[test]
+pub fn test_import_06() {
+    // No loader
+    let err = compile_il("import \"lib.il\";\n", &Options::default()).unwrap_err();
+    assert_eq!(err.code, "P005");
+}
+
+#[test]
+pub fn test_import_07() {
+    // Nested imports are relative to the importing file
+    let files = [("lib/a.il", "import \"b.il\";\n.a\ngoto b;\n"), ("lib/b.il", ".b\nstop;\n"), ("b.il", ".c\nstop;\n")];
+    check(&files, "import \"lib/a.il\";\n", ".b\nstop;\n.a\ngoto b;\n");
+    check(&files, "import \"./lib/../b.il\";\n", ".c\nstop;\n");
+}
+
+#[test]
+pub fn test_import_08() {
+    // Errors identify the imported file
+    let files = [("lib/a.il", "import \"b.il\";\n"), ("lib/b.il", "stop;\ngoto;\n")];
+    let err = compile(&files, "import \"lib/a.il\";\n").unwrap_err();
+    assert_eq!(err.code, "P003");
+    assert_eq!(err.notes.last().unwrap(), "in imported file \"lib/b.il\"");
+    assert_eq!(err.location, Location::Span(10..11));
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn compile(files: &[(&str,&str)], input: &str) -> Result<Vec<u8>,Diagnostic> {
+    let loader : BTreeMap<String,String> = files.iter().map(|(p,s)| (p.to_string(),s.to_string())).collect();
+    compile_il_with(input, &Options::default(), loader)
+}
+
+/// Check that compiling a program with imports matches compiling the
+/// program with its imports inlined.
+fn check(files: &[(&str,&str)], input: &str, expected: &str) {
+    let bytes = compile(files, input).unwrap();
+    assert_eq!(bytes, compile_il(expected, &Options::default()).unwrap());
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use evmil::ToHexString;
+use evmil::util::{u256,ParseError};
+
+const MAX : &str = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+#[test]
+fn test_u256_01() {
+    for i in 0..1000u32 {
+        let v = u256::from(i);
+        assert_eq!((v.high(),v.low()), (0,i as u128));
+        assert_eq!(v.to_string(), i.to_string());
+        assert_eq!(u256::try_from(i.to_string().as_str()), Ok(v));
+        assert_eq!(u256::try_from(format!("{:#x}",i).as_str()), Ok(v));
+    }
+}
+
+#[test]
+fn test_u256_02() {
+    let v = u256::from_str(MAX).unwrap();
+    assert_eq!(v, u256::MAX);
+    assert_eq!(v.to_string(), MAX);
+    assert_eq!(v.to_hex_string(), format!("0x{}","f".repeat(64)));
+    assert_eq!(u256::from_str(&v.to_hex_string()), Ok(v));
+}
+
+#[test]
+fn test_u256_03() {
+    let v = u256::from(u128::MAX);
+    let w = u256::from_str("0x100000000000000000000000000000000").unwrap();
+    assert!(v < w);
+    assert!(u256::ZERO < v);
+    assert_eq!(w.to_string(), "340282366920938463463374607431768211456");
+    assert_eq!(format!("{:x}",w), format!("1{}","0".repeat(32)));
+}
+
+#[test]
+fn test_u256_04() {
+    assert_eq!(u256::from_str(""), Err(ParseError::Empty));
+    assert_eq!(u256::from_str("0x"), Err(ParseError::Empty));
+    assert_eq!(u256::from_str("12a"), Err(ParseError::InvalidDigit));
+    assert_eq!(u256::from_str("0xfg"), Err(ParseError::InvalidDigit));
+    // One more than the maximum
+    let max_plus_one = "115792089237316195423570985008687907853269984665640564039457584007913129639936";
+    assert_eq!(u256::from_str(max_plus_one), Err(ParseError::Overflow));
+    assert_eq!(u256::from_str(&format!("0x1{}","0".repeat(64))), Err(ParseError::Overflow));
+}
+
+#[test]
+fn test_u256_05() {
+    let mut map = HashMap::new();
+    map.insert(u256::from(1u8),"one");
+    map.insert(u256::MAX,"max");
+    assert_eq!(map.get(&u256::from(1u64)), Some(&"one"));
+    assert_eq!(map.get(&u256::from_str(MAX).unwrap()), Some(&"max"));
+}
+
+#[test]
+fn test_u256_06() {
+    let two128 = u256::from_halves(1,0);
+    assert_eq!(u256::MAX + u256::ONE, u256::ZERO);
+    assert_eq!(u256::ZERO - u256::ONE, u256::MAX);
+    assert_eq!(u256::from(u128::MAX) + u256::ONE, two128);
+    assert_eq!(two128 - u256::ONE, u256::from(u128::MAX));
+    assert_eq!(two128 * two128, u256::ZERO);
+    assert_eq!(u256::MAX * u256::MAX, u256::ONE);
+    // (2^128-1)^2 == 2^256 - 2^129 + 1
+    let v = u256::from(u128::MAX);
+    assert_eq!(v * v, u256::from_halves(u128::MAX - 1, 1));
+}
+
+#[test]
+fn test_u256_07() {
+    // Compare against native arithmetic
+    let mut seed = 1u64;
+    for _ in 0..1000 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let x = seed >> 1;
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let y = seed >> 1;
+        let (a,b) = (u256::from(x),u256::from(y));
+        assert_eq!(a + b, u256::from(x as u128 + y as u128));
+        assert_eq!(a * b, u256::from(x as u128 * y as u128));
+        assert_eq!(a.max(b) - a.min(b), u256::from(x.max(y) - x.min(y)));
+        assert_eq!((a * b) + a, a * (b + u256::ONE));
+    }
+}
+
+#[test]
+fn test_u256_08() {
+    let two128 = u256::from_halves(1,0);
+    assert_eq!(u256::MAX / u256::from(2u8), u256::from_halves(u128::MAX >> 1, u128::MAX));
+    assert_eq!(u256::MAX % two128, u256::from(u128::MAX));
+    assert_eq!(two128 / u256::from(u128::MAX), u256::ONE);
+    // Dividing by zero gives zero
+    assert_eq!(u256::MAX / u256::ZERO, u256::ZERO);
+    assert_eq!(u256::MAX % u256::ZERO, u256::ZERO);
+}
+
+#[test]
+fn test_u256_09() {
+    let two128 = u256::from_halves(1,0);
+    assert_eq!(u256::ONE << 128, two128);
+    assert_eq!(u256::ONE << 255 >> 255, u256::ONE);
+    assert_eq!(u256::MAX << 256, u256::ZERO);
+    assert_eq!(u256::MAX >> 192, u256::from(u64::MAX));
+    assert_eq!(!u256::ZERO, u256::MAX);
+    assert_eq!((u256::MAX >> 128) & two128, u256::ZERO);
+    assert_eq!((u256::MAX >> 128) | two128, (two128 << 1) - u256::ONE);
+}
+
+#[test]
+fn test_u256_10() {
+    let two = u256::from(2u8);
+    assert_eq!(two.pow(u256::from(128u8)), u256::from_halves(1,0));
+    assert_eq!(two.pow(u256::from(256u16)), u256::ZERO);
+    assert_eq!(u256::ZERO.pow(u256::ZERO), u256::ONE);
+    assert_eq!(u256::from(3u8).pow(u256::from(5u8)), u256::from(243u8));
+}
+
+#[test]
+fn test_u256_11() {
+    // Compare against native division
+    let mut seed = 1u64;
+    for _ in 0..1000 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let x = seed as u128 * seed as u128;
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let y = (seed >> 1) as u128 + 1;
+        let (a,b) = (u256::from(x),u256::from(y));
+        assert_eq!(a / b, u256::from(x / y));
+        assert_eq!(a % b, u256::from(x % y));
+        assert_eq!(u256::from_be_bytes(&a.to_be_bytes()), a);
+    }
+}
@@ -0,0 +1,56 @@
+use evmil::{Bytecode,Parser,ToHexString};
+
+// ============================================================================
+// Require
+// ============================================================================
+
+#[test]
+pub fn test_require_01() {
+    let p = "require 1;";
+    check(&p, "0x6001600a5760006000fd5b");
+}
+
+#[test]
+pub fn test_require_02() {
+    let p = "require calldatasize() >= 4;";
+    check(&p, "0x6004361015600d5760006000fd5b");
+}
+
+// ============================================================================
+// Intrinsics
+// ============================================================================
+
+#[test]
+pub fn test_intrinsic_01() {
+    let p = "assert msg.value == 0;";
+    check(&p, "0x60003414600857fe5b");
+}
+
+#[test]
+pub fn test_intrinsic_02() {
+    let p = "assert balance(msg.sender) > 0;";
+    check(&p, "0x6000333111600957fe5b");
+}
+
+#[test]
+pub fn test_intrinsic_03() {
+    assert!(Parser::new("assert msg.origin;").parse().is_err());
+    assert!(Parser::new("assert balance();").parse().is_err());
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Check that compiling a given sequence of terms produces a given
+/// hex string.
+fn check(stmt: &str, hex: &str) {
+    // Parse statement into a term
+    let ts = Parser::new(stmt).parse().unwrap();
+    // Translate statements into bytecode instructions
+    let code = Bytecode::try_from(ts.as_slice()).unwrap();
+    // Translate instructions into bytes
+    let bytes : Vec<u8> = code.try_into().unwrap();
+    // Check against expected hex string
+    assert_eq!(hex, bytes.to_hex_string());
+}
@@ -0,0 +1,36 @@
+use evmil::ToHexString;
+use evmil::util::keccak256;
+
+#[test]
+fn test_keccak_01() {
+    check(b"", "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+}
+
+#[test]
+fn test_keccak_02() {
+    check(b"abc", "0x4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45");
+}
+
+#[test]
+fn test_keccak_03() {
+    // Function selector of transfer(address,uint256)
+    let h = keccak256(b"transfer(address,uint256)");
+    assert_eq!(h[0..4].to_hex_string(), "0xa9059cbb");
+}
+
+#[test]
+fn test_keccak_04() {
+    // Inputs spanning multiple blocks
+    let bytes = vec![0x61u8; 300];
+    assert_ne!(keccak256(&bytes), keccak256(&bytes[..299]));
+    assert_eq!(keccak256(&bytes), keccak256(&bytes.clone()));
+    check(&[0u8;136], "0x3a5912a7c5faa06ee4fe906253e339467a9ce87d533c65be3c15cb231cdb25f9");
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(bytes: &[u8], hex: &str) {
+    assert_eq!(keccak256(bytes).to_hex_string(), hex);
+}
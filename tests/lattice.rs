@@ -0,0 +1,61 @@
+use evmil::{AbstractState,AbstractWord,CfaState,Disassembly,FromHexString,Instruction};
+use evmil::Instruction::*;
+
+// ============================================================================
+// Custom Domain
+// ============================================================================
+
+/// A value which is either a known constant or not.
+#[derive(Clone,Debug,PartialEq)]
+struct Word(Option<usize>);
+
+impl AbstractWord for Word {
+    fn is_known(&self) -> bool { self.0.is_some() }
+    fn unwrap(&self) -> usize { self.0.unwrap() }
+}
+
+/// A state which reuses `CfaState`, but exposes a different value
+/// domain.
+#[derive(Clone,Debug,PartialEq)]
+struct State(CfaState);
+
+impl AbstractState for State {
+    type Value = Word;
+
+    fn is_reachable(&self) -> bool { self.0.is_reachable() }
+    fn transfer(self, insn: &Instruction) -> Self { State(self.0.transfer(insn)) }
+    fn branch(&self, target: usize, insn: &Instruction) -> Self { State(self.0.branch(target,insn)) }
+    fn merge(&mut self, other: Self) -> bool { self.0.merge(other.0) }
+    fn peek(&self, n: usize) -> Word {
+        let v = self.0.peek(n);
+        Word(if v.is_known() { Some(v.unwrap()) } else { None })
+    }
+    fn bottom() -> Self { State(CfaState::bottom()) }
+    fn origin() -> Self { State(CfaState::origin()) }
+}
+
+impl std::fmt::Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f,"{}",self.0)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[test]
+pub fn test_lattice_01() {
+    let bytes = "0x600456fe5b00".from_hex_string().unwrap();
+    let disasm : Disassembly<State> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.to_vec(), vec![PUSH(vec![4]),JUMP,DATA(vec![0xfe]),JUMPDEST(4),STOP]);
+    assert_eq!(disasm.get_state(2).peek(0), Word(Some(4)));
+}
+
+#[test]
+pub fn test_lattice_02() {
+    // Jump target is not a JUMPDEST
+    let bytes = "0x600356fe5b00".from_hex_string().unwrap();
+    let disasm : Disassembly<State> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.to_vec(), vec![PUSH(vec![3]),JUMP,INVALID,DATA(vec![0x5b,0x00])]);
+}
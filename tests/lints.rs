@@ -0,0 +1,125 @@
+use evmil::{CfaState,Diagnostic,Disassembly,FromHexString,Options,analyze_with};
+use evmil::lints::{self,Lint};
+use evmil::lints::Lint::*;
+
+// ============================================================================
+// Selfdestruct
+// ============================================================================
+
+#[test]
+pub fn test_selfdestruct_01() {
+    check("0x33ff", &[(1,ReachableSelfdestruct)]);
+}
+
+#[test]
+pub fn test_selfdestruct_02() {
+    // Unreachable
+    check("0x0033ff", &[]);
+}
+
+// ============================================================================
+// Delegatecall
+// ============================================================================
+
+#[test]
+pub fn test_delegatecall_01() {
+    // Target from call data
+    check("0x6000600060006000600435 5af4600052 00", &[(12,UserDelegatecall)]);
+}
+
+#[test]
+pub fn test_delegatecall_02() {
+    // Target derived from call data
+    check("0x6000600060006000600435 6001165af4600052 00", &[(15,UserDelegatecall)]);
+}
+
+#[test]
+pub fn test_delegatecall_03() {
+    // Constant target
+    check("0x60006000600060006042 5af4600052 00", &[]);
+}
+
+// ============================================================================
+// Unchecked Calls
+// ============================================================================
+
+#[test]
+pub fn test_unchecked_call_01() {
+    check("0x600060006000600060006000 5af150 00", &[(13,UncheckedCall)]);
+}
+
+#[test]
+pub fn test_unchecked_call_02() {
+    // Result left on stack
+    check("0x600060006000600060006000 5afa 00", &[(13,UncheckedCall)]);
+}
+
+#[test]
+pub fn test_unchecked_call_03() {
+    // Result checked by branch
+    check("0x600060006000600060006000 5af1601257 005b00", &[]);
+}
+
+// ============================================================================
+// Tx Origin
+// ============================================================================
+
+#[test]
+pub fn test_tx_origin_01() {
+    check("0x32604214600857005b00", &[(6,TxOriginAuth)]);
+}
+
+#[test]
+pub fn test_tx_origin_02() {
+    // Negated comparison
+    check("0x3260421415600957005b00", &[(7,TxOriginAuth)]);
+}
+
+#[test]
+pub fn test_tx_origin_03() {
+    // Comparison against msg.sender
+    check("0x323314600757005b00", &[]);
+}
+
+#[test]
+pub fn test_tx_origin_04() {
+    // Comparison via DUP and SWAP
+    check("0x3260428190149050600c57005b00", &[(10,TxOriginAuth)]);
+}
+
+// ============================================================================
+// Pipeline
+// ============================================================================
+
+#[test]
+pub fn test_lint_pipeline_01() {
+    let options = Options{lint: true, ..Default::default()};
+    let report = analyze_with(&[0x33,0xff], &options);
+    let d = Diagnostic::warning("S001","reachable SELFDESTRUCT").with_offset(1);
+    assert!(report.diagnostics.contains(&d));
+    // Not reported without linting
+    let report = analyze_with(&[0x33,0xff], &Options::default());
+    assert!(report.diagnostics.is_empty());
+}
+
+#[test]
+pub fn test_lint_pipeline_02() {
+    // Contains data (an invalid opcode)
+    let options = Options{lint: true, ..Default::default()};
+    let report = analyze_with(&[0x0c], &options);
+    assert!(report.diagnostics.iter().all(|d| d.code != "S001"));
+    let report = analyze_with(&[0x33,0xff,0x0c], &options);
+    let d = Diagnostic::warning("S001","reachable SELFDESTRUCT").with_offset(1);
+    assert!(report.diagnostics.contains(&d));
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(hex: &str, expected: &[(usize,Lint)]) {
+    let bytes = hex.replace(' ',"").from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let findings : Vec<(usize,Lint)> = lints::check(&disasm).iter().map(|f| (f.pc,f.lint)).collect();
+    assert_eq!(findings, expected);
+}
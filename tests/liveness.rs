@@ -0,0 +1,123 @@
+use evmil::{CfaState,Diagnostic,Disassembly,FromHexString,Instruction};
+use evmil::Instruction::*;
+use evmil::dfa::{LiveSet,Liveness};
+
+#[test]
+pub fn test_liveset_01() {
+    check_transfer(&[0], ADD, &[0,1]);
+    check_transfer(&[], ADD, &[]);
+    check_transfer(&[1], ADD, &[2]);
+}
+
+#[test]
+pub fn test_liveset_02() {
+    check_transfer(&[], MSTORE, &[0,1]);
+    check_transfer(&[0], MSTORE, &[0,1,2]);
+    check_transfer(&[0,1], PUSH(vec![1]), &[0]);
+    check_transfer(&[0], POP, &[1]);
+}
+
+#[test]
+pub fn test_liveset_03() {
+    check_transfer(&[0], DUP(2), &[1]);
+    check_transfer(&[1], DUP(2), &[0]);
+    check_transfer(&[0,2], DUP(2), &[1]);
+    check_transfer(&[0], SWAP(1), &[1]);
+    check_transfer(&[2], SWAP(2), &[0]);
+}
+
+#[test]
+pub fn test_liveset_04() {
+    assert_eq!(LiveSet::all().transfer(&STOP), LiveSet::empty());
+    assert_eq!(LiveSet::all().transfer(&RETURN), LiveSet::new(&[0,1]));
+    assert_eq!(LiveSet::all().transfer(&POP).to_string(), "{1..}");
+    assert_eq!(LiveSet::all().to_string(), "{0..}");
+    assert_eq!(LiveSet::new(&[0,2]).to_string(), "{0,2}");
+}
+
+#[test]
+pub fn test_liveness_01() {
+    // Top of stack popped by target, rest stored.
+    let live = check("0x600160026007565b5060005500");
+    assert_eq!(live.live_in(0), &LiveSet::empty());
+    assert_eq!(live.live_out(0), &LiveSet::new(&[1]));
+    assert_eq!(live.live_in(1), &LiveSet::new(&[1]));
+}
+
+#[test]
+pub fn test_liveness_02() {
+    // Conditional branch within block
+    let live = check("0x6001600236600b575050005b5500");
+    assert_eq!(live.live_in(0), &LiveSet::empty());
+    assert_eq!(live.live_out(0), &LiveSet::empty());
+    assert_eq!(live.live_in(1), &LiveSet::new(&[0,1]));
+}
+
+#[test]
+pub fn test_liveness_03() {
+    // Unknown branch target
+    let live = check("0x60003556");
+    assert_eq!(live.live_out(0), &LiveSet::all());
+}
+
+#[test]
+pub fn test_liveness_04() {
+    // Unreachable block
+    let live = check("0x0060015000");
+    assert_eq!(live.live_in(1), &LiveSet::empty());
+    assert_eq!(live.live_out(1), &LiveSet::empty());
+}
+
+#[test]
+pub fn test_unused_01() {
+    check_unused("0x6001600055", &[]);
+    check_unused("0x600160025000", &[unused(0,"PUSH1 0x01",None),
+                                      unused(2,"PUSH1 0x02",Some(4))]);
+}
+
+#[test]
+pub fn test_unused_02() {
+    // Only the addition is reported
+    check_unused("0x60016002015000", &[unused(4,"ADD",Some(5))]);
+}
+
+#[test]
+pub fn test_unused_03() {
+    // Unused across blocks
+    check_unused("0x60016005565b00", &[unused(0,"PUSH1 0x01",None)]);
+    check_unused("0x60016005565b600055", &[]);
+}
+
+#[test]
+pub fn test_unused_04() {
+    check_unused("0x6001805500", &[]);
+    check_unused("0x6001805060005500", &[unused(2,"DUP(1)",Some(3))]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(hex: &str) -> Liveness {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    disasm.liveness()
+}
+
+fn check_transfer(after: &[usize], insn: Instruction, before: &[usize]) {
+    assert_eq!(LiveSet::new(after).transfer(&insn), LiveSet::new(before));
+}
+
+fn check_unused(hex: &str, diags: &[Diagnostic]) {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(diags, disasm.unused_values());
+}
+
+fn unused(pc: usize, insn: &str, popped: Option<usize>) -> Diagnostic {
+    let d = Diagnostic::warning("D005",&format!("value produced by {} is never used",insn)).with_offset(pc);
+    match popped {
+        Some(p) => d.with_note(&format!("discarded at {:#x}",p)),
+        None => d
+    }
+}
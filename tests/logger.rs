@@ -0,0 +1,67 @@
+use evmil::{AnalysisLogger,CfaState,Disassembly,FromHexString,Instruction,WriteLogger};
+
+#[test]
+fn test_logger_01() {
+    let events = record("0x6003565b00");
+    assert_eq!(events, vec![
+        "visit 0","transfer 0x0","merge 0 -> 1 (changed)","transfer 0x2","merge 0 -> 1","visit 1","transfer 0x3","transfer 0x4","pass [1]",
+        "visit 0","transfer 0x0","merge 0 -> 1","transfer 0x2","merge 0 -> 1","visit 1","transfer 0x3","transfer 0x4","pass []"
+    ]);
+}
+
+#[test]
+fn test_logger_02() {
+    // Results are unaffected by logging
+    let bytes = "0x6005600d565b600b600d565b005b56".from_hex_string().unwrap();
+    let mut events = Events(Vec::new());
+    let logged : Disassembly<CfaState> = Disassembly::new(&bytes).build_logged(&mut events);
+    let built : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(logged.to_vec(), built.to_vec());
+    assert_eq!(events.0.last().unwrap(), "pass []");
+}
+
+#[test]
+fn test_logger_03() {
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let mut log = WriteLogger::new(String::new()).for_block(1);
+    let _ : Disassembly<CfaState> = Disassembly::new(&bytes).build_logged(&mut log);
+    let text = log.into_inner();
+    let lines : Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "merge 0 into 1: (0..0)[] ==> (0..0)[] (changed)");
+    assert_eq!(lines[2], "block 1: (0..0)[]");
+    assert_eq!(lines[3], "0x000003: (0..0)[] ==> JUMPDEST(3) ==> (0..0)[]");
+    assert!(!text.contains("block 0"));
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+struct Events(Vec<String>);
+
+impl AnalysisLogger<CfaState> for Events {
+    fn visit_block(&mut self, id: usize, _ctx: &CfaState) {
+        self.0.push(format!("visit {}",id));
+    }
+
+    fn transfer(&mut self, pc: usize, _insn: &Instruction, _before: &CfaState, _after: &CfaState) {
+        self.0.push(format!("transfer {:#x}",pc));
+    }
+
+    fn merge(&mut self, from: usize, to: usize, _state: &CfaState, _result: &CfaState, changed: bool) {
+        let outcome = if changed { " (changed)" } else { "" };
+        self.0.push(format!("merge {} -> {}{}",from,to,outcome));
+    }
+
+    fn end_pass(&mut self, changed: &[usize]) {
+        self.0.push(format!("pass {:?}",changed));
+    }
+}
+
+/// Record the events whilst building a disassembly of a given hex string.
+fn record(hex: &str) -> Vec<String> {
+    let bytes = hex.from_hex_string().unwrap();
+    let mut events = Events(Vec::new());
+    let _ : Disassembly<CfaState> = Disassembly::new(&bytes).build_logged(&mut events);
+    events.0
+}
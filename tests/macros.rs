@@ -0,0 +1,54 @@
+use evmil::{bytecode,insns,CfaState,Disassembly,Instruction,ToHexString};
+use evmil::Instruction::*;
+
+#[test]
+fn test_insns_01() {
+    assert_eq!(insns![PUSH1 0x60, PUSH1 0x40, MSTORE], vec![PUSH(vec![0x60]),PUSH(vec![0x40]),MSTORE]);
+    assert_eq!(insns![DUP1, SWAP16, LOG0, LOG4,], vec![DUP(1),SWAP(16),LOG(0),LOG(4)]);
+    assert_eq!(insns![], Vec::<Instruction>::new());
+}
+
+#[test]
+fn test_insns_02() {
+    // Operands are padded to the width of the push
+    assert_eq!(insns![PUSH2 1, PUSH4 0x12_34], vec![PUSH(vec![0,1]),PUSH(vec![0,0,0x12,0x34])]);
+    assert_eq!(insns![PUSH32 0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff], vec![PUSH(vec![0xff;32])]);
+}
+
+#[test]
+fn test_insns_03() {
+    // Jump destinations are given their offset
+    assert_eq!(insns![PUSH1 3, JUMP, JUMPDEST, STOP], vec![PUSH(vec![3]),JUMP,JUMPDEST(3),STOP]);
+}
+
+#[test]
+#[should_panic]
+fn test_insns_04() {
+    insns![PUSH1 0x100];
+}
+
+#[test]
+#[should_panic]
+fn test_insns_05() {
+    insns![DUP17];
+}
+
+#[test]
+#[should_panic]
+fn test_insns_06() {
+    insns![STOP 1];
+}
+
+#[test]
+fn test_bytecode_01() {
+    assert_eq!(bytecode![PUSH1 0x60, PUSH1 0x40, MSTORE].to_hex_string(), "0x6060604052");
+    assert_eq!(bytecode![CALLVALUE, DUP1, ISZERO, INVALID].to_hex_string(), "0x348015fe");
+}
+
+#[test]
+fn test_bytecode_02() {
+    // Round trip through the disassembler
+    let bytes = bytecode![PUSH1 0x01, PUSH1 7, JUMPI, PUSH1 2, JUMPDEST, STOP];
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.to_vec(), insns![PUSH1 0x01, PUSH1 7, JUMPI, PUSH1 2, JUMPDEST, STOP]);
+}
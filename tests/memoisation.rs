@@ -0,0 +1,85 @@
+use evmil::{AnalysisLogger,Bytecode,CfaState,Disassembly,FromHexString,Instruction,Parser};
+
+// Internal function (at 0x0d) called from two sites (returning to 0x05
+// and 0x0b).
+const CALLS : &str = "0x6005600d565b600b600d565b005b56";
+
+// A counting loop, followed by a dispatch on the call data.
+const LOOP : &str = "memory[0] = 0;\n.loop\nif memory[0] == 10 goto end;\nmemory[0] = memory[0] + 1;\ngoto loop;\n.end\nswitch calldata[0] { 1: goto a; 2: goto b; }\nrevert;\n.a\nstop;\n.b\nfail;";
+
+// ============================================================================
+// Memoisation
+// ============================================================================
+
+#[test]
+pub fn test_memoisation_01() {
+    let bytes = compile(LOOP);
+    check(&bytes, |d| d);
+}
+
+#[test]
+pub fn test_memoisation_02() {
+    // Context sensitivity
+    let bytes = CALLS.from_hex_string().unwrap();
+    check(&bytes, |d| d.with_context_depth(1));
+    let bytes = compile(LOOP);
+    check(&bytes, |d| d.with_context_depth(2));
+}
+
+#[test]
+pub fn test_memoisation_03() {
+    // Return tracking
+    let bytes = CALLS.from_hex_string().unwrap();
+    check(&bytes, |d| d.with_return_tracking(true));
+}
+
+#[test]
+pub fn test_memoisation_04() {
+    // Blocks are skipped
+    let bytes = compile(LOOP);
+    let mut without = Counter::default();
+    let _ = Disassembly::<CfaState>::new(&bytes).build_logged(&mut without);
+    let mut with = Counter::default();
+    let _ = Disassembly::<CfaState>::new(&bytes).with_memoisation(true).build_logged(&mut with);
+    assert_eq!(with.passes, without.passes);
+    assert!(with.transfers < without.transfers);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Counts the instructions executed, and the passes made.
+#[derive(Default)]
+struct Counter {
+    transfers: usize,
+    passes: usize
+}
+
+impl<T> AnalysisLogger<T> for Counter {
+    fn transfer(&mut self, _pc: usize, _insn: &Instruction, _before: &T, _after: &T) {
+        self.transfers += 1;
+    }
+
+    fn end_pass(&mut self, _changed: &[usize]) {
+        self.passes += 1;
+    }
+}
+
+fn compile(stmts: &str) -> Vec<u8> {
+    let terms = Parser::new(stmts).parse().unwrap();
+    Bytecode::try_from(terms.as_slice()).unwrap().to_bytes().unwrap()
+}
+
+/// Check that memoisation does not affect the outcome of an analysis
+/// configured in a given way.
+fn check<F>(bytes: &[u8], configure: F)
+where F:for<'a> Fn(Disassembly<'a,CfaState>) -> Disassembly<'a,CfaState> {
+    let without = configure(Disassembly::new(bytes)).build();
+    let with = configure(Disassembly::new(bytes).with_memoisation(true)).build();
+    assert_eq!(with.to_vec(), without.to_vec());
+    assert_eq!(with.invalid_jumps(), without.invalid_jumps());
+    for b in without.blocks() {
+        assert_eq!(with.get_state(b.start).to_string(), without.get_state(b.start).to_string());
+    }
+}
@@ -0,0 +1,41 @@
+use evmil::{Metadata,FromHexString,ToHexString,metadata_end};
+
+#[test]
+fn test_metadata_01() {
+    let m = Metadata{version: "0.2.9".to_string(), source_hash: [0xab;32]};
+    let bytes = m.to_bytes();
+    let hash = "ab".repeat(32);
+    assert_eq!(bytes.to_hex_string(), format!("0xa26565766d696c65302e322e3966736f757263655820{}0036",hash));
+    assert_eq!(Metadata::decode(&bytes), Some(m));
+}
+
+#[test]
+fn test_metadata_02() {
+    // Recognised after code, and before constructor arguments
+    let m = Metadata::new("stop;");
+    let mut bytes = vec![0x60,0x80,0xfe];
+    bytes.extend(m.to_bytes());
+    assert_eq!(Metadata::decode(&bytes), Some(m.clone()));
+    assert_eq!(metadata_end(&bytes), Some(bytes.len()));
+    let n = bytes.len();
+    bytes.extend([0;32]);
+    assert_eq!(metadata_end(&bytes), Some(n));
+    assert_eq!(Metadata::decode(&bytes[..n]), Some(m));
+}
+
+#[test]
+fn test_metadata_03() {
+    // Source hash distinguishes programs
+    assert_ne!(Metadata::new("stop;").source_hash, Metadata::new("fail;").source_hash);
+    assert_eq!(Metadata::new("stop;").version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn test_metadata_04() {
+    // Solidity metadata is not recognised
+    let hex = format!("0xa264697066735822{}64736f6c63430008110033","00".repeat(34));
+    let bytes = hex.from_hex_string().unwrap();
+    assert_eq!(Metadata::decode(&bytes), None);
+    assert_eq!(Metadata::decode(&[]), None);
+    assert_eq!(Metadata::decode(&[0x00,0x05]), None);
+}
@@ -1,4 +1,4 @@
-use evmil::{Bytecode,Term,ToHexString};
+use evmil::{Bytecode,Instruction,Location,Term,TermId,ToHexString};
 use evmil::Term::*;
 use evmil::BinOp::*;
 
@@ -34,6 +34,49 @@ pub fn test_stmt_02() {
     check(&[s1.clone(),s1], "0x6001600657fe5b6001600d57fe5b");
 }
 
+// ============================================================================
+// Stack Depth
+// ============================================================================
+
+#[test]
+pub fn test_depth_01() {
+    let mut code = Bytecode::new();
+    code.push(Instruction::DUP(16));
+    code.push(Instruction::SWAP(16));
+    assert_eq!("0x8f9f", code.to_bytes().unwrap().to_hex_string());
+}
+
+#[test]
+pub fn test_depth_02() {
+    // DUP17 does not exist
+    let mut code = Bytecode::new();
+    code.push(Instruction::DUP(17));
+    assert!(code.to_bytes().is_err());
+}
+
+#[test]
+pub fn test_depth_03() {
+    // SWAP17 does not exist
+    let mut code = Bytecode::new();
+    code.push(Instruction::SWAP(17));
+    assert!(code.to_bytes().is_err());
+}
+
+#[test]
+pub fn test_depth_04() {
+    // Every word returned is on the stack at once, along with the
+    // address it is stored at.
+    let succeed = |n: usize| Succeed(vec![Int(vec![1]); n]);
+    assert!(Bytecode::try_from(&[succeed(1023)]).is_ok());
+    let d = Bytecode::try_from(&[Fail,succeed(1024)]).err().unwrap();
+    assert_eq!(d.code, "C006");
+    assert_eq!(d.location, Location::Term(TermId{statement: 1, index: 0}));
+    // Reported for the innermost term
+    let d = Bytecode::try_from(&[Fail,succeed(1025)]).err().unwrap();
+    assert_eq!(d.location, Location::Term(TermId{statement: 1, index: 1025}));
+    assert_eq!(d.notes[1], "in expression 1");
+}
+
 // ============================================================================
 // Helpers
 // ============================================================================
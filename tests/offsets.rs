@@ -0,0 +1,35 @@
+use evmil::{Bytecode,Term,ToHexString};
+use evmil::Term::*;
+
+// ============================================================================
+// Offset Maps
+// ============================================================================
+
+#[test]
+pub fn test_offsets_01() {
+    let s1 = Assert(Box::new(Int(vec![1])));
+    let code = Bytecode::try_from(&[s1]).unwrap();
+    let (bytes,map) = code.to_bytes_with_map().unwrap();
+    assert_eq!("0x6001600657fe5b", bytes.to_hex_string());
+    // Forward mapping
+    assert_eq!(map.insn_offsets(), &[0,2,4,5,6]);
+    assert_eq!(map.label_offset(0), Some(6));
+    assert_eq!(map.insn_offset(5), None);
+    // Reverse mapping
+    assert_eq!(map.insn_at(4), Some(2));
+    assert_eq!(map.insn_at(1), None);
+    assert_eq!(map.label_at(6), Some(0));
+    assert_eq!(map.label_at(5), None);
+}
+
+#[test]
+pub fn test_offsets_02() {
+    let s1 = Assert(Box::new(Int(vec![1])));
+    let terms : [Term;2] = [s1.clone(),s1];
+    let code = Bytecode::try_from(&terms).unwrap();
+    let (bytes,map) = code.to_bytes_with_map().unwrap();
+    assert_eq!("0x6001600657fe5b6001600d57fe5b", bytes.to_hex_string());
+    assert_eq!(map.label_offsets(), &[6,13]);
+    assert_eq!(map.insn_at(7), Some(5));
+    assert_eq!(map.label_at(13), Some(1));
+}
@@ -0,0 +1,67 @@
+use evmil::{AbstractState,CfaState,Disassembly,FromHexString};
+use evmil::dfa::{AbstractValue,Symbol};
+
+const TRUE : AbstractValue = AbstractValue::Known(1);
+const UNKNOWN : AbstractValue = AbstractValue::Unknown;
+
+#[test]
+fn test_opaque_01() {
+    // CALLVALUE
+    let callvalue = AbstractValue::Opaque(Symbol::new(0x34,None));
+    check("0x3400", 1, callvalue);
+}
+
+#[test]
+fn test_opaque_02() {
+    // CALLVALUE == CALLVALUE
+    check("0x34341400", 3, TRUE);
+}
+
+#[test]
+fn test_opaque_03() {
+    // CALLVALUE == CALLER
+    check("0x34331400", 3, UNKNOWN);
+}
+
+#[test]
+fn test_opaque_04() {
+    // BALANCE(1) == BALANCE(1)
+    check("0x600131600131141500", 7, TRUE);
+    // BALANCE(1) == BALANCE(2)
+    check("0x600131600231141500", 7, UNKNOWN);
+}
+
+#[test]
+fn test_opaque_05() {
+    // SELFBALANCE == SELFBALANCE
+    check("0x47471400", 3, TRUE);
+    // SELFBALANCE; CALL(...); SELFBALANCE
+    check(&format!("0x47{}f150471400","6000".repeat(7)), 19, UNKNOWN);
+}
+
+#[test]
+fn test_opaque_06() {
+    // Opaque values round-trip through memory
+    check("0x33600052600051331400", 9, TRUE);
+}
+
+#[test]
+fn test_opaque_07() {
+    // Wide constants are not truncated before comparison
+    check(&format!("0x7001{}0560051400","00".repeat(15)), 21, UNKNOWN);
+    check(&format!("0x6f{}0560051400","00".repeat(15)), 20, TRUE);
+    // Hence, both branches remain
+    let bytes = format!("0x7001{}05600514601957005b00","00".repeat(15)).from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert!((0..disasm.blocks().len()).all(|i| disasm.is_block_reachable(i)));
+}
+
+/// Check the value on top of the stack at a given program point.
+fn check(hex: &str, pc: usize, expected: AbstractValue) {
+    // Parse hex string into bytes
+    let bytes = hex.from_hex_string().unwrap();
+    // Disassemble bytes into instructions
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    // Check state at given point
+    assert_eq!(disasm.get_state(pc).peek(0), expected);
+}
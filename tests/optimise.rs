@@ -0,0 +1,98 @@
+use evmil::{Bytecode,Parser,ToHexString};
+use evmil::opt::*;
+
+// ============================================================================
+// Jump Chains
+// ============================================================================
+
+#[test]
+pub fn test_jump_chains_01() {
+    let p = "goto a;\n.a\ngoto b;\n.b\nstop;";
+    check(&p, remove_jump_chains, "0x6003565b00");
+}
+
+#[test]
+pub fn test_jump_chains_02() {
+    // Trampoline reachable by falling through
+    let p = "if 1 goto a;\n.a\ngoto b;\n.b\nstop;";
+    check(&p, remove_jump_chains, "0x60016009575b6009565b00");
+}
+
+// ============================================================================
+// Identical Blocks
+// ============================================================================
+
+#[test]
+pub fn test_merge_blocks_01() {
+    let p = "if 1 goto a;\nif 2 goto b;\nstop;\n.a\nrevert;\n.b\nrevert;";
+    check(&p, merge_identical_blocks, "0x6001600b576002600b57005b60006000fd");
+}
+
+#[test]
+pub fn test_merge_blocks_02() {
+    // Blocks differ (though the unreachable one is removed)
+    let p = "if 1 goto a;\nstop;\n.a\nrevert;\n.b\nrevert 1;";
+    check(&p, merge_identical_blocks, "0x6001600657005b60006000fd");
+}
+
+// ============================================================================
+// Block Layout
+// ============================================================================
+
+#[test]
+pub fn test_reorder_blocks_01() {
+    // Jump replaced by fall through
+    let p = "goto b;\n.a\nstop;\n.b\nif 1 goto a;\nrevert;";
+    check(&p, reorder_blocks, "0x6001600a5760006000fd5b00");
+}
+
+#[test]
+pub fn test_reorder_blocks_02() {
+    // Revert block moved to end
+    let p = "if 1 goto a;\ngoto b;\n.a\nrevert;\n.b\nstop;";
+    check(&p, reorder_blocks, "0x6001600657005b60006000fd");
+}
+
+#[test]
+pub fn test_reorder_blocks_03() {
+    // Final block no longer last, so must stop
+    let p = "goto a;\n.b\nrevert;\n.a\nif 1 goto b;";
+    check(&p, reorder_blocks, "0x6001600657005b60006000fd");
+}
+
+#[test]
+pub fn test_reorder_blocks_04() {
+    // Nothing to do
+    let p = "if 1 goto a;\nstop;\n.a\nstop;";
+    check(&p, reorder_blocks, "0x6001600657005b00");
+}
+
+#[test]
+pub fn test_reorder_blocks_05() {
+    // Only unreachable code removed, which is still a change
+    let p = "stop;\nrevert;";
+    check(&p, reorder_blocks, "0x00");
+    let ts = Parser::new(p).parse().unwrap();
+    let mut code = Bytecode::try_from(ts.as_slice()).unwrap();
+    assert!(reorder_blocks(&mut code));
+    assert!(!reorder_blocks(&mut code));
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Check that compiling a given program and then applying a given
+/// optimisation produces a given hex string.
+fn check(stmt: &str, pass: fn(&mut Bytecode) -> bool, hex: &str) {
+    // Parse statement into a term
+    let ts = Parser::new(stmt).parse().unwrap();
+    // Translate statements into bytecode instructions
+    let mut code = Bytecode::try_from(ts.as_slice()).unwrap();
+    // Apply optimisation pass
+    pass(&mut code);
+    // Translate instructions into bytes
+    let bytes : Vec<u8> = code.try_into().unwrap();
+    // Check against expected hex string
+    assert_eq!(hex, bytes.to_hex_string());
+}
@@ -1,4 +1,4 @@
-use evmil::{Bytecode,Parser,ToHexString};
+use evmil::{Backend,Bytecode,Compiler,Evaluator,Outcome,Parser,ToHexString};
 
 // ============================================================================
 // Fail
@@ -23,7 +23,19 @@ pub fn test_succeed_01() {
 #[test]
 pub fn test_succeed_02() {
     let p = "succeed 1;";
-    check(&p, "0x600160005260006020f3");
+    check(&p, "0x600160005260206000f3");
+}
+
+#[test]
+pub fn test_succeed_03() {
+    let p = "succeed (1, 2);";
+    check(&p, "0x6001600260205260005260406000f3");
+}
+
+#[test]
+pub fn test_succeed_04() {
+    let p = "succeed (1, (2, 3)), 4;";
+    check(&p, "0x600160026003600460605260405260205260005260806000f3");
 }
 
 // ============================================================================
@@ -39,13 +51,52 @@ pub fn test_revert_01() {
 #[test]
 pub fn test_revert_02() {
     let p = "revert 1;";
-    check(&p, "0x600160005260006020fd");
+    check(&p, "0x600160005260206000fd");
+}
+
+// ============================================================================
+// Returned Data
+// ============================================================================
+
+#[test]
+pub fn test_returned_01() {
+    check_returned("revert;", &[]);
+    check_returned("succeed 1;", &word(1));
+    check_returned("revert 1;", &word(1));
+}
+
+#[test]
+pub fn test_returned_02() {
+    check_returned("succeed (1, 2);", &[word(1),word(2)].concat());
+    check_returned("revert (1, (2, 3)), 4;", &[word(1),word(2),word(3),word(4)].concat());
 }
 
 // ============================================================================
 // Helpers
 // ============================================================================
 
+/// Check the data returned by a statement which succeeds or reverts,
+/// by executing everything before its final instruction.
+fn check_returned(stmt: &str, data: &[u8]) {
+    let ts = Parser::new(stmt).parse().unwrap();
+    let n = Bytecode::try_from(ts.as_slice()).unwrap().instructions().len();
+    let mut e = Evaluator::new();
+    Compiler::new(&mut e).translate(&ts[0]).unwrap();
+    let Outcome::OutOfFuel(m) = e.run_with_fuel(n-1) else { panic!("unexpected outcome") };
+    // The offset is on top of the size
+    let (offset,size) = (m.stack[m.stack.len()-1].low() as usize,m.stack[m.stack.len()-2].low() as usize);
+    let mut memory = m.memory.clone();
+    memory.resize(offset + size,0);
+    assert_eq!(&memory[offset..offset+size], data);
+}
+
+/// Construct a word holding a given (small) value.
+fn word(v: u8) -> Vec<u8> {
+    let mut w = vec![0; 32];
+    w[31] = v;
+    w
+}
+
 /// Check that compiling a given sequence of terms produces a given
 /// hex string.
 fn check(stmt: &str, hex: &str) {
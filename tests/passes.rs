@@ -0,0 +1,193 @@
+use evmil::{BinOp,Intrinsic,Term,ToHexString};
+use evmil::opt::*;
+
+// ============================================================================
+// Passes
+// ============================================================================
+
+#[test]
+pub fn test_pass_names_01() {
+    for p in Pass::ALL {
+        assert_eq!(Pass::from_name(p.name()), Some(*p));
+    }
+    assert_eq!(Pass::from_name("unknown"), None);
+}
+
+// ============================================================================
+// Pass Manager
+// ============================================================================
+
+#[test]
+pub fn test_pass_manager_01() {
+    // No passes
+    let mut pm = PassManager::new();
+    check(&mut pm, revert(add(int(1),int(2))), "0x600260010160005260206000fd");
+    assert!(pm.stats().is_empty());
+}
+
+#[test]
+pub fn test_pass_manager_02() {
+    let mut pm = PassManager::new().with_pass(Pass::FoldConstants);
+    check(&mut pm, revert(add(int(1),int(2))), "0x600360005260206000fd");
+    let s = &pm.stats()[0];
+    assert_eq!(s.pass, Pass::FoldConstants);
+    assert!(s.changed);
+    assert_eq!(s.instructions_removed(), 2);
+    assert_eq!(s.bytes_saved(), 3);
+}
+
+#[test]
+pub fn test_pass_manager_03() {
+    // IL passes run first
+    let mut pm = PassManager::new().with_pass(Pass::ReorderBlocks).with_pass(Pass::FoldConstants);
+    check(&mut pm, revert(add(int(1),int(2))), "0x600360005260206000fd");
+    let passes : Vec<Pass> = pm.stats().iter().map(|s| s.pass).collect();
+    assert_eq!(passes, vec![Pass::FoldConstants,Pass::ReorderBlocks]);
+    assert!(!pm.stats()[1].changed);
+    assert_eq!(pm.stats()[1].bytes_saved(), 0);
+}
+
+#[test]
+pub fn test_pass_manager_04() {
+    // Bytecode passes
+    let terms = vec![goto("a"),label("a"),goto("b"),label("b"),Term::Stop];
+    let mut pm = PassManager::new().with_pass(Pass::RemoveJumpChains);
+    let code = pm.run(terms).unwrap();
+    assert_eq!(code.to_bytes().unwrap().to_hex_string(), "0x6003565b00");
+    assert_eq!(pm.stats()[0].bytes_saved(), 4);
+}
+
+#[test]
+pub fn test_pass_manager_05() {
+    // Debug mode
+    let mut pm = PassManager::default().with_debug(true);
+    check(&mut pm, revert(add(int(1),int(2))), "0x600360005260206000fd");
+    assert_eq!(pm.dumps().len(), Pass::DEFAULT.len());
+    assert_eq!(pm.dumps()[0], (Pass::FoldConstants,"Revert([Hex([3])])".to_string()));
+    assert!(pm.dumps()[1].1.starts_with("PUSH1 0x03 [fold-constants]\n"));
+    // Nothing recorded otherwise
+    let mut pm = PassManager::default();
+    check(&mut pm, revert(int(1)), "0x600160005260206000fd");
+    assert!(pm.dumps().is_empty());
+}
+
+#[test]
+pub fn test_use_push0_01() {
+    // Not applied by default
+    let mut pm = PassManager::default();
+    check(&mut pm, revert(int(0)), "0x600060005260206000fd");
+}
+
+#[test]
+pub fn test_use_push0_02() {
+    let mut pm = PassManager::new().with_pass(Pass::UsePush0);
+    check(&mut pm, revert(int(0)), "0x5f5f5260205ffd");
+}
+
+#[test]
+pub fn test_use_push0_03() {
+    // Non-zero pushes are unchanged
+    let mut pm = PassManager::new().with_pass(Pass::UsePush0);
+    check(&mut pm, revert(int(1)), "0x60015f5260205ffd");
+    assert_eq!(pm.stats()[0].bytes_saved(), 2);
+}
+
+// ============================================================================
+// Provenance
+// ============================================================================
+
+#[test]
+pub fn test_provenance_01() {
+    let mut pm = PassManager::new().with_pass(Pass::UsePush0).with_debug(true);
+    let code = pm.run(vec![revert(int(0))]).unwrap();
+    let p = |o| Some(Provenance{pass: Pass::UsePush0, origins: vec![o]});
+    assert_eq!(pm.provenance(), &[p(0),p(1),None,None,p(4),None]);
+    let asm = pm.assembly(&code).to_string();
+    assert!(asm.starts_with("PUSH0 [push0 from #0]\nPUSH0 [push0 from #1]\nMSTORE\n"));
+    assert_eq!(pm.dumps()[0].1, asm);
+}
+
+#[test]
+pub fn test_provenance_02() {
+    // Branch redirected past a trampoline
+    let terms = vec![ifgoto(msg_value(),"a"),Term::Stop,label("a"),goto("b"),label("b"),Term::Stop];
+    let mut pm = PassManager::new().with_pass(Pass::RemoveJumpChains).with_debug(true);
+    let code = pm.run(terms).unwrap();
+    let p = Some(Provenance{pass: Pass::RemoveJumpChains, origins: vec![1]});
+    assert_eq!(pm.provenance(), &[None,p,None,None,None,None]);
+    assert_eq!(pm.assembly(&code).to_string().lines().nth(1), Some("PUSHL(1) [jump-chains from #1]"));
+}
+
+#[test]
+pub fn test_provenance_03() {
+    // Later passes keep earlier provenance
+    let mut pm = PassManager::new().with_pass(Pass::UsePush0).with_pass(Pass::ReorderBlocks).with_debug(true);
+    pm.run(vec![revert(int(0))]).unwrap();
+    assert_eq!(pm.provenance()[0], Some(Provenance{pass: Pass::UsePush0, origins: vec![0]}));
+}
+
+#[test]
+pub fn test_provenance_04() {
+    // Nothing recorded outside debug mode
+    let mut pm = PassManager::new().with_pass(Pass::UsePush0);
+    let code = pm.run(vec![revert(int(0))]).unwrap();
+    assert!(pm.provenance().is_empty());
+    assert!(!pm.assembly(&code).to_string().contains('['));
+}
+
+#[test]
+pub fn test_provenance_05() {
+    // Blocks which are only moved keep their provenance
+    let terms = vec![goto("b"),label("a"),revert(int(0)),label("b"),ifgoto(msg_value(),"a"),Term::Stop];
+    let mut pm = PassManager::new().with_pass(Pass::UsePush0).with_pass(Pass::ReorderBlocks).with_debug(true);
+    let code = pm.run(terms).unwrap();
+    let p = |o| Some(Provenance{pass: Pass::UsePush0, origins: vec![o]});
+    assert_eq!(pm.provenance(), &[None,None,None,None,None,p(3),p(4),None,None,p(7),None]);
+    assert_eq!(pm.assembly(&code).to_string().lines().nth(5), Some("PUSH0 [push0 from #3]"));
+}
+
+#[test]
+pub fn test_provenance_06() {
+    // Instructions compiled from statements rewritten by IL passes
+    let mut pm = PassManager::new().with_pass(Pass::FoldConstants).with_debug(true);
+    pm.run(vec![revert(add(int(1),int(1))),Term::Stop]).unwrap();
+    let p = Some(Provenance{pass: Pass::FoldConstants, origins: vec![]});
+    assert_eq!(pm.provenance(), &[p.clone(),p.clone(),p.clone(),p.clone(),p.clone(),p,None]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(pm: &mut PassManager, term: Term, hex: &str) {
+    let code = pm.run(vec![term]).unwrap();
+    assert_eq!(code.to_bytes().unwrap().to_hex_string(), hex.to_string());
+}
+
+fn revert(t: Term) -> Term {
+    Term::Revert(vec![t])
+}
+
+fn goto(l: &str) -> Term {
+    Term::Goto(l.to_string())
+}
+
+fn ifgoto(c: Term, l: &str) -> Term {
+    Term::IfGoto(Box::new(c),l.to_string())
+}
+
+fn label(l: &str) -> Term {
+    Term::Label(l.to_string())
+}
+
+fn msg_value() -> Term {
+    Term::Intrinsic(Intrinsic::MsgValue,vec![])
+}
+
+fn int(d: u8) -> Term {
+    Term::Int(vec![d])
+}
+
+fn add(l: Term, r: Term) -> Term {
+    Term::Binary(BinOp::Add,Box::new(l),Box::new(r))
+}
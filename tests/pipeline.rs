@@ -0,0 +1,160 @@
+use evmil::{Diagnostic,InvalidJump,Options,Pipeline,analyze,analyze_with,disassemble_hex,hexdump_hex};
+#[cfg(feature = "std")]
+use evmil::{CfaState,Disassembly,EvmVersion,Metadata,compile_il,metadata_end};
+
+// ============================================================================
+// Disassemble
+// ============================================================================
+
+#[test]
+pub fn test_disassemble_hex_01() {
+    let out = disassemble_hex("0x600100", &Options::default()).unwrap();
+    assert_eq!(out, "0x000000: PUSH1 0x01\n0x000002: STOP\n");
+}
+
+#[test]
+pub fn test_disassemble_hex_02() {
+    let out = disassemble_hex("0x6003565b00", &Options::default()).unwrap();
+    assert_eq!(out, "0x000000: PUSH1 0x03\n0x000002: JUMP // (0x000003)\n\n// Stack +0\n0x000003: JUMPDEST(3)\n0x000004: STOP\n");
+}
+
+#[test]
+pub fn test_disassemble_hex_03() {
+    // Trailing data
+    let options = Options{data_from: Some(2), ..Default::default()};
+    let out = disassemble_hex("0x60005b50", &options).unwrap();
+    assert_eq!(out, "0x000000: PUSH1 0x00\n0x000002: 0x5b50\n");
+}
+
+#[test]
+pub fn test_disassemble_hex_04() {
+    let err = Diagnostic::error("H001","invalid hex string");
+    assert_eq!(disassemble_hex("0x600", &Options::default()), Err(err.clone()));
+    assert_eq!(disassemble_hex("0xzz", &Options::default()), Err(err));
+}
+
+#[test]
+pub fn test_disassemble_hex_05() {
+    // Explain reachability
+    let options = Options{explain: true, ..Default::default()};
+    let out = disassemble_hex("0x6003565b00", &options).unwrap();
+    assert_eq!(out, "// entry point\n0x000000: PUSH1 0x03\n0x000002: JUMP // (0x000003)\n\n// Stack +0\n// reached from 0x000002\n0x000003: JUMPDEST(3)\n0x000004: STOP\n");
+}
+
+#[test]
+pub fn test_hexdump_hex_01() {
+    let options = Options{data_from: Some(2), ..Default::default()};
+    let out = hexdump_hex("0x60005b50", &options).unwrap();
+    assert_eq!(out, "000000: <code block=\"0\">60 00</code> <data block=\"1\">5b 50</data>\n");
+}
+
+// ============================================================================
+// Compile
+// ============================================================================
+
+#[cfg(feature = "std")]
+#[test]
+pub fn test_compile_il_01() {
+    let bytes = compile_il("goto lab; .lab", &Options::default()).unwrap();
+    assert_eq!(bytes, vec![0x60,0x03,0x56,0x5b]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+pub fn test_compile_il_02() {
+    let err = compile_il("goto", &Options::default()).unwrap_err();
+    assert!(err.is_error());
+}
+
+#[cfg(feature = "std")]
+#[test]
+pub fn test_compile_il_03() {
+    let options = Options{metadata: true, ..Default::default()};
+    let bytes = compile_il("goto lab; .lab", &options).unwrap();
+    assert_eq!(&bytes[..4], &[0x60,0x03,0x56,0x5b]);
+    assert_eq!(metadata_end(&bytes), Some(bytes.len()));
+    assert_eq!(Metadata::decode(&bytes), Some(Metadata::new("goto lab; .lab")));
+}
+
+#[cfg(feature = "std")]
+#[test]
+pub fn test_compile_il_05() {
+    // Code cannot fall through into the metadata
+    let options = Options{metadata: true, ..Default::default()};
+    let bytes = compile_il("storage[0] = 1;", &options).unwrap();
+    assert_eq!(&bytes[..6], &[0x60,0x01,0x60,0x00,0x55,0xfe]);
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert!(disasm.diagnostics().is_empty());
+    assert!(!disasm.explain(6).unwrap().is_code);
+}
+
+#[cfg(feature = "std")]
+#[test]
+pub fn test_compile_il_06() {
+    // Constant folding produces a full word
+    let options = Options{optimise: true, ..Default::default()};
+    let bytes = compile_il("memory[0] = 0 - 1;", &options).unwrap();
+    assert_eq!(bytes.len(), 36);
+    assert_eq!(&bytes[..2], &[0x7f,0xff]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+pub fn test_compile_il_04() {
+    // Compilation is deterministic
+    let p = "switch calldatasize() { 1: goto a; 2: goto b; 3: goto c; 4: goto d; 5: goto a; }
+.a goto e; .b goto d; .c revert; .d stop; .e goto b;";
+    let options = Options{optimise: true, metadata: true, ..Default::default()};
+    let bytes = compile_il(p, &options).unwrap();
+    for _ in 0..10 {
+        assert_eq!(compile_il(p, &options.clone()).unwrap(), bytes);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+pub fn test_compile_il_07() {
+    // PUSH0 is used only when targeting Shanghai (or later)
+    let check = |v: Option<EvmVersion>, expected: &[u8]| {
+        let options = Options{evm_version: v, ..Default::default()};
+        assert_eq!(compile_il("storage[0] = 1;", &options).unwrap(), expected);
+    };
+    check(None, &[0x60,0x01,0x60,0x00,0x55]);
+    check(Some(EvmVersion::London), &[0x60,0x01,0x60,0x00,0x55]);
+    check(Some(EvmVersion::Shanghai), &[0x60,0x01,0x5f,0x55]);
+    check(Some(EvmVersion::Cancun), &[0x60,0x01,0x5f,0x55]);
+}
+
+// ============================================================================
+// Analyze
+// ============================================================================
+
+#[test]
+pub fn test_analyze_01() {
+    let report = analyze(&[0x60,0x03,0x56,0x00]);
+    assert_eq!(report.invalid_jumps, vec![InvalidJump::Target{pc:2,target:3}]);
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.statistics.instructions(), 3);
+    assert_eq!(report.summaries.len(), 2);
+}
+
+#[test]
+pub fn test_analyze_02() {
+    // No code
+    let report = analyze(&[]);
+    assert!(report.diagnostics.is_empty());
+    assert_eq!(report.statistics.instructions(), 0);
+    assert!(report.summaries.is_empty());
+    assert_eq!(disassemble_hex("", &Options::default()).unwrap(), "");
+}
+
+#[test]
+pub fn test_analyze_03() {
+    // Analysing once gives the same results
+    let bytes = [0x60,0x03,0x56,0x00];
+    let options = Options{lint: true, explain: true, ..Options::default()};
+    let pipeline = Pipeline::new(&bytes,&options);
+    let report = pipeline.report();
+    assert_eq!(report, analyze_with(&bytes,&options));
+    assert_eq!(pipeline.listing(&()), disassemble_hex("0x60035600",&options).unwrap());
+}
@@ -0,0 +1,17 @@
+use evmil::prelude::*;
+
+#[test]
+fn test_prelude_01() {
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    assert_eq!(disasm.blocks().len(), 2);
+    assert_eq!(disasm.to_vec()[1], Instruction::JUMP);
+}
+
+#[test]
+fn test_prelude_02() {
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let report = analyze_with(&bytes,&Options::default());
+    assert!(report.diagnostics.is_empty());
+    assert!(report.invalid_jumps.is_empty());
+}
@@ -0,0 +1,67 @@
+use evmil::{Disassembly,FromHexString,Proxy,BEACON_SLOT,IMPLEMENTATION_SLOT};
+
+const IMPL : &str = "360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+const BEACON : &str = "a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+#[test]
+fn test_minimal_01() {
+    let p = check("0x363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf3");
+    assert_eq!(p, Some(Proxy::Minimal(vec![0xbe;20])));
+}
+
+#[test]
+fn test_minimal_02() {
+    // Vanity address with leading zero byte
+    let p = check("0x363d3d373d3d3d363d72bebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602a57fd5bf3");
+    assert_eq!(p, Some(Proxy::Minimal(vec![0xbe;19])));
+}
+
+#[test]
+fn test_minimal_03() {
+    // Incorrect jump target
+    let p = check("0x363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602c57fd5bf3");
+    assert_eq!(p, None);
+    // Trailing bytes
+    let p = check("0x363d3d373d3d3d363d73bebebebebebebebebebebebebebebebebebebebe5af43d82803e903d91602b57fd5bf300");
+    assert_eq!(p, None);
+}
+
+#[test]
+fn test_eip1967_01() {
+    let p = check(&format!("0x7f{}543660006000376000600036600084f400",IMPL));
+    assert_eq!(p, Some(Proxy::Eip1967{slot: IMPLEMENTATION_SLOT.to_vec(), beacon: false}));
+}
+
+#[test]
+fn test_eip1967_02() {
+    let p = check(&format!("0x7f{}543660006000376000600036600084f400",BEACON));
+    assert_eq!(p, Some(Proxy::Eip1967{slot: BEACON_SLOT.to_vec(), beacon: true}));
+}
+
+#[test]
+fn test_eip1967_03() {
+    // Slot is read but never delegated to
+    let p = check(&format!("0x7f{}5400",IMPL));
+    assert_eq!(p, None);
+}
+
+#[test]
+fn test_uups_01() {
+    let p = check(&format!("0x60003560e01c806352d1902d14601157005b7f{}5400",IMPL));
+    assert_eq!(p, Some(Proxy::Uups{slot: IMPLEMENTATION_SLOT.to_vec()}));
+}
+
+#[test]
+fn test_proxy_01() {
+    assert_eq!(check("0x6001600201"), None);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(hex: &str) -> Option<Proxy> {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly = Disassembly::new(&bytes);
+    disasm.proxy()
+}
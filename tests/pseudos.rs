@@ -0,0 +1,104 @@
+use evmil::{CfaState,Disassembly,Instruction,Options,Pseudos,disassemble_hex};
+use evmil::Instruction::*;
+
+// ============================================================================
+// Decoding
+// ============================================================================
+
+#[test]
+pub fn test_pseudos_01() {
+    let pseudos = pseudos(&[(&[0x0c],"NOP")]);
+    assert_eq!(pseudos.decode(1,&[0x00,0x0c]), Some(pseudo("NOP",&[0x0c])));
+    assert_eq!(pseudos.decode(0,&[0x00,0x0c]), None);
+}
+
+#[test]
+pub fn test_pseudos_02() {
+    // Longest match wins
+    let pseudos = pseudos(&[(&[0xef],"A"),(&[0xef,0x00],"B")]);
+    assert_eq!(pseudos.decode(0,&[0xef,0x00]), Some(pseudo("B",&[0xef,0x00])));
+    assert_eq!(pseudos.decode(0,&[0xef,0x01]), Some(pseudo("A",&[0xef])));
+    assert_eq!(pseudos.decode(0,&[0xef]), Some(pseudo("A",&[0xef])));
+}
+
+#[test]
+pub fn test_pseudos_03() {
+    // Registering again replaces the name
+    let pseudos = pseudos(&[(&[0x0c],"A"),(&[0x0c],"B")]);
+    assert_eq!(pseudos.decode(0,&[0x0c]), Some(pseudo("B",&[0x0c])));
+}
+
+#[test]
+pub fn test_pseudos_04() {
+    let insn = pseudo("NOP",&[0x0c,0x0d]);
+    assert_eq!(insn.to_string(), "NOP");
+    assert_eq!(insn.length(&[]), 2);
+    assert_eq!(insn.stack_effect(), (0,0));
+    assert!(insn.fallthru());
+    let mut bytes = Vec::new();
+    insn.encode(&[],&mut bytes).unwrap();
+    assert_eq!(bytes, vec![0x0c,0x0d]);
+}
+
+// ============================================================================
+// Disassembly
+// ============================================================================
+
+#[test]
+pub fn test_pseudos_05() {
+    // Execution continues after a pseudo-instruction
+    let bytes = [0x0c,0x60,0x04,0x56,0x5b,0x00];
+    let insns = Disassembly::<CfaState>::new(&bytes).build().to_vec();
+    assert_eq!(insns[0], DATA(vec![0x0c]));
+    assert_eq!(insns.last(), Some(&DATA(vec![0x5b,0x00])));
+    let pseudos = pseudos(&[(&[0x0c],"NOP")]);
+    let insns = Disassembly::<CfaState>::new(&bytes).with_pseudos(&pseudos).build().to_vec();
+    assert_eq!(insns, vec![pseudo("NOP",&[0x0c]),PUSH(vec![0x04]),JUMP,JUMPDEST(4),STOP]);
+}
+
+#[test]
+pub fn test_pseudos_06() {
+    // Pseudo-instructions hide their bytes
+    let bytes = [0xef,0x5b,0x00];
+    let disasm = Disassembly::<CfaState>::new(&bytes).build();
+    assert_eq!(disasm.blocks().len(), 2);
+    let pseudos = pseudos(&[(&[0xef,0x5b],"MAGIC")]);
+    let disasm = Disassembly::<CfaState>::new(&bytes).with_pseudos(&pseudos).build();
+    assert_eq!(disasm.to_vec(), vec![pseudo("MAGIC",&[0xef,0x5b]),STOP]);
+    assert_eq!(disasm.reassemble(), Ok(bytes.to_vec()));
+    assert!(disasm.diagnostics().is_empty());
+}
+
+#[test]
+pub fn test_pseudos_07() {
+    // Patterns do not apply within data
+    let bytes = [0x00,0x0c,0x0c];
+    let pseudos = pseudos(&[(&[0x0c],"NOP")]);
+    let insns = Disassembly::<CfaState>::new(&bytes).with_pseudos(&pseudos).with_data_from(1).build().to_vec();
+    assert_eq!(insns, vec![STOP,DATA(vec![0x0c,0x0c])]);
+}
+
+#[test]
+pub fn test_pseudos_08() {
+    let pseudos = pseudos(&[(&[0x0c],"NOP")]);
+    let options = Options{pseudos, ..Default::default()};
+    let listing = disassemble_hex("0x0c00",&options).unwrap();
+    assert!(listing.contains("NOP"));
+    assert!(!listing.contains("0x0c"));
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn pseudos(patterns: &[(&[u8],&str)]) -> Pseudos {
+    let mut pseudos = Pseudos::new();
+    for (p,n) in patterns {
+        pseudos.insert(p,n);
+    }
+    pseudos
+}
+
+fn pseudo(name: &str, bytes: &[u8]) -> Instruction {
+    PSEUDO(name.to_string(),bytes.to_vec())
+}
@@ -0,0 +1,96 @@
+use evmil::{Bytecode,CostDelta,CostReport,FunctionCost,FunctionDelta,Parser,compare_costs,compare_function_costs};
+
+#[test]
+pub fn test_report_01() {
+    let code = compile("assert 1;");
+    let report = CostReport::new(&code).unwrap();
+    assert_eq!(report, CostReport{bytes: 7, instructions: 5, gas: 17});
+}
+
+#[test]
+pub fn test_report_02() {
+    let before = compile("assert 1;");
+    let after = compile("assert 1; assert 2;");
+    let delta = compare_costs(&before,&after).unwrap();
+    assert_eq!(delta, CostDelta{bytes: 7, instructions: 5, gas: 17});
+    assert_eq!(delta.to_string(), "bytes +7, instructions +5, gas +17");
+    // Reverse direction
+    let delta = compare_costs(&after,&before).unwrap();
+    assert_eq!(delta, CostDelta{bytes: -7, instructions: -5, gas: -17});
+}
+
+#[test]
+pub fn test_report_03() {
+    // Cost of each function
+    let code = compile(&dispatch("storage[1] = 2;\nstorage[2] = 3;"));
+    let fns = CostReport::functions(&code).unwrap();
+    assert_eq!(fns, vec![FunctionCost{selector: Some(vec![0x12,0x34,0x56,0x78]), entry: 0x1f,
+                                      cost: CostReport{bytes: 7, instructions: 5, gas: 107}},
+                         FunctionCost{selector: Some(vec![0x87,0x65,0x43,0x21]), entry: 0x26,
+                                      cost: CostReport{bytes: 12, instructions: 8, gas: 213}}]);
+}
+
+#[test]
+pub fn test_report_04() {
+    // Only the changed function differs
+    let before = compile(&dispatch("storage[1] = 2;\nstorage[2] = 3;"));
+    let after = compile(&dispatch("storage[1] = 2;"));
+    let deltas = compare_function_costs(&before,&after).unwrap();
+    assert_eq!(deltas, vec![FunctionDelta{selector: Some(vec![0x12,0x34,0x56,0x78]), delta: CostDelta{bytes: 0, instructions: 0, gas: 0}},
+                            FunctionDelta{selector: Some(vec![0x87,0x65,0x43,0x21]), delta: CostDelta{bytes: -5, instructions: -3, gas: -106}}]);
+}
+
+#[test]
+pub fn test_report_05() {
+    // Functions found in only one artifact
+    let before = compile("stop;");
+    let after = compile(&dispatch("stop;"));
+    let deltas = compare_function_costs(&before,&after).unwrap();
+    assert_eq!(deltas.len(), 2);
+    assert_eq!(deltas[1].delta, CostDelta{bytes: 2, instructions: 2, gas: 1});
+}
+
+#[test]
+pub fn test_report_06() {
+    // Several functions without a selector
+    let before = compile(&fallbacks("storage[1] = 2;\nstorage[2] = 3;"));
+    let after = compile(&fallbacks("storage[1] = 2;"));
+    let fns = CostReport::functions(&before).unwrap();
+    assert_eq!(fns.iter().filter(|f| f.selector.is_none()).count(), 2);
+    let deltas = compare_function_costs(&before,&after).unwrap();
+    assert_eq!(deltas, vec![FunctionDelta{selector: None, delta: CostDelta{bytes: 0, instructions: 0, gas: 0}},
+                            FunctionDelta{selector: None, delta: CostDelta{bytes: -5, instructions: -3, gas: -106}}]);
+}
+
+fn compile(input: &str) -> Bytecode {
+    let terms = Parser::new(input).parse().unwrap();
+    Bytecode::try_from(terms.as_slice()).unwrap()
+}
+
+/// Construct a contract which dispatches to two functions, the second
+/// of which has a given body.
+fn dispatch(body: &str) -> String {
+    format!("if calldata[0] >> 224 == 0x12345678 goto f;
+if calldata[0] >> 224 == 0x87654321 goto g;
+stop;
+.f
+storage[0] = 1;
+stop;
+.g
+{}
+stop;",body)
+}
+
+/// Construct a contract which dispatches on short calldata to two
+/// functions, the second of which has a given body.
+fn fallbacks(body: &str) -> String {
+    format!("if calldatasize() < 4 goto f;
+if calldatasize() < 4 goto g;
+stop;
+.f
+storage[0] = 1;
+stop;
+.g
+{}
+stop;",body)
+}
@@ -0,0 +1,147 @@
+use evmil::{BinOp,Bytecode,Intrinsic,Region,Term,ToHexString};
+use evmil::opt::*;
+
+// ============================================================================
+// Rewriting
+// ============================================================================
+
+#[test]
+pub fn test_rewrite_01() {
+    // Rewrite applies to nested terms
+    let t = Term::Assert(Box::new(add(int(1),add(int(2),int(3)))));
+    let r = t.rewrite(&mut |t: &Term| match t {
+        Term::Int(ds) => Some(Term::Hex(ds.clone())),
+        _ => None
+    });
+    assert_eq!(r, Term::Assert(Box::new(add(hex(&[1]),add(hex(&[2]),hex(&[3]))))));
+}
+
+#[test]
+pub fn test_rewrite_02() {
+    // Rewrite continues until a fixpoint
+    let t = Term::Tuple(vec![int(5)]);
+    let r = t.rewrite(&mut |t: &Term| match t {
+        Term::Int(ds) if ds[0] > 0 => Some(int(ds[0]-1)),
+        _ => None
+    });
+    assert_eq!(r, Term::Tuple(vec![int(0)]));
+}
+
+#[test]
+pub fn test_rewrite_03() {
+    // Labels are unchanged
+    let t = Term::IfGoto(Box::new(int(1)),"l".to_string());
+    let r = t.map_children(|_| int(2));
+    assert_eq!(r, Term::IfGoto(Box::new(int(2)),"l".to_string()));
+}
+
+// ============================================================================
+// Constant Folding
+// ============================================================================
+
+#[test]
+pub fn test_fold_constants_01() {
+    check(add(int(1),int(2)), hex(&[3]));
+}
+
+#[test]
+pub fn test_fold_constants_02() {
+    // Nested operations
+    check(mul(add(int(1),int(2)),hex(&[1,0])), hex(&[3,0]));
+}
+
+#[test]
+pub fn test_fold_constants_03() {
+    // Subtraction wraps
+    check(bin(BinOp::Subtract,int(0),int(1)), hex(&[15;64]));
+}
+
+#[test]
+pub fn test_fold_constants_04() {
+    // Comparisons
+    check(bin(BinOp::LessThan,int(1),int(2)), hex(&[1]));
+    check(bin(BinOp::GreaterThanOrEquals,int(1),int(2)), hex(&[0]));
+}
+
+#[test]
+pub fn test_fold_constants_05() {
+    // Non-constant operands are left
+    let t = add(msg_sender(),add(int(1),int(1)));
+    check(t, add(msg_sender(),hex(&[2])));
+}
+
+#[test]
+pub fn test_fold_constants_06() {
+    // Logical operators and division are left
+    let t = bin(BinOp::LogicalAnd,int(1),int(2));
+    check(t.clone(), t);
+    let t = bin(BinOp::Divide,int(4),int(2));
+    check(t.clone(), t);
+}
+
+#[test]
+pub fn test_fold_constants_07() {
+    // Overflowing literals are left
+    let t = add(hex(&[1;65]),int(1));
+    check(t.clone(), t);
+}
+
+#[test]
+pub fn test_fold_constants_08() {
+    // Folding shrinks the compiled code
+    let t = vec![Term::Revert(vec![add(int(1),int(2))])];
+    let code = Bytecode::try_from(fold_constants(t).as_slice()).unwrap();
+    assert_eq!(code.to_bytes().unwrap().to_hex_string(), "0x600360005260206000fd".to_string());
+}
+
+#[test]
+pub fn test_fold_constants_09() {
+    // Results wider than 128 bits still compile
+    let t = vec![Term::Assignment(Box::new(memory(int(0))),Box::new(bin(BinOp::Subtract,int(0),int(1))))];
+    let code = Bytecode::try_from(fold_constants(t).as_slice()).unwrap();
+    assert_eq!(code.to_bytes().unwrap().to_hex_string(), format!("0x7f{}600052","ff".repeat(32)));
+}
+
+#[test]
+pub fn test_fold_constants_10() {
+    // Overflowing literals are reported by the compiler
+    let t = vec![Term::Revert(vec![add(hex(&[1;65]),int(1))])];
+    assert!(matches!(Bytecode::try_from(fold_constants(t).as_slice()), Err(d) if d.code == "C001"));
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(term: Term, expected: Term) {
+    let actual = fold_constants(vec![Term::Assert(Box::new(term))]);
+    assert_eq!(actual, vec![Term::Assert(Box::new(expected))]);
+}
+
+fn int(d: u8) -> Term {
+    Term::Int(vec![d])
+}
+
+fn hex(ds: &[u8]) -> Term {
+    Term::Hex(ds.to_vec())
+}
+
+fn memory(index: Term) -> Term {
+    Term::ArrayAccess(Box::new(Term::MemoryAccess(Region::Memory)),Box::new(index))
+}
+
+fn msg_sender() -> Term {
+    Term::Intrinsic(Intrinsic::MsgSender,vec![])
+}
+
+fn add(l: Term, r: Term) -> Term {
+    bin(BinOp::Add,l,r)
+}
+
+fn mul(l: Term, r: Term) -> Term {
+    bin(BinOp::Multiply,l,r)
+}
+
+fn bin(bop: BinOp, l: Term, r: Term) -> Term {
+    Term::Binary(bop,Box::new(l),Box::new(r))
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "std")]
+use serde_json::json;
+use evmil::{Diagnostic,Options,Severity,analyze_with,to_sarif};
+
+// ============================================================================
+// SARIF
+// ============================================================================
+
+#[test]
+pub fn test_sarif_01() {
+    let log = to_sarif(&[], "a.hex");
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(log["runs"][0]["tool"]["driver"]["name"], "evmil");
+    assert_eq!(log["runs"][0]["results"], json!([]));
+    assert_eq!(log["runs"][0]["artifacts"], json!([{"location": {"uri": "a.hex"}}]));
+}
+
+#[test]
+pub fn test_sarif_02() {
+    // Byte offsets
+    let d = Diagnostic::error("D002","invalid jump").with_offset(0x1f);
+    let result = &to_sarif(&[d], "a.hex")["runs"][0]["results"][0];
+    assert_eq!(result["ruleId"], "D002");
+    assert_eq!(result["level"], "error");
+    assert_eq!(result["message"]["text"], "invalid jump");
+    assert_eq!(result["locations"][0]["physicalLocation"], json!({
+        "artifactLocation": {"uri": "a.hex"},
+        "region": {"byteOffset": 31, "byteLength": 1}
+    }));
+}
+
+#[test]
+pub fn test_sarif_03() {
+    // Source spans, notes and missing locations
+    let d1 = Diagnostic::error("P003","missing token").with_span(4..7).with_note("expected Semicolon");
+    let d2 = Diagnostic::new(Severity::Note,"X001","no location");
+    let log = to_sarif(&[d1,d2], "a.eil");
+    let results = &log["runs"][0]["results"];
+    assert_eq!(results[0]["locations"][0]["physicalLocation"]["region"], json!({"charOffset": 4, "charLength": 3}));
+    assert_eq!(results[0]["properties"]["notes"], json!(["expected Semicolon"]));
+    assert_eq!(results[1]["level"], "note");
+    assert!(results[1].get("locations").is_none());
+}
+
+#[test]
+pub fn test_sarif_04() {
+    // Rules are shared between results
+    let ds = [Diagnostic::warning("D003","unknown jump target").with_offset(1),
+              Diagnostic::warning("D001","unreachable").with_offset(2),
+              Diagnostic::warning("D003","unknown jump target").with_offset(3)];
+    let log = to_sarif(&ds, "a.hex");
+    let rules = &log["runs"][0]["tool"]["driver"]["rules"];
+    assert_eq!(rules.as_array().unwrap().len(), 2);
+    assert_eq!(rules[0]["id"], "D003");
+    assert_eq!(rules[0]["defaultConfiguration"]["level"], "warning");
+    let indices : Vec<u64> = (0..3).map(|i| log["runs"][0]["results"][i]["ruleIndex"].as_u64().unwrap()).collect();
+    assert_eq!(indices, vec![0,1,0]);
+}
+
+#[test]
+pub fn test_sarif_05() {
+    // Security lints from an analysis
+    let options = Options{lint: true, ..Default::default()};
+    let log = analyze_with(&[0x33,0xff], &options).to_sarif("a.hex");
+    let result = &log["runs"][0]["results"][0];
+    assert_eq!(result["ruleId"], "S001");
+    assert_eq!(result["locations"][0]["physicalLocation"]["region"]["byteOffset"], 1);
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "std")]
+use std::sync::Arc;
+use std::thread;
+use evmil::{CfaState,Disassembly,FromHexString,Instruction::*,SharedDisassembly};
+
+#[test]
+fn test_shared_01() {
+    fn is_send_sync<T:Send+Sync>() {}
+    is_send_sync::<SharedDisassembly<'static,CfaState>>();
+}
+
+#[test]
+fn test_shared_02() {
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let shared = check(&bytes);
+    assert_eq!(shared.instructions(), &[(0,PUSH(vec![3])),(2,JUMP),(3,JUMPDEST(3)),(4,STOP)]);
+    assert_eq!(shared.instruction_at(2), Some(&JUMP));
+    assert_eq!(shared.instruction_at(1), None);
+    assert_eq!(shared.block_of(0), Some(0));
+    assert_eq!(shared.block_of(4), Some(1));
+    assert_eq!(shared.block_of(5), None);
+    assert_eq!(shared.successors(0), &[1]);
+    assert!(shared.successors(1).is_empty());
+}
+
+#[test]
+fn test_shared_03() {
+    // Unreachable code given as data
+    let bytes = "0x00600100".from_hex_string().unwrap();
+    let shared = check(&bytes);
+    assert_eq!(shared.instructions(), &[(0,STOP),(1,DATA(vec![0x60,0x01,0x00]))]);
+    assert_eq!(shared.block_of(2), Some(1));
+}
+
+#[test]
+fn test_shared_04() {
+    // States agree with the underlying disassembly
+    let bytes = "0x6000358063aabbccdd14600f5700015b00".from_hex_string().unwrap();
+    let shared = check(&bytes);
+    for (pc,_) in shared.instructions() {
+        assert_eq!(shared.get_state(*pc), shared.disassembly().get_state(*pc));
+    }
+}
+
+#[test]
+fn test_shared_05() {
+    // Concurrent queries
+    let bytes = "0x6000358063aabbccdd14600f5700015b00".from_hex_string().unwrap();
+    let shared = Arc::new(check(&bytes));
+    thread::scope(|s| {
+        for _ in 0..4 {
+            let shared = Arc::clone(&shared);
+            s.spawn(move || {
+                assert_eq!(shared.instructions().len(), 11);
+                assert_eq!(shared.successors(0), &[2]);
+                assert_eq!(shared.get_state(15), shared.disassembly().get_state(15));
+            });
+        }
+    });
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(bytes: &[u8]) -> SharedDisassembly<'_,CfaState> {
+    let disasm : Disassembly<CfaState> = Disassembly::new(bytes).build();
+    disasm.freeze()
+}
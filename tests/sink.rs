@@ -0,0 +1,66 @@
+use evmil::{AssemblySink,Bytecode,HexSink,SizeSink};
+use evmil::Instruction::*;
+
+#[test]
+fn test_sink_01() {
+    let code = example();
+    let mut bytes = Vec::new();
+    code.encode_into(&mut bytes).unwrap();
+    assert_eq!(bytes, code.to_bytes().unwrap());
+    assert_eq!(bytes, vec![0x60,0x05,0x56,0x60,0x01,0x5b,0x00]);
+}
+
+#[test]
+fn test_sink_02() {
+    let mut hex = HexSink::new();
+    example().encode_into(&mut hex).unwrap();
+    assert_eq!(hex.0, "0x60055660015b00");
+}
+
+#[test]
+fn test_sink_03() {
+    let mut asm = AssemblySink::default();
+    example().encode_into(&mut asm).unwrap();
+    assert_eq!(asm.0, "0x0000: 0x6005 PUSHL(0)\n0x0002: 0x56 JUMP\n0x0003: 0x6001 PUSH1 0x01\n0x0005: 0x5b JUMPDEST(0)\n0x0006: 0x00 STOP\n");
+}
+
+#[test]
+fn test_sink_04() {
+    let mut size = SizeSink::default();
+    example().encode_into(&mut size).unwrap();
+    assert_eq!(size, SizeSink{bytes: 7, instructions: 5});
+}
+
+#[test]
+fn test_sink_05() {
+    // Multiple outputs in one pass
+    let mut sinks = (HexSink::new(),(SizeSink::default(),Vec::new()));
+    example().encode_into(&mut sinks).unwrap();
+    assert_eq!(sinks.0.0, "0x60055660015b00");
+    assert_eq!(sinks.1.0.bytes, 7);
+    assert_eq!(sinks.1.1.len(), 7);
+}
+
+#[test]
+fn test_sink_06() {
+    // Invalid instructions are reported
+    let mut code = Bytecode::new();
+    code.push(DUP(17));
+    let mut hex = HexSink::new();
+    assert!(code.encode_into(&mut hex).is_err());
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn example() -> Bytecode {
+    let mut code = Bytecode::new();
+    let lab = code.fresh_label();
+    code.push(PUSHL(lab));
+    code.push(JUMP);
+    code.push(PUSH(vec![1]));
+    code.push(JUMPDEST(lab));
+    code.push(STOP);
+    code
+}
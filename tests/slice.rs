@@ -0,0 +1,38 @@
+use evmil::{CfaState,Disassembly,FromHexString,ToHexString};
+
+#[test]
+pub fn test_extract_01() {
+    check("0x60003560e01c63123456788114601157005b5000", "0x5b5000");
+}
+
+#[test]
+pub fn test_extract_02() {
+    // Jump within function
+    check("0x60003560e01c63123456788114601157005b601656005b5000", "0x5b6004565b5000");
+}
+
+#[test]
+pub fn test_extract_03() {
+    // Jump outside function
+    check("0x60003560e01c63123456788114601157005b601650005b00", "0x5b600550005b00");
+}
+
+#[test]
+pub fn test_extract_04() {
+    // Function entered by falling through
+    check("0x60003560e01c63123456788114601457005b50005b60016011575b00", "0x5b6001600957600c565b50005b00");
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Extract the (only) function of a contract.
+fn check(hex: &str, expected: &str) {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let fns = disasm.functions();
+    assert_eq!(fns.len(), 1);
+    let code = disasm.extract_function(&fns[0]);
+    assert_eq!(code.to_bytes().unwrap().to_hex_string(), expected);
+}
@@ -0,0 +1,93 @@
+use evmil::{CfaState,Coverage,Disassembly,FromHexString,Statistics};
+
+#[test]
+pub fn test_stats_01() {
+    let stats = check("0x00");
+    assert_eq!(stats.instructions(), 1);
+    assert_eq!(stats.count(0x00), 1);
+    assert_eq!(stats.block_sizes, vec![1]);
+    assert_eq!(stats.data_bytes, 0);
+    assert_eq!(stats.selectors, 0);
+}
+
+#[test]
+pub fn test_stats_02() {
+    // Unreachable code counted as data
+    let stats = check("0x6001600201005b00");
+    assert_eq!(stats.instructions(), 4);
+    assert_eq!(stats.count(0x60), 2);
+    assert_eq!(stats.count(0x5b), 0);
+    assert_eq!(stats.push_values.get(&vec![1]), Some(&1));
+    assert_eq!(stats.data_bytes, 2);
+}
+
+#[test]
+pub fn test_stats_03() {
+    // Dispatcher with one selector
+    let stats = check("0x6000358063aabbccdd14600f5700015b00");
+    assert_eq!(stats.instructions(), 10);
+    assert_eq!(stats.count(0x60), 2);
+    assert_eq!(stats.count(0x63), 1);
+    assert_eq!(stats.count(0x5b), 1);
+    assert_eq!(stats.block_sizes, vec![8,2]);
+    assert_eq!(stats.push_values.get(&vec![0xaa,0xbb,0xcc,0xdd]), Some(&1));
+    assert_eq!(stats.data_bytes, 1);
+    assert_eq!(stats.selectors, 1);
+}
+
+#[test]
+pub fn test_coverage_01() {
+    let coverage = check_coverage("0x00");
+    assert_eq!(coverage, Coverage{code: 1, data: 0, unknown: 0, gaps: vec![]});
+    assert_eq!(coverage.code_fraction(), 1.0);
+}
+
+#[test]
+pub fn test_coverage_02() {
+    // Unreachable code counted as data
+    let coverage = check_coverage("0x6001600201005b00");
+    assert_eq!(coverage, Coverage{code: 6, data: 2, unknown: 0, gaps: vec![]});
+    assert_eq!(coverage.total(), 8);
+    assert_eq!(coverage.data_fraction(), 0.25);
+}
+
+#[test]
+pub fn test_coverage_03() {
+    // Unresolved jump, so unreachable jumpdest could be code
+    let coverage = check_coverage("0x600035565b00fe");
+    assert_eq!(coverage, Coverage{code: 4, data: 1, unknown: 2, gaps: vec![]});
+    assert_eq!(coverage.unknown_fraction(), 2.0 / 7.0);
+}
+
+#[test]
+pub fn test_coverage_04() {
+    // Data hints
+    let bytes = "0x600035565b00fe".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).with_data_from(4).build();
+    assert_eq!(disasm.coverage(), Coverage{code: 4, data: 3, unknown: 0, gaps: vec![]});
+}
+
+#[test]
+pub fn test_coverage_05() {
+    // Truncated push
+    let coverage = check_coverage("0x6001600201610a");
+    assert_eq!(coverage, Coverage{code: 7, data: 0, unknown: 0, gaps: vec![]});
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Compute statistics for a given hex string.
+fn check(hex: &str) -> Statistics {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    disasm.statistics()
+}
+
+/// Compute coverage for a given hex string.
+fn check_coverage(hex: &str) -> Coverage {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    disasm.coverage()
+}
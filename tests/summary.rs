@@ -0,0 +1,49 @@
+use evmil::{BlockSummary,Disassembly,FromHexString};
+
+#[test]
+pub fn test_summary_01() {
+    let s = check("0x600160020100");
+    assert_eq!(s, vec![BlockSummary{stack_delta: 1, can_terminate: true, ..Default::default()}]);
+}
+
+#[test]
+pub fn test_summary_02() {
+    // Stack needed on entry
+    let s = check("0x80");
+    assert_eq!(s, vec![BlockSummary{stack_delta: 1, stack_needed: 1, ..Default::default()}]);
+    let s = check("0x9055");
+    assert_eq!(s, vec![BlockSummary{stack_delta: -2, stack_needed: 2, writes_storage: true, ..Default::default()}]);
+}
+
+#[test]
+pub fn test_summary_03() {
+    // Items produced by the block itself are not needed
+    let s = check("0x5b50600054600101");
+    assert_eq!(s, vec![BlockSummary{stack_delta: 0, stack_needed: 1, reads_storage: true, ..Default::default()}]);
+}
+
+#[test]
+pub fn test_summary_04() {
+    let s = check("0x6000600052");
+    assert_eq!(s, vec![BlockSummary{stack_delta: 0, writes_memory: true, ..Default::default()}]);
+    let s = check("0x60006000f3");
+    assert_eq!(s, vec![BlockSummary{reads_memory: true, can_terminate: true, ..Default::default()}]);
+}
+
+#[test]
+pub fn test_summary_05() {
+    // Multiple blocks
+    let s = check("0x6003565b00");
+    assert_eq!(s, vec![BlockSummary::default(),BlockSummary{can_terminate: true, ..Default::default()}]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(hex: &str) -> Vec<BlockSummary> {
+    let bytes = hex.from_hex_string().unwrap();
+    // Summaries do not depend on flow analysis
+    let disasm : Disassembly = Disassembly::new(&bytes);
+    disasm.summaries()
+}
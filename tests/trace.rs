@@ -0,0 +1,77 @@
+#![cfg(feature = "std")]
+use serde_json::json;
+use evmil::{CfaState,Disassembly,FromHexString,Inconsistency,Replay,Step,Trace};
+
+#[test]
+pub fn test_trace_01() {
+    let json = json!({"gas": 21010, "failed": false, "returnValue": "", "structLogs": [
+        {"pc": 0, "op": "PUSH1", "gas": 100, "gasCost": 3, "depth": 1, "stack": []},
+        {"pc": 2, "op": "JUMP", "gas": 97, "gasCost": 8, "depth": 1}
+    ]});
+    let trace = Trace::from_json(&json).unwrap();
+    assert_eq!(trace.steps, vec![Step{pc: 0, op: "PUSH1".to_string(), depth: 1, stack: Some(0)},
+                                 Step{pc: 2, op: "JUMP".to_string(), depth: 1, stack: None}]);
+    // JSON-RPC response
+    let rpc = json!({"jsonrpc": "2.0", "id": 1, "result": json});
+    assert_eq!(Trace::from_json(&rpc), Some(trace.clone()));
+    // Just the logs
+    assert_eq!(Trace::from_json(&json["structLogs"]), Some(trace));
+}
+
+#[test]
+pub fn test_trace_02() {
+    assert_eq!(Trace::from_json(&json!({"structLogs": [{"op": "STOP"}]})), None);
+    assert_eq!(Trace::from_json(&json!({"gas": 0})), None);
+}
+
+#[test]
+pub fn test_replay_01() {
+    let replay = check("0x6003565b00", &[(0,0),(2,1),(3,0),(4,0)]);
+    assert_eq!(replay.counts, vec![1,1]);
+    assert_eq!(replay.inconsistencies, vec![]);
+}
+
+#[test]
+pub fn test_replay_02() {
+    // Loop executed three times
+    let replay = check("0x5b6000565b00", &[(0,0),(1,0),(3,1),(0,0),(1,0),(3,1),(0,0)]);
+    assert_eq!(replay.counts, vec![3,0]);
+}
+
+#[test]
+pub fn test_replay_03() {
+    let replay = check("0x6003565b00", &[(0,0),(1,0),(2,0),(3,0),(4,0),(5,0)]);
+    assert_eq!(replay.inconsistencies, vec![Inconsistency::Pc{step: 1, pc: 1},
+                                            Inconsistency::StackHeight{step: 2, pc: 2, height: 0},
+                                            Inconsistency::Pc{step: 5, pc: 5}]);
+}
+
+#[test]
+pub fn test_replay_04() {
+    let replay = check("0x005b00", &[(0,0),(1,0),(2,0)]);
+    assert_eq!(replay.counts, vec![1,1]);
+    assert_eq!(replay.inconsistencies, vec![Inconsistency::Unreachable{step: 1, pc: 1},
+                                            Inconsistency::Unreachable{step: 2, pc: 2}]);
+}
+
+#[test]
+pub fn test_replay_05() {
+    // Steps in other calls are ignored
+    let bytes = "0x6003565b00".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let step = |pc: usize, depth: usize| Step{pc, op: String::new(), depth, stack: None};
+    let trace = Trace{steps: vec![step(0,1),step(2,1),step(0,2),step(7,2),step(3,1),step(4,1)]};
+    assert_eq!(disasm.replay(&trace), Replay{counts: vec![1,1], inconsistencies: vec![]});
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Replay a trace given as pairs of locations and stack heights.
+fn check(hex: &str, steps: &[(usize,usize)]) -> Replay {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let steps = steps.iter().map(|(pc,h)| Step{pc: *pc, op: String::new(), depth: 1, stack: Some(*h)}).collect();
+    disasm.replay(&Trace{steps})
+}
@@ -0,0 +1,74 @@
+use std::collections::{BTreeSet,HashMap,HashSet};
+use evmil::{BinOp,Block,Instruction,Intrinsic,Region,Term};
+use evmil::util::u256;
+
+#[test]
+fn test_traits_01() {
+    let mut map = HashMap::new();
+    map.insert(u256::from(1u8),"one");
+    map.insert(u256::from(2u8),"two");
+    assert_eq!(map.get(&u256::from(1u8)), Some(&"one"));
+    assert_eq!(u256::default(), u256::from(0u8));
+}
+
+#[test]
+fn test_traits_02() {
+    let insns : HashSet<Instruction> = [Instruction::JUMP,Instruction::PUSH(vec![1]),Instruction::JUMP].into_iter().collect();
+    assert_eq!(insns.len(), 2);
+    assert!(insns.contains(&Instruction::PUSH(vec![1])));
+}
+
+#[test]
+fn test_traits_03() {
+    let blocks : BTreeSet<Block> = [Block::new(3,5),Block::new(0,3),Block::new(0,2)].into_iter().collect();
+    let blocks : Vec<Block> = blocks.into_iter().collect();
+    assert_eq!(blocks, vec![Block::new(0,2),Block::new(0,3),Block::new(3,5)]);
+    assert_eq!(Block::new(3,0x10).to_string(), "0x3..0x10");
+}
+
+#[test]
+fn test_traits_04() {
+    let terms : HashSet<Term> = [Term::Stop,Term::Fail,Term::Stop].into_iter().collect();
+    assert_eq!(terms.len(), 2);
+}
+
+#[test]
+fn test_traits_05() {
+    check(Term::Stop, "stop;");
+    check(Term::Goto("lab".to_string()), "goto lab;");
+    check(Term::Label("lab".to_string()), ".lab");
+    check(Term::Revert(vec![]), "revert;");
+    check(Term::Succeed(vec![int(1),hex(&[0xf,0xf])]), "succeed 1, 0xff;");
+}
+
+#[test]
+fn test_traits_06() {
+    let lhs = Term::ArrayAccess(Box::new(Term::MemoryAccess(Region::Storage)),Box::new(int(0)));
+    let rhs = Term::Binary(BinOp::Add,Box::new(Term::Intrinsic(Intrinsic::MsgValue,vec![])),Box::new(int(1)));
+    check(Term::Assignment(Box::new(lhs),Box::new(rhs)), "storage[0] = (msg.value + 1);");
+    let bal = Term::Intrinsic(Intrinsic::Balance,vec![Term::Intrinsic(Intrinsic::MsgSender,vec![])]);
+    check(Term::Require(Box::new(bal)), "require balance(msg.sender);");
+    check(Term::IfGoto(Box::new(Term::Intrinsic(Intrinsic::CallDataSize,vec![])),"l".to_string()), "if calldatasize() goto l;");
+}
+
+#[test]
+fn test_traits_07() {
+    let cases = vec![(int(1),"a".to_string()),(hex(&[2]),"b".to_string())];
+    check(Term::Switch(Box::new(Term::Tuple(vec![int(1),int(2)])),cases), "switch (1, 2) { 1: goto a; 0x2: goto b; }");
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn check(term: Term, expected: &str) {
+    assert_eq!(term.to_string(), expected);
+}
+
+fn int(n: u8) -> Term {
+    Term::Int(n.to_string().chars().map(|c| c.to_digit(10).unwrap() as u8).collect())
+}
+
+fn hex(digits: &[u8]) -> Term {
+    Term::Hex(digits.to_vec())
+}
@@ -0,0 +1,93 @@
+use evmil::{Block,BlockVisitor,CfaState,Disassembly,FromHexString,Instruction,InstructionVisitor};
+use evmil::Instruction::*;
+
+#[test]
+fn test_visit_01() {
+    let insns = instructions("0x6003565b00");
+    assert_eq!(insns, vec![(0,PUSH(vec![3])),(2,JUMP),(3,JUMPDEST(3)),(4,STOP)]);
+}
+
+#[test]
+fn test_visit_02() {
+    // Unreachable instructions are not visited
+    let insns = instructions("0x60055600fe5b00");
+    assert_eq!(insns, vec![(0,PUSH(vec![5])),(2,JUMP),(5,JUMPDEST(5)),(6,STOP)]);
+}
+
+#[test]
+fn test_visit_03() {
+    let events = blocks("0x6003565b00");
+    assert_eq!(events, vec!["enter 0","exit 0","enter 1","exit 1"]);
+}
+
+#[test]
+fn test_visit_04() {
+    let events = blocks("0x600556005b00");
+    assert_eq!(events, vec!["enter 0","exit 0","data 1 (1 bytes)","enter 2","exit 2"]);
+}
+
+#[test]
+fn test_visit_05() {
+    // States are those immediately before each instruction
+    let bytes = "0x6001600201".from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let mut heights = Heights(Vec::new());
+    disasm.visit_instructions(&mut heights);
+    assert_eq!(heights.0, vec![0,1,2]);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+struct Recorder(Vec<(usize,Instruction)>);
+
+impl<T> InstructionVisitor<T> for Recorder {
+    fn visit_instruction(&mut self, pc: usize, insn: &Instruction, _state: &T) {
+        self.0.push((pc,insn.clone()));
+    }
+}
+
+struct Events(Vec<String>);
+
+impl<T> InstructionVisitor<T> for Events {}
+
+impl<T> BlockVisitor<T> for Events {
+    fn enter_block(&mut self, id: usize, _blk: &Block) {
+        self.0.push(format!("enter {}",id));
+    }
+
+    fn exit_block(&mut self, id: usize, _blk: &Block) {
+        self.0.push(format!("exit {}",id));
+    }
+
+    fn visit_data(&mut self, id: usize, _blk: &Block, bytes: &[u8]) {
+        self.0.push(format!("data {} ({} bytes)",id,bytes.len()));
+    }
+}
+
+struct Heights(Vec<usize>);
+
+impl InstructionVisitor<CfaState> for Heights {
+    fn visit_instruction(&mut self, _pc: usize, _insn: &Instruction, state: &CfaState) {
+        self.0.push(state.stack().len().unwrap());
+    }
+}
+
+/// Visit the reachable instructions of a given hex string.
+fn instructions(hex: &str) -> Vec<(usize,Instruction)> {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let mut recorder = Recorder(Vec::new());
+    disasm.visit_instructions(&mut recorder);
+    recorder.0
+}
+
+/// Record the blocks visited for a given hex string.
+fn blocks(hex: &str) -> Vec<String> {
+    let bytes = hex.from_hex_string().unwrap();
+    let disasm : Disassembly<CfaState> = Disassembly::new(&bytes).build();
+    let mut events = Events(Vec::new());
+    disasm.visit(&mut events);
+    events.0
+}